@@ -3,6 +3,10 @@ use criterion::{Criterion, criterion_group, criterion_main};
 use std::hint::black_box as bb;
 
 use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
 
 pub fn bench_affine_mac_new(c: &mut Criterion) {
     let k = 2;
@@ -14,32 +18,35 @@ pub fn bench_affine_mac_new(c: &mut Criterion) {
 }
 
 pub fn bench_affine_mac_gen(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let mac = AffineMAC::new(k, msg_len);
 
-    c.bench_function("affine_mac_gen (128)", |b| b.iter(|| mac.gen_mac()));
+    c.bench_function("affine_mac_gen (128)", |b| b.iter(|| mac.gen_mac(&mut rng)));
 }
 
 pub fn bench_affine_mac_tag(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let mac = AffineMAC::new(k, msg_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
     let message = generate_random_message_128();
 
     c.bench_function("affine_mac_tag (128)", |b| {
-        b.iter(|| mac.tag(bb(&sk), bb(&message)))
+        b.iter(|| mac.tag(&mut rng, bb(&sk), bb(&message)))
     });
 }
 
 pub fn bench_affine_mac_verify(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let mac = AffineMAC::new(k, msg_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
     let message = generate_random_message_128();
-    let tag = mac.tag(&sk, &message);
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
 
     c.bench_function("affine_mac_verify (128)", |b| {
         b.iter(|| mac.verify(bb(&sk), bb(&message), bb(&tag)))
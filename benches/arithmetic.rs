@@ -3,22 +3,29 @@ use criterion::{Criterion, criterion_group, criterion_main};
 use std::hint::black_box as bb;
 
 use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
 
 pub fn bench_scalar_mul_g1(c: &mut Criterion) {
-    let scalar = random_field_element();
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scalar = random_field_element(&mut rng);
 
     c.bench_function("scalar_mul_g1", |b| b.iter(|| scalar_mul_g1(bb(scalar))));
 }
 
 pub fn bench_scalar_mul_g2(c: &mut Criterion) {
-    let scalar = random_field_element();
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scalar = random_field_element(&mut rng);
 
     c.bench_function("scalar_mul_g2", |b| b.iter(|| scalar_mul_g2(bb(scalar))));
 }
 
 pub fn bench_pairing(c: &mut Criterion) {
-    let a = random_field_element();
-    let b = random_field_element();
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let a = random_field_element(&mut rng);
+    let b = random_field_element(&mut rng);
     let g1 = scalar_mul_g1(a);
     let g2 = scalar_mul_g2(b);
 
@@ -26,11 +33,12 @@ pub fn bench_pairing(c: &mut Criterion) {
 }
 
 pub fn bench_multi_pairing(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let length = 5;
     let mut pairs = Vec::with_capacity(length);
     for _ in 0..length {
-        let a = random_field_element();
-        let b = random_field_element();
+        let a = random_field_element(&mut rng);
+        let b = random_field_element(&mut rng);
         let g1 = scalar_mul_g1(a);
         let g2 = scalar_mul_g2(b);
 
@@ -43,39 +51,45 @@ pub fn bench_multi_pairing(c: &mut Criterion) {
 }
 
 pub fn bench_random_field_element(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+
     c.bench_function("random_field_element", |b| {
-        b.iter(|| random_field_element())
+        b.iter(|| random_field_element(&mut rng))
     });
 }
 
 pub fn bench_random_vector(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let length = 100;
 
     c.bench_function("random_vector (100)", |b| {
-        b.iter(|| random_vector(bb(length)))
+        b.iter(|| random_vector(&mut rng, bb(length)))
     });
 }
 
 pub fn bench_random_matrix(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 50;
 
     c.bench_function("random_matrix (50)", |b| {
-        b.iter(|| random_matrix(bb(size), bb(size)))
+        b.iter(|| random_matrix(&mut rng, bb(size), bb(size)))
     });
 }
 
 pub fn bench_vector_add(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 50;
-    let v = random_vector(size);
-    let w = random_vector(size);
+    let v = random_vector(&mut rng, size);
+    let w = random_vector(&mut rng, size);
 
     c.bench_function("vector_add (50)", |b| b.iter(|| vector_add(bb(&v), bb(&w))));
 }
 
 pub fn bench_vector_scalar_mul(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 50;
-    let scalar = random_field_element();
-    let v = random_vector(size);
+    let scalar = random_field_element(&mut rng);
+    let v = random_vector(&mut rng, size);
 
     c.bench_function("vector_scalar_mul (50)", |b| {
         b.iter(|| scalar_vector_mul(bb(scalar), bb(&v)))
@@ -83,9 +97,10 @@ pub fn bench_vector_scalar_mul(c: &mut Criterion) {
 }
 
 pub fn bench_vector_concat(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 100;
-    let v = random_vector(size);
-    let w = random_vector(size);
+    let v = random_vector(&mut rng, size);
+    let w = random_vector(&mut rng, size);
 
     c.bench_function("vector_concat (100)", |b| {
         b.iter(|| vector_concat(bb(&v), bb(&w)))
@@ -93,9 +108,10 @@ pub fn bench_vector_concat(c: &mut Criterion) {
 }
 
 pub fn bench_matrix_vector_mul(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 50;
-    let v = random_vector(size);
-    let m = random_matrix(size, size);
+    let v = random_vector(&mut rng, size);
+    let m = random_matrix(&mut rng, size, size);
 
     c.bench_function("matrix_vector_mul (50)", |b| {
         b.iter(|| matrix_vector_mul(bb(&m), bb(&v)))
@@ -103,9 +119,10 @@ pub fn bench_matrix_vector_mul(c: &mut Criterion) {
 }
 
 pub fn bench_matrix_mul(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 50;
-    let m = random_matrix(size, size);
-    let n = random_matrix(size, size);
+    let m = random_matrix(&mut rng, size, size);
+    let n = random_matrix(&mut rng, size, size);
 
     c.bench_function("matrix_mul (50)", |b| {
         b.iter(|| matrix_multiply(bb(&m), bb(&n)))
@@ -113,9 +130,10 @@ pub fn bench_matrix_mul(c: &mut Criterion) {
 }
 
 pub fn bench_matrix_concat(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 50;
-    let m = random_matrix(size, size);
-    let n = random_matrix(size, size);
+    let m = random_matrix(&mut rng, size, size);
+    let n = random_matrix(&mut rng, size, size);
 
     c.bench_function("matrix_concat (50)", |b| {
         b.iter(|| matrix_concat(bb(&m), bb(&n)))
@@ -123,8 +141,9 @@ pub fn bench_matrix_concat(c: &mut Criterion) {
 }
 
 pub fn bench_matrix_transpose(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 50;
-    let m = random_matrix(size, size);
+    let m = random_matrix(&mut rng, size, size);
 
     c.bench_function("matrix_transpose (50)", |b| {
         b.iter(|| matrix_transpose(bb(&m)))
@@ -132,10 +151,11 @@ pub fn bench_matrix_transpose(c: &mut Criterion) {
 }
 
 pub fn bench_group_matrix_vector_mul_msm(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 20;
-    let m = random_matrix(size, size);
+    let m = random_matrix(&mut rng, size, size);
     let m_g = matrix_lift_g1(&m);
-    let v = random_vector(size);
+    let v = random_vector(&mut rng, size);
 
     c.bench_function("group_matrix_vector_mul_msm (20)", |b| {
         b.iter(|| group_matrix_vector_mul_msm(bb(&m_g), bb(&v)))
@@ -143,10 +163,11 @@ pub fn bench_group_matrix_vector_mul_msm(c: &mut Criterion) {
 }
 
 pub fn bench_matrix_field_multiply(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 20;
-    let m = random_matrix(size, size);
+    let m = random_matrix(&mut rng, size, size);
     let m_g = matrix_lift_g1(&m);
-    let n = random_matrix(size, size);
+    let n = random_matrix(&mut rng, size, size);
 
     c.bench_function("matrix_field_multiply (20)", |b| {
         b.iter(|| g1_matrix_field_multiply(bb(&m_g), bb(&n)))
@@ -154,8 +175,9 @@ pub fn bench_matrix_field_multiply(c: &mut Criterion) {
 }
 
 pub fn bench_g1_matrix_transpose(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 20;
-    let m = random_matrix(size, size);
+    let m = random_matrix(&mut rng, size, size);
     let m_g = matrix_lift_g1(&m);
 
     c.bench_function("g1_matrix_transpose", |b| {
@@ -164,8 +186,9 @@ pub fn bench_g1_matrix_transpose(c: &mut Criterion) {
 }
 
 pub fn bench_g2_matrix_transpose(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let size = 20;
-    let m = random_matrix(size, size);
+    let m = random_matrix(&mut rng, size, size);
     let m_g = matrix_lift_g2(&m);
 
     c.bench_function("g2_matrix_transpose", |b| {
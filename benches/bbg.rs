@@ -0,0 +1,41 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use ibe_schemes::pes::bbg::*;
+
+mod common;
+use common::*;
+
+const DEPTHS: [usize; 9] = [1, 2, 5, 10, 15, 20, 50, 100, 250];
+const ID_SIZE: usize = 5;
+// one level more than the deepest keys, so that these can still be delegated
+const MAX_DEPTH: usize = DEPTHS[DEPTHS.len() - 1] + 1;
+
+pub fn bench_bbg_setup(c: &mut Criterion) {
+    bench_hibe_scheme_setup(BBG::new(MAX_DEPTH), c);
+}
+
+pub fn bench_bbg_keygen(c: &mut Criterion) {
+    bench_hibe_scheme_keygen(BBG::new(MAX_DEPTH), &DEPTHS, ID_SIZE, c);
+}
+
+pub fn bench_bbg_encrypt(c: &mut Criterion) {
+    bench_hibe_scheme_encrypt(BBG::new(MAX_DEPTH), &DEPTHS, ID_SIZE, c);
+}
+
+pub fn bench_bbg_decrypt(c: &mut Criterion) {
+    bench_hibe_scheme_decrypt(BBG::new(MAX_DEPTH), &DEPTHS, ID_SIZE, c);
+}
+
+pub fn bench_bbg_delegate(c: &mut Criterion) {
+    bench_hibe_scheme_delegate(BBG::new(MAX_DEPTH), &DEPTHS, ID_SIZE, c);
+}
+
+criterion_group!(
+    benches,
+    bench_bbg_setup,
+    bench_bbg_keygen,
+    bench_bbg_encrypt,
+    bench_bbg_decrypt,
+    bench_bbg_delegate
+);
+criterion_main!(benches);
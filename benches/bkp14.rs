@@ -0,0 +1,78 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use std::hint::black_box as bb;
+
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+pub fn bench_bkp14_new(c: &mut Criterion) {
+    let k = 2;
+    let msg_len = 128;
+
+    c.bench_function("bkp14_new (128)", |b| {
+        b.iter(|| BKP14::with_mac(AffineMacNr::new(bb(k), bb(msg_len))))
+    });
+}
+
+pub fn bench_bkp14_setup(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let k = 2;
+    let msg_len = 128;
+    let ibkem = BKP14::with_mac(AffineMacNr::new(k, msg_len));
+
+    c.bench_function("bkp14_setup (128)", |b| b.iter(|| ibkem.setup(&mut rng)));
+}
+
+pub fn bench_bkp14_extract(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let k = 2;
+    let msg_len = 128;
+    let ibkem = BKP14::with_mac(AffineMacNr::new(k, msg_len));
+    let (_, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    c.bench_function("bkp14_extract (128)", |b| {
+        b.iter(|| ibkem.extract(&mut rng, bb(&sk), bb(&identity)))
+    });
+}
+
+pub fn bench_bkp14_encrypt(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let k = 2;
+    let msg_len = 128;
+    let ibkem = BKP14::with_mac(AffineMacNr::new(k, msg_len));
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    c.bench_function("bkp14_encrypt (128)", |b| {
+        b.iter(|| ibkem.encrypt(&mut rng, bb(&pk), bb(&identity)))
+    });
+}
+
+pub fn bench_bkp14_decrypt(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let k = 2;
+    let msg_len = 128;
+    let ibkem = BKP14::with_mac(AffineMacNr::new(k, msg_len));
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+
+    c.bench_function("bkp14_decrypt (128)", |b| {
+        b.iter(|| ibkem.decrypt(bb(&usk), bb(&ct)))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_bkp14_new,
+    bench_bkp14_setup,
+    bench_bkp14_extract,
+    bench_bkp14_encrypt,
+    bench_bkp14_decrypt
+);
+criterion_main!(benches);
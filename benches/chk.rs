@@ -0,0 +1,84 @@
+use ark_bls12_381::Fq12 as Gt;
+use ark_ff::UniformRand;
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use std::hint::black_box as bb;
+
+use ibe_schemes::pes::HIBEScheme;
+use ibe_schemes::pes::chk::Chk;
+use ibe_schemes::pes::lw::LW;
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+// CHK-from-LW against plain (CPA) LW at the same depth and against IBKEM2, whose CCA
+// security comes from the QANIZK proof instead of a one-time signature
+
+fn lw_identity() -> Vec<String> {
+    vec![String::from("chk"), String::from("0123456789abcdef")]
+}
+
+pub fn bench_cca_encrypt(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mut group = c.benchmark_group("cca_encrypt");
+
+    let lw = LW::new();
+    let (_, mpk) = lw.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+    group.bench_function("lw (cpa, depth 2)", |b| {
+        b.iter(|| lw.encrypt(&mut rng, bb(&msg), bb(&mpk), lw_identity()))
+    });
+
+    let chk = Chk::new(LW::new());
+    let (pk, _) = chk.keygen(&mut rng).unwrap();
+    group.bench_function("chk_lw", |b| {
+        b.iter(|| chk.encrypt(&mut rng, bb(&pk), bb(&msg)))
+    });
+
+    let ibkem = IBKEM2::new(2, 128, 128);
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    group.bench_function("ibkem2 (128)", |b| {
+        b.iter(|| ibkem.encrypt(&mut rng, bb(&pk), bb(&identity)))
+    });
+
+    group.finish();
+}
+
+pub fn bench_cca_decrypt(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mut group = c.benchmark_group("cca_decrypt");
+
+    let lw = LW::new();
+    let (msk, mpk) = lw.setup(&mut rng);
+    let usk = lw.keygen(&mut rng, &msk, lw_identity()).unwrap();
+    let msg = Gt::rand(&mut rng);
+    let ct = lw.encrypt(&mut rng, &msg, &mpk, lw_identity()).unwrap();
+    group.bench_function("lw (cpa, depth 2)", |b| {
+        b.iter(|| lw.decrypt(bb(&usk), bb(&ct)))
+    });
+
+    // includes verifying the signature and delegating to the ciphertext's identity
+    let chk = Chk::new(LW::new());
+    let (pk, sk) = chk.keygen(&mut rng).unwrap();
+    let ct = chk.encrypt(&mut rng, &pk, &msg).unwrap();
+    group.bench_function("chk_lw", |b| {
+        b.iter(|| chk.decrypt(&mut rng, bb(&sk), bb(&ct)))
+    });
+
+    let ibkem = IBKEM2::new(2, 128, 128);
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    group.bench_function("ibkem2 (128)", |b| {
+        b.iter(|| ibkem.decrypt(bb(&pk), bb(&usk), bb(&identity), bb(&ct)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_cca_encrypt, bench_cca_decrypt);
+criterion_main!(benches);
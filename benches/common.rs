@@ -100,13 +100,13 @@ pub fn bench_ibe_scheme_decrypt<T: IBEScheme>(scheme: T, sizes: &[usize], c: &mu
                 let ids = (0..n).map(|_| rand_string(size)).collect::<Vec<_>>();
                 let usks = ids
                     .iter()
-                    .map(|i| scheme.keygen(&mut rng, &msk, i.clone()))
+                    .map(|i| scheme.keygen(&mut rng, &msk, i.clone()).unwrap())
                     .collect::<Vec<_>>();
                 let ks = ids.iter().map(|_| Gt::rand(&mut rng)).collect::<Vec<_>>();
                 let cts = ids
                     .iter()
                     .zip(ks)
-                    .map(|(i, k)| scheme.encrypt(&mut rng, &k, &mpk, i.clone()))
+                    .map(|(i, k)| scheme.encrypt(&mut rng, &k, &mpk, i.clone()).unwrap())
                     .collect::<Vec<_>>();
 
                 let start = Instant::now();
@@ -212,13 +212,13 @@ pub fn bench_hibe_scheme_decrypt<T: HIBEScheme>(
                 let ids = (0..n).map(|_| rand_hierarchy(d, size)).collect::<Vec<_>>();
                 let usks = ids
                     .iter()
-                    .map(|i| scheme.keygen(&mut rng, &msk, i.clone()))
+                    .map(|i| scheme.keygen(&mut rng, &msk, i.clone()).unwrap())
                     .collect::<Vec<_>>();
                 let ks = ids.iter().map(|_| Gt::rand(&mut rng)).collect::<Vec<_>>();
                 let cts = ids
                     .iter()
                     .zip(ks)
-                    .map(|(i, k)| scheme.encrypt(&mut rng, &k, &mpk, i.clone()))
+                    .map(|(i, k)| scheme.encrypt(&mut rng, &k, &mpk, i.clone()).unwrap())
                     .collect::<Vec<_>>();
 
                 let start = Instant::now();
@@ -254,7 +254,7 @@ pub fn bench_hibe_scheme_delegate<T: HIBEScheme>(
                 let ids = (0..n).map(|_| rand_hierarchy(d, size)).collect::<Vec<_>>();
                 let usks = ids
                     .iter()
-                    .map(|i| scheme.keygen(&mut rng, &msk, i.clone()))
+                    .map(|i| scheme.keygen(&mut rng, &msk, i.clone()).unwrap())
                     .collect::<Vec<_>>();
                 let extensions = (0..n).map(|_| rand_string(size)).collect::<Vec<_>>();
 
@@ -262,7 +262,7 @@ pub fn bench_hibe_scheme_delegate<T: HIBEScheme>(
                 let res = usks
                     .iter()
                     .zip(extensions)
-                    .map(|(usk, ext)| scheme.delegate(&mut rng, &mpk, &usk, ext))
+                    .map(|(usk, ext)| scheme.delegate(&mut rng, &mpk, &usk, ext).unwrap())
                     .collect::<Vec<_>>();
                 let time = start.elapsed();
                 let _ = blb(res);
@@ -0,0 +1,34 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use ibe_schemes::pes::cw13::*;
+
+mod common;
+use common::*;
+
+// Performance of CW13 should be independent of identity length
+const SIZES: [usize; 1] = [5];
+
+pub fn bench_cw13_setup(c: &mut Criterion) {
+    bench_ibe_scheme_setup(CW13::new(), c);
+}
+
+pub fn bench_cw13_keygen(c: &mut Criterion) {
+    bench_ibe_scheme_keygen(CW13::new(), &SIZES, c);
+}
+
+pub fn bench_cw13_encrypt(c: &mut Criterion) {
+    bench_ibe_scheme_encrypt(CW13::new(), &SIZES, c);
+}
+
+pub fn bench_cw13_decrypt(c: &mut Criterion) {
+    bench_ibe_scheme_decrypt(CW13::new(), &SIZES, c);
+}
+
+criterion_group!(
+    benches,
+    bench_cw13_setup,
+    bench_cw13_keygen,
+    bench_cw13_encrypt,
+    bench_cw13_decrypt,
+);
+criterion_main!(benches);
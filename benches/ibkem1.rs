@@ -3,6 +3,10 @@ use criterion::{Criterion, criterion_group, criterion_main};
 use std::hint::black_box as bb;
 
 use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
 
 pub fn bench_ibkem1_new(c: &mut Criterion) {
     let k = 2;
@@ -14,45 +18,49 @@ pub fn bench_ibkem1_new(c: &mut Criterion) {
 }
 
 pub fn bench_ibkem1_setup(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let ibkem = IBKEM1::new(k, msg_len);
 
-    c.bench_function("ibkem1_setup (128)", |b| b.iter(|| ibkem.setup()));
+    c.bench_function("ibkem1_setup (128)", |b| b.iter(|| ibkem.setup(&mut rng)));
 }
 
 pub fn bench_ibkem1_extract(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let ibkem = IBKEM1::new(k, msg_len);
-    let (_, sk) = ibkem.setup();
+    let (_, sk) = ibkem.setup(&mut rng).unwrap();
     let (_, identity) = generate_email_and_hash_identity(128);
 
     c.bench_function("ibkem1_extract (128)", |b| {
-        b.iter(|| ibkem.extract(bb(&sk), bb(&identity)))
+        b.iter(|| ibkem.extract(&mut rng, bb(&sk), bb(&identity)))
     });
 }
 
 pub fn bench_ibkem1_encrypt(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let ibkem = IBKEM1::new(k, msg_len);
-    let (pk, _) = ibkem.setup();
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
     let (_, identity) = generate_email_and_hash_identity(128);
 
     c.bench_function("ibkem1_encrypt (128)", |b| {
-        b.iter(|| ibkem.encrypt(bb(&pk), bb(&identity)))
+        b.iter(|| ibkem.encrypt(&mut rng, bb(&pk), bb(&identity)))
     });
 }
 
 pub fn bench_ibkem1_decrypt(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let ibkem = IBKEM1::new(k, msg_len);
-    let (pk, sk) = ibkem.setup();
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
     let (_, identity) = generate_email_and_hash_identity(128);
-    let usk = ibkem.extract(&sk, &identity);
-    let (ct, _) = ibkem.encrypt(&pk, &identity);
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
 
     c.bench_function("ibkem1_decrypt (128)", |b| {
         b.iter(|| ibkem.decrypt(bb(&usk), bb(&ct)))
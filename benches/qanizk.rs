@@ -4,6 +4,10 @@ use std::hint::black_box as bb;
 
 use ark_bls12_381::G1Projective as G1;
 use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
 
 pub fn bench_qanizk_new(c: &mut Criterion) {
     let k = 2;
@@ -15,48 +19,51 @@ pub fn bench_qanizk_new(c: &mut Criterion) {
 }
 
 pub fn bench_qanizk_gen_crs(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let lambda = 128;
     let qanizk = QANIZK::new(k, lambda);
-    let m_matrix = random_matrix(3 * k, k);
+    let m_matrix = random_matrix(&mut rng, 3 * k, k);
     let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
 
     c.bench_function("qanizk_gen_crs (128)", |b| {
-        b.iter(|| qanizk.gen_crs(bb(&m_g1_matrix)))
+        b.iter(|| qanizk.gen_crs(&mut rng, bb(&m_g1_matrix)))
     });
 }
 
 pub fn bench_qanizk_prove(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let lambda = 128;
     let qanizk = QANIZK::new(k, lambda);
-    let m_matrix = random_matrix(3 * k, k);
+    let m_matrix = random_matrix(&mut rng, 3 * k, k);
     let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
-    let (crs, _) = qanizk.gen_crs(&m_g1_matrix);
+    let (crs, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
 
     let tag = generate_random_message_128();
-    let r = random_vector(k);
+    let r = random_vector(&mut rng, k);
     let c0_field = matrix_vector_mul(&m_matrix, &r);
     let c0_g1: Vec<G1> = vector_lift_g1(&c0_field);
 
     c.bench_function("qanizk_prove (128)", |b| {
-        b.iter(|| qanizk.prove(bb(&crs), bb(&tag), bb(&c0_g1), bb(&r)))
+        b.iter(|| qanizk.prove(&mut rng, bb(&crs), bb(&tag), bb(&c0_g1), bb(&r)))
     });
 }
 
 pub fn bench_qanizk_verify(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let lambda = 128;
     let qanizk = QANIZK::new(k, lambda);
-    let m_matrix = random_matrix(3 * k, k);
+    let m_matrix = random_matrix(&mut rng, 3 * k, k);
     let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
-    let (crs, _) = qanizk.gen_crs(&m_g1_matrix);
+    let (crs, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
 
     let tag = generate_random_message_128();
-    let r = random_vector(k);
+    let r = random_vector(&mut rng, k);
     let c0_field = matrix_vector_mul(&m_matrix, &r);
     let c0_g1: Vec<G1> = vector_lift_g1(&c0_field);
-    let pi = qanizk.prove(&crs, &tag, &c0_g1, &r);
+    let pi = qanizk.prove(&mut rng, &crs, &tag, &c0_g1, &r).unwrap();
 
     c.bench_function("qanizk_verify (128)", |b| {
         b.iter(|| qanizk.verify(bb(&crs), bb(&tag), bb(&c0_g1), bb(&pi)))
@@ -0,0 +1,67 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use std::hint::black_box as bb;
+
+use ibe_schemes::pes::sakai_kasahara::*;
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+mod common;
+use common::*;
+
+const SEED: u64 = 42;
+
+// Performance of SK should be independent of identity length
+const SIZES: [usize; 1] = [5];
+
+pub fn bench_sakai_kasahara_setup(c: &mut Criterion) {
+    bench_ibe_scheme_setup(SakaiKasahara::new(), c);
+}
+
+pub fn bench_sakai_kasahara_keygen(c: &mut Criterion) {
+    bench_ibe_scheme_keygen(SakaiKasahara::new(), &SIZES, c);
+}
+
+pub fn bench_sakai_kasahara_encrypt(c: &mut Criterion) {
+    bench_ibe_scheme_encrypt(SakaiKasahara::new(), &SIZES, c);
+}
+
+pub fn bench_sakai_kasahara_decrypt(c: &mut Criterion) {
+    bench_ibe_scheme_decrypt(SakaiKasahara::new(), &SIZES, c);
+}
+
+pub fn bench_sakai_kasahara_kem_encrypt(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = SakaiKasaharaKem::new();
+    let (pk, _) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    c.bench_function("sakai_kasahara_kem_encrypt (128)", |b| {
+        b.iter(|| IBKEM::encrypt(&kem, &mut rng, bb(&pk), bb(&identity)))
+    });
+}
+
+pub fn bench_sakai_kasahara_kem_decrypt(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = SakaiKasaharaKem::new();
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, &identity).unwrap();
+    let (ct, _) = IBKEM::encrypt(&kem, &mut rng, &pk, &identity).unwrap();
+
+    c.bench_function("sakai_kasahara_kem_decrypt (128)", |b| {
+        b.iter(|| IBKEM::decrypt(&kem, bb(&pk), bb(&usk), bb(&identity), bb(&ct)))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_sakai_kasahara_setup,
+    bench_sakai_kasahara_keygen,
+    bench_sakai_kasahara_encrypt,
+    bench_sakai_kasahara_decrypt,
+    bench_sakai_kasahara_kem_encrypt,
+    bench_sakai_kasahara_kem_decrypt,
+);
+criterion_main!(benches);
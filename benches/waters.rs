@@ -0,0 +1,35 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use ibe_schemes::pes::waters::*;
+
+mod common;
+use common::*;
+
+// Identities are hashed to N bits, so their length does not matter
+const N: usize = 128;
+const SIZES: [usize; 1] = [5];
+
+pub fn bench_waters_setup(c: &mut Criterion) {
+    bench_ibe_scheme_setup(Waters::new(N), c);
+}
+
+pub fn bench_waters_keygen(c: &mut Criterion) {
+    bench_ibe_scheme_keygen(Waters::new(N), &SIZES, c);
+}
+
+pub fn bench_waters_encrypt(c: &mut Criterion) {
+    bench_ibe_scheme_encrypt(Waters::new(N), &SIZES, c);
+}
+
+pub fn bench_waters_decrypt(c: &mut Criterion) {
+    bench_ibe_scheme_decrypt(Waters::new(N), &SIZES, c);
+}
+
+criterion_group!(
+    benches,
+    bench_waters_setup,
+    bench_waters_keygen,
+    bench_waters_encrypt,
+    bench_waters_decrypt,
+);
+criterion_main!(benches);
@@ -1,8 +1,16 @@
 use crate::bit_utils::bit_at;
+use crate::error::{IbeError, check_dim, check_identity_len};
 use crate::field_utils::*;
+use crate::serialization::*;
 use crate::types::*;
 
 use ark_bls12_381::G2Projective as G2;
+use ark_ff::{One, Zero};
+use ark_std::ops::{Add, Mul};
+use ark_serialize::{CanonicalSerialize, Compress, SerializationError, Validate};
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+use std::io::{Read, Write};
 
 pub struct SecretKey {
     pub b: Matrix<FieldElement>,
@@ -12,10 +20,240 @@ pub struct SecretKey {
     pub x_prime: Vec<Vector>,
 }
 
+/// A tag `([t]_2, [u]_2)`. It holds group elements only and may be handed out freely.
 pub struct Tag {
     pub t_g2: Vec<G2>,
     pub u_g2: Vec<G2>,
-    pub t_field: Vector,
+}
+
+/// The discrete logarithm `t` behind a [`Tag`]. The PKG needs it to derive user keys, but it is
+/// secret randomness, so it is opaque outside the crate and cannot be serialized or printed.
+pub struct TagContext {
+    t_field: Vector,
+}
+
+impl TagContext {
+    pub(crate) fn new(t_field: Vector) -> Self {
+        Self { t_field }
+    }
+
+    pub(crate) fn t_field(&self) -> &Vector {
+        &self.t_field
+    }
+}
+
+/// An affine MAC over `Z_p^n` (BKP14, HJP18): a tag is `t = B s` together with
+/// `u = sum_{i < l} f_i(m) x_i t + sum_{j <= l'} f'_j(m) x'_j`, where `x_i` is `eta x n` and
+/// `x'_j` has length `eta`.
+///
+/// The IBKEM transforms only need the affine functions and these shapes: they hide every `x_i`
+/// and `x'_j` behind fresh `y_i`, `y'_j` and evaluate the same functions on identities.
+pub trait AffineMac {
+    type SecretKey;
+
+    /// `k` of the underlying MDDH assumption, the length of `s`.
+    fn k(&self) -> usize;
+
+    /// Message length in bits.
+    fn msg_len(&self) -> usize;
+
+    /// Length `n` of `t`.
+    fn n(&self) -> usize;
+
+    /// Length `eta` of `u`.
+    fn eta(&self) -> usize;
+
+    /// Number `l` of the functions `f_i`.
+    fn l(&self) -> usize;
+
+    /// Index of the last function `f'_j`, so there are `l' + 1` of them.
+    fn l_prime(&self) -> usize;
+
+    /// `f_0(m), ..., f_{l-1}(m)`.
+    fn f(&self, message: &[u8]) -> Vector;
+
+    /// `f'_0(m), ..., f'_{l'}(m)`.
+    fn f_prime(&self, message: &[u8]) -> Vector;
+
+    /// `x_0, ..., x_{l-1}`.
+    fn x_matrices<'a>(&self, sk: &'a Self::SecretKey) -> &'a [Matrix<FieldElement>];
+
+    /// `x'_0, ..., x'_{l'}`.
+    fn x_prime<'a>(&self, sk: &'a Self::SecretKey) -> &'a [Vector];
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Self::SecretKey;
+
+    /// A tag on `message` together with its context, for deriving user keys from the tag.
+    fn tag_with_context<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &Self::SecretKey,
+        message: &[u8],
+    ) -> Result<(Tag, TagContext), IbeError>;
+
+    fn tag<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &Self::SecretKey,
+        message: &[u8],
+    ) -> Result<Tag, IbeError> {
+        self.tag_with_context(rng, sk, message).map(|(tag, _)| tag)
+    }
+
+    fn verify(&self, sk: &Self::SecretKey, message: &[u8], tag: &Tag) -> Result<(), IbeError>;
+}
+
+/// A hierarchical affine MAC over identities `m = (m_1, ..., m_p)` of up to `max_levels` levels of
+/// `identity_len` bits each (BKP14, section 5): a tag is `t_i = B s_i` together with
+/// `u = sum_{i <= p} sum_{j < l_i} f_{i,j}(m) x_{i,j} t_i + x'`, where `x_{i,j}` is `eta x n` and
+/// `x'` has length `eta`. A MAC with a single `t` for all levels has `t_i = t`.
+///
+/// The HIBKEM transforms hide every `x_{i,j}` and `x'` behind fresh `y_{i,j}`, `y'`, delegate
+/// through `[x_{i,j} B]_2` and `[y_{i,j} B]_2`, and evaluate the functions of each level on
+/// identities. They store the keys of a level in pairs `(x_{i,2j}, x_{i,2j+1})` of a binary
+/// choice, so `l_i` has to be `2 * i * identity_len`.
+pub trait LevelAffineMac {
+    type SecretKey;
+    type Tag;
+    type TagContext;
+
+    /// `k` of the underlying MDDH assumption, the length of every `s_i`.
+    fn k(&self) -> usize;
+
+    fn max_levels(&self) -> usize;
+
+    /// Length in bits of each level of an identity.
+    fn identity_len(&self) -> usize;
+
+    /// Length `n` of every `t_i`.
+    fn n(&self) -> usize;
+
+    /// Length `eta` of `u`.
+    fn eta(&self) -> usize;
+
+    /// Number `l_i` of the functions `f_{i,j}` of level `i`, counting levels from 1.
+    fn l(&self, level: usize) -> usize;
+
+    /// `f_{i,0}(m), ..., f_{i,l_i - 1}(m)` of level `i <= m.len()`.
+    fn f(&self, level: usize, message: &[Vec<u8>]) -> Vector;
+
+    fn b<'a>(&self, sk: &'a Self::SecretKey) -> &'a Matrix<FieldElement>;
+
+    /// `x_{i,0}, ..., x_{i,l_i - 1}` of level `i`, in pairs.
+    fn x_matrices<'a>(
+        &self,
+        sk: &'a Self::SecretKey,
+        level: usize,
+    ) -> &'a [Vec<Matrix<FieldElement>>];
+
+    fn x_prime<'a>(&self, sk: &'a Self::SecretKey) -> &'a Vector;
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Self::SecretKey;
+
+    /// A tag on `message` together with its context, for deriving user keys from the tag.
+    fn tag_with_context<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &Self::SecretKey,
+        message: &[Vec<u8>],
+    ) -> Result<(Self::Tag, Self::TagContext), IbeError>;
+
+    fn tag<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &Self::SecretKey,
+        message: &[Vec<u8>],
+    ) -> Result<Self::Tag, IbeError> {
+        self.tag_with_context(rng, sk, message).map(|(tag, _)| tag)
+    }
+
+    fn verify(
+        &self,
+        sk: &Self::SecretKey,
+        message: &[Vec<u8>],
+        tag: &Self::Tag,
+    ) -> Result<(), IbeError>;
+}
+
+/// `sum_{j < l_i} f_{i,j}(m) * levels[i - 1][j]` for level `i` of `m`, with `levels` stored in
+/// pairs like the keys of a [`LevelAffineMac`].
+pub(crate) fn level_affine_combination<M: LevelAffineMac, T>(
+    mac: &M,
+    level: usize,
+    message: &[Vec<u8>],
+    levels: &[Vec<Vec<Matrix<T>>>],
+    rows: usize,
+    cols: usize,
+) -> Result<Matrix<T>, IbeError>
+where
+    T: Zero + Copy + Add + Mul<FieldElement, Output = T>,
+{
+    let pairs = levels.get(level - 1).ok_or(IbeError::DimensionMismatch {
+        expected: level,
+        found: levels.len(),
+    })?;
+    matrix_affine_combination(&mac.f(level, message), pairs.iter().flatten(), rows, cols)
+}
+
+/// `x_m = sum_{i < l} f_i(m) x_i`, the part of `u` that multiplies `t`.
+pub(crate) fn x_m<M: AffineMac>(
+    mac: &M,
+    sk: &M::SecretKey,
+    message: &[u8],
+) -> Result<Matrix<FieldElement>, IbeError> {
+    matrix_affine_combination(&mac.f(message), mac.x_matrices(sk), mac.eta(), mac.n())
+}
+
+/// `sum_{j <= l'} f'_j(m) x'_j`, the part of `u` that does not depend on `t`.
+pub(crate) fn x_prime_m<M: AffineMac>(
+    mac: &M,
+    sk: &M::SecretKey,
+    message: &[u8],
+) -> Result<Vector, IbeError> {
+    vector_affine_combination(&mac.f_prime(message), mac.x_prime(sk), mac.eta())
+}
+
+/// Checks `[u]_2` of a tag against the recomputed `expected`. The comparison runs in constant
+/// time, so timing does not reveal how much of a forged tag is correct.
+pub(crate) fn check_tag_u(expected: &[G2], u_g2: &[G2]) -> Result<(), IbeError> {
+    check_dim(expected.len(), u_g2.len())?;
+
+    let (mut expected_bytes, mut u_bytes) = (Vec::new(), Vec::new());
+    let encoding = "writing to a Vec does not fail";
+    expected.serialize_compressed(&mut expected_bytes).expect(encoding);
+    u_g2.serialize_compressed(&mut u_bytes).expect(encoding);
+
+    if bool::from(expected_bytes.ct_eq(&u_bytes)) {
+        Ok(())
+    } else {
+        Err(IbeError::InvalidTag)
+    }
+}
+
+/// `f_{2i+b}(m) = [m_i = b]`: one function per message bit and value, selecting `x_{i,m_i}`.
+pub(crate) fn bit_selectors(message: &[u8], msg_len: usize) -> Vector {
+    let mut f = Vec::with_capacity(2 * msg_len);
+    for i in 0..msg_len {
+        let b = bit_at(i, message);
+        for value in 0..2 {
+            f.push(if b == value {
+                FieldElement::one()
+            } else {
+                FieldElement::zero()
+            });
+        }
+    }
+    f
+}
+
+/// `f_{i,2j+b}(m) = [bit j of m_1 || ... || m_i = b]`: the bit selectors of the first `level`
+/// levels of `m`, which pick one of each pair `(x_{i,2j}, x_{i,2j+1})`.
+pub(crate) fn level_bit_selectors(
+    message: &[Vec<u8>],
+    level: usize,
+    identity_len: usize,
+) -> Vector {
+    bit_selectors(&message[..level].concat(), level * identity_len)
 }
 
 pub struct AffineMAC {
@@ -28,14 +266,59 @@ impl AffineMAC {
         assert_eq!(msg_len % 8, 0);
         Self { k, msg_len }
     }
+}
+
+impl AffineMac for AffineMAC {
+    type SecretKey = SecretKey;
+
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    fn msg_len(&self) -> usize {
+        self.msg_len
+    }
+
+    fn n(&self) -> usize {
+        self.k
+    }
+
+    fn eta(&self) -> usize {
+        2 * self.k
+    }
+
+    fn l(&self) -> usize {
+        2 * self.msg_len
+    }
+
+    fn l_prime(&self) -> usize {
+        0
+    }
 
-    pub fn gen_mac(&self) -> SecretKey {
-        let b = random_matrix(self.k, self.k);
+    fn f(&self, message: &[u8]) -> Vector {
+        bit_selectors(message, self.msg_len)
+    }
+
+    /// The single constant function `f'_0(m) = 1`.
+    fn f_prime(&self, _message: &[u8]) -> Vector {
+        vec![FieldElement::one()]
+    }
+
+    fn x_matrices<'a>(&self, sk: &'a SecretKey) -> &'a [Matrix<FieldElement>] {
+        &sk.x_matrices
+    }
+
+    fn x_prime<'a>(&self, sk: &'a SecretKey) -> &'a [Vector] {
+        &sk.x_prime
+    }
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> SecretKey {
+        let b = random_matrix(rng, self.k, self.k);
         let mut x_matrices = Vec::with_capacity(2 * self.msg_len);
         for _ in 0..2 * self.msg_len {
-            x_matrices.push(random_matrix(2 * self.k, self.k));
+            x_matrices.push(random_matrix(rng, 2 * self.k, self.k));
         }
-        let x_prime = vec![random_vector(2 * self.k)];
+        let x_prime = vec![random_vector(rng, 2 * self.k)];
         SecretKey {
             b,
             x_matrices,
@@ -43,47 +326,86 @@ impl AffineMAC {
         }
     }
 
-    pub fn tag(&self, sk: &SecretKey, message: &[u8]) -> Tag {
-        assert_eq!(message.len() * 8, self.msg_len);
+    fn tag_with_context<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &SecretKey,
+        message: &[u8],
+    ) -> Result<(Tag, TagContext), IbeError> {
+        check_identity_len(message, self.msg_len)?;
 
-        let s = random_vector(self.k);
+        let s = random_vector(rng, self.k);
         let t_field = matrix_vector_mul(&sk.b, &s);
 
-        let mut x_m = matrix_zero(2 * self.k, self.k);
-        for i in 0..self.msg_len {
-            let b = bit_at(i, message);
-            let x_i = &sk.x_matrices[2 * i + b];
-            x_m = matrix_add(&x_m, x_i);
-        }
-
-        let mut u_field = matrix_vector_mul(&x_m, &t_field);
-        u_field = vector_add(&u_field, &sk.x_prime[0]);
+        let x_m = x_m(self, sk, message)?;
+        let x_prime = x_prime_m(self, sk, message)?;
+        let u_field = vector_add(&matrix_vector_mul(&x_m, &t_field), &x_prime);
 
         let t_g2: Vec<G2> = vector_lift_g2(&t_field);
         let u_g2: Vec<G2> = vector_lift_g2(&u_field);
 
-        Tag {
-            t_g2,
-            u_g2,
-            t_field,
-        }
+        Ok((Tag { t_g2, u_g2 }, TagContext::new(t_field)))
     }
 
-    pub fn verify(&self, sk: &SecretKey, message: &[u8], tag: &Tag) -> bool {
-        assert_eq!(message.len() * 8, self.msg_len);
-        assert_eq!(tag.u_g2.len(), 2 * self.k);
+    fn verify(&self, sk: &SecretKey, message: &[u8], tag: &Tag) -> Result<(), IbeError> {
+        check_identity_len(message, self.msg_len)?;
+        check_dim(self.k, tag.t_g2.len())?;
+
+        let x_m = x_m(self, sk, message)?;
+        let x_prime = vector_lift_g2(&x_prime_m(self, sk, message)?);
+        let expected = matrix_vector_g2_mul_msm(&x_m, &tag.t_g2)?;
+        let expected = vector_add_g2(&expected, &x_prime);
 
-        let mut x_m = matrix_zero(2 * self.k, self.k);
-        for i in 0..self.msg_len {
-            let b = bit_at(i, message);
-            let x_i = &sk.x_matrices[2 * i + b];
-            x_m = matrix_add(&x_m, x_i);
+        check_tag_u(&expected, &tag.u_g2)
+    }
+}
+
+impl KemParams for AffineMAC {
+    fn dimensions(&self) -> KemDimensions {
+        KemDimensions {
+            k: dim(self.k),
+            msg_len: dim(self.msg_len),
+            max_levels: 0,
+            identity_len: 0,
+            lambda: 0,
         }
+    }
+}
 
-        let x_prime = vector_lift_g2(&sk.x_prime[0]);
-        let mut expected = matrix_vector_g2_mul_msm(&x_m, &tag.t_g2);
-        expected = vector_add_g2(&expected, &x_prime);
+impl KemSerialize<AffineMAC> for SecretKey {
+    const KIND: u8 = KIND_AFFINE_MAC_SECRET_KEY;
 
-        expected.iter().zip(tag.u_g2.iter()).all(|(e, u)| e == u)
+    fn serialize_body<W: Write>(
+        &self,
+        mac: &AffineMAC,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = mac.k;
+        write_matrix(&self.b, k, k, &mut writer, compress)?;
+        check_len(self.x_matrices.len(), 2 * mac.msg_len)?;
+        for x in &self.x_matrices {
+            write_matrix(x, 2 * k, k, &mut writer, compress)?;
+        }
+        write_matrix(&self.x_prime, 1, 2 * k, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        mac: &AffineMAC,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = mac.k;
+        let b = read_matrix(k, k, &mut reader, compress, validate)?;
+        let x_matrices = (0..2 * mac.msg_len)
+            .map(|_| read_matrix(2 * k, k, &mut reader, compress, validate))
+            .collect::<Result<_, _>>()?;
+        let x_prime = read_matrix(1, 2 * k, &mut reader, compress, validate)?;
+        Ok(SecretKey {
+            b,
+            x_matrices,
+            x_prime,
+        })
     }
 }
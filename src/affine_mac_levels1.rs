@@ -1,7 +1,14 @@
-use crate::bit_utils::bit_at;
+use crate::affine_mac::{
+    LevelAffineMac, TagContext, check_tag_u, level_affine_combination, level_bit_selectors,
+};
+use crate::error::{IbeError, check_dim, check_hierarchical_identity};
 use crate::field_utils::*;
+use crate::serialization::*;
 use crate::types::*;
 use ark_bls12_381::G2Projective as G2;
+use ark_serialize::{Compress, SerializationError, Validate};
+use rand::{CryptoRng, RngCore};
+use std::io::{Read, Write};
 
 pub struct AffineMacLevels1SecretKey {
     pub b: Matrix<FieldElement>,
@@ -12,7 +19,6 @@ pub struct AffineMacLevels1SecretKey {
 pub struct AffineMacLevels1Tag {
     pub t_g2: Vec<G2>,
     pub u_g2: Vec<G2>,
-    pub t_field: Vector,
 }
 
 pub struct AffineMacLevels1 {
@@ -36,8 +42,72 @@ impl AffineMacLevels1 {
         }
     }
 
-    pub fn gen_mac(&self) -> AffineMacLevels1SecretKey {
-        let b = random_matrix(3 * self.k, self.k);
+    // sum_{i <= p} sum_j f_{i,j}(m) * x_{i,j}
+    fn x_m(
+        &self,
+        sk_mac: &AffineMacLevels1SecretKey,
+        m: &[Vec<u8>],
+    ) -> Result<Matrix<FieldElement>, IbeError> {
+        let mut x_m = matrix_zero(self.k, 3 * self.k);
+        for i in 1..=m.len() {
+            let x_i = level_affine_combination(self, i, m, &sk_mac.x_matrices, self.k, 3 * self.k)?;
+            x_m = matrix_add(&x_m, &x_i)?;
+        }
+        Ok(x_m)
+    }
+}
+
+impl LevelAffineMac for AffineMacLevels1 {
+    type SecretKey = AffineMacLevels1SecretKey;
+    type Tag = AffineMacLevels1Tag;
+    type TagContext = TagContext;
+
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    fn max_levels(&self) -> usize {
+        self.max_levels
+    }
+
+    fn identity_len(&self) -> usize {
+        self.identity_len
+    }
+
+    fn n(&self) -> usize {
+        3 * self.k
+    }
+
+    fn eta(&self) -> usize {
+        self.k
+    }
+
+    fn l(&self, level: usize) -> usize {
+        2 * level * self.identity_len
+    }
+
+    fn f(&self, level: usize, message: &[Vec<u8>]) -> Vector {
+        level_bit_selectors(message, level, self.identity_len)
+    }
+
+    fn b<'a>(&self, sk: &'a AffineMacLevels1SecretKey) -> &'a Matrix<FieldElement> {
+        &sk.b
+    }
+
+    fn x_matrices<'a>(
+        &self,
+        sk: &'a AffineMacLevels1SecretKey,
+        level: usize,
+    ) -> &'a [Vec<Matrix<FieldElement>>] {
+        &sk.x_matrices[level - 1]
+    }
+
+    fn x_prime<'a>(&self, sk: &'a AffineMacLevels1SecretKey) -> &'a Vector {
+        &sk.x_prime
+    }
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> AffineMacLevels1SecretKey {
+        let b = random_matrix(rng, 3 * self.k, self.k);
         let mut x_matrices = Vec::with_capacity(self.max_levels);
         for i in 1..=self.max_levels {
             let num_j = i * self.identity_len;
@@ -45,7 +115,7 @@ impl AffineMacLevels1 {
             for _j in 0..num_j {
                 let mut b_matrices = Vec::with_capacity(2);
                 for _b in 0..2 {
-                    let x_matrix = random_matrix(self.k, 3 * self.k);
+                    let x_matrix = random_matrix(rng, self.k, 3 * self.k);
                     b_matrices.push(x_matrix);
                 }
 
@@ -55,7 +125,7 @@ impl AffineMacLevels1 {
             x_matrices.push(i_matrices);
         }
 
-        let x_prime = random_vector(self.k);
+        let x_prime = random_vector(rng, self.k);
 
         AffineMacLevels1SecretKey {
             b,
@@ -64,80 +134,96 @@ impl AffineMacLevels1 {
         }
     }
 
-    pub fn tag(&self, sk_mac: &AffineMacLevels1SecretKey, m: &[Vec<u8>]) -> AffineMacLevels1Tag {
-        let p = m.len();
-        assert!(p > 0 && p <= self.max_levels, "Invalid depth p");
+    fn tag_with_context<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk_mac: &AffineMacLevels1SecretKey,
+        m: &[Vec<u8>],
+    ) -> Result<(AffineMacLevels1Tag, TagContext), IbeError> {
+        check_hierarchical_identity(m, self.max_levels, self.identity_len)?;
 
-        let s = random_vector(self.k);
+        let s = random_vector(rng, self.k);
 
         let t_field = matrix_vector_mul(&sk_mac.b, &s);
 
         let t_g2 = vector_lift_g2(&t_field);
 
-        let mut u = sk_mac.x_prime.clone();
-
-        for i in 1..=p {
-            for j in 1..=(i * self.identity_len) {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_in_msg = (j - 1) % self.identity_len;
-                let b = bit_at(bit_in_msg, &m[msg_idx]);
-
-                let x_i_j_b = &sk_mac.x_matrices[i - 1][j - 1][b];
-
-                let x_t = matrix_vector_mul(x_i_j_b, &t_field);
-
-                u = vector_add(&u, &x_t);
-            }
-        }
+        let x_m = self.x_m(sk_mac, m)?;
+        let u = vector_add(&sk_mac.x_prime, &matrix_vector_mul(&x_m, &t_field));
 
         let u_g2 = vector_lift_g2(&u);
 
-        AffineMacLevels1Tag {
-            t_g2,
-            u_g2,
-            t_field,
-        }
+        Ok((AffineMacLevels1Tag { t_g2, u_g2 }, TagContext::new(t_field)))
     }
 
-    pub fn verify(
+    fn verify(
         &self,
         sk_mac: &AffineMacLevels1SecretKey,
         m: &[Vec<u8>],
         tag: &AffineMacLevels1Tag,
-    ) -> bool {
-        let p = m.len();
+    ) -> Result<(), IbeError> {
+        check_hierarchical_identity(m, self.max_levels, self.identity_len)?;
+        check_dim(3 * self.k, tag.t_g2.len())?;
 
-        if p == 0 || p > self.max_levels {
-            return false;
-        }
+        let x_m = self.x_m(sk_mac, m)?;
 
-        if tag.t_g2.len() != 3 * self.k {
-            return false;
-        }
+        let x_t = matrix_vector_g2_mul_msm(&x_m, &tag.t_g2)?;
+        let u_expected = vector_add_g2(&vector_lift_g2(&sk_mac.x_prime), &x_t);
 
-        if tag.u_g2.len() != self.k {
-            return false;
-        }
-
-        let mut u_expected = sk_mac.x_prime.clone();
-
-        for i in 1..=p {
-            for j in 1..=(i * self.identity_len) {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_in_msg = (j - 1) % self.identity_len;
-                let b = bit_at(bit_in_msg, &m[msg_idx]);
+        check_tag_u(&u_expected, &tag.u_g2)
+    }
+}
 
-                let x_i_j_b = &sk_mac.x_matrices[i - 1][j - 1][b];
-                let x_t = matrix_vector_mul(x_i_j_b, &tag.t_field);
-                u_expected = vector_add(&u_expected, &x_t);
-            }
+impl KemParams for AffineMacLevels1 {
+    fn dimensions(&self) -> KemDimensions {
+        KemDimensions {
+            k: dim(self.k),
+            msg_len: 0,
+            max_levels: dim(self.max_levels),
+            identity_len: dim(self.identity_len),
+            lambda: 0,
         }
+    }
+}
 
-        let u_expected_g2 = vector_lift_g2(&u_expected);
+impl KemSerialize<AffineMacLevels1> for AffineMacLevels1SecretKey {
+    const KIND: u8 = KIND_AFFINE_MAC_LEVELS1_SECRET_KEY;
 
-        u_expected_g2
-            .iter()
-            .zip(tag.u_g2.iter())
-            .all(|(e, u)| e == u)
+    fn serialize_body<W: Write>(
+        &self,
+        mac: &AffineMacLevels1,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = mac.k;
+        write_matrix(&self.b, 3 * k, k, &mut writer, compress)?;
+        write_levels(
+            &self.x_matrices,
+            1,
+            mac.max_levels,
+            mac.identity_len,
+            &mut writer,
+            |x, w| write_matrix(x, k, 3 * k, w, compress),
+        )?;
+        write_vector(&self.x_prime, k, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        mac: &AffineMacLevels1,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = mac.k;
+        let b = read_matrix(3 * k, k, &mut reader, compress, validate)?;
+        let x_matrices = read_levels(1, mac.max_levels, mac.identity_len, &mut reader, |r| {
+            read_matrix(k, 3 * k, r, compress, validate)
+        })?;
+        let x_prime = read_vector(k, &mut reader, compress, validate)?;
+        Ok(AffineMacLevels1SecretKey {
+            b,
+            x_matrices,
+            x_prime,
+        })
     }
 }
@@ -1,7 +1,14 @@
-use crate::bit_utils::bit_at;
+use crate::affine_mac::{
+    LevelAffineMac, check_tag_u, level_affine_combination, level_bit_selectors,
+};
+use crate::error::{IbeError, check_dim, check_hierarchical_identity};
 use crate::field_utils::*;
+use crate::serialization::*;
 use crate::types::*;
 use ark_bls12_381::G2Projective as G2;
+use ark_serialize::{Compress, SerializationError, Validate};
+use rand::{CryptoRng, RngCore};
+use std::io::{Read, Write};
 
 pub struct AffineMacLevels2SecretKey {
     pub b: Matrix<FieldElement>,
@@ -12,7 +19,18 @@ pub struct AffineMacLevels2SecretKey {
 pub struct AffineMacLevels2Tag {
     pub t_g2: Vec<Vec<G2>>,
     pub u_g2: Vec<G2>,
-    pub t_fields: Vec<Vector>,
+}
+
+/// The discrete logarithms `t_i` behind an [`AffineMacLevels2Tag`], one per level. Like
+/// [`crate::TagContext`], it is secret randomness of the PKG and opaque outside the crate.
+pub struct AffineMacLevels2TagContext {
+    t_fields: Vec<Vector>,
+}
+
+impl AffineMacLevels2TagContext {
+    pub(crate) fn t_fields(&self) -> &[Vector] {
+        &self.t_fields
+    }
 }
 
 pub struct AffineMacLevels2 {
@@ -35,9 +53,59 @@ impl AffineMacLevels2 {
             identity_len,
         }
     }
+}
+
+impl LevelAffineMac for AffineMacLevels2 {
+    type SecretKey = AffineMacLevels2SecretKey;
+    type Tag = AffineMacLevels2Tag;
+    type TagContext = AffineMacLevels2TagContext;
+
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    fn max_levels(&self) -> usize {
+        self.max_levels
+    }
 
-    pub fn gen_mac(&self) -> AffineMacLevels2SecretKey {
-        let b = random_matrix(3 * self.k, self.k);
+    fn identity_len(&self) -> usize {
+        self.identity_len
+    }
+
+    fn n(&self) -> usize {
+        3 * self.k
+    }
+
+    fn eta(&self) -> usize {
+        self.k
+    }
+
+    fn l(&self, level: usize) -> usize {
+        2 * level * self.identity_len
+    }
+
+    fn f(&self, level: usize, message: &[Vec<u8>]) -> Vector {
+        level_bit_selectors(message, level, self.identity_len)
+    }
+
+    fn b<'a>(&self, sk: &'a AffineMacLevels2SecretKey) -> &'a Matrix<FieldElement> {
+        &sk.b
+    }
+
+    fn x_matrices<'a>(
+        &self,
+        sk: &'a AffineMacLevels2SecretKey,
+        level: usize,
+    ) -> &'a [Vec<Matrix<FieldElement>>] {
+        &sk.x_matrices[level - 1]
+    }
+
+    fn x_prime<'a>(&self, sk: &'a AffineMacLevels2SecretKey) -> &'a Vector {
+        &sk.x_prime
+    }
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> AffineMacLevels2SecretKey {
+        let b = random_matrix(rng, 3 * self.k, self.k);
         let mut x_matrices = Vec::with_capacity(self.max_levels);
         for i in 1..=self.max_levels {
             let num_j = i * self.identity_len;
@@ -45,7 +113,7 @@ impl AffineMacLevels2 {
             for _j in 0..num_j {
                 let mut b_matrices = Vec::with_capacity(2);
                 for _b in 0..2 {
-                    let x_matrix = random_matrix(self.k, 3 * self.k);
+                    let x_matrix = random_matrix(rng, self.k, 3 * self.k);
                     b_matrices.push(x_matrix);
                 }
 
@@ -55,7 +123,7 @@ impl AffineMacLevels2 {
             x_matrices.push(i_matrices);
         }
 
-        let x_prime = random_vector(self.k);
+        let x_prime = random_vector(rng, self.k);
 
         AffineMacLevels2SecretKey {
             b,
@@ -64,14 +132,19 @@ impl AffineMacLevels2 {
         }
     }
 
-    pub fn tag(&self, sk_mac: &AffineMacLevels2SecretKey, m: &[Vec<u8>]) -> AffineMacLevels2Tag {
+    fn tag_with_context<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk_mac: &AffineMacLevels2SecretKey,
+        m: &[Vec<u8>],
+    ) -> Result<(AffineMacLevels2Tag, AffineMacLevels2TagContext), IbeError> {
+        check_hierarchical_identity(m, self.max_levels, self.identity_len)?;
         let p = m.len();
-        assert!(p > 0 && p <= self.max_levels, "Invalid depth p");
 
         let mut t_fields: Vec<Vector> = Vec::with_capacity(p);
         let mut t_g2: Vec<Vec<G2>> = Vec::with_capacity(p);
         for _i in 0..p {
-            let s_i = random_vector(self.k);
+            let s_i = random_vector(rng, self.k);
             let t_i = matrix_vector_mul(&sk_mac.b, &s_i);
             t_g2.push(vector_lift_g2(&t_i));
             t_fields.push(t_i);
@@ -80,72 +153,93 @@ impl AffineMacLevels2 {
         let mut u = sk_mac.x_prime.clone();
 
         for i in 1..=p {
-            for j in 1..=(i * self.identity_len) {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_in_msg = (j - 1) % self.identity_len;
-                let b = bit_at(bit_in_msg, &m[msg_idx]);
-
-                let x_i_j_b = &sk_mac.x_matrices[i - 1][j - 1][b];
-
-                let x_t = matrix_vector_mul(x_i_j_b, &t_fields[i-1]);
-
-                u = vector_add(&u, &x_t);
-            }
+            let x_i = level_affine_combination(self, i, m, &sk_mac.x_matrices, self.k, 3 * self.k)?;
+            u = vector_add(&u, &matrix_vector_mul(&x_i, &t_fields[i - 1]));
         }
 
         let u_g2 = vector_lift_g2(&u);
 
-        AffineMacLevels2Tag {
-            t_g2,
-            u_g2,
-            t_fields,
-        }
+        Ok((
+            AffineMacLevels2Tag { t_g2, u_g2 },
+            AffineMacLevels2TagContext { t_fields },
+        ))
     }
 
-    pub fn verify(
+    fn verify(
         &self,
         sk_mac: &AffineMacLevels2SecretKey,
         m: &[Vec<u8>],
         tag: &AffineMacLevels2Tag,
-    ) -> bool {
+    ) -> Result<(), IbeError> {
+        check_hierarchical_identity(m, self.max_levels, self.identity_len)?;
         let p = m.len();
+        check_dim(p, tag.t_g2.len())?;
 
-        if p == 0 || p > self.max_levels {
-            return false;
-        }
+        let mut u_expected = vector_lift_g2(&sk_mac.x_prime);
 
-        if tag.t_g2.len() != p {
-            return false;
-        }
-        for t_i in tag.t_g2.iter() {
-            if t_i.len() != 3 * self.k {
-                return false;
-            }
-        }
+        for i in 1..=p {
+            // level i is tagged with its own t_i
+            let x_i = level_affine_combination(self, i, m, &sk_mac.x_matrices, self.k, 3 * self.k)?;
 
-        if tag.u_g2.len() != self.k {
-            return false;
+            check_dim(3 * self.k, tag.t_g2[i - 1].len())?;
+            let x_t = matrix_vector_g2_mul_msm(&x_i, &tag.t_g2[i - 1])?;
+            u_expected = vector_add_g2(&u_expected, &x_t);
         }
 
-        let mut u_expected = sk_mac.x_prime.clone();
+        check_tag_u(&u_expected, &tag.u_g2)
+    }
+}
 
-        for i in 1..=p {
-            for j in 1..=(i * self.identity_len) {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_in_msg = (j - 1) % self.identity_len;
-                let b = bit_at(bit_in_msg, &m[msg_idx]);
-
-                let x_i_j_b = &sk_mac.x_matrices[i - 1][j - 1][b];
-                let x_t = matrix_vector_mul(x_i_j_b, &tag.t_fields[i-1]);
-                u_expected = vector_add(&u_expected, &x_t);
-            }
+impl KemParams for AffineMacLevels2 {
+    fn dimensions(&self) -> KemDimensions {
+        KemDimensions {
+            k: dim(self.k),
+            msg_len: 0,
+            max_levels: dim(self.max_levels),
+            identity_len: dim(self.identity_len),
+            lambda: 0,
         }
+    }
+}
 
-        let u_expected_g2 = vector_lift_g2(&u_expected);
+impl KemSerialize<AffineMacLevels2> for AffineMacLevels2SecretKey {
+    const KIND: u8 = KIND_AFFINE_MAC_LEVELS2_SECRET_KEY;
 
-        u_expected_g2
-            .iter()
-            .zip(tag.u_g2.iter())
-            .all(|(e, u)| e == u)
+    fn serialize_body<W: Write>(
+        &self,
+        mac: &AffineMacLevels2,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = mac.k;
+        write_matrix(&self.b, 3 * k, k, &mut writer, compress)?;
+        write_levels(
+            &self.x_matrices,
+            1,
+            mac.max_levels,
+            mac.identity_len,
+            &mut writer,
+            |x, w| write_matrix(x, k, 3 * k, w, compress),
+        )?;
+        write_vector(&self.x_prime, k, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        mac: &AffineMacLevels2,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = mac.k;
+        let b = read_matrix(3 * k, k, &mut reader, compress, validate)?;
+        let x_matrices = read_levels(1, mac.max_levels, mac.identity_len, &mut reader, |r| {
+            read_matrix(k, 3 * k, r, compress, validate)
+        })?;
+        let x_prime = read_vector(k, &mut reader, compress, validate)?;
+        Ok(AffineMacLevels2SecretKey {
+            b,
+            x_matrices,
+            x_prime,
+        })
     }
 }
@@ -0,0 +1,176 @@
+use crate::affine_mac::{AffineMac, Tag, TagContext, bit_selectors, check_tag_u, x_m, x_prime_m};
+use crate::error::{IbeError, check_dim, check_identity_len};
+use crate::field_utils::*;
+use crate::serialization::*;
+use crate::types::*;
+
+use ark_ff::One;
+use ark_serialize::{Compress, SerializationError, Validate};
+use rand::{CryptoRng, RngCore};
+use std::io::{Read, Write};
+
+pub struct AffineMacNrSecretKey {
+    pub b: Matrix<FieldElement>,
+    // x_matrices = [x_{0,0}, x_{0,1}, x_{1,0}, x_{1,1}, ..., x_{l-1,0}, x_{l-1,1}]
+    pub x_matrices: Vec<Matrix<FieldElement>>,
+    // x_prime = [x'_0]
+    pub x_prime: Vec<Vector>,
+}
+
+/// MAC_NR of Blazy–Kiltz–Pan (Crypto 2014), the affine MAC behind the BKP14 IBKEM.
+///
+/// As in BKP14, `B` is a `(k + 1) x k` matrix and `u` is a single element, so the tag is
+/// `t = B s` and `u = sum_i x_{i,m_i} t + x'_0`, with `l' = 0`.
+pub struct AffineMacNr {
+    pub k: usize,
+    pub msg_len: usize,
+}
+
+impl AffineMacNr {
+    pub fn new(k: usize, msg_len: usize) -> Self {
+        assert_eq!(msg_len % 8, 0);
+        Self { k, msg_len }
+    }
+}
+
+impl AffineMac for AffineMacNr {
+    type SecretKey = AffineMacNrSecretKey;
+
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    fn msg_len(&self) -> usize {
+        self.msg_len
+    }
+
+    fn n(&self) -> usize {
+        self.k + 1
+    }
+
+    fn eta(&self) -> usize {
+        1
+    }
+
+    fn l(&self) -> usize {
+        2 * self.msg_len
+    }
+
+    fn l_prime(&self) -> usize {
+        0
+    }
+
+    fn f(&self, message: &[u8]) -> Vector {
+        bit_selectors(message, self.msg_len)
+    }
+
+    /// The single constant function `f'_0(m) = 1`.
+    fn f_prime(&self, _message: &[u8]) -> Vector {
+        vec![FieldElement::one()]
+    }
+
+    fn x_matrices<'a>(&self, sk: &'a AffineMacNrSecretKey) -> &'a [Matrix<FieldElement>] {
+        &sk.x_matrices
+    }
+
+    fn x_prime<'a>(&self, sk: &'a AffineMacNrSecretKey) -> &'a [Vector] {
+        &sk.x_prime
+    }
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> AffineMacNrSecretKey {
+        let b = random_matrix(rng, self.k + 1, self.k);
+        let mut x_matrices = Vec::with_capacity(2 * self.msg_len);
+        for _ in 0..2 * self.msg_len {
+            x_matrices.push(random_matrix(rng, 1, self.k + 1));
+        }
+        let x_prime = vec![random_vector(rng, 1)];
+        AffineMacNrSecretKey {
+            b,
+            x_matrices,
+            x_prime,
+        }
+    }
+
+    fn tag_with_context<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &AffineMacNrSecretKey,
+        message: &[u8],
+    ) -> Result<(Tag, TagContext), IbeError> {
+        check_identity_len(message, self.msg_len)?;
+
+        let s = random_vector(rng, self.k);
+        let t_field = matrix_vector_mul(&sk.b, &s);
+
+        let x_m = x_m(self, sk, message)?;
+        let x_prime = x_prime_m(self, sk, message)?;
+        let u_field = vector_add(&matrix_vector_mul(&x_m, &t_field), &x_prime);
+
+        let tag = Tag {
+            t_g2: vector_lift_g2(&t_field),
+            u_g2: vector_lift_g2(&u_field),
+        };
+        Ok((tag, TagContext::new(t_field)))
+    }
+
+    fn verify(&self, sk: &AffineMacNrSecretKey, message: &[u8], tag: &Tag) -> Result<(), IbeError> {
+        check_identity_len(message, self.msg_len)?;
+        check_dim(self.k + 1, tag.t_g2.len())?;
+
+        let x_m = x_m(self, sk, message)?;
+        let expected = matrix_vector_g2_mul_msm(&x_m, &tag.t_g2)?;
+        let expected = vector_add_g2(&expected, &vector_lift_g2(&x_prime_m(self, sk, message)?));
+
+        check_tag_u(&expected, &tag.u_g2)
+    }
+}
+
+impl KemParams for AffineMacNr {
+    fn dimensions(&self) -> KemDimensions {
+        KemDimensions {
+            k: dim(self.k),
+            msg_len: dim(self.msg_len),
+            max_levels: 0,
+            identity_len: 0,
+            lambda: 0,
+        }
+    }
+}
+
+impl KemSerialize<AffineMacNr> for AffineMacNrSecretKey {
+    const KIND: u8 = KIND_AFFINE_MAC_NR_SECRET_KEY;
+
+    fn serialize_body<W: Write>(
+        &self,
+        mac: &AffineMacNr,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = mac.k;
+        write_matrix(&self.b, k + 1, k, &mut writer, compress)?;
+        check_len(self.x_matrices.len(), 2 * mac.msg_len)?;
+        for x in &self.x_matrices {
+            write_matrix(x, 1, k + 1, &mut writer, compress)?;
+        }
+        write_matrix(&self.x_prime, 1, 1, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        mac: &AffineMacNr,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = mac.k;
+        let b = read_matrix(k + 1, k, &mut reader, compress, validate)?;
+        let x_matrices = (0..2 * mac.msg_len)
+            .map(|_| read_matrix(1, k + 1, &mut reader, compress, validate))
+            .collect::<Result<_, _>>()?;
+        let x_prime = read_matrix(1, 1, &mut reader, compress, validate)?;
+        Ok(AffineMacNrSecretKey {
+            b,
+            x_matrices,
+            x_prime,
+        })
+    }
+}
@@ -0,0 +1,459 @@
+use std::fmt;
+
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use crate::affine_mac::{AffineMac, LevelAffineMac};
+use crate::hibkem1::*;
+use crate::hibkem2::*;
+use crate::ibkem1::*;
+use crate::ibkem2::*;
+use crate::pes::{
+    bb, bb_cca, bbg, bf, bf_cca, cw13, hiberla_dec, hiberla_enc, lw, sakai_kasahara, waters,
+};
+use crate::serialization::{KemParams, KemSerialize};
+
+// Armor layout:
+//
+//   -----BEGIN <LABEL>-----
+//   <base64 body, 64 characters per line>
+//   =<base64 of the 24-bit CRC of the body>
+//   -----END <LABEL>-----
+//
+// The checksum is the CRC-24 used by OpenPGP armor. Text around the block is ignored, so a
+// block can be pasted into a ticket or config file as is.
+//
+// The KEM labels name the transform, not the affine MAC it is instantiated with, so a BKP14
+// key armors as an IBKEM1 key. The kind byte at the start of the body still tells the
+// instantiations apart.
+const LINE_WIDTH: usize = 64;
+const BEGIN: &str = "-----BEGIN ";
+const END: &str = "-----END ";
+const DASHES: &str = "-----";
+
+#[derive(Debug)]
+pub enum ArmorError {
+    MissingBegin,
+    LabelMismatch { expected: String, found: String },
+    MissingEnd { label: String },
+    InvalidBase64 { line: usize },
+    MissingChecksum,
+    ChecksumMismatch { expected: u32, computed: u32 },
+    Malformed(SerializationError),
+}
+
+impl fmt::Display for ArmorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArmorError::MissingBegin => write!(f, "no \"{}...{}\" line found", BEGIN, DASHES),
+            ArmorError::LabelMismatch { expected, found } => {
+                write!(f, "expected a {:?} block, found {:?}", expected, found)
+            }
+            ArmorError::MissingEnd { label } => {
+                write!(f, "missing \"{}{}{}\" line", END, label, DASHES)
+            }
+            ArmorError::InvalidBase64 { line } => {
+                write!(f, "invalid base64 in line {} of the armored block", line)
+            }
+            ArmorError::MissingChecksum => write!(f, "missing checksum line"),
+            ArmorError::ChecksumMismatch { expected, computed } => write!(
+                f,
+                "checksum mismatch: block says {:06x}, body has {:06x}",
+                expected, computed
+            ),
+            ArmorError::Malformed(e) => write!(f, "malformed armored body: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArmorError {}
+
+impl From<SerializationError> for ArmorError {
+    fn from(e: SerializationError) -> Self {
+        ArmorError::Malformed(e)
+    }
+}
+
+/// A key or ciphertext type with a fixed armor label.
+pub trait Armored {
+    const LABEL: &'static str;
+}
+
+fn crc24(data: &[u8]) -> u32 {
+    const CRC24_INIT: u32 = 0xB704CE;
+    const CRC24_POLY: u32 = 0x1864CFB;
+
+    let mut crc = CRC24_INIT;
+    for &byte in data {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc & 0xFFFFFF
+}
+
+/// Wraps `bytes` into an armored block with the given label.
+pub fn armor(label: &str, bytes: &[u8]) -> String {
+    let body = BASE64.encode(bytes);
+    let crc = crc24(bytes).to_be_bytes();
+
+    let mut out = format!("{}{}{}\n", BEGIN, label, DASHES);
+    // base64 output is ASCII, so splitting at any byte offset is fine
+    for line in body.as_bytes().chunks(LINE_WIDTH) {
+        out.push_str(std::str::from_utf8(line).unwrap());
+        out.push('\n');
+    }
+    out.push('=');
+    out.push_str(&BASE64.encode(&crc[1..]));
+    out.push('\n');
+    out.push_str(&format!("{}{}{}\n", END, label, DASHES));
+    out
+}
+
+/// Extracts the body of the first armored block in `text`, which must carry `label`.
+pub fn dearmor(label: &str, text: &str) -> Result<Vec<u8>, ArmorError> {
+    let mut lines = text.lines().map(str::trim).enumerate();
+
+    let found = lines
+        .by_ref()
+        .find_map(|(_, line)| line.strip_prefix(BEGIN)?.strip_suffix(DASHES))
+        .ok_or(ArmorError::MissingBegin)?;
+    if found != label {
+        return Err(ArmorError::LabelMismatch {
+            expected: label.to_string(),
+            found: found.to_string(),
+        });
+    }
+
+    let end = format!("{}{}{}", END, label, DASHES);
+    let mut body = Vec::new();
+    let mut checksum = None;
+    let mut closed = false;
+    for (i, line) in lines {
+        if line == end {
+            closed = true;
+            break;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let decoded = match line.strip_prefix('=') {
+            Some(crc) => BASE64.decode(crc).ok().filter(|crc| crc.len() == 3),
+            None => BASE64.decode(line).ok(),
+        };
+        let Some(decoded) = decoded else {
+            return Err(ArmorError::InvalidBase64 { line: i + 1 });
+        };
+        if checksum.is_some() {
+            // nothing but the end line may follow the checksum
+            return Err(ArmorError::MissingEnd {
+                label: label.to_string(),
+            });
+        }
+        if line.starts_with('=') {
+            checksum = Some(u32::from_be_bytes([0, decoded[0], decoded[1], decoded[2]]));
+        } else {
+            body.extend_from_slice(&decoded);
+        }
+    }
+
+    if !closed {
+        return Err(ArmorError::MissingEnd {
+            label: label.to_string(),
+        });
+    }
+    let expected = checksum.ok_or(ArmorError::MissingChecksum)?;
+    let computed = crc24(&body);
+    if expected != computed {
+        return Err(ArmorError::ChecksumMismatch { expected, computed });
+    }
+    Ok(body)
+}
+
+fn reject_trailing(bytes: &[u8]) -> Result<(), ArmorError> {
+    if !bytes.is_empty() {
+        return Err(SerializationError::InvalidData.into());
+    }
+    Ok(())
+}
+
+/// Armors a `pes` key or ciphertext, using its compressed canonical encoding.
+pub fn to_armor<T: Armored + CanonicalSerialize>(value: &T) -> Result<String, ArmorError> {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes)?;
+    Ok(armor(T::LABEL, &bytes))
+}
+
+pub fn from_armor<T: Armored + CanonicalDeserialize>(text: &str) -> Result<T, ArmorError> {
+    let bytes = dearmor(T::LABEL, text)?;
+    let mut reader = bytes.as_slice();
+    let value = T::deserialize_with_mode(&mut reader, Compress::Yes, Validate::Yes)?;
+    reject_trailing(reader)?;
+    Ok(value)
+}
+
+/// Armors a KEM key or ciphertext, using its [`KemSerialize`] encoding.
+pub fn to_armor_with_params<P: KemParams, T: Armored + KemSerialize<P>>(
+    value: &T,
+    params: &P,
+) -> Result<String, ArmorError> {
+    Ok(armor(T::LABEL, &value.to_bytes(params)?))
+}
+
+pub fn from_armor_with_params<P: KemParams, T: Armored + KemSerialize<P>>(
+    params: &P,
+    text: &str,
+) -> Result<T, ArmorError> {
+    let bytes = dearmor(T::LABEL, text)?;
+    Ok(T::from_bytes(params, &bytes)?)
+}
+
+impl Armored for bf::MSK {
+    const LABEL: &'static str = "IBE BF MASTER SECRET KEY";
+}
+
+impl Armored for bf::MPK {
+    const LABEL: &'static str = "IBE BF PUBLIC KEY";
+}
+
+impl Armored for bf::USK {
+    const LABEL: &'static str = "IBE BF USER KEY";
+}
+
+impl Armored for bf::CT {
+    const LABEL: &'static str = "IBE BF CIPHERTEXT";
+}
+
+// The CCA variants share the master keys, and with them the labels, of the CPA scheme.
+impl Armored for bf_cca::USK {
+    const LABEL: &'static str = "IBE BF CCA USER KEY";
+}
+
+impl Armored for bf_cca::CT {
+    const LABEL: &'static str = "IBE BF CCA CIPHERTEXT";
+}
+
+impl Armored for bb::MSK {
+    const LABEL: &'static str = "IBE BB MASTER SECRET KEY";
+}
+
+impl Armored for bb::MPK {
+    const LABEL: &'static str = "IBE BB PUBLIC KEY";
+}
+
+impl Armored for bb::USK {
+    const LABEL: &'static str = "IBE BB USER KEY";
+}
+
+impl Armored for bb::CT {
+    const LABEL: &'static str = "IBE BB CIPHERTEXT";
+}
+
+// As for BF, the master keys are those of BB.
+impl Armored for bb_cca::USK {
+    const LABEL: &'static str = "IBE BB CCA USER KEY";
+}
+
+impl Armored for bb_cca::CT {
+    const LABEL: &'static str = "IBE BB CCA CIPHERTEXT";
+}
+
+impl Armored for waters::MSK {
+    const LABEL: &'static str = "IBE WATERS MASTER SECRET KEY";
+}
+
+impl Armored for waters::MPK {
+    const LABEL: &'static str = "IBE WATERS PUBLIC KEY";
+}
+
+impl Armored for waters::USK {
+    const LABEL: &'static str = "IBE WATERS USER KEY";
+}
+
+impl Armored for waters::CT {
+    const LABEL: &'static str = "IBE WATERS CIPHERTEXT";
+}
+
+impl Armored for cw13::MSK {
+    const LABEL: &'static str = "IBE CW13 MASTER SECRET KEY";
+}
+
+impl Armored for cw13::MPK {
+    const LABEL: &'static str = "IBE CW13 PUBLIC KEY";
+}
+
+impl Armored for cw13::USK {
+    const LABEL: &'static str = "IBE CW13 USER KEY";
+}
+
+impl Armored for cw13::CT {
+    const LABEL: &'static str = "IBE CW13 CIPHERTEXT";
+}
+
+// The native KEM shares the keys with the IBE, so only its ciphertexts have a label of their own.
+impl Armored for sakai_kasahara::MSK {
+    const LABEL: &'static str = "IBE SAKAI KASAHARA MASTER SECRET KEY";
+}
+
+impl Armored for sakai_kasahara::MPK {
+    const LABEL: &'static str = "IBE SAKAI KASAHARA PUBLIC KEY";
+}
+
+impl Armored for sakai_kasahara::USK {
+    const LABEL: &'static str = "IBE SAKAI KASAHARA USER KEY";
+}
+
+impl Armored for sakai_kasahara::CT {
+    const LABEL: &'static str = "IBE SAKAI KASAHARA CIPHERTEXT";
+}
+
+impl Armored for sakai_kasahara::KemCT {
+    const LABEL: &'static str = "IBE SAKAI KASAHARA KEM CIPHERTEXT";
+}
+
+impl Armored for lw::MSK {
+    const LABEL: &'static str = "HIBE LW MASTER SECRET KEY";
+}
+
+impl Armored for lw::MPK {
+    const LABEL: &'static str = "HIBE LW PUBLIC KEY";
+}
+
+impl Armored for lw::USK {
+    const LABEL: &'static str = "HIBE LW USER KEY";
+}
+
+impl Armored for lw::CT {
+    const LABEL: &'static str = "HIBE LW CIPHERTEXT";
+}
+
+impl Armored for bbg::MSK {
+    const LABEL: &'static str = "HIBE BBG MASTER SECRET KEY";
+}
+
+impl Armored for bbg::MPK {
+    const LABEL: &'static str = "HIBE BBG PUBLIC KEY";
+}
+
+impl Armored for bbg::USK {
+    const LABEL: &'static str = "HIBE BBG USER KEY";
+}
+
+impl Armored for bbg::CT {
+    const LABEL: &'static str = "HIBE BBG CIPHERTEXT";
+}
+
+impl Armored for hiberla_enc::MSK {
+    const LABEL: &'static str = "HIBE HIBERLA ENC MASTER SECRET KEY";
+}
+
+impl Armored for hiberla_enc::MPK {
+    const LABEL: &'static str = "HIBE HIBERLA ENC PUBLIC KEY";
+}
+
+impl Armored for hiberla_enc::USK {
+    const LABEL: &'static str = "HIBE HIBERLA ENC USER KEY";
+}
+
+impl Armored for hiberla_enc::CT {
+    const LABEL: &'static str = "HIBE HIBERLA ENC CIPHERTEXT";
+}
+
+impl Armored for hiberla_dec::MSK {
+    const LABEL: &'static str = "HIBE HIBERLA DEC MASTER SECRET KEY";
+}
+
+impl Armored for hiberla_dec::MPK {
+    const LABEL: &'static str = "HIBE HIBERLA DEC PUBLIC KEY";
+}
+
+impl Armored for hiberla_dec::USK {
+    const LABEL: &'static str = "HIBE HIBERLA DEC USER KEY";
+}
+
+impl Armored for hiberla_dec::CT {
+    const LABEL: &'static str = "HIBE HIBERLA DEC CIPHERTEXT";
+}
+
+impl Armored for IBKEM1PublicKey {
+    const LABEL: &'static str = "IBKEM1 PUBLIC KEY";
+}
+
+impl<M: AffineMac> Armored for IBKEM1SecretKey<M> {
+    const LABEL: &'static str = "IBKEM1 SECRET KEY";
+}
+
+impl Armored for IBKEM1UserSecretKey {
+    const LABEL: &'static str = "IBKEM1 USER KEY";
+}
+
+impl Armored for IBKEM1Ciphertext {
+    const LABEL: &'static str = "IBKEM1 CIPHERTEXT";
+}
+
+impl Armored for IBKEM2PublicKey {
+    const LABEL: &'static str = "IBKEM2 PUBLIC KEY";
+}
+
+impl<M: AffineMac> Armored for IBKEM2SecretKey<M> {
+    const LABEL: &'static str = "IBKEM2 SECRET KEY";
+}
+
+impl Armored for IBKEM2UserSecretKey {
+    const LABEL: &'static str = "IBKEM2 USER KEY";
+}
+
+impl Armored for IBKEM2Ciphertext {
+    const LABEL: &'static str = "IBKEM2 CIPHERTEXT";
+}
+
+impl Armored for HIBKEM1PublicKey {
+    const LABEL: &'static str = "HIBKEM1 PUBLIC KEY";
+}
+
+impl Armored for HIBKEM1DelegationKey {
+    const LABEL: &'static str = "HIBKEM1 DELEGATION KEY";
+}
+
+impl<M: LevelAffineMac> Armored for HIBKEM1SecretKey<M> {
+    const LABEL: &'static str = "HIBKEM1 SECRET KEY";
+}
+
+impl Armored for HIBKEM1UserSecretKey {
+    const LABEL: &'static str = "HIBKEM1 USER KEY";
+}
+
+impl Armored for HIBKEM1UserDelegationKey {
+    const LABEL: &'static str = "HIBKEM1 USER DELEGATION KEY";
+}
+
+impl Armored for HIBKEM1Ciphertext {
+    const LABEL: &'static str = "HIBKEM1 CIPHERTEXT";
+}
+
+impl Armored for HIBKEM2PublicKey {
+    const LABEL: &'static str = "HIBKEM2 PUBLIC KEY";
+}
+
+impl Armored for HIBKEM2DelegationKey {
+    const LABEL: &'static str = "HIBKEM2 DELEGATION KEY";
+}
+
+impl<M: LevelAffineMac> Armored for HIBKEM2SecretKey<M> {
+    const LABEL: &'static str = "HIBKEM2 SECRET KEY";
+}
+
+impl Armored for HIBKEM2UserSecretKey {
+    const LABEL: &'static str = "HIBKEM2 USER KEY";
+}
+
+impl Armored for HIBKEM2Ciphertext {
+    const LABEL: &'static str = "HIBKEM2 CIPHERTEXT";
+}
@@ -5,9 +5,20 @@ use digest::{FixedOutput, FixedOutputReset, Output, OutputSizeUser, Reset, Updat
 pub struct Blake3(blake3::Hasher);
 
 impl Blake3 {
+    /// Hasher in BLAKE3's derive_key mode. `context` has to be a hardcoded, globally unique
+    /// string describing the purpose of the derived key.
+    pub fn new_derive_key(context: &str) -> Self {
+        Self(blake3::Hasher::new_derive_key(context))
+    }
+
     pub fn hash(&self, input: &[u8]) -> [u8; 32] {
         blake3::hash(input).as_bytes().clone()
     }
+
+    /// Fills `out` with the extendable output for everything hashed so far.
+    pub fn finalize_xof_into(&self, out: &mut [u8]) {
+        self.0.finalize_xof().fill(out);
+    }
 }
 
 impl Default for Blake3 {
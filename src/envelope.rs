@@ -0,0 +1,869 @@
+use std::fmt;
+use std::io::{Read, Write};
+
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate,
+};
+
+use crate::affine_mac_nr::AffineMacNr;
+use crate::hibkem1::{HIBKEM1, HIBKEM1Ciphertext};
+use crate::hibkem2::{HIBKEM2, HIBKEM2Ciphertext};
+use crate::ibkem1::{BKP14, BKP14Ciphertext, IBKEM1, IBKEM1Ciphertext};
+use crate::ibkem2::{IBKEM2, IBKEM2Ciphertext};
+use crate::pes::bb::{self, BB};
+use crate::pes::bb_cca::{self, BBCca};
+use crate::pes::bbg::{self, BBG};
+use crate::pes::bf::{self, BF};
+use crate::pes::bf_cca::{self, BFCca};
+use crate::pes::cw13::{self, CW13};
+use crate::pes::hiberla_dec::{self, HiberlaDec};
+use crate::pes::hiberla_enc::{self, HiberlaEnc};
+use crate::pes::kem::PesKem;
+use crate::pes::lw::{self, LW};
+use crate::pes::sakai_kasahara::{self, SakaiKasahara, SakaiKasaharaKem};
+use crate::pes::waters::{self, Waters};
+use crate::serialization::{KemDimensions, KemParams, KemSerialize, read_len, write_len};
+
+// Envelope layout:
+//
+//   magic (4) || version (1) || scheme id (1 + n) || curve id (1 + n)
+//   || params (4 + n) || payload
+//
+// Lengths are little-endian. The payload is the compressed ciphertext of the scheme;
+// its format is determined by the scheme id and the parameter block.
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"IBEC";
+pub const ENVELOPE_VERSION: u8 = 1;
+pub const CURVE_BLS12_381: &str = "bls12-381";
+
+#[derive(Debug)]
+pub enum EnvelopeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownScheme(String),
+    SchemeMismatch { expected: String, found: String },
+    UnsupportedCurve(String),
+    ParamsMismatch { scheme: String },
+    Malformed(SerializationError),
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeError::BadMagic => write!(f, "not a ciphertext envelope (bad magic bytes)"),
+            EnvelopeError::UnsupportedVersion(v) => {
+                write!(f, "unsupported envelope version {}", v)
+            }
+            EnvelopeError::UnknownScheme(id) => write!(f, "unknown scheme id {:?}", id),
+            EnvelopeError::SchemeMismatch { expected, found } => write!(
+                f,
+                "envelope holds a {:?} ciphertext, expected {:?}",
+                found, expected
+            ),
+            EnvelopeError::UnsupportedCurve(id) => write!(f, "unsupported curve id {:?}", id),
+            EnvelopeError::ParamsMismatch { scheme } => write!(
+                f,
+                "parameters of the {:?} ciphertext do not match the scheme instance",
+                scheme
+            ),
+            EnvelopeError::Malformed(e) => write!(f, "malformed envelope: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+impl From<SerializationError> for EnvelopeError {
+    fn from(e: SerializationError) -> Self {
+        EnvelopeError::Malformed(e)
+    }
+}
+
+/// A scheme instance whose ciphertexts can be wrapped in an envelope.
+///
+/// `SCHEME_ID` matches the `name()` of the pes schemes. The parameter block holds whatever
+/// is needed to rebuild the instance, e.g. the partition size of Hiberla or the
+/// [`KemDimensions`] of the KEMs.
+pub trait EnvelopeScheme: Sized {
+    const SCHEME_ID: &'static str;
+
+    type Ciphertext;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError>;
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError>;
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &Self::Ciphertext,
+        writer: W,
+    ) -> Result<(), SerializationError>;
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<Self::Ciphertext, SerializationError>;
+}
+
+/// The header of an envelope, with the parameter block still encoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnvelopeHeader {
+    pub version: u8,
+    pub scheme_id: String,
+    pub curve_id: String,
+    pub params: Vec<u8>,
+}
+
+fn params_bytes<S: EnvelopeScheme>(scheme: &S) -> Result<Vec<u8>, SerializationError> {
+    let mut params = Vec::new();
+    scheme.write_params(&mut params)?;
+    Ok(params)
+}
+
+fn write_short_string(s: &str, out: &mut Vec<u8>) -> Result<(), SerializationError> {
+    let len = u8::try_from(s.len()).map_err(|_| SerializationError::InvalidData)?;
+    out.push(len);
+    out.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], EnvelopeError> {
+    if bytes.len() < n {
+        return Err(SerializationError::InvalidData.into());
+    }
+    let (head, tail) = bytes.split_at(n);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn read_short_string(bytes: &mut &[u8]) -> Result<String, EnvelopeError> {
+    let len = take(bytes, 1)?[0] as usize;
+    let s = take(bytes, len)?;
+    String::from_utf8(s.to_vec()).map_err(|_| SerializationError::InvalidData.into())
+}
+
+/// Wraps a ciphertext of `scheme` into an envelope.
+pub fn seal<S: EnvelopeScheme>(scheme: &S, ct: &S::Ciphertext) -> Result<Vec<u8>, EnvelopeError> {
+    let params = params_bytes(scheme)?;
+    let params_len = u32::try_from(params.len()).map_err(|_| SerializationError::InvalidData)?;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&ENVELOPE_MAGIC);
+    out.push(ENVELOPE_VERSION);
+    write_short_string(S::SCHEME_ID, &mut out)?;
+    write_short_string(CURVE_BLS12_381, &mut out)?;
+    out.extend_from_slice(&params_len.to_le_bytes());
+    out.extend_from_slice(&params);
+    scheme.write_ciphertext(ct, &mut out)?;
+    Ok(out)
+}
+
+/// Parses the envelope header and returns it together with the payload.
+pub fn parse_header(mut bytes: &[u8]) -> Result<(EnvelopeHeader, &[u8]), EnvelopeError> {
+    if take(&mut bytes, ENVELOPE_MAGIC.len()).map_err(|_| EnvelopeError::BadMagic)?
+        != ENVELOPE_MAGIC
+    {
+        return Err(EnvelopeError::BadMagic);
+    }
+
+    let version = take(&mut bytes, 1)?[0];
+    if version != ENVELOPE_VERSION {
+        return Err(EnvelopeError::UnsupportedVersion(version));
+    }
+
+    let scheme_id = read_short_string(&mut bytes)?;
+    let curve_id = read_short_string(&mut bytes)?;
+    if curve_id != CURVE_BLS12_381 {
+        return Err(EnvelopeError::UnsupportedCurve(curve_id));
+    }
+
+    let params_len = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().unwrap()) as usize;
+    let params = take(&mut bytes, params_len)?.to_vec();
+
+    let header = EnvelopeHeader {
+        version,
+        scheme_id,
+        curve_id,
+        params,
+    };
+    Ok((header, bytes))
+}
+
+fn read_payload<S: EnvelopeScheme>(
+    scheme: &S,
+    mut payload: &[u8],
+) -> Result<S::Ciphertext, EnvelopeError> {
+    let ct = scheme.read_ciphertext(&mut payload)?;
+    if !payload.is_empty() {
+        return Err(SerializationError::InvalidData.into());
+    }
+    Ok(ct)
+}
+
+/// Unwraps a ciphertext of `scheme`, checking the scheme id and the parameters.
+pub fn open<S: EnvelopeScheme>(scheme: &S, bytes: &[u8]) -> Result<S::Ciphertext, EnvelopeError> {
+    let (header, payload) = parse_header(bytes)?;
+    if header.scheme_id != S::SCHEME_ID {
+        return Err(EnvelopeError::SchemeMismatch {
+            expected: S::SCHEME_ID.to_string(),
+            found: header.scheme_id,
+        });
+    }
+    if header.params != params_bytes(scheme)? {
+        return Err(EnvelopeError::ParamsMismatch {
+            scheme: header.scheme_id,
+        });
+    }
+    read_payload(scheme, payload)
+}
+
+/// A ciphertext of any supported scheme, together with the instance it belongs to.
+pub enum AnyCiphertext {
+    Bf(BF, bf::CT),
+    Bb(BB, bb::CT),
+    BfCca(BFCca, bf_cca::CT),
+    BbCca(BBCca, bb_cca::CT),
+    Lw(LW, lw::CT),
+    Bbg(BBG, bbg::CT),
+    HiberlaEnc(HiberlaEnc, hiberla_enc::CT),
+    HiberlaDec(HiberlaDec, hiberla_dec::CT),
+    SakaiKasahara(SakaiKasahara, sakai_kasahara::CT),
+    SakaiKasaharaKem(SakaiKasaharaKem, sakai_kasahara::KemCT),
+    Waters(Waters, waters::CT),
+    Cw13(CW13, cw13::CT),
+    Ibkem1(IBKEM1, IBKEM1Ciphertext),
+    Ibkem2(IBKEM2, IBKEM2Ciphertext),
+    Bkp14(BKP14, BKP14Ciphertext),
+    Hibkem1(HIBKEM1, HIBKEM1Ciphertext),
+    Hibkem2(HIBKEM2, HIBKEM2Ciphertext),
+}
+
+impl AnyCiphertext {
+    pub fn scheme_id(&self) -> &'static str {
+        match self {
+            AnyCiphertext::Bf(..) => BF::SCHEME_ID,
+            AnyCiphertext::Bb(..) => BB::SCHEME_ID,
+            AnyCiphertext::BfCca(..) => BFCca::SCHEME_ID,
+            AnyCiphertext::BbCca(..) => BBCca::SCHEME_ID,
+            AnyCiphertext::Lw(..) => LW::SCHEME_ID,
+            AnyCiphertext::Bbg(..) => BBG::SCHEME_ID,
+            AnyCiphertext::HiberlaEnc(..) => HiberlaEnc::SCHEME_ID,
+            AnyCiphertext::HiberlaDec(..) => HiberlaDec::SCHEME_ID,
+            AnyCiphertext::SakaiKasahara(..) => SakaiKasahara::SCHEME_ID,
+            AnyCiphertext::SakaiKasaharaKem(..) => SakaiKasaharaKem::SCHEME_ID,
+            AnyCiphertext::Waters(..) => Waters::SCHEME_ID,
+            AnyCiphertext::Cw13(..) => CW13::SCHEME_ID,
+            AnyCiphertext::Ibkem1(..) => <IBKEM1>::SCHEME_ID,
+            AnyCiphertext::Ibkem2(..) => IBKEM2::SCHEME_ID,
+            AnyCiphertext::Bkp14(..) => BKP14::SCHEME_ID,
+            AnyCiphertext::Hibkem1(..) => HIBKEM1::SCHEME_ID,
+            AnyCiphertext::Hibkem2(..) => HIBKEM2::SCHEME_ID,
+        }
+    }
+}
+
+fn open_as<S: EnvelopeScheme>(
+    header: &EnvelopeHeader,
+    payload: &[u8],
+) -> Result<(S, S::Ciphertext), EnvelopeError> {
+    let mut params = header.params.as_slice();
+    let scheme = S::read_params(&mut params)?;
+    if !params.is_empty() {
+        return Err(EnvelopeError::ParamsMismatch {
+            scheme: header.scheme_id.clone(),
+        });
+    }
+    let ct = read_payload(&scheme, payload)?;
+    Ok((scheme, ct))
+}
+
+/// Unwraps a ciphertext of whichever scheme the envelope names.
+pub fn open_any(bytes: &[u8]) -> Result<AnyCiphertext, EnvelopeError> {
+    let (header, payload) = parse_header(bytes)?;
+    let any = match header.scheme_id.as_str() {
+        BF::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Bf(scheme, ct)
+        }
+        BB::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Bb(scheme, ct)
+        }
+        BFCca::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::BfCca(scheme, ct)
+        }
+        BBCca::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::BbCca(scheme, ct)
+        }
+        LW::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Lw(scheme, ct)
+        }
+        BBG::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Bbg(scheme, ct)
+        }
+        HiberlaEnc::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::HiberlaEnc(scheme, ct)
+        }
+        HiberlaDec::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::HiberlaDec(scheme, ct)
+        }
+        SakaiKasahara::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::SakaiKasahara(scheme, ct)
+        }
+        SakaiKasaharaKem::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::SakaiKasaharaKem(scheme, ct)
+        }
+        Waters::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Waters(scheme, ct)
+        }
+        CW13::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Cw13(scheme, ct)
+        }
+        <IBKEM1>::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Ibkem1(scheme, ct)
+        }
+        IBKEM2::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Ibkem2(scheme, ct)
+        }
+        BKP14::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Bkp14(scheme, ct)
+        }
+        HIBKEM1::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Hibkem1(scheme, ct)
+        }
+        HIBKEM2::SCHEME_ID => {
+            let (scheme, ct) = open_as(&header, payload)?;
+            AnyCiphertext::Hibkem2(scheme, ct)
+        }
+        _ => return Err(EnvelopeError::UnknownScheme(header.scheme_id)),
+    };
+    Ok(any)
+}
+
+fn write_ct<T: CanonicalSerialize, W: Write>(ct: &T, writer: W) -> Result<(), SerializationError> {
+    ct.serialize_compressed(writer)
+}
+
+fn read_ct<T: CanonicalDeserialize, R: Read>(reader: R) -> Result<T, SerializationError> {
+    T::deserialize_with_mode(reader, Compress::Yes, Validate::Yes)
+}
+
+fn read_partition_size<R: Read>(reader: R) -> Result<usize, SerializationError> {
+    let l = u32::deserialize_compressed(reader)? as usize;
+    if l == 0 {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(l)
+}
+
+impl EnvelopeScheme for BF {
+    const SCHEME_ID: &'static str = "bf";
+
+    type Ciphertext = bf::CT;
+
+    fn write_params<W: Write>(&self, _writer: W) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn read_params<R: Read>(_reader: R) -> Result<Self, SerializationError> {
+        Ok(BF::new())
+    }
+
+    fn write_ciphertext<W: Write>(&self, ct: &bf::CT, writer: W) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<bf::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+impl EnvelopeScheme for BB {
+    const SCHEME_ID: &'static str = "bb";
+
+    type Ciphertext = bb::CT;
+
+    fn write_params<W: Write>(&self, _writer: W) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn read_params<R: Read>(_reader: R) -> Result<Self, SerializationError> {
+        Ok(BB::new())
+    }
+
+    fn write_ciphertext<W: Write>(&self, ct: &bb::CT, writer: W) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<bb::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+// The CCA ciphertexts carry an extra masked message, so they get scheme ids of their own.
+impl EnvelopeScheme for BFCca {
+    const SCHEME_ID: &'static str = "bf_cca";
+
+    type Ciphertext = bf_cca::CT;
+
+    fn write_params<W: Write>(&self, _writer: W) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn read_params<R: Read>(_reader: R) -> Result<Self, SerializationError> {
+        Ok(BFCca::new())
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &bf_cca::CT,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<bf_cca::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+impl EnvelopeScheme for BBCca {
+    const SCHEME_ID: &'static str = "bb_cca";
+
+    type Ciphertext = bb_cca::CT;
+
+    fn write_params<W: Write>(&self, _writer: W) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn read_params<R: Read>(_reader: R) -> Result<Self, SerializationError> {
+        Ok(BBCca::new())
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &bb_cca::CT,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<bb_cca::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+impl EnvelopeScheme for LW {
+    const SCHEME_ID: &'static str = "lw";
+
+    type Ciphertext = lw::CT;
+
+    fn write_params<W: Write>(&self, _writer: W) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn read_params<R: Read>(_reader: R) -> Result<Self, SerializationError> {
+        Ok(LW::new())
+    }
+
+    fn write_ciphertext<W: Write>(&self, ct: &lw::CT, writer: W) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<lw::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+impl EnvelopeScheme for BBG {
+    const SCHEME_ID: &'static str = "bbg";
+
+    type Ciphertext = bbg::CT;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        write_len(self.max_depth, writer)
+    }
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(BBG::new(read_len(reader)?))
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &bbg::CT,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<bbg::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+impl EnvelopeScheme for HiberlaEnc {
+    const SCHEME_ID: &'static str = "hiberla_enc";
+
+    type Ciphertext = hiberla_enc::CT;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        write_len(self.l, writer)
+    }
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(HiberlaEnc::new(read_partition_size(reader)?))
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &hiberla_enc::CT,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<hiberla_enc::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+impl EnvelopeScheme for HiberlaDec {
+    const SCHEME_ID: &'static str = "hiberla_dec";
+
+    type Ciphertext = hiberla_dec::CT;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        write_len(self.l, writer)
+    }
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(HiberlaDec::new(read_partition_size(reader)?))
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &hiberla_dec::CT,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<hiberla_dec::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+impl EnvelopeScheme for SakaiKasahara {
+    const SCHEME_ID: &'static str = "sakai_kasahara";
+
+    type Ciphertext = sakai_kasahara::CT;
+
+    fn write_params<W: Write>(&self, _writer: W) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn read_params<R: Read>(_reader: R) -> Result<Self, SerializationError> {
+        Ok(SakaiKasahara::new())
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &sakai_kasahara::CT,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(
+        &self,
+        reader: R,
+    ) -> Result<sakai_kasahara::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+// The native KEM has its own, shorter ciphertexts and therefore its own scheme id.
+impl EnvelopeScheme for SakaiKasaharaKem {
+    const SCHEME_ID: &'static str = "sakai_kasahara_kem";
+
+    type Ciphertext = sakai_kasahara::KemCT;
+
+    fn write_params<W: Write>(&self, _writer: W) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn read_params<R: Read>(_reader: R) -> Result<Self, SerializationError> {
+        Ok(SakaiKasaharaKem::new())
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &sakai_kasahara::KemCT,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(
+        &self,
+        reader: R,
+    ) -> Result<sakai_kasahara::KemCT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+impl EnvelopeScheme for Waters {
+    const SCHEME_ID: &'static str = "waters";
+
+    type Ciphertext = waters::CT;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        write_len(self.n, writer)
+    }
+
+    // `Waters::new` panics on identities longer than 256 bits, so check the length first.
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let n = read_len(reader)?;
+        if n > 256 {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(Waters::new(n))
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &waters::CT,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<waters::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+impl EnvelopeScheme for CW13 {
+    const SCHEME_ID: &'static str = "cw13";
+
+    type Ciphertext = cw13::CT;
+
+    fn write_params<W: Write>(&self, _writer: W) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn read_params<R: Read>(_reader: R) -> Result<Self, SerializationError> {
+        Ok(CW13::new())
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &cw13::CT,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        write_ct(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<cw13::CT, SerializationError> {
+        read_ct(reader)
+    }
+}
+
+// The KEMs store their dimensions as parameter block, so the payload is the bare body.
+fn read_dimensions<P: KemParams, R: Read>(
+    reader: R,
+    new: impl FnOnce(&KemDimensions) -> P,
+) -> Result<P, SerializationError> {
+    let dims = KemDimensions::deserialize_compressed(reader)?;
+    // the constructors assert on these, so reject them before calling `new`; a scheme
+    // has either a message length (IBKEMs) or a hierarchy (HIBKEMs), never both
+    if dims.k == 0
+        || dims.msg_len % 8 != 0
+        || dims.identity_len % 8 != 0
+        || (dims.max_levels == 0) != (dims.identity_len == 0)
+        || (dims.msg_len == 0) == (dims.max_levels == 0)
+    {
+        return Err(SerializationError::InvalidData);
+    }
+    let kem = new(&dims);
+    // rejects dimensions the scheme does not have
+    if kem.dimensions() != dims {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(kem)
+}
+
+impl EnvelopeScheme for IBKEM1 {
+    const SCHEME_ID: &'static str = "ibkem1";
+
+    type Ciphertext = IBKEM1Ciphertext;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.dimensions().serialize_compressed(writer)
+    }
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_dimensions(reader, |d| IBKEM1::new(d.k as usize, d.msg_len as usize))
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &IBKEM1Ciphertext,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        ct.serialize_body(self, writer, Compress::Yes)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<IBKEM1Ciphertext, SerializationError> {
+        IBKEM1Ciphertext::deserialize_body(self, reader, Compress::Yes, Validate::Yes)
+    }
+}
+
+impl EnvelopeScheme for IBKEM2 {
+    const SCHEME_ID: &'static str = "ibkem2";
+
+    type Ciphertext = IBKEM2Ciphertext;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.dimensions().serialize_compressed(writer)
+    }
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_dimensions(reader, |d| {
+            IBKEM2::new(d.k as usize, d.msg_len as usize, d.lambda as usize)
+        })
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &IBKEM2Ciphertext,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        ct.serialize_body(self, writer, Compress::Yes)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<IBKEM2Ciphertext, SerializationError> {
+        IBKEM2Ciphertext::deserialize_body(self, reader, Compress::Yes, Validate::Yes)
+    }
+}
+
+impl EnvelopeScheme for BKP14 {
+    const SCHEME_ID: &'static str = "bkp14";
+
+    type Ciphertext = BKP14Ciphertext;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.dimensions().serialize_compressed(writer)
+    }
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_dimensions(reader, |d| {
+            BKP14::with_mac(AffineMacNr::new(d.k as usize, d.msg_len as usize))
+        })
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &BKP14Ciphertext,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        ct.serialize_body(self, writer, Compress::Yes)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<BKP14Ciphertext, SerializationError> {
+        BKP14Ciphertext::deserialize_body(self, reader, Compress::Yes, Validate::Yes)
+    }
+}
+
+impl EnvelopeScheme for HIBKEM1 {
+    const SCHEME_ID: &'static str = "hibkem1";
+
+    type Ciphertext = HIBKEM1Ciphertext;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.dimensions().serialize_compressed(writer)
+    }
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_dimensions(reader, |d| {
+            HIBKEM1::new(d.k as usize, d.max_levels as usize, d.identity_len as usize)
+        })
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &HIBKEM1Ciphertext,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        ct.serialize_body(self, writer, Compress::Yes)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<HIBKEM1Ciphertext, SerializationError> {
+        HIBKEM1Ciphertext::deserialize_body(self, reader, Compress::Yes, Validate::Yes)
+    }
+}
+
+impl EnvelopeScheme for HIBKEM2 {
+    const SCHEME_ID: &'static str = "hibkem2";
+
+    type Ciphertext = HIBKEM2Ciphertext;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.dimensions().serialize_compressed(writer)
+    }
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        read_dimensions(reader, |d| {
+            HIBKEM2::new(d.k as usize, d.max_levels as usize, d.identity_len as usize)
+        })
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &HIBKEM2Ciphertext,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        ct.serialize_body(self, writer, Compress::Yes)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<HIBKEM2Ciphertext, SerializationError> {
+        HIBKEM2Ciphertext::deserialize_body(self, reader, Compress::Yes, Validate::Yes)
+    }
+}
+
+// A KEM view of a pes scheme produces the scheme's own ciphertexts, so it shares its envelope.
+impl<T: EnvelopeScheme> EnvelopeScheme for PesKem<T> {
+    const SCHEME_ID: &'static str = T::SCHEME_ID;
+
+    type Ciphertext = T::Ciphertext;
+
+    fn write_params<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.scheme.write_params(writer)
+    }
+
+    fn read_params<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(PesKem::new(T::read_params(reader)?))
+    }
+
+    fn write_ciphertext<W: Write>(
+        &self,
+        ct: &T::Ciphertext,
+        writer: W,
+    ) -> Result<(), SerializationError> {
+        self.scheme.write_ciphertext(ct, writer)
+    }
+
+    fn read_ciphertext<R: Read>(&self, reader: R) -> Result<T::Ciphertext, SerializationError> {
+        self.scheme.read_ciphertext(reader)
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use ark_serialize::SerializationError;
+
+use crate::armor::ArmorError;
+use crate::envelope::EnvelopeError;
+
+#[derive(Debug)]
+pub enum IbeError {
+    /// The identity (or one level of a hierarchical identity) has the wrong length in bits.
+    InvalidIdentityLength { expected: usize, found: usize },
+    /// The hierarchical identity is empty or deeper than supported.
+    DepthOutOfRange { depth: usize, max: usize },
+    /// The identity has no components at all.
+    EmptyIdentity,
+    /// No key can be extracted for the identity under this master key, e.g. because the
+    /// identity hashes to the negated master secret in Sakai–Kasahara.
+    InvalidIdentity,
+    /// A vector or matrix does not have the dimension the scheme instance expects.
+    DimensionMismatch { expected: usize, found: usize },
+    /// The key is not allowed to decrypt the ciphertext, e.g. because it belongs to a
+    /// different identity.
+    IdentityMismatch,
+    InvalidProof,
+    /// A MAC tag does not verify for the message.
+    InvalidTag,
+    /// The ciphertext has been modified: authenticated decryption, a re-encryption check or a
+    /// signature check failed.
+    DecryptionFailed,
+    /// The chunk size of a stream is zero or larger than supported.
+    InvalidChunkSize { size: usize, max: usize },
+    /// No recipient slot of a multi-recipient ciphertext matches the identity or index.
+    RecipientNotFound,
+    MalformedEncoding(SerializationError),
+    Envelope(EnvelopeError),
+    Armor(ArmorError),
+}
+
+impl fmt::Display for IbeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IbeError::InvalidIdentityLength { expected, found } => write!(
+                f,
+                "identity has {} bits, expected {} bits",
+                found, expected
+            ),
+            IbeError::DepthOutOfRange { depth, max } => {
+                write!(f, "identity depth {} outside of 1..={}", depth, max)
+            }
+            IbeError::EmptyIdentity => write!(f, "identity is empty"),
+            IbeError::InvalidIdentity => write!(f, "no key can be extracted for the identity"),
+            IbeError::DimensionMismatch { expected, found } => write!(
+                f,
+                "dimension mismatch: expected {}, found {}",
+                expected, found
+            ),
+            IbeError::IdentityMismatch => {
+                write!(f, "the key's identity does not match the ciphertext")
+            }
+            IbeError::InvalidProof => write!(f, "the ciphertext proof does not verify"),
+            IbeError::InvalidTag => write!(f, "the MAC tag does not verify"),
+            IbeError::DecryptionFailed => write!(f, "authenticated decryption failed"),
+            IbeError::InvalidChunkSize { size, max } => {
+                write!(f, "chunk size {} outside of 1..={}", size, max)
+            }
+            IbeError::RecipientNotFound => {
+                write!(f, "no recipient slot matches the identity or index")
+            }
+            IbeError::MalformedEncoding(e) => write!(f, "malformed encoding: {}", e),
+            IbeError::Envelope(e) => write!(f, "{}", e),
+            IbeError::Armor(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for IbeError {}
+
+impl From<SerializationError> for IbeError {
+    fn from(e: SerializationError) -> Self {
+        IbeError::MalformedEncoding(e)
+    }
+}
+
+impl From<EnvelopeError> for IbeError {
+    fn from(e: EnvelopeError) -> Self {
+        IbeError::Envelope(e)
+    }
+}
+
+impl From<ArmorError> for IbeError {
+    fn from(e: ArmorError) -> Self {
+        IbeError::Armor(e)
+    }
+}
+
+pub(crate) fn check_dim(expected: usize, found: usize) -> Result<(), IbeError> {
+    if expected != found {
+        return Err(IbeError::DimensionMismatch { expected, found });
+    }
+    Ok(())
+}
+
+pub(crate) fn check_non_empty<T>(v: &[T]) -> Result<(), IbeError> {
+    if v.is_empty() {
+        return Err(IbeError::DimensionMismatch {
+            expected: 1,
+            found: 0,
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn check_identity_non_empty<T>(identity: &[T]) -> Result<(), IbeError> {
+    if identity.is_empty() {
+        return Err(IbeError::EmptyIdentity);
+    }
+    Ok(())
+}
+
+/// Checks that `identity` has exactly `bits` bits.
+pub(crate) fn check_identity_len(identity: &[u8], bits: usize) -> Result<(), IbeError> {
+    if identity.len() * 8 != bits {
+        return Err(IbeError::InvalidIdentityLength {
+            expected: bits,
+            found: identity.len() * 8,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that a hierarchical identity has depth `1..=max` and `bits` bits per level.
+pub(crate) fn check_hierarchical_identity(
+    identity: &[Vec<u8>],
+    max: usize,
+    bits: usize,
+) -> Result<(), IbeError> {
+    if identity.is_empty() || identity.len() > max {
+        return Err(IbeError::DepthOutOfRange {
+            depth: identity.len(),
+            max,
+        });
+    }
+    identity
+        .iter()
+        .try_for_each(|level| check_identity_len(level, bits))
+}
+
+/// Checks the `[i][j][b]` shape of the per-level collections of the hierarchical schemes,
+/// covering levels `first_level..=max_levels`.
+pub(crate) fn check_levels<T>(
+    levels: &[Vec<Vec<T>>],
+    first_level: usize,
+    max_levels: usize,
+    identity_len: usize,
+) -> Result<(), IbeError> {
+    check_dim((max_levels + 1).saturating_sub(first_level), levels.len())?;
+    for (level, i) in levels.iter().zip(first_level..) {
+        check_dim(i * identity_len, level.len())?;
+        level.iter().try_for_each(|pair| check_dim(2, pair.len()))?;
+    }
+    Ok(())
+}
@@ -1,23 +1,37 @@
 use ark_bls12_381::{G1Affine, G1Projective as G1, G2Affine, G2Projective as G2};
 use ark_ec::{CurveGroup, VariableBaseMSM};
-use ark_ff::{UniformRand, Zero};
-use ark_std::ops::Add;
-use rand::thread_rng;
+use ark_ff::{One, UniformRand, Zero};
+use ark_std::ops::{Add, Mul};
+use rand::RngCore;
 
+use crate::error::{IbeError, check_dim, check_non_empty};
 use crate::group_functions::{scalar_mul_g1, scalar_mul_g2};
 use crate::{FieldElement, Matrix, Vector};
 
-pub fn random_field_element() -> FieldElement {
-    let mut rng = thread_rng();
-    FieldElement::rand(&mut rng)
+// `msm` fails iff the number of bases and scalars differ
+fn msm_dim_err(bases: usize, scalars: usize) -> impl FnOnce(usize) -> IbeError {
+    move |_| IbeError::DimensionMismatch {
+        expected: bases,
+        found: scalars,
+    }
+}
+
+// The samplers only ask for `RngCore`, so that the `pes` schemes, which take any `Rng`, can use
+// them too. The KEM and MAC APIs that call them still require a `CryptoRng`.
+pub fn random_field_element<R: RngCore>(rng: &mut R) -> FieldElement {
+    FieldElement::rand(rng)
 }
 
-pub fn random_vector(len: usize) -> Vector {
-    (0..len).map(|_| random_field_element()).collect()
+pub fn random_vector<R: RngCore>(rng: &mut R, len: usize) -> Vector {
+    (0..len).map(|_| random_field_element(rng)).collect()
 }
 
-pub fn random_matrix(rows: usize, cols: usize) -> Matrix<FieldElement> {
-    (0..rows).map(|_| random_vector(cols)).collect()
+pub fn random_matrix<R: RngCore>(
+    rng: &mut R,
+    rows: usize,
+    cols: usize,
+) -> Matrix<FieldElement> {
+    (0..rows).map(|_| random_vector(rng, cols)).collect()
 }
 
 pub fn matrix_vector_mul(matrix: &Matrix<FieldElement>, vector: &Vector) -> Vector {
@@ -32,10 +46,13 @@ pub fn matrix_vector_mul(matrix: &Matrix<FieldElement>, vector: &Vector) -> Vect
         .collect()
 }
 
-pub fn vector_matrix_mul(vector: &Vector, matrix: &Matrix<FieldElement>) -> Vector {
-    assert_eq!(vector.len(), matrix.len());
+pub fn vector_matrix_mul(vector: &Vector, matrix: &Matrix<FieldElement>) -> Result<Vector, IbeError> {
+    check_dim(matrix.len(), vector.len())?;
+    check_non_empty(matrix)?;
+    let cols = matrix[0].len();
+    matrix.iter().try_for_each(|row| check_dim(cols, row.len()))?;
 
-    (0..matrix[0].len())
+    Ok((0..cols)
         .map(|j| {
             vector
                 .iter()
@@ -43,7 +60,7 @@ pub fn vector_matrix_mul(vector: &Vector, matrix: &Matrix<FieldElement>) -> Vect
                 .map(|(&v_i, row_i)| v_i * row_i[j])
                 .fold(FieldElement::zero(), |acc, x| acc + x)
         })
-        .collect()
+        .collect())
 }
 
 pub fn vector_add(a: &Vector, b: &Vector) -> Vector {
@@ -58,24 +75,29 @@ pub fn vector_add_g2(a: &Vec<G2>, b: &Vec<G2>) -> Vec<G2> {
     a.iter().zip(b.iter()).map(|(x, y)| *x + *y).collect()
 }
 
-pub fn vector_dot_g1(a: &Vector, b: &Vec<G1>) -> G1 {
-    assert_eq!(a.len(), b.len());
+pub fn vector_dot_g1(a: &Vector, b: &Vec<G1>) -> Result<G1, IbeError> {
+    check_dim(a.len(), b.len())?;
     let mut sum = G1::zero();
     for i in 0..a.len() {
         sum += b[i] * a[i];
     }
-    sum
+    Ok(sum)
 }
 
 pub fn scalar_vector_mul(scalar: FieldElement, vector: &Vector) -> Vector {
     vector.iter().map(|&x| scalar * x).collect()
 }
 
-pub fn matrix_multiply(a: &Matrix<FieldElement>, b: &Matrix<FieldElement>) -> Matrix<FieldElement> {
+pub fn matrix_multiply(
+    a: &Matrix<FieldElement>,
+    b: &Matrix<FieldElement>,
+) -> Result<Matrix<FieldElement>, IbeError> {
+    check_non_empty(a)?;
+    check_non_empty(b)?;
     let rows_a = a.len();
     let cols_a = a[0].len();
     let cols_b = b[0].len();
-    assert_eq!(cols_a, b.len());
+    check_dim(cols_a, b.len())?;
 
     let mut result = matrix_zero::<FieldElement>(rows_a, cols_b);
     for i in 0..rows_a {
@@ -85,12 +107,12 @@ pub fn matrix_multiply(a: &Matrix<FieldElement>, b: &Matrix<FieldElement>) -> Ma
             }
         }
     }
-    result
+    Ok(result)
 }
 
-pub fn matrix_multiply_scalar(a: &Matrix<G1>, x: FieldElement) -> Matrix<G1> {
-    assert!(!a.is_empty());
-    assert!(!a[0].is_empty());
+pub fn matrix_multiply_scalar(a: &Matrix<G1>, x: FieldElement) -> Result<Matrix<G1>, IbeError> {
+    check_non_empty(a)?;
+    check_non_empty(&a[0])?;
     let rows = a.len();
     let cols = a[0].len();
     let mut result = matrix_zero::<G1>(rows, cols);
@@ -99,16 +121,18 @@ pub fn matrix_multiply_scalar(a: &Matrix<G1>, x: FieldElement) -> Matrix<G1> {
             result[i][j] = a[i][j] * x;
         }
     }
-    result
+    Ok(result)
 }
 
-pub fn matrix_add<T: Zero + Copy + Add>(a: &Matrix<T>, b: &Matrix<T>) -> Matrix<T> {
+pub fn matrix_add<T: Zero + Copy + Add>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>, IbeError> {
+    check_non_empty(a)?;
+    check_dim(a.len(), b.len())?;
     let rows_a = a.len();
     let cols_a = a[0].len();
-    let rows_b = b.len();
-    let cols_b = b[0].len();
-    assert_eq!(rows_a, rows_b);
-    assert_eq!(cols_a, cols_b);
+    for (row_a, row_b) in a.iter().zip(b) {
+        check_dim(cols_a, row_a.len())?;
+        check_dim(cols_a, row_b.len())?;
+    }
 
     let mut result = matrix_zero::<T>(rows_a, cols_a);
     for i in 0..rows_a {
@@ -116,7 +140,65 @@ pub fn matrix_add<T: Zero + Copy + Add>(a: &Matrix<T>, b: &Matrix<T>) -> Matrix<
             result[i][j] = a[i][j] + b[i][j];
         }
     }
-    result
+    Ok(result)
+}
+
+/// `sum_i coefficients[i] * matrices[i]` for `rows x cols` matrices. Zero coefficients are
+/// skipped, so selecting matrices by 0/1 coefficients costs only additions.
+pub fn matrix_affine_combination<'a, T>(
+    coefficients: &[FieldElement],
+    matrices: impl IntoIterator<Item = &'a Matrix<T>>,
+    rows: usize,
+    cols: usize,
+) -> Result<Matrix<T>, IbeError>
+where
+    T: 'a + Zero + Copy + Add + Mul<FieldElement, Output = T>,
+{
+    let matrices: Vec<_> = matrices.into_iter().collect();
+    check_dim(coefficients.len(), matrices.len())?;
+
+    let mut result = matrix_zero::<T>(rows, cols);
+    for (c, matrix) in coefficients.iter().zip(matrices) {
+        if c.is_zero() {
+            continue;
+        }
+        if c.is_one() {
+            result = matrix_add(&result, matrix)?;
+        } else {
+            let scaled = matrix
+                .iter()
+                .map(|row| row.iter().map(|x| *x * *c).collect())
+                .collect();
+            result = matrix_add(&result, &scaled)?;
+        }
+    }
+    Ok(result)
+}
+
+/// `sum_i coefficients[i] * vectors[i]` for vectors of length `len`, skipping zero coefficients
+/// like [`matrix_affine_combination`].
+pub fn vector_affine_combination<'a, T>(
+    coefficients: &[FieldElement],
+    vectors: impl IntoIterator<Item = &'a Vec<T>>,
+    len: usize,
+) -> Result<Vec<T>, IbeError>
+where
+    T: 'a + Zero + Copy + Add + Mul<FieldElement, Output = T>,
+{
+    let vectors: Vec<_> = vectors.into_iter().collect();
+    check_dim(coefficients.len(), vectors.len())?;
+
+    let mut result = vector_zero::<T>(len);
+    for (c, vector) in coefficients.iter().zip(vectors) {
+        if c.is_zero() {
+            continue;
+        }
+        check_dim(len, vector.len())?;
+        for (r, x) in result.iter_mut().zip(vector) {
+            *r = if c.is_one() { *r + *x } else { *r + *x * *c };
+        }
+    }
+    Ok(result)
 }
 
 pub fn matrix_zero<T: Zero + Copy>(rows: usize, cols: usize) -> Matrix<T> {
@@ -147,15 +229,15 @@ pub fn matrix_lift_g2(m: &Matrix<FieldElement>) -> Matrix<G2> {
         .collect()
 }
 
-pub fn matrix_concat<T: Copy>(a: &Matrix<T>, b: &Matrix<T>) -> Matrix<T> {
-    assert_eq!(a.len(), b.len());
+pub fn matrix_concat<T: Copy>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>, IbeError> {
+    check_dim(a.len(), b.len())?;
     let mut result = Vec::with_capacity(a.len());
     for i in 0..a.len() {
         let mut row = a[i].clone();
         row.extend_from_slice(&b[i]);
         result.push(row);
     }
-    result
+    Ok(result)
 }
 
 pub fn vector_concat(a: &Vector, b: &Vector) -> Vector {
@@ -164,59 +246,65 @@ pub fn vector_concat(a: &Vector, b: &Vector) -> Vector {
     result
 }
 
-pub fn matrix_transpose<T: Zero + Copy>(matrix: &Matrix<T>) -> Matrix<T> {
-    assert!(!matrix.is_empty());
+pub fn matrix_transpose<T: Zero + Copy>(matrix: &Matrix<T>) -> Result<Matrix<T>, IbeError> {
+    check_non_empty(matrix)?;
 
     let rows = matrix.len();
     let cols = matrix[0].len();
+    matrix.iter().try_for_each(|row| check_dim(cols, row.len()))?;
     let mut result = matrix_zero::<T>(cols, rows);
     for i in 0..rows {
         for j in 0..cols {
             result[j][i] = matrix[i][j];
         }
     }
-    result
+    Ok(result)
 }
 
-pub fn group_matrix_vector_mul_msm(matrix_g1: &Matrix<G1>, vector: &Vector) -> Vec<G1> {
+pub fn group_matrix_vector_mul_msm(matrix_g1: &Matrix<G1>, vector: &Vector) -> Result<Vec<G1>, IbeError> {
     matrix_g1
         .iter()
         .map(|row| {
             let row_affine: Vec<G1Affine> = row.iter().map(|g| g.into_affine()).collect();
-            G1::msm(&row_affine, &vector).unwrap()
+            G1::msm(&row_affine, &vector).map_err(msm_dim_err(row.len(), vector.len()))
         })
         .collect()
 }
 
-pub fn group2_matrix_vector_mul_msm(matrix_g2: &Matrix<G2>, vector: &Vector) -> Vec<G2> {
+pub fn group2_matrix_vector_mul_msm(matrix_g2: &Matrix<G2>, vector: &Vector) -> Result<Vec<G2>, IbeError> {
     matrix_g2
         .iter()
         .map(|row| {
             let row_affine: Vec<G2Affine> = row.iter().map(|g| g.into_affine()).collect();
-            G2::msm(&row_affine, &vector).unwrap()
+            G2::msm(&row_affine, &vector).map_err(msm_dim_err(row.len(), vector.len()))
         })
         .collect()
 }
 
-pub fn matrix_vector_g2_mul_msm(matrix: &Matrix<FieldElement>, vector_g2: &Vec<G2>) -> Vec<G2> {
+pub fn matrix_vector_g2_mul_msm(
+    matrix: &Matrix<FieldElement>,
+    vector_g2: &Vec<G2>,
+) -> Result<Vec<G2>, IbeError> {
     let vec_g2_affine: Vec<G2Affine> = vector_g2.iter().map(|g| g.into_affine()).collect();
 
     matrix
         .iter()
-        .map(|row| G2::msm(&vec_g2_affine, &row).unwrap())
+        .map(|row| G2::msm(&vec_g2_affine, &row).map_err(msm_dim_err(vector_g2.len(), row.len())))
         .collect()
 }
 
 pub fn g1_matrix_field_multiply(
     left_g1: &Matrix<G1>,
     right_field: &Matrix<FieldElement>,
-) -> Matrix<G1> {
+) -> Result<Matrix<G1>, IbeError> {
+    check_non_empty(left_g1)?;
+    check_non_empty(right_field)?;
     let rows_left = left_g1.len();
     let cols_left = left_g1[0].len();
     let rows_right = right_field.len();
     let cols_right = right_field[0].len();
 
-    assert_eq!(cols_left, rows_right);
+    check_dim(cols_left, rows_right)?;
 
     let mut result = matrix_zero::<G1>(rows_left, cols_right);
 
@@ -231,14 +319,18 @@ pub fn g1_matrix_field_multiply(
         }
     }
 
-    result
+    Ok(result)
 }
 
-pub fn matrix_transpose_vector_mul(matrix: &Matrix<FieldElement>, vector: &Vector) -> Vector {
-    assert!(!matrix.is_empty());
+pub fn matrix_transpose_vector_mul(
+    matrix: &Matrix<FieldElement>,
+    vector: &Vector,
+) -> Result<Vector, IbeError> {
+    check_non_empty(matrix)?;
     let rows = matrix.len();
     let cols = matrix[0].len();
-    assert_eq!(rows, vector.len());
+    check_dim(rows, vector.len())?;
+    matrix.iter().try_for_each(|row| check_dim(cols, row.len()))?;
 
     let mut result = vec![FieldElement::zero(); cols];
     for j in 0..cols {
@@ -246,20 +338,22 @@ pub fn matrix_transpose_vector_mul(matrix: &Matrix<FieldElement>, vector: &Vecto
             result[j] += matrix[i][j] * vector[i];
         }
     }
-    result
+    Ok(result)
 }
 
 pub fn matrix_concat_multiply(
     a: &Matrix<FieldElement>,
     b: &Matrix<FieldElement>,
     c: &Matrix<FieldElement>,
-) -> Matrix<FieldElement> {
-    assert_eq!(a.len(), b.len());
+) -> Result<Matrix<FieldElement>, IbeError> {
+    check_non_empty(a)?;
+    check_non_empty(c)?;
+    check_dim(a.len(), b.len())?;
     let rows = a.len();
     let cols_a = a[0].len();
     let cols_b = b[0].len();
     let cols_c = c[0].len();
-    assert_eq!(cols_a + cols_b, c.len());
+    check_dim(cols_a + cols_b, c.len())?;
 
     let mut result = matrix_zero::<FieldElement>(rows, cols_c);
     for i in 0..rows {
@@ -272,15 +366,19 @@ pub fn matrix_concat_multiply(
             }
         }
     }
-    result
+    Ok(result)
 }
 
-pub fn group_matrix_transpose_vector_mul_msm(matrix_g1: &Matrix<G1>, vector: &Vector) -> Vec<G1> {
-    assert!(!matrix_g1.is_empty());
-    assert!(!matrix_g1[0].is_empty());
+pub fn group_matrix_transpose_vector_mul_msm(
+    matrix_g1: &Matrix<G1>,
+    vector: &Vector,
+) -> Result<Vec<G1>, IbeError> {
+    check_non_empty(matrix_g1)?;
+    check_non_empty(&matrix_g1[0])?;
     let rows = matrix_g1.len();
     let cols = matrix_g1[0].len();
-    assert_eq!(rows, vector.len(), "Matrix rows must equal vector length");
+    check_dim(rows, vector.len())?;
+    matrix_g1.iter().try_for_each(|row| check_dim(cols, row.len()))?;
 
     let mut result = Vec::with_capacity(cols);
 
@@ -295,24 +393,22 @@ pub fn group_matrix_transpose_vector_mul_msm(matrix_g1: &Matrix<G1>, vector: &Ve
 
         let bases_affine: Vec<G1Affine> = bases.iter().map(|g| g.into_affine()).collect();
 
-        result.push(G1::msm(&bases_affine, &scalars).unwrap());
+        result.push(G1::msm(&bases_affine, &scalars).map_err(msm_dim_err(rows, rows))?);
     }
 
-    result
+    Ok(result)
 }
 
 pub fn matrix_multiply_lift_g2(
     left: &Matrix<FieldElement>,
     right: &Matrix<FieldElement>,
-) -> Matrix<G2> {
+) -> Result<Matrix<G2>, IbeError> {
+    check_non_empty(left)?;
+    check_non_empty(right)?;
     let rows_left = left.len();
     let cols_left = left[0].len();
     let cols_right = right[0].len();
-    assert_eq!(
-        cols_left,
-        right.len(),
-        "Matrix dimensions must match for multiplication"
-    );
+    check_dim(cols_left, right.len())?;
 
     let mut result = matrix_zero::<G2>(rows_left, cols_right);
 
@@ -326,22 +422,20 @@ pub fn matrix_multiply_lift_g2(
         }
     }
 
-    result
+    Ok(result)
 }
 
 pub fn matrix_transpose_multiply_lift_g1(
     left: &Matrix<FieldElement>,
     right: &Matrix<FieldElement>,
-) -> Matrix<G1> {
+) -> Result<Matrix<G1>, IbeError> {
+    check_non_empty(left)?;
+    check_non_empty(right)?;
     let rows_left = left.len();
     let rows_result = left[0].len();
     let cols_result = right[0].len();
 
-    assert_eq!(
-        rows_left,
-        right.len(),
-        "Transposed left columns must match right rows"
-    );
+    check_dim(rows_left, right.len())?;
     let mut result = matrix_zero::<G1>(rows_result, cols_result);
 
     for i in 0..rows_result {
@@ -353,22 +447,20 @@ pub fn matrix_transpose_multiply_lift_g1(
             result[i][j] = scalar_mul_g1(sum);
         }
     }
-    result
+    Ok(result)
 }
 
 pub fn g1_matrix_transpose_multiply(
     left_g1: &Matrix<G1>,
     right_field: &Matrix<FieldElement>,
-) -> Matrix<G1> {
+) -> Result<Matrix<G1>, IbeError> {
+    check_non_empty(left_g1)?;
+    check_non_empty(right_field)?;
     let rows_left = left_g1.len();
     let rows_result = left_g1[0].len();
     let cols_result = right_field[0].len();
 
-    assert_eq!(
-        left_g1.len(),
-        right_field.len(),
-        "Transposed left columns must match right rows"
-    );
+    check_dim(left_g1.len(), right_field.len())?;
     let mut result = matrix_zero::<G1>(rows_result, cols_result);
 
     for i in 0..rows_result {
@@ -380,5 +472,5 @@ pub fn g1_matrix_transpose_multiply(
             result[i][j] = sum;
         }
     }
-    result
+    Ok(result)
 }
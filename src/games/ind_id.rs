@@ -0,0 +1,578 @@
+use std::fmt;
+
+use ark_ff::UniformRand;
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::error::IbeError;
+use crate::kem::{HIBKEM, IBKEM};
+use crate::pes::{HIBEScheme, IBEScheme};
+use crate::types::GTElement;
+
+/// Which oracles the adversary gets besides key extraction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndIdMode {
+    Cpa,
+    /// Adds the decryption oracle.
+    Cca,
+}
+
+#[derive(Debug)]
+pub enum OracleError {
+    /// A key for the challenge identity or one of its prefixes was asked for, or the challenge
+    /// identity has such a key already.
+    ChallengeIdentity,
+    /// The challenge ciphertext was submitted for decryption under the challenge identity.
+    ChallengeCiphertext,
+    /// Decryption queries in the IND-ID-CPA experiment.
+    NoDecryptionOracle,
+    /// The challenge can only be asked for once.
+    ChallengeIssued,
+    Scheme(IbeError),
+}
+
+impl fmt::Display for OracleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OracleError::ChallengeIdentity => write!(
+                f,
+                "keys for the challenge identity and its prefixes are not available"
+            ),
+            OracleError::ChallengeCiphertext => {
+                write!(f, "the challenge ciphertext cannot be decrypted")
+            }
+            OracleError::NoDecryptionOracle => {
+                write!(f, "the IND-ID-CPA experiment has no decryption oracle")
+            }
+            OracleError::ChallengeIssued => write!(f, "the challenge has already been issued"),
+            OracleError::Scheme(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for OracleError {}
+
+impl From<IbeError> for OracleError {
+    fn from(e: IbeError) -> Self {
+        OracleError::Scheme(e)
+    }
+}
+
+/// A scheme as seen by the IND-ID experiments. [`IbeGame`], [`HibeGame`], [`IbkemGame`] and
+/// [`HibkemGame`] adapt the four scheme traits.
+///
+/// For encryption schemes the adversary picks two messages and gets an encryption of one of
+/// them. For KEMs it gets the ciphertext together with either its key (bit 0) or a random one
+/// (bit 1).
+pub trait IndIdScheme {
+    type Identity: Clone + PartialEq;
+    type PK;
+    type SK;
+    type USK;
+    type CT: Clone + PartialEq;
+    /// What the adversary hands in for the challenge: two messages, or nothing for a KEM.
+    type ChallengeQuery: Clone;
+    /// What it gets back next to the ciphertext: nothing, or a real-or-random key for a KEM.
+    type ChallengeKey;
+
+    fn setup<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(Self::PK, Self::SK), IbeError>;
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &Self::SK,
+        identity: &Self::Identity,
+    ) -> Result<Self::USK, IbeError>;
+
+    fn decrypt(
+        &self,
+        pk: &Self::PK,
+        usk: &Self::USK,
+        identity: &Self::Identity,
+        ct: &Self::CT,
+    ) -> Result<GTElement, IbeError>;
+
+    fn challenge<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &Self::PK,
+        identity: &Self::Identity,
+        query: Self::ChallengeQuery,
+        bit: bool,
+    ) -> Result<(Self::CT, Self::ChallengeKey), IbeError>;
+
+    /// What the challenge ciphertext decrypts to if the bit is 0.
+    fn real_plaintext(query: &Self::ChallengeQuery, key: &Self::ChallengeKey) -> GTElement;
+
+    /// Whether a key for `prefix` gives a key for `identity`. Every identity is its own prefix.
+    fn is_prefix(prefix: &Self::Identity, identity: &Self::Identity) -> bool;
+}
+
+pub struct IbeGame<S> {
+    pub scheme: S,
+}
+
+impl<S> IbeGame<S> {
+    pub fn new(scheme: S) -> Self {
+        Self { scheme }
+    }
+}
+
+impl<S: IBEScheme> IndIdScheme for IbeGame<S>
+where
+    S::CT: Clone + PartialEq,
+{
+    type Identity = String;
+    type PK = S::MPK;
+    type SK = S::MSK;
+    type USK = S::USK;
+    type CT = S::CT;
+    type ChallengeQuery = [GTElement; 2];
+    type ChallengeKey = ();
+
+    fn setup<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(S::MPK, S::MSK), IbeError> {
+        let (msk, mpk) = self.scheme.setup(rng);
+        Ok((mpk, msk))
+    }
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &S::MSK,
+        identity: &String,
+    ) -> Result<S::USK, IbeError> {
+        self.scheme.keygen(rng, sk, identity.clone())
+    }
+
+    fn decrypt(
+        &self,
+        _pk: &S::MPK,
+        usk: &S::USK,
+        _identity: &String,
+        ct: &S::CT,
+    ) -> Result<GTElement, IbeError> {
+        self.scheme.decrypt(usk, ct)
+    }
+
+    fn challenge<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &S::MPK,
+        identity: &String,
+        query: [GTElement; 2],
+        bit: bool,
+    ) -> Result<(S::CT, ()), IbeError> {
+        let ct = self
+            .scheme
+            .encrypt(rng, &query[bit as usize], pk, identity.clone())?;
+        Ok((ct, ()))
+    }
+
+    fn real_plaintext(query: &[GTElement; 2], _key: &()) -> GTElement {
+        query[0]
+    }
+
+    fn is_prefix(prefix: &String, identity: &String) -> bool {
+        prefix == identity
+    }
+}
+
+pub struct HibeGame<S> {
+    pub scheme: S,
+}
+
+impl<S> HibeGame<S> {
+    pub fn new(scheme: S) -> Self {
+        Self { scheme }
+    }
+}
+
+impl<S: HIBEScheme> IndIdScheme for HibeGame<S>
+where
+    S::CT: Clone + PartialEq,
+{
+    type Identity = Vec<String>;
+    type PK = S::MPK;
+    type SK = S::MSK;
+    type USK = S::USK;
+    type CT = S::CT;
+    type ChallengeQuery = [GTElement; 2];
+    type ChallengeKey = ();
+
+    fn setup<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(S::MPK, S::MSK), IbeError> {
+        let (msk, mpk) = self.scheme.setup(rng);
+        Ok((mpk, msk))
+    }
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &S::MSK,
+        identity: &Vec<String>,
+    ) -> Result<S::USK, IbeError> {
+        self.scheme.keygen(rng, sk, identity.clone())
+    }
+
+    fn decrypt(
+        &self,
+        _pk: &S::MPK,
+        usk: &S::USK,
+        _identity: &Vec<String>,
+        ct: &S::CT,
+    ) -> Result<GTElement, IbeError> {
+        self.scheme.decrypt(usk, ct)
+    }
+
+    fn challenge<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &S::MPK,
+        identity: &Vec<String>,
+        query: [GTElement; 2],
+        bit: bool,
+    ) -> Result<(S::CT, ()), IbeError> {
+        let ct = self
+            .scheme
+            .encrypt(rng, &query[bit as usize], pk, identity.clone())?;
+        Ok((ct, ()))
+    }
+
+    fn real_plaintext(query: &[GTElement; 2], _key: &()) -> GTElement {
+        query[0]
+    }
+
+    fn is_prefix(prefix: &Vec<String>, identity: &Vec<String>) -> bool {
+        identity.starts_with(prefix)
+    }
+}
+
+pub struct IbkemGame<S> {
+    pub scheme: S,
+}
+
+impl<S> IbkemGame<S> {
+    pub fn new(scheme: S) -> Self {
+        Self { scheme }
+    }
+}
+
+impl<S: IBKEM> IndIdScheme for IbkemGame<S>
+where
+    S::CT: Clone + PartialEq,
+{
+    type Identity = Vec<u8>;
+    type PK = S::PK;
+    type SK = S::SK;
+    type USK = S::USK;
+    type CT = S::CT;
+    type ChallengeQuery = ();
+    type ChallengeKey = GTElement;
+
+    fn setup<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(S::PK, S::SK), IbeError> {
+        self.scheme.setup(rng)
+    }
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &S::SK,
+        identity: &Vec<u8>,
+    ) -> Result<S::USK, IbeError> {
+        self.scheme.extract(rng, sk, identity)
+    }
+
+    fn decrypt(
+        &self,
+        pk: &S::PK,
+        usk: &S::USK,
+        identity: &Vec<u8>,
+        ct: &S::CT,
+    ) -> Result<GTElement, IbeError> {
+        self.scheme.decrypt(pk, usk, identity, ct)
+    }
+
+    fn challenge<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &S::PK,
+        identity: &Vec<u8>,
+        _query: (),
+        bit: bool,
+    ) -> Result<(S::CT, GTElement), IbeError> {
+        let (ct, key) = self.scheme.encrypt(rng, pk, identity)?;
+        let key = if bit { GTElement::rand(rng) } else { key };
+        Ok((ct, key))
+    }
+
+    fn real_plaintext(_query: &(), key: &GTElement) -> GTElement {
+        *key
+    }
+
+    fn is_prefix(prefix: &Vec<u8>, identity: &Vec<u8>) -> bool {
+        prefix == identity
+    }
+}
+
+/// [`HIBKEM`] schemes in the experiment. The delegation key is public, so the adversary gets
+/// it as part of the public key.
+pub struct HibkemGame<S> {
+    pub scheme: S,
+}
+
+impl<S> HibkemGame<S> {
+    pub fn new(scheme: S) -> Self {
+        Self { scheme }
+    }
+}
+
+impl<S: HIBKEM> IndIdScheme for HibkemGame<S>
+where
+    S::CT: Clone + PartialEq,
+{
+    type Identity = Vec<Vec<u8>>;
+    type PK = (S::PK, S::DK);
+    type SK = S::SK;
+    type USK = S::USK;
+    type CT = S::CT;
+    type ChallengeQuery = ();
+    type ChallengeKey = GTElement;
+
+    fn setup<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<((S::PK, S::DK), S::SK), IbeError> {
+        let (pk, dk, sk) = self.scheme.setup(rng)?;
+        Ok(((pk, dk), sk))
+    }
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &S::SK,
+        identity: &Vec<Vec<u8>>,
+    ) -> Result<S::USK, IbeError> {
+        self.scheme.extract(rng, sk, identity)
+    }
+
+    fn decrypt(
+        &self,
+        _pk: &(S::PK, S::DK),
+        usk: &S::USK,
+        _identity: &Vec<Vec<u8>>,
+        ct: &S::CT,
+    ) -> Result<GTElement, IbeError> {
+        self.scheme.decrypt(usk, ct)
+    }
+
+    fn challenge<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &(S::PK, S::DK),
+        identity: &Vec<Vec<u8>>,
+        _query: (),
+        bit: bool,
+    ) -> Result<(S::CT, GTElement), IbeError> {
+        let (ct, key) = self.scheme.encrypt(rng, &pk.0, identity)?;
+        let key = if bit { GTElement::rand(rng) } else { key };
+        Ok((ct, key))
+    }
+
+    fn real_plaintext(_query: &(), key: &GTElement) -> GTElement {
+        *key
+    }
+
+    fn is_prefix(prefix: &Vec<Vec<u8>>, identity: &Vec<Vec<u8>>) -> bool {
+        identity.starts_with(prefix)
+    }
+}
+
+/// The IND-ID-CPA and IND-ID-CCA experiments (BF01, BB04, and GS02 for the hierarchical
+/// case): the adversary may extract keys and, under CCA, decrypt, and it wins by guessing the
+/// bit behind a single challenge.
+///
+/// Keys for the challenge identity or any of its prefixes are never handed out, whether they
+/// are asked for before or after the challenge, and the challenge ciphertext cannot be
+/// decrypted under the challenge identity.
+pub struct IndIdChallenger<'a, S: IndIdScheme, R: RngCore + CryptoRng> {
+    scheme: &'a S,
+    mode: IndIdMode,
+    pk: S::PK,
+    sk: S::SK,
+    rng: R,
+    bit: bool,
+    extracted: Vec<S::Identity>,
+    challenge: Option<(S::Identity, S::CT)>,
+}
+
+impl<'a, S: IndIdScheme, R: RngCore + CryptoRng> IndIdChallenger<'a, S, R> {
+    pub fn new(scheme: &'a S, mode: IndIdMode, mut rng: R) -> Result<Self, IbeError> {
+        let bit = rng.gen_bool(0.5);
+        Self::with_bit(scheme, mode, bit, rng)
+    }
+
+    /// A challenger with a fixed challenge bit, to run an adversary against both bits.
+    pub fn with_bit(
+        scheme: &'a S,
+        mode: IndIdMode,
+        bit: bool,
+        mut rng: R,
+    ) -> Result<Self, IbeError> {
+        let (pk, sk) = scheme.setup(&mut rng)?;
+        Ok(Self {
+            scheme,
+            mode,
+            pk,
+            sk,
+            rng,
+            bit,
+            extracted: Vec::new(),
+            challenge: None,
+        })
+    }
+
+    pub fn scheme(&self) -> &S {
+        self.scheme
+    }
+
+    pub fn mode(&self) -> IndIdMode {
+        self.mode
+    }
+
+    pub fn pk(&self) -> &S::PK {
+        &self.pk
+    }
+
+    /// The key extraction oracle.
+    pub fn extract_oracle(&mut self, identity: &S::Identity) -> Result<S::USK, OracleError> {
+        if let Some((challenge_identity, _)) = &self.challenge
+            && S::is_prefix(identity, challenge_identity)
+        {
+            return Err(OracleError::ChallengeIdentity);
+        }
+        self.extracted.push(identity.clone());
+        Ok(self.scheme.extract(&mut self.rng, &self.sk, identity)?)
+    }
+
+    /// The decryption oracle, which decrypts with a fresh key for `identity`. The challenge
+    /// ciphertext is refused under the challenge identity and each of its prefixes, as a key
+    /// for a prefix could be delegated down to the challenge identity.
+    pub fn decrypt_oracle(
+        &mut self,
+        identity: &S::Identity,
+        ct: &S::CT,
+    ) -> Result<GTElement, OracleError> {
+        if self.mode == IndIdMode::Cpa {
+            return Err(OracleError::NoDecryptionOracle);
+        }
+        if let Some((challenge_identity, challenge_ct)) = &self.challenge
+            && S::is_prefix(identity, challenge_identity)
+            && challenge_ct == ct
+        {
+            return Err(OracleError::ChallengeCiphertext);
+        }
+        let usk = self.scheme.extract(&mut self.rng, &self.sk, identity)?;
+        Ok(self.scheme.decrypt(&self.pk, &usk, identity, ct)?)
+    }
+
+    /// Issues the challenge for `identity`, unless a key for it or one of its prefixes has
+    /// been extracted.
+    pub fn challenge(
+        &mut self,
+        identity: &S::Identity,
+        query: S::ChallengeQuery,
+    ) -> Result<(S::CT, S::ChallengeKey), OracleError> {
+        if self.challenge.is_some() {
+            return Err(OracleError::ChallengeIssued);
+        }
+        if self.extracted.iter().any(|id| S::is_prefix(id, identity)) {
+            return Err(OracleError::ChallengeIdentity);
+        }
+        let (ct, key) =
+            self.scheme
+                .challenge(&mut self.rng, &self.pk, identity, query, self.bit)?;
+        self.challenge = Some((identity.clone(), ct.clone()));
+        Ok((ct, key))
+    }
+
+    /// Whether `guess` wins: the challenge has been issued and `guess` is its bit.
+    pub fn is_correct(&self, guess: bool) -> bool {
+        self.challenge.is_some() && guess == self.bit
+    }
+}
+
+pub trait IndIdAdversary<S: IndIdScheme> {
+    /// Plays against the oracles and outputs a guess for the challenge bit.
+    fn guess<R: RngCore + CryptoRng>(&mut self, challenger: &mut IndIdChallenger<'_, S, R>)
+    -> bool;
+}
+
+/// Runs the experiment with a random bit and returns whether the adversary won.
+pub fn play_ind_id<S, A, R>(
+    scheme: &S,
+    mode: IndIdMode,
+    adversary: &mut A,
+    rng: R,
+) -> Result<bool, IbeError>
+where
+    S: IndIdScheme,
+    A: IndIdAdversary<S>,
+    R: RngCore + CryptoRng,
+{
+    let mut challenger = IndIdChallenger::new(scheme, mode, rng)?;
+    let guess = adversary.guess(&mut challenger);
+    Ok(challenger.is_correct(guess))
+}
+
+/// Runs the experiment for both bits and returns whether the adversary guessed right both
+/// times, i.e. whether it breaks the scheme rather than guessing.
+pub fn distinguishes<S, A, R>(
+    scheme: &S,
+    mode: IndIdMode,
+    adversary: &mut A,
+    rng: &mut R,
+) -> Result<bool, IbeError>
+where
+    S: IndIdScheme,
+    A: IndIdAdversary<S>,
+    R: RngCore + CryptoRng,
+{
+    for bit in [false, true] {
+        let mut challenger = IndIdChallenger::with_bit(scheme, mode, bit, &mut *rng)?;
+        let guess = adversary.guess(&mut challenger);
+        if !challenger.is_correct(guess) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// A chosen-ciphertext attack on malleable ciphertexts: asks for the challenge on `identity`
+/// and for the decryption of `maul(ct)`. If the scheme answers with `relate` applied to the
+/// bit 0 plaintext, it guesses 0, otherwise 1. Rejected queries leave it guessing 0.
+pub struct MaulingAdversary<S: IndIdScheme, F, G> {
+    pub identity: S::Identity,
+    pub query: S::ChallengeQuery,
+    pub maul: F,
+    pub relate: G,
+}
+
+impl<S, F, G> IndIdAdversary<S> for MaulingAdversary<S, F, G>
+where
+    S: IndIdScheme,
+    F: Fn(&S::CT) -> S::CT,
+    G: Fn(&GTElement) -> GTElement,
+{
+    fn guess<R: RngCore + CryptoRng>(
+        &mut self,
+        challenger: &mut IndIdChallenger<'_, S, R>,
+    ) -> bool {
+        let Ok((ct, key)) = challenger.challenge(&self.identity, self.query.clone()) else {
+            return false;
+        };
+        let expected = (self.relate)(&S::real_plaintext(&self.query, &key));
+
+        match challenger.decrypt_oracle(&self.identity, &(self.maul)(&ct)) {
+            Ok(decrypted) => decrypted != expected,
+            Err(_) => false,
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Security experiments as executable games. A challenger keeps the secret state of an
+//! experiment and exposes its oracles, together with the bookkeeping that decides whether an
+//! adversary won. Scripted adversaries replay known attacks against it, so the oracle
+//! semantics of the schemes are pinned down by tests rather than by the happy path alone.
+
+pub mod ind_id;
+pub mod uf_cmva;
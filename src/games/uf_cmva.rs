@@ -0,0 +1,397 @@
+use rand::{CryptoRng, RngCore};
+
+use crate::affine_mac::{AffineMAC, AffineMac, LevelAffineMac, Tag};
+use crate::affine_mac_levels1::{AffineMacLevels1, AffineMacLevels1SecretKey, AffineMacLevels1Tag};
+use crate::affine_mac_levels2::{AffineMacLevels2, AffineMacLevels2SecretKey, AffineMacLevels2Tag};
+use crate::error::IbeError;
+
+/// A MAC as seen by the UF-CMVA experiment: keys, tags and messages are opaque, and the game
+/// only needs to compare messages and flip their bits.
+pub trait UfCmvaMac {
+    type SecretKey;
+    type Tag;
+    type Message: Clone + PartialEq;
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Self::SecretKey;
+
+    fn tag<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &Self::SecretKey,
+        message: &Self::Message,
+    ) -> Result<Self::Tag, IbeError>;
+
+    fn verify(
+        &self,
+        sk: &Self::SecretKey,
+        message: &Self::Message,
+        tag: &Self::Tag,
+    ) -> Result<(), IbeError>;
+
+    /// `message` with its `bit`-th bit flipped, counting across all levels. Bits past the end
+    /// of `message` are a logic error: they panic in debug builds and change nothing otherwise.
+    fn flip_bit(message: &Self::Message, bit: usize) -> Self::Message;
+}
+
+/// The UF-CMVA experiment (BKP14, HJP18): the adversary may ask for tags on messages of its
+/// choice and check candidate tags, and it wins by getting a tag on a fresh message accepted.
+///
+/// Verification queries on messages that were tagged before are answered, but never count as
+/// a forgery.
+pub struct UfCmvaChallenger<'a, M: UfCmvaMac, R: RngCore + CryptoRng> {
+    mac: &'a M,
+    sk: M::SecretKey,
+    rng: R,
+    tagged: Vec<M::Message>,
+    forged: bool,
+}
+
+impl<'a, M: UfCmvaMac, R: RngCore + CryptoRng> UfCmvaChallenger<'a, M, R> {
+    pub fn new(mac: &'a M, mut rng: R) -> Self {
+        let sk = mac.gen_mac(&mut rng);
+        Self::with_key(mac, sk, rng)
+    }
+
+    /// A challenger for a given key, e.g. to check the bookkeeping with a known forgery.
+    pub fn with_key(mac: &'a M, sk: M::SecretKey, rng: R) -> Self {
+        Self {
+            mac,
+            sk,
+            rng,
+            tagged: Vec::new(),
+            forged: false,
+        }
+    }
+
+    pub fn mac(&self) -> &M {
+        self.mac
+    }
+
+    /// The tag oracle. `message` no longer counts as fresh afterwards, even if tagging fails.
+    pub fn tag_oracle(&mut self, message: &M::Message) -> Result<M::Tag, IbeError> {
+        self.tagged.push(message.clone());
+        self.mac.tag(&mut self.rng, &self.sk, message)
+    }
+
+    /// The verification oracle. Returns whether `tag` is valid for `message`.
+    pub fn verify_oracle(&mut self, message: &M::Message, tag: &M::Tag) -> bool {
+        let valid = self.mac.verify(&self.sk, message, tag).is_ok();
+        if valid && self.is_fresh(message) {
+            self.forged = true;
+        }
+        valid
+    }
+
+    /// Whether `message` has never been queried to the tag oracle.
+    pub fn is_fresh(&self, message: &M::Message) -> bool {
+        !self.tagged.contains(message)
+    }
+
+    /// Whether `(message, tag)` is a forgery: valid, and on a message that was never tagged.
+    pub fn is_forgery(&self, message: &M::Message, tag: &M::Tag) -> bool {
+        self.is_fresh(message) && self.mac.verify(&self.sk, message, tag).is_ok()
+    }
+
+    pub fn adversary_won(&self) -> bool {
+        self.forged
+    }
+}
+
+pub trait UfCmvaAdversary<M: UfCmvaMac> {
+    /// Plays against the oracles and may output a final forgery attempt.
+    fn forge<R: RngCore + CryptoRng>(
+        &mut self,
+        challenger: &mut UfCmvaChallenger<'_, M, R>,
+    ) -> Option<(M::Message, M::Tag)>;
+}
+
+/// Runs the experiment with a fresh key and returns whether the adversary won.
+pub fn play_uf_cmva<M, A, R>(mac: &M, adversary: &mut A, rng: R) -> bool
+where
+    M: UfCmvaMac,
+    A: UfCmvaAdversary<M>,
+    R: RngCore + CryptoRng,
+{
+    let mut challenger = UfCmvaChallenger::new(mac, rng);
+    let forgery = adversary.forge(&mut challenger);
+
+    let forged = forgery.is_some_and(|(message, tag)| challenger.is_forgery(&message, &tag));
+    forged || challenger.adversary_won()
+}
+
+/// Asks for a tag on `message` and hands it back unchanged. The tag verifies, but the message
+/// is not fresh.
+pub struct ReplayAdversary<Message> {
+    pub message: Message,
+}
+
+impl<M: UfCmvaMac> UfCmvaAdversary<M> for ReplayAdversary<M::Message> {
+    fn forge<R: RngCore + CryptoRng>(
+        &mut self,
+        challenger: &mut UfCmvaChallenger<'_, M, R>,
+    ) -> Option<(M::Message, M::Tag)> {
+        let tag = challenger.tag_oracle(&self.message).ok()?;
+        challenger.verify_oracle(&self.message, &tag);
+        Some((self.message.clone(), tag))
+    }
+}
+
+/// Asks for a tag on `message` and submits it for `message` with one of `bits` flipped.
+pub struct BitFlipAdversary<Message> {
+    pub message: Message,
+    pub bits: Vec<usize>,
+}
+
+impl<M: UfCmvaMac> UfCmvaAdversary<M> for BitFlipAdversary<M::Message> {
+    fn forge<R: RngCore + CryptoRng>(
+        &mut self,
+        challenger: &mut UfCmvaChallenger<'_, M, R>,
+    ) -> Option<(M::Message, M::Tag)> {
+        let tag = challenger.tag_oracle(&self.message).ok()?;
+        for &bit in &self.bits {
+            challenger.verify_oracle(&M::flip_bit(&self.message, bit), &tag);
+        }
+        let last = self.bits.last()?;
+        Some((M::flip_bit(&self.message, *last), tag))
+    }
+}
+
+/// Asks for tags on two messages and combines their parts into tags on mixed messages: across
+/// levels for the hierarchical MACs, and `[t]_2` of one tag with `[u]_2` of the other.
+pub struct TagMixAdversary<Message> {
+    pub first: Message,
+    pub second: Message,
+}
+
+fn mix_tags<M, R>(
+    challenger: &mut UfCmvaChallenger<'_, M, R>,
+    first: &M::Message,
+    second: &M::Message,
+    candidates: impl Fn(&M::Tag, &M::Tag) -> Vec<(M::Message, M::Tag)>,
+) -> Option<(M::Message, M::Tag)>
+where
+    M: UfCmvaMac,
+    R: RngCore + CryptoRng,
+{
+    let first_tag = challenger.tag_oracle(first).ok()?;
+    let second_tag = challenger.tag_oracle(second).ok()?;
+
+    let mut candidates = candidates(&first_tag, &second_tag);
+    for (message, tag) in &candidates {
+        challenger.verify_oracle(message, tag);
+    }
+    candidates.pop()
+}
+
+impl UfCmvaAdversary<AffineMAC> for TagMixAdversary<Vec<u8>> {
+    fn forge<R: RngCore + CryptoRng>(
+        &mut self,
+        challenger: &mut UfCmvaChallenger<'_, AffineMAC, R>,
+    ) -> Option<(Vec<u8>, Tag)> {
+        // a single level, so only the message halves can be mixed
+        let half = self.first.len() / 2;
+        let mut mixed = self.first[..half].to_vec();
+        mixed.extend_from_slice(&self.second[half..]);
+
+        mix_tags(challenger, &self.first, &self.second, |a, b| {
+            vec![
+                (
+                    mixed.clone(),
+                    Tag {
+                        t_g2: a.t_g2.clone(),
+                        u_g2: b.u_g2.clone(),
+                    },
+                ),
+                (
+                    mixed.clone(),
+                    Tag {
+                        t_g2: b.t_g2.clone(),
+                        u_g2: a.u_g2.clone(),
+                    },
+                ),
+            ]
+        })
+    }
+}
+
+/// The mixed messages `[a_1, b_2, ...]` and `[b_1, a_2, ...]`, and `[a_1]` as a prefix of `a`.
+fn mixed_levels(first: &[Vec<u8>], second: &[Vec<u8>]) -> Vec<Vec<Vec<u8>>> {
+    let mut mixed = vec![first.to_vec(), second.to_vec()];
+    for (i, (a, b)) in first.iter().zip(second).enumerate() {
+        let (x, y) = if i % 2 == 0 { (a, b) } else { (b, a) };
+        mixed[0][i] = x.clone();
+        mixed[1][i] = y.clone();
+    }
+    mixed.push(first[..1].to_vec());
+    mixed
+}
+
+impl UfCmvaAdversary<AffineMacLevels1> for TagMixAdversary<Vec<Vec<u8>>> {
+    fn forge<R: RngCore + CryptoRng>(
+        &mut self,
+        challenger: &mut UfCmvaChallenger<'_, AffineMacLevels1, R>,
+    ) -> Option<(Vec<Vec<u8>>, AffineMacLevels1Tag)> {
+        let mixed = mixed_levels(&self.first, &self.second);
+
+        mix_tags(challenger, &self.first, &self.second, |a, b| {
+            let mut candidates = Vec::new();
+            for message in &mixed {
+                for (t, u) in [(a, a), (a, b), (b, a), (b, b)] {
+                    let tag = AffineMacLevels1Tag {
+                        t_g2: t.t_g2.clone(),
+                        u_g2: u.u_g2.clone(),
+                    };
+                    candidates.push((message.clone(), tag));
+                }
+            }
+            candidates
+        })
+    }
+}
+
+impl UfCmvaAdversary<AffineMacLevels2> for TagMixAdversary<Vec<Vec<u8>>> {
+    fn forge<R: RngCore + CryptoRng>(
+        &mut self,
+        challenger: &mut UfCmvaChallenger<'_, AffineMacLevels2, R>,
+    ) -> Option<(Vec<Vec<u8>>, AffineMacLevels2Tag)> {
+        let mixed = mixed_levels(&self.first, &self.second);
+
+        mix_tags(challenger, &self.first, &self.second, |a, b| {
+            let mut candidates = Vec::new();
+            for message in &mixed {
+                // the per-level t_i follow the levels of the mixed message
+                let t_g2 = (0..message.len())
+                    .map(|i| {
+                        let from = if message[i] == self.first[i] { a } else { b };
+                        from.t_g2[i].clone()
+                    })
+                    .collect::<Vec<_>>();
+                for u in [a, b] {
+                    let tag = AffineMacLevels2Tag {
+                        t_g2: t_g2.clone(),
+                        u_g2: u.u_g2.clone(),
+                    };
+                    candidates.push((message.clone(), tag));
+                }
+            }
+            candidates
+        })
+    }
+}
+
+fn flip_bit_in(bytes: &mut [u8], bit: usize) {
+    debug_assert!(bit < 8 * bytes.len(), "bit {} outside of the message", bit);
+    // bits are numbered as in `bit_at`, most significant first
+    if let Some(byte) = bytes.get_mut(bit / 8) {
+        *byte ^= 0x80 >> (bit % 8);
+    }
+}
+
+impl<M: AffineMac> UfCmvaMac for M {
+    type SecretKey = M::SecretKey;
+    type Tag = Tag;
+    type Message = Vec<u8>;
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> M::SecretKey {
+        AffineMac::gen_mac(self, rng)
+    }
+
+    fn tag<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &M::SecretKey,
+        message: &Vec<u8>,
+    ) -> Result<Tag, IbeError> {
+        AffineMac::tag(self, rng, sk, message)
+    }
+
+    fn verify(&self, sk: &M::SecretKey, message: &Vec<u8>, tag: &Tag) -> Result<(), IbeError> {
+        AffineMac::verify(self, sk, message, tag)
+    }
+
+    fn flip_bit(message: &Vec<u8>, bit: usize) -> Vec<u8> {
+        let mut flipped = message.clone();
+        flip_bit_in(&mut flipped, bit);
+        flipped
+    }
+}
+
+fn flip_bit_in_levels(message: &[Vec<u8>], bit: usize) -> Vec<Vec<u8>> {
+    let total: usize = message.iter().map(|level| 8 * level.len()).sum();
+    debug_assert!(bit < total, "bit {} outside of the message", bit);
+
+    let mut flipped = message.to_vec();
+    let mut bit = bit;
+    for level in &mut flipped {
+        if bit < level.len() * 8 {
+            flip_bit_in(level, bit);
+            break;
+        }
+        bit -= level.len() * 8;
+    }
+    flipped
+}
+
+impl UfCmvaMac for AffineMacLevels1 {
+    type SecretKey = AffineMacLevels1SecretKey;
+    type Tag = AffineMacLevels1Tag;
+    type Message = Vec<Vec<u8>>;
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> AffineMacLevels1SecretKey {
+        LevelAffineMac::gen_mac(self, rng)
+    }
+
+    fn tag<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &AffineMacLevels1SecretKey,
+        message: &Vec<Vec<u8>>,
+    ) -> Result<AffineMacLevels1Tag, IbeError> {
+        LevelAffineMac::tag(self, rng, sk, message)
+    }
+
+    fn verify(
+        &self,
+        sk: &AffineMacLevels1SecretKey,
+        message: &Vec<Vec<u8>>,
+        tag: &AffineMacLevels1Tag,
+    ) -> Result<(), IbeError> {
+        LevelAffineMac::verify(self, sk, message, tag)
+    }
+
+    fn flip_bit(message: &Vec<Vec<u8>>, bit: usize) -> Vec<Vec<u8>> {
+        flip_bit_in_levels(message, bit)
+    }
+}
+
+impl UfCmvaMac for AffineMacLevels2 {
+    type SecretKey = AffineMacLevels2SecretKey;
+    type Tag = AffineMacLevels2Tag;
+    type Message = Vec<Vec<u8>>;
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> AffineMacLevels2SecretKey {
+        LevelAffineMac::gen_mac(self, rng)
+    }
+
+    fn tag<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &AffineMacLevels2SecretKey,
+        message: &Vec<Vec<u8>>,
+    ) -> Result<AffineMacLevels2Tag, IbeError> {
+        LevelAffineMac::tag(self, rng, sk, message)
+    }
+
+    fn verify(
+        &self,
+        sk: &AffineMacLevels2SecretKey,
+        message: &Vec<Vec<u8>>,
+        tag: &AffineMacLevels2Tag,
+    ) -> Result<(), IbeError> {
+        LevelAffineMac::verify(self, sk, message, tag)
+    }
+
+    fn flip_bit(message: &Vec<Vec<u8>>, bit: usize) -> Vec<Vec<u8>> {
+        flip_bit_in_levels(message, bit)
+    }
+}
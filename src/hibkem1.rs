@@ -1,10 +1,16 @@
-use crate::affine_mac_levels1::{AffineMacLevels1, AffineMacLevels1SecretKey, AffineMacLevels1Tag};
-use crate::bit_utils::bit_at;
+use crate::affine_mac::{LevelAffineMac, TagContext, level_affine_combination};
+use crate::affine_mac_levels1::{AffineMacLevels1, AffineMacLevels1Tag};
+use crate::error::{IbeError, check_dim, check_hierarchical_identity, check_levels, check_non_empty};
 use crate::field_utils::*;
 use crate::group_functions::{multi_pairing, pairing};
+use crate::kem::HIBKEM;
+use crate::serialization::*;
 use crate::types::*;
 use ark_bls12_381::{G1Projective as G1, G2Projective as G2};
 use ark_ec::PrimeGroup;
+use ark_serialize::{Compress, SerializationError, Validate};
+use rand::{CryptoRng, RngCore};
+use std::io::{Read, Write};
 
 pub struct HIBKEM1PublicKey {
     pub a_g1: Matrix<G1>,
@@ -18,8 +24,8 @@ pub struct HIBKEM1DelegationKey {
     pub e_g2: Vec<Vec<Vec<Matrix<G2>>>>,
 }
 
-pub struct HIBKEM1SecretKey {
-    pub sk_mac: AffineMacLevels1SecretKey,
+pub struct HIBKEM1SecretKey<M: LevelAffineMac = AffineMacLevels1> {
+    pub sk_mac: M::SecretKey,
     pub y_matrices: Vec<Vec<Vec<Matrix<FieldElement>>>>,
     pub y_prime: Vector,
 }
@@ -35,32 +41,70 @@ pub struct HIBKEM1UserDelegationKey {
     pub e_g2: Vec<Vec<Vec<Vec<G2>>>>,
 }
 
+#[derive(Clone, PartialEq)]
 pub struct HIBKEM1Ciphertext {
     pub c0_g1: Vec<G1>,
     pub c1_g1: Vec<G1>,
 }
 
-pub struct HIBKEM1 {
+/// The HIBKEM transform of a hierarchical affine MAC `M` whose tags share one `t` across all
+/// levels. It uses only the level functions and shapes of [`LevelAffineMac`].
+pub struct HIBKEM1<M: LevelAffineMac = AffineMacLevels1> {
     pub k: usize,
     pub max_levels: usize,
     pub identity_len: usize,
-    pub mac: AffineMacLevels1,
+    pub mac: M,
+}
+
+/// A MAC with one `t` per tag, which [`HIBKEM1`] is built on, and with a serializable instance:
+/// the name of the resulting HIBKEM and the kinds of its objects.
+pub trait HIBKEM1Mac:
+    LevelAffineMac<Tag = AffineMacLevels1Tag, TagContext = TagContext> + KemParams
+{
+    const NAME: &'static str;
+    const PUBLIC_KEY_KIND: u8;
+    const DELEGATION_KEY_KIND: u8;
+    const SECRET_KEY_KIND: u8;
+    const USER_SECRET_KEY_KIND: u8;
+    const USER_DELEGATION_KEY_KIND: u8;
+    const CIPHERTEXT_KIND: u8;
+}
+
+impl HIBKEM1Mac for AffineMacLevels1 {
+    const NAME: &'static str = "hibkem1";
+    const PUBLIC_KEY_KIND: u8 = KIND_HIBKEM1_PUBLIC_KEY;
+    const DELEGATION_KEY_KIND: u8 = KIND_HIBKEM1_DELEGATION_KEY;
+    const SECRET_KEY_KIND: u8 = KIND_HIBKEM1_SECRET_KEY;
+    const USER_SECRET_KEY_KIND: u8 = KIND_HIBKEM1_USER_SECRET_KEY;
+    const USER_DELEGATION_KEY_KIND: u8 = KIND_HIBKEM1_USER_DELEGATION_KEY;
+    const CIPHERTEXT_KIND: u8 = KIND_HIBKEM1_CIPHERTEXT;
 }
 
 impl HIBKEM1 {
     pub fn new(k: usize, max_levels: usize, identity_len: usize) -> Self {
+        Self::with_mac(AffineMacLevels1::new(k, max_levels, identity_len))
+    }
+}
+
+impl<M: LevelAffineMac<Tag = AffineMacLevels1Tag, TagContext = TagContext>> HIBKEM1<M> {
+    pub fn with_mac(mac: M) -> Self {
         Self {
-            k,
-            max_levels,
-            identity_len,
-            mac: AffineMacLevels1::new(k, max_levels, identity_len),
+            k: mac.k(),
+            max_levels: mac.max_levels(),
+            identity_len: mac.identity_len(),
+            mac,
         }
     }
 
-    pub fn setup(&self) -> (HIBKEM1PublicKey, HIBKEM1DelegationKey, HIBKEM1SecretKey) {
-        let sk_mac = self.mac.gen_mac();
+    pub fn setup<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(HIBKEM1PublicKey, HIBKEM1DelegationKey, HIBKEM1SecretKey<M>), IbeError> {
+        let sk_mac = self.mac.gen_mac(rng);
+        let b_matrix = self.mac.b(&sk_mac);
+        let n = self.mac.n();
 
-        let a_matrix = random_matrix(2 * self.k, self.k); 
+        let a_matrix = random_matrix(rng, self.k + self.mac.eta(), self.k);
 
         let mut y_matrices = Vec::with_capacity(self.max_levels);
         let mut z_g1 = Vec::with_capacity(self.max_levels);
@@ -68,33 +112,35 @@ impl HIBKEM1 {
         let mut e_g2 = Vec::with_capacity(self.max_levels);
 
         for i in 1..=self.max_levels {
-            let num_j = i * self.identity_len;
-            let mut y_j = Vec::with_capacity(num_j);
-            let mut z_j = Vec::with_capacity(num_j);
-            let mut d_j = Vec::with_capacity(num_j);
-            let mut e_j = Vec::with_capacity(num_j);
+            let x_i = self.mac.x_matrices(&sk_mac, i);
+            check_dim(self.mac.l(i), 2 * x_i.len())?;
 
-            for j in 1..=num_j {
+            let mut y_j = Vec::with_capacity(x_i.len());
+            let mut z_j = Vec::with_capacity(x_i.len());
+            let mut d_j = Vec::with_capacity(x_i.len());
+            let mut e_j = Vec::with_capacity(x_i.len());
+
+            for x_pair in x_i {
                 let mut y_b = Vec::with_capacity(2);
                 let mut z_b = Vec::with_capacity(2);
                 let mut d_b = Vec::with_capacity(2);
                 let mut e_b = Vec::with_capacity(2);
 
-                for b in 0..2 {
-                    let y_matrix = random_matrix(self.k, 3 * self.k);
+                for x_matrix in x_pair {
+                    let y_matrix = random_matrix(rng, self.k, n);
                     y_b.push(y_matrix.clone());
 
-                    let x_t = matrix_transpose(&sk_mac.x_matrices[i - 1][j - 1][b]);
-                    let y_t = matrix_transpose(&y_matrix);
-                    let y_x = matrix_concat(&y_t, &x_t);
+                    let x_t = matrix_transpose(x_matrix)?;
+                    let y_t = matrix_transpose(&y_matrix)?;
+                    let y_x = matrix_concat(&y_t, &x_t)?;
                     
-                    let z_matrix = matrix_multiply(&y_x, &a_matrix);
+                    let z_matrix = matrix_multiply(&y_x, &a_matrix)?;
                     z_b.push(matrix_lift_g1(&z_matrix));
 
-                    let d_matrix = matrix_multiply(&sk_mac.x_matrices[i - 1][j - 1][b], &sk_mac.b);
+                    let d_matrix = matrix_multiply(x_matrix, b_matrix)?;
                     d_b.push(matrix_lift_g2(&d_matrix));
 
-                    let e_matrix = matrix_multiply(&y_matrix, &sk_mac.b);
+                    let e_matrix = matrix_multiply(&y_matrix, b_matrix)?;
                     e_b.push(matrix_lift_g2(&e_matrix));
                 }
 
@@ -110,13 +156,13 @@ impl HIBKEM1 {
             e_g2.push(e_j);
         }
 
-        let y_prime = random_vector(self.k);
-        let y_x_prime = vector_concat(&y_prime, &sk_mac.x_prime);
+        let y_prime = random_vector(rng, self.k);
+        let y_x_prime = vector_concat(&y_prime, self.mac.x_prime(&sk_mac));
 
-        let z_field = vector_matrix_mul(&y_x_prime, &a_matrix);
+        let z_field = vector_matrix_mul(&y_x_prime, &a_matrix)?;
         let z_prime_g1 = vector_lift_g1(&z_field);
 
-        let b_g2 = matrix_lift_g2(&sk_mac.b);
+        let b_g2 = matrix_lift_g2(b_matrix);
         let a_g1 = matrix_lift_g1(&a_matrix);
 
         let pk = HIBKEM1PublicKey {
@@ -131,37 +177,31 @@ impl HIBKEM1 {
             y_prime,
         };
 
-        (pk, dk, sk)
+        Ok((pk, dk, sk))
     }
 
-    pub fn extract(
+    pub fn extract<R: RngCore + CryptoRng>(
         &self,
-        sk: &HIBKEM1SecretKey,
+        rng: &mut R,
+        sk: &HIBKEM1SecretKey<M>,
         id: &[Vec<u8>],
-    ) -> (HIBKEM1UserSecretKey, HIBKEM1UserDelegationKey) {
+    ) -> Result<(HIBKEM1UserSecretKey, HIBKEM1UserDelegationKey), IbeError> {
+        check_hierarchical_identity(id, self.max_levels, self.identity_len)?;
         let p = id.len();
-        assert!(p > 0 && p <= self.max_levels);
+        let n = self.mac.n();
 
-        let tag: AffineMacLevels1Tag = self.mac.tag(&sk.sk_mac, id);
-        let t_field = tag.t_field;
+        let (tag, context) = self.mac.tag_with_context(rng, &sk.sk_mac, id)?;
+        let t_field = context.t_field();
         let t_g2 = tag.t_g2;
         let u_g2 = tag.u_g2;
 
-        let mut v = sk.y_prime.clone();
-
+        // (sum_{i <= p} sum_j f_{i,j}(id) * y_{i,j}) * t + y'
+        let mut y_m = matrix_zero(self.k, n);
         for i in 1..=p {
-            for j in 1..=(i * self.identity_len) {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_in_msg = (j - 1) % self.identity_len;
-                let b = bit_at(bit_in_msg, &id[msg_idx]);
-
-                let y_i_j_b = &sk.y_matrices[i - 1][j - 1][b];
-
-                let y_t = matrix_vector_mul(y_i_j_b, &t_field);
-
-                v = vector_add(&v, &y_t);
-            }
+            let y_i = level_affine_combination(&self.mac, i, id, &sk.y_matrices, self.k, n)?;
+            y_m = matrix_add(&y_m, &y_i)?;
         }
+        let v = vector_add(&sk.y_prime, &matrix_vector_mul(&y_m, t_field));
 
         let v_g2 = vector_lift_g2(&v);
 
@@ -169,22 +209,20 @@ impl HIBKEM1 {
         let mut e_g2 = Vec::with_capacity(self.max_levels - p);
 
         for i in (p + 1)..=self.max_levels {
-            let num_j = i * self.identity_len;
-            let mut i_d = Vec::with_capacity(num_j);
-            let mut i_e = Vec::with_capacity(num_j);
+            let x_i = self.mac.x_matrices(&sk.sk_mac, i);
+            let mut i_d = Vec::with_capacity(x_i.len());
+            let mut i_e = Vec::with_capacity(x_i.len());
 
-            for j in 1..=num_j {
+            for (x_pair, y_pair) in x_i.iter().zip(&sk.y_matrices[i - 1]) {
                 let mut j_d = Vec::with_capacity(2);
                 let mut j_e = Vec::with_capacity(2);
 
-                for b in 0..=1 {
-                    let x_i_j_b = &sk.sk_mac.x_matrices[i - 1][j - 1][b];
-                    let d_field = matrix_vector_mul(x_i_j_b, &t_field);
+                for (x_i_j_b, y_i_j_b) in x_pair.iter().zip(y_pair) {
+                    let d_field = matrix_vector_mul(x_i_j_b, t_field);
                     let d_g2_v = vector_lift_g2(&d_field);
                     j_d.push(d_g2_v);
 
-                    let y_i_j_b = &sk.y_matrices[i - 1][j - 1][b];
-                    let e_field = matrix_vector_mul(y_i_j_b, &t_field);
+                    let e_field = matrix_vector_mul(y_i_j_b, t_field);
                     let e_g2_v = vector_lift_g2(&e_field);
                     j_e.push(e_g2_v);
                 }
@@ -200,77 +238,66 @@ impl HIBKEM1 {
         let usk = HIBKEM1UserSecretKey { t_g2, u_g2, v_g2 };
         let udk = HIBKEM1UserDelegationKey { d_g2, e_g2 };
 
-        (usk, udk)
+        Ok((usk, udk))
     }
 
-    pub fn delegate(
+    pub fn delegate<R: RngCore + CryptoRng>(
         &self,
+        rng: &mut R,
         dk: &HIBKEM1DelegationKey,
         usk: &HIBKEM1UserSecretKey,
         udk: &HIBKEM1UserDelegationKey,
         id_prefix: &[Vec<u8>],
         id_next: Vec<u8>,
-    ) -> (HIBKEM1UserSecretKey, HIBKEM1UserDelegationKey) {
+    ) -> Result<(HIBKEM1UserSecretKey, HIBKEM1UserDelegationKey), IbeError> {
         let p = id_prefix.len();
-        assert!(p > 0 && p < self.max_levels);
-
-        let s_prime = random_vector(self.k);
-
-        let bs_prime = group2_matrix_vector_mul_msm(&dk.b_g2, &s_prime);
-        let t_prime_g2 = vector_add_g2(&usk.t_g2, &bs_prime);
+        if p == 0 {
+            return Err(IbeError::DepthOutOfRange {
+                depth: p,
+                max: self.max_levels,
+            });
+        }
 
         let mut id_prime = id_prefix.to_vec();
         id_prime.push(id_next);
+        check_hierarchical_identity(&id_prime, self.max_levels, self.identity_len)?;
 
-        let mut u_prime_g2 = usk.u_g2.clone();
-
-        let num_j = (p + 1) * self.identity_len;
-        for j in 1..=num_j {
-            let msg_idx = (j - 1) / self.identity_len;
-            let bit_idx = (j - 1) % self.identity_len;
-            let b = bit_at(bit_idx, &id_prime[msg_idx]);
-
-            let d_vec_g2 = &udk.d_g2[0][j - 1][b];
-            u_prime_g2 = vector_add_g2(&u_prime_g2, &d_vec_g2);
-        }
+        let (n, eta) = (self.mac.n(), self.mac.eta());
+        check_levels(&dk.d_g2, 1, self.max_levels, self.identity_len)?;
+        check_levels(&dk.e_g2, 1, self.max_levels, self.identity_len)?;
+        check_levels(&udk.d_g2, p + 1, self.max_levels, self.identity_len)?;
+        check_levels(&udk.e_g2, p + 1, self.max_levels, self.identity_len)?;
+        check_dim(n, usk.t_g2.len())?;
+        check_dim(eta, usk.u_g2.len())?;
+        check_dim(self.k, usk.v_g2.len())?;
 
-        for i in 1..=(p + 1) {
-            let num_j = i * self.identity_len;
-            for j in 1..=num_j {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_idx = (j - 1) % self.identity_len;
-                let b = bit_at(bit_idx, &id_prime[msg_idx]);
+        let s_prime = random_vector(rng, self.k);
 
-                let d_cap_g2 = &dk.d_g2[i - 1][j - 1][b];
-                let d_cap_s_prime = group2_matrix_vector_mul_msm(&d_cap_g2, &s_prime);
-                u_prime_g2 = vector_add_g2(&u_prime_g2, &d_cap_s_prime);
-            }
-        }
-
-        let mut v_prime_g2 = usk.v_g2.clone();
+        let bs_prime = group2_matrix_vector_mul_msm(&dk.b_g2, &s_prime)?;
+        let t_prime_g2 = vector_add_g2(&usk.t_g2, &bs_prime);
 
-        let num_j_p1 = (p + 1) * self.identity_len;
-        for j in 1..=num_j_p1 {
-            let msg_idx = (j - 1) / self.identity_len;
-            let bit_idx = (j - 1) % self.identity_len;
-            let b = bit_at(bit_idx, &id_prime[msg_idx]);
+        // the user key covers levels 1..=p, level p + 1 comes from the user delegation key
+        let f_next = self.mac.f(p + 1, &id_prime);
+        let d_next = vector_affine_combination(&f_next, udk.d_g2[0].iter().flatten(), eta)?;
+        let e_next = vector_affine_combination(&f_next, udk.e_g2[0].iter().flatten(), self.k)?;
 
-            let e_vec_g2 = &udk.e_g2[0][j - 1][b];
-            v_prime_g2 = vector_add_g2(&v_prime_g2, &e_vec_g2);
+        // rerandomize with t' = t + B s' across all levels 1..=p + 1
+        let mut d_cap_g2 = matrix_zero::<G2>(eta, self.k);
+        let mut e_cap_g2 = matrix_zero::<G2>(self.k, self.k);
+        for i in 1..=(p + 1) {
+            let d_i = level_affine_combination(&self.mac, i, &id_prime, &dk.d_g2, eta, self.k)?;
+            d_cap_g2 = matrix_add(&d_cap_g2, &d_i)?;
+            let e_i = level_affine_combination(&self.mac, i, &id_prime, &dk.e_g2, self.k, self.k)?;
+            e_cap_g2 = matrix_add(&e_cap_g2, &e_i)?;
         }
 
-        for i in 1..=(p + 1) {
-            let num_j = i * self.identity_len;
-            for j in 1..=num_j {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_idx = (j - 1) % self.identity_len;
-                let b = bit_at(bit_idx, &id_prime[msg_idx]);
+        let u_prime_g2 = vector_add_g2(&usk.u_g2, &d_next);
+        let d_cap_s_prime = group2_matrix_vector_mul_msm(&d_cap_g2, &s_prime)?;
+        let u_prime_g2 = vector_add_g2(&u_prime_g2, &d_cap_s_prime);
 
-                let e_cap_g2 = &dk.e_g2[i - 1][j - 1][b];
-                let e_cap_s_prime = group2_matrix_vector_mul_msm(&e_cap_g2, &s_prime);
-                v_prime_g2 = vector_add_g2(&v_prime_g2, &e_cap_s_prime);
-            }
-        }
+        let v_prime_g2 = vector_add_g2(&usk.v_g2, &e_next);
+        let e_cap_s_prime = group2_matrix_vector_mul_msm(&e_cap_g2, &s_prime)?;
+        let v_prime_g2 = vector_add_g2(&v_prime_g2, &e_cap_s_prime);
 
         //udk
         let mut d_prime = Vec::with_capacity(self.max_levels - p);
@@ -290,7 +317,7 @@ impl HIBKEM1 {
                     let d_g2 = &udk.d_g2[udk_idx][j - 1][b];
 
                     let d_cap_g2 = &dk.d_g2[i - 1][j - 1][b];
-                    let d_cap_s_prime_g2 = group2_matrix_vector_mul_msm(&d_cap_g2, &s_prime);
+                    let d_cap_s_prime_g2 = group2_matrix_vector_mul_msm(&d_cap_g2, &s_prime)?;
 
                     let d_new_g2 = vector_add_g2(&d_g2, &d_cap_s_prime_g2);
                     j_d.push(d_new_g2);
@@ -298,7 +325,7 @@ impl HIBKEM1 {
                     let e_g2 = &udk.e_g2[udk_idx][j - 1][b];
 
                     let e_cap_g2 = &dk.e_g2[i - 1][j - 1][b as usize];
-                    let e_cap_s_prime = group2_matrix_vector_mul_msm(&e_cap_g2, &s_prime);
+                    let e_cap_s_prime = group2_matrix_vector_mul_msm(&e_cap_g2, &s_prime)?;
 
                     let e_new = vector_add_g2(&e_g2, &e_cap_s_prime);
                     j_e.push(e_new);
@@ -322,51 +349,55 @@ impl HIBKEM1 {
             e_g2: e_prime,
         };
 
-        (usk_prime, udk_prime)
+        Ok((usk_prime, udk_prime))
     }
 
-    pub fn encrypt(&self, pk: &HIBKEM1PublicKey, id: &[Vec<u8>]) -> (GTElement, HIBKEM1Ciphertext) {
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &HIBKEM1PublicKey,
+        id: &[Vec<u8>],
+    ) -> Result<(GTElement, HIBKEM1Ciphertext), IbeError> {
+        check_hierarchical_identity(id, self.max_levels, self.identity_len)?;
+        check_levels(&pk.z_g1, 1, self.max_levels, self.identity_len)?;
+        check_non_empty(&pk.z_g1[0][0][0])?;
         let p = id.len();
-        assert!(p > 0 && p <= self.max_levels);
 
-        let r = random_vector(self.k);
-        let c0_g1 = group_matrix_vector_mul_msm(&pk.a_g1, &r);
+        let r = random_vector(rng, self.k);
+        let c0_g1 = group_matrix_vector_mul_msm(&pk.a_g1, &r)?;
 
+        // (sum_{i <= p} sum_j f_{i,j}(id) * z_{i,j}) * r
         let n = pk.z_g1[0][0][0].len();
         let mut z_i_sum = matrix_zero::<G1>(n, self.k);
-
         for i in 1..=p {
-            let num_j = i * self.identity_len;
-            for j in 1..=num_j {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_in_msg = (j - 1) % self.identity_len;
-                let b = bit_at(bit_in_msg, &id[msg_idx]);
-
-                let z_i_j = &pk.z_g1[i - 1][j - 1][b];
-                z_i_sum = matrix_add(&z_i_sum, &z_i_j);
-            }
+            let z_i = level_affine_combination(&self.mac, i, id, &pk.z_g1, n, self.k)?;
+            z_i_sum = matrix_add(&z_i_sum, &z_i)?;
         }
-        let c1_g1 = group_matrix_vector_mul_msm(&z_i_sum, &r);
+        let c1_g1 = group_matrix_vector_mul_msm(&z_i_sum, &r)?;
 
-        let k_g1 = vector_dot_g1(&r, &pk.z_prime_g1);
+        let k_g1 = vector_dot_g1(&r, &pk.z_prime_g1)?;
         let k_t = pairing(&k_g1, &G2::generator());
 
         let ct = HIBKEM1Ciphertext { c0_g1, c1_g1 };
 
-        (k_t, ct)
+        Ok((k_t, ct))
     }
 
-    pub fn decrypt(&self, usk: &HIBKEM1UserSecretKey, ct: &HIBKEM1Ciphertext) -> GTElement {
+    pub fn decrypt(
+        &self,
+        usk: &HIBKEM1UserSecretKey,
+        ct: &HIBKEM1Ciphertext,
+    ) -> Result<GTElement, IbeError> {
         let mut v_u_g2 = usk.v_g2.clone();
         v_u_g2.extend_from_slice(&usk.u_g2);
 
         let c0_g1 = &ct.c0_g1;
         let c1_g1 = &ct.c1_g1;
 
-        assert_ne!(c0_g1.len(), 0);
-        assert_ne!(c1_g1.len(), 0);
-        assert_eq!(c0_g1.len(), v_u_g2.len());
-        assert_eq!(c1_g1.len(), usk.t_g2.len());
+        check_non_empty(c0_g1)?;
+        check_non_empty(c1_g1)?;
+        check_dim(v_u_g2.len(), c0_g1.len())?;
+        check_dim(usk.t_g2.len(), c1_g1.len())?;
 
         let first_term: Vec<_> = (0..c0_g1.len())
             .map(|i| (c0_g1[i].clone(), v_u_g2[i].clone()))
@@ -376,6 +407,314 @@ impl HIBKEM1 {
             .map(|i| (c1_g1[i].clone(), usk.t_g2[i].clone()))
             .collect();
 
-        multi_pairing(&first_term) / multi_pairing(&second_term)
+        Ok(multi_pairing(&first_term) / multi_pairing(&second_term))
+    }
+}
+
+impl<M: HIBKEM1Mac> HIBKEM for HIBKEM1<M> {
+    type PK = HIBKEM1PublicKey;
+    type DK = HIBKEM1DelegationKey;
+    type SK = HIBKEM1SecretKey<M>;
+    type USK = (HIBKEM1UserSecretKey, HIBKEM1UserDelegationKey);
+    type CT = HIBKEM1Ciphertext;
+
+    fn name(&self) -> String {
+        String::from(M::NAME)
+    }
+
+    fn setup<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(HIBKEM1PublicKey, HIBKEM1DelegationKey, HIBKEM1SecretKey<M>), IbeError> {
+        HIBKEM1::setup(self, rng)
+    }
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &HIBKEM1SecretKey<M>,
+        identity: &[Vec<u8>],
+    ) -> Result<(HIBKEM1UserSecretKey, HIBKEM1UserDelegationKey), IbeError> {
+        HIBKEM1::extract(self, rng, sk, identity)
+    }
+
+    fn delegate<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        dk: &HIBKEM1DelegationKey,
+        usk: &(HIBKEM1UserSecretKey, HIBKEM1UserDelegationKey),
+        id_prefix: &[Vec<u8>],
+        id_next: Vec<u8>,
+    ) -> Result<(HIBKEM1UserSecretKey, HIBKEM1UserDelegationKey), IbeError> {
+        HIBKEM1::delegate(self, rng, dk, &usk.0, &usk.1, id_prefix, id_next)
+    }
+
+    fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &HIBKEM1PublicKey,
+        identity: &[Vec<u8>],
+    ) -> Result<(HIBKEM1Ciphertext, GTElement), IbeError> {
+        let (key, ct) = HIBKEM1::encrypt(self, rng, pk, identity)?;
+        Ok((ct, key))
+    }
+
+    fn decrypt(
+        &self,
+        usk: &(HIBKEM1UserSecretKey, HIBKEM1UserDelegationKey),
+        ct: &HIBKEM1Ciphertext,
+    ) -> Result<GTElement, IbeError> {
+        HIBKEM1::decrypt(self, &usk.0, ct)
+    }
+}
+
+impl<M: LevelAffineMac> KemParams for HIBKEM1<M> {
+    fn dimensions(&self) -> KemDimensions {
+        KemDimensions {
+            k: dim(self.k),
+            msg_len: 0,
+            max_levels: dim(self.max_levels),
+            identity_len: dim(self.identity_len),
+            lambda: 0,
+        }
+    }
+}
+
+impl<M: HIBKEM1Mac> KemSerialize<HIBKEM1<M>> for HIBKEM1PublicKey {
+    const KIND: u8 = M::PUBLIC_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        write_matrix(&self.a_g1, k + eta, k, &mut writer, compress)?;
+        write_levels(
+            &self.z_g1,
+            1,
+            kem.max_levels,
+            kem.identity_len,
+            &mut writer,
+            |z, w| write_matrix(z, n, k, w, compress),
+        )?;
+        write_vector(&self.z_prime_g1, k, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        let a_g1 = read_matrix(k + eta, k, &mut reader, compress, validate)?;
+        let z_g1 = read_levels(1, kem.max_levels, kem.identity_len, &mut reader, |r| {
+            read_matrix(n, k, r, compress, validate)
+        })?;
+        let z_prime_g1 = read_vector(k, &mut reader, compress, validate)?;
+        Ok(HIBKEM1PublicKey {
+            a_g1,
+            z_g1,
+            z_prime_g1,
+        })
+    }
+}
+
+impl<M: HIBKEM1Mac> KemSerialize<HIBKEM1<M>> for HIBKEM1DelegationKey {
+    const KIND: u8 = M::DELEGATION_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        write_matrix(&self.b_g2, n, k, &mut writer, compress)?;
+        for (levels, rows) in [(&self.d_g2, eta), (&self.e_g2, k)] {
+            write_levels(
+                levels,
+                1,
+                kem.max_levels,
+                kem.identity_len,
+                &mut writer,
+                |m, w| write_matrix(m, rows, k, w, compress),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        let b_g2 = read_matrix(n, k, &mut reader, compress, validate)?;
+        let d_g2 = read_levels(1, kem.max_levels, kem.identity_len, &mut reader, |r| {
+            read_matrix(eta, k, r, compress, validate)
+        })?;
+        let e_g2 = read_levels(1, kem.max_levels, kem.identity_len, &mut reader, |r| {
+            read_matrix(k, k, r, compress, validate)
+        })?;
+        Ok(HIBKEM1DelegationKey { b_g2, d_g2, e_g2 })
+    }
+}
+
+impl<M: HIBKEM1Mac> KemSerialize<HIBKEM1<M>> for HIBKEM1SecretKey<M>
+where
+    M::SecretKey: KemSerialize<M>,
+{
+    const KIND: u8 = M::SECRET_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n) = (kem.k, kem.mac.n());
+        self.sk_mac
+            .serialize_body(&kem.mac, &mut writer, compress)?;
+        write_levels(
+            &self.y_matrices,
+            1,
+            kem.max_levels,
+            kem.identity_len,
+            &mut writer,
+            |y, w| write_matrix(y, k, n, w, compress),
+        )?;
+        write_vector(&self.y_prime, k, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n) = (kem.k, kem.mac.n());
+        let sk_mac =
+            M::SecretKey::deserialize_body(&kem.mac, &mut reader, compress, validate)?;
+        let y_matrices = read_levels(1, kem.max_levels, kem.identity_len, &mut reader, |r| {
+            read_matrix(k, n, r, compress, validate)
+        })?;
+        let y_prime = read_vector(k, &mut reader, compress, validate)?;
+        Ok(HIBKEM1SecretKey {
+            sk_mac,
+            y_matrices,
+            y_prime,
+        })
+    }
+}
+
+impl<M: HIBKEM1Mac> KemSerialize<HIBKEM1<M>> for HIBKEM1UserSecretKey {
+    const KIND: u8 = M::USER_SECRET_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        write_vector(&self.t_g2, n, &mut writer, compress)?;
+        write_vector(&self.u_g2, eta, &mut writer, compress)?;
+        write_vector(&self.v_g2, k, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        let t_g2 = read_vector(n, &mut reader, compress, validate)?;
+        let u_g2 = read_vector(eta, &mut reader, compress, validate)?;
+        let v_g2 = read_vector(k, &mut reader, compress, validate)?;
+        Ok(HIBKEM1UserSecretKey { t_g2, u_g2, v_g2 })
+    }
+}
+
+// A user delegation key at depth p covers levels p+1..=max_levels, so the body starts with p.
+impl<M: HIBKEM1Mac> KemSerialize<HIBKEM1<M>> for HIBKEM1UserDelegationKey {
+    const KIND: u8 = M::USER_DELEGATION_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, eta) = (kem.k, kem.mac.eta());
+        let depth = kem.max_levels.saturating_sub(self.d_g2.len());
+        write_len(depth, &mut writer)?;
+        for (levels, len) in [(&self.d_g2, eta), (&self.e_g2, k)] {
+            write_levels(
+                levels,
+                depth + 1,
+                kem.max_levels,
+                kem.identity_len,
+                &mut writer,
+                |v, w| write_vector(v, len, w, compress),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, eta) = (kem.k, kem.mac.eta());
+        let depth = read_depth(kem.max_levels, &mut reader)?;
+        let d_g2 = read_levels(
+            depth + 1,
+            kem.max_levels,
+            kem.identity_len,
+            &mut reader,
+            |r| read_vector(eta, r, compress, validate),
+        )?;
+        let e_g2 = read_levels(
+            depth + 1,
+            kem.max_levels,
+            kem.identity_len,
+            &mut reader,
+            |r| read_vector(k, r, compress, validate),
+        )?;
+        Ok(HIBKEM1UserDelegationKey { d_g2, e_g2 })
+    }
+}
+
+impl<M: HIBKEM1Mac> KemSerialize<HIBKEM1<M>> for HIBKEM1Ciphertext {
+    const KIND: u8 = M::CIPHERTEXT_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        write_vector(&self.c0_g1, k + eta, &mut writer, compress)?;
+        write_vector(&self.c1_g1, n, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        let c0_g1 = read_vector(k + eta, &mut reader, compress, validate)?;
+        let c1_g1 = read_vector(n, &mut reader, compress, validate)?;
+        Ok(HIBKEM1Ciphertext { c0_g1, c1_g1 })
     }
 }
@@ -1,10 +1,16 @@
-use crate::affine_mac_levels2::{AffineMacLevels2, AffineMacLevels2SecretKey, AffineMacLevels2Tag};
-use crate::bit_utils::bit_at;
+use crate::affine_mac::{LevelAffineMac, level_affine_combination};
+use crate::affine_mac_levels2::{AffineMacLevels2, AffineMacLevels2Tag, AffineMacLevels2TagContext};
+use crate::error::{IbeError, check_dim, check_hierarchical_identity, check_levels, check_non_empty};
 use crate::field_utils::*;
 use crate::group_functions::{multi_pairing, pairing};
+use crate::kem::HIBKEM;
+use crate::serialization::*;
 use crate::types::*;
 use ark_bls12_381::{G1Projective as G1, G2Projective as G2};
 use ark_ec::PrimeGroup;
+use ark_serialize::{Compress, SerializationError, Validate};
+use rand::{CryptoRng, RngCore};
+use std::io::{Read, Write};
 
 pub struct HIBKEM2PublicKey {
     pub a_g1: Matrix<G1>,
@@ -18,8 +24,8 @@ pub struct HIBKEM2DelegationKey {
     pub e_g2: Vec<Vec<Vec<Matrix<G2>>>>,
 }
 
-pub struct HIBKEM2SecretKey {
-    pub sk_mac: AffineMacLevels2SecretKey,
+pub struct HIBKEM2SecretKey<M: LevelAffineMac = AffineMacLevels2> {
+    pub sk_mac: M::SecretKey,
     pub y_matrices: Vec<Vec<Vec<Matrix<FieldElement>>>>,
     pub y_prime: Vector,
 }
@@ -31,32 +37,71 @@ pub struct HIBKEM2UserSecretKey {
 }
 
 
+#[derive(Clone, PartialEq)]
 pub struct HIBKEM2Ciphertext {
     pub c0_g1: Vec<G1>,
     pub c1_g1: Vec<Vec<G1>>,
 }
 
-pub struct HIBKEM2 {
+/// The HIBKEM transform of a hierarchical affine MAC `M` whose tags carry their own `t_i` for
+/// every level. It uses only the level functions and shapes of [`LevelAffineMac`].
+pub struct HIBKEM2<M: LevelAffineMac = AffineMacLevels2> {
     pub k: usize,
     pub max_levels: usize,
     pub identity_len: usize,
-    pub mac: AffineMacLevels2,
+    pub mac: M,
+}
+
+/// A MAC with one `t_i` per level, which [`HIBKEM2`] is built on, and with a serializable
+/// instance: the name of the resulting HIBKEM and the kinds of its objects.
+pub trait HIBKEM2Mac:
+    LevelAffineMac<Tag = AffineMacLevels2Tag, TagContext = AffineMacLevels2TagContext> + KemParams
+{
+    const NAME: &'static str;
+    const PUBLIC_KEY_KIND: u8;
+    const DELEGATION_KEY_KIND: u8;
+    const SECRET_KEY_KIND: u8;
+    const USER_SECRET_KEY_KIND: u8;
+    const CIPHERTEXT_KIND: u8;
+}
+
+impl HIBKEM2Mac for AffineMacLevels2 {
+    const NAME: &'static str = "hibkem2";
+    const PUBLIC_KEY_KIND: u8 = KIND_HIBKEM2_PUBLIC_KEY;
+    const DELEGATION_KEY_KIND: u8 = KIND_HIBKEM2_DELEGATION_KEY;
+    const SECRET_KEY_KIND: u8 = KIND_HIBKEM2_SECRET_KEY;
+    const USER_SECRET_KEY_KIND: u8 = KIND_HIBKEM2_USER_SECRET_KEY;
+    const CIPHERTEXT_KIND: u8 = KIND_HIBKEM2_CIPHERTEXT;
 }
 
 impl HIBKEM2 {
     pub fn new(k: usize, max_levels: usize, identity_len: usize) -> Self {
+        Self::with_mac(AffineMacLevels2::new(k, max_levels, identity_len))
+    }
+}
+
+impl<M> HIBKEM2<M>
+where
+    M: LevelAffineMac<Tag = AffineMacLevels2Tag, TagContext = AffineMacLevels2TagContext>,
+{
+    pub fn with_mac(mac: M) -> Self {
         Self {
-            k,
-            max_levels,
-            identity_len,
-            mac: AffineMacLevels2::new(k, max_levels, identity_len),
+            k: mac.k(),
+            max_levels: mac.max_levels(),
+            identity_len: mac.identity_len(),
+            mac,
         }
     }
 
-    pub fn setup(&self) -> (HIBKEM2PublicKey, HIBKEM2DelegationKey, HIBKEM2SecretKey) {
-        let sk_mac = self.mac.gen_mac();
+    pub fn setup<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(HIBKEM2PublicKey, HIBKEM2DelegationKey, HIBKEM2SecretKey<M>), IbeError> {
+        let sk_mac = self.mac.gen_mac(rng);
+        let b_matrix = self.mac.b(&sk_mac);
+        let n = self.mac.n();
 
-        let a_matrix = random_matrix(2 * self.k, self.k); 
+        let a_matrix = random_matrix(rng, self.k + self.mac.eta(), self.k);
 
         let mut y_matrices = Vec::with_capacity(self.max_levels);
         let mut z_g1 = Vec::with_capacity(self.max_levels);
@@ -64,33 +109,35 @@ impl HIBKEM2 {
         let mut e_g2 = Vec::with_capacity(self.max_levels);
 
         for i in 1..=self.max_levels {
-            let num_j = i * self.identity_len;
-            let mut y_j = Vec::with_capacity(num_j);
-            let mut z_j = Vec::with_capacity(num_j);
-            let mut d_j = Vec::with_capacity(num_j);
-            let mut e_j = Vec::with_capacity(num_j);
+            let x_i = self.mac.x_matrices(&sk_mac, i);
+            check_dim(self.mac.l(i), 2 * x_i.len())?;
+
+            let mut y_j = Vec::with_capacity(x_i.len());
+            let mut z_j = Vec::with_capacity(x_i.len());
+            let mut d_j = Vec::with_capacity(x_i.len());
+            let mut e_j = Vec::with_capacity(x_i.len());
 
-            for j in 1..=num_j {
+            for x_pair in x_i {
                 let mut y_b = Vec::with_capacity(2);
                 let mut z_b = Vec::with_capacity(2);
                 let mut d_b = Vec::with_capacity(2);
                 let mut e_b = Vec::with_capacity(2);
 
-                for b in 0..2 {
-                    let y_matrix = random_matrix(self.k, 3 * self.k);
+                for x_matrix in x_pair {
+                    let y_matrix = random_matrix(rng, self.k, n);
                     y_b.push(y_matrix.clone());
 
-                    let x_t = matrix_transpose(&sk_mac.x_matrices[i - 1][j - 1][b]);
-                    let y_t = matrix_transpose(&y_matrix);
-                    let y_x = matrix_concat(&y_t, &x_t);
+                    let x_t = matrix_transpose(x_matrix)?;
+                    let y_t = matrix_transpose(&y_matrix)?;
+                    let y_x = matrix_concat(&y_t, &x_t)?;
                     
-                    let z_matrix = matrix_multiply(&y_x, &a_matrix);
+                    let z_matrix = matrix_multiply(&y_x, &a_matrix)?;
                     z_b.push(matrix_lift_g1(&z_matrix));
 
-                    let d_matrix = matrix_multiply(&sk_mac.x_matrices[i - 1][j - 1][b], &sk_mac.b);
+                    let d_matrix = matrix_multiply(x_matrix, b_matrix)?;
                     d_b.push(matrix_lift_g2(&d_matrix));
 
-                    let e_matrix = matrix_multiply(&y_matrix, &sk_mac.b);
+                    let e_matrix = matrix_multiply(&y_matrix, b_matrix)?;
                     e_b.push(matrix_lift_g2(&e_matrix));
                 }
 
@@ -106,13 +153,13 @@ impl HIBKEM2 {
             e_g2.push(e_j);
         }
 
-        let y_prime = random_vector(self.k);
-        let y_x_prime = vector_concat(&y_prime, &sk_mac.x_prime);
+        let y_prime = random_vector(rng, self.k);
+        let y_x_prime = vector_concat(&y_prime, self.mac.x_prime(&sk_mac));
 
-        let z_field = vector_matrix_mul(&y_x_prime, &a_matrix);
+        let z_field = vector_matrix_mul(&y_x_prime, &a_matrix)?;
         let z_prime_g1 = vector_lift_g1(&z_field);
 
-        let b_g2 = matrix_lift_g2(&sk_mac.b);
+        let b_g2 = matrix_lift_g2(b_matrix);
         let a_g1 = matrix_lift_g1(&a_matrix);
 
         let pk = HIBKEM2PublicKey {
@@ -127,148 +174,137 @@ impl HIBKEM2 {
             y_prime,
         };
 
-        (pk, dk, sk)
+        Ok((pk, dk, sk))
     }
 
-    pub fn extract(
+    pub fn extract<R: RngCore + CryptoRng>(
         &self,
-        sk: &HIBKEM2SecretKey,
+        rng: &mut R,
+        sk: &HIBKEM2SecretKey<M>,
         id: &[Vec<u8>],
-    ) -> HIBKEM2UserSecretKey {
+    ) -> Result<HIBKEM2UserSecretKey, IbeError> {
+        check_hierarchical_identity(id, self.max_levels, self.identity_len)?;
         let p = id.len();
-        assert!(p > 0 && p <= self.max_levels);
 
-        let tag: AffineMacLevels2Tag = self.mac.tag(&sk.sk_mac, id);
-        let t_fields = tag.t_fields;
+        let (tag, context) = self.mac.tag_with_context(rng, &sk.sk_mac, id)?;
+        let t_fields = context.t_fields();
         let t_g2 = tag.t_g2;
         let u_g2 = tag.u_g2;
 
+        // y' + sum_{i <= p} (sum_j f_{i,j}(id) * y_{i,j}) * t_i
+        let n = self.mac.n();
         let mut v = sk.y_prime.clone();
-
         for i in 1..=p {
-            for j in 1..=(i * self.identity_len) {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_in_msg = (j - 1) % self.identity_len;
-                let b = bit_at(bit_in_msg, &id[msg_idx]);
-
-                let y_i_j_b = &sk.y_matrices[i - 1][j - 1][b];
-
-                let y_t = matrix_vector_mul(y_i_j_b, &t_fields[i-1]);
-
-                v = vector_add(&v, &y_t);
-            }
+            let y_i = level_affine_combination(&self.mac, i, id, &sk.y_matrices, self.k, n)?;
+            v = vector_add(&v, &matrix_vector_mul(&y_i, &t_fields[i - 1]));
         }
 
         let v_g2 = vector_lift_g2(&v);
 
-        HIBKEM2UserSecretKey { t_g2, u_g2, v_g2 }
+        Ok(HIBKEM2UserSecretKey { t_g2, u_g2, v_g2 })
     }
 
-    pub fn delegate(
+    pub fn delegate<R: RngCore + CryptoRng>(
         &self,
+        rng: &mut R,
         dk: &HIBKEM2DelegationKey,
         usk: &HIBKEM2UserSecretKey,
         id_prefix: &[Vec<u8>],
         id_next: Vec<u8>,
-    ) -> HIBKEM2UserSecretKey {
-
+    ) -> Result<HIBKEM2UserSecretKey, IbeError> {
         let p = id_prefix.len();
-        assert!(p > 0 && p < self.max_levels);
-        assert_eq!(usk.t_g2.len(), p);
+        if p == 0 {
+            return Err(IbeError::DepthOutOfRange {
+                depth: p,
+                max: self.max_levels,
+            });
+        }
 
+        let mut id_prime = id_prefix.to_vec();
+        id_prime.push(id_next);
+        check_hierarchical_identity(&id_prime, self.max_levels, self.identity_len)?;
+
+        let (n, eta) = (self.mac.n(), self.mac.eta());
+        check_levels(&dk.d_g2, 1, self.max_levels, self.identity_len)?;
+        check_levels(&dk.e_g2, 1, self.max_levels, self.identity_len)?;
+        check_dim(p, usk.t_g2.len())?;
+        usk.t_g2
+            .iter()
+            .try_for_each(|t_i| check_dim(n, t_i.len()))?;
+        check_dim(eta, usk.u_g2.len())?;
+        check_dim(self.k, usk.v_g2.len())?;
 
         let mut s_primes: Vec<Vector> = Vec::with_capacity(p + 1);
         let mut t_prime_g2: Vec<Vec<G2>> = Vec::with_capacity(p + 1);
 
         for i in 1..=p {
-            let s_prime_i = random_vector(self.k);
-            let bs = group2_matrix_vector_mul_msm(&dk.b_g2, &s_prime_i);
+            let s_prime_i = random_vector(rng, self.k);
+            let bs = group2_matrix_vector_mul_msm(&dk.b_g2, &s_prime_i)?;
             let t_prime_i = vector_add_g2(&usk.t_g2[i-1], &bs);
             t_prime_g2.push(t_prime_i);
             s_primes.push(s_prime_i);
         }
 
-        let s_prime_new = random_vector(self.k);
-        let t_prime_new = group2_matrix_vector_mul_msm(&dk.b_g2, &s_prime_new);
+        let s_prime_new = random_vector(rng, self.k);
+        let t_prime_new = group2_matrix_vector_mul_msm(&dk.b_g2, &s_prime_new)?;
         t_prime_g2.push(t_prime_new);
         s_primes.push(s_prime_new);
 
-        let mut id_prime = id_prefix.to_vec();
-        id_prime.push(id_next);
-
+        // level i is rerandomized with its own s'_i
         let mut u_prime_g2 = usk.u_g2.clone();
-
-        for i in 1..=(p + 1) {
-            let num_j = i * self.identity_len;
-            for j in 1..=num_j {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_idx = (j - 1) % self.identity_len;
-                let b = bit_at(bit_idx, &id_prime[msg_idx]);
-
-                let d_cap_g2 = &dk.d_g2[i - 1][j - 1][b];
-                let d_cap_s_prime = group2_matrix_vector_mul_msm(&d_cap_g2, &s_primes[i-1]);
-                u_prime_g2 = vector_add_g2(&u_prime_g2, &d_cap_s_prime);
-            }
-        }
-
         let mut v_prime_g2 = usk.v_g2.clone();
-
         for i in 1..=(p + 1) {
-            let num_j = i * self.identity_len;
-            for j in 1..=num_j {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_idx = (j - 1) % self.identity_len;
-                let b = bit_at(bit_idx, &id_prime[msg_idx]);
-
-                let e_cap_g2 = &dk.e_g2[i - 1][j - 1][b];
-                let e_cap_s_prime = group2_matrix_vector_mul_msm(&e_cap_g2, &s_primes[i-1]);
-                v_prime_g2 = vector_add_g2(&v_prime_g2, &e_cap_s_prime);
-            }
+            let d_i = level_affine_combination(&self.mac, i, &id_prime, &dk.d_g2, eta, self.k)?;
+            let d_s_prime = group2_matrix_vector_mul_msm(&d_i, &s_primes[i - 1])?;
+            u_prime_g2 = vector_add_g2(&u_prime_g2, &d_s_prime);
+
+            let e_i = level_affine_combination(&self.mac, i, &id_prime, &dk.e_g2, self.k, self.k)?;
+            let e_s_prime = group2_matrix_vector_mul_msm(&e_i, &s_primes[i - 1])?;
+            v_prime_g2 = vector_add_g2(&v_prime_g2, &e_s_prime);
         }
 
-        HIBKEM2UserSecretKey {
+        Ok(HIBKEM2UserSecretKey {
             t_g2: t_prime_g2,
             u_g2: u_prime_g2,
             v_g2: v_prime_g2,
-        }
+        })
     }
 
-    pub fn encrypt(&self, pk: &HIBKEM2PublicKey, id: &[Vec<u8>]) -> (GTElement, HIBKEM2Ciphertext) {
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &HIBKEM2PublicKey,
+        id: &[Vec<u8>],
+    ) -> Result<(GTElement, HIBKEM2Ciphertext), IbeError> {
+        check_hierarchical_identity(id, self.max_levels, self.identity_len)?;
+        check_levels(&pk.z_g1, 1, self.max_levels, self.identity_len)?;
+        check_non_empty(&pk.z_g1[0][0][0])?;
         let p = id.len();
-        assert!(p > 0 && p <= self.max_levels);
 
-        let r = random_vector(self.k);
-        let c0_g1 = group_matrix_vector_mul_msm(&pk.a_g1, &r);
+        let r = random_vector(rng, self.k);
+        let c0_g1 = group_matrix_vector_mul_msm(&pk.a_g1, &r)?;
 
+        // (sum_j f_{i,j}(id) * z_{i,j}) * r for every level i <= p
         let n = pk.z_g1[0][0][0].len();
         let mut c1_g1: Vec<Vec<G1>> = Vec::with_capacity(p);
-
         for i in 1..=p {
-            let num_j = i * self.identity_len;
-            let mut z_i_sum = matrix_zero::<G1>(n, self.k);
-
-            for j in 1..=num_j {
-                let msg_idx = (j - 1) / self.identity_len;
-                let bit_in_msg = (j - 1) % self.identity_len;
-                let b = bit_at(bit_in_msg, &id[msg_idx]);
-
-                let z_i_j = &pk.z_g1[i - 1][j - 1][b];
-                z_i_sum = matrix_add(&z_i_sum, &z_i_j);
-            }
-
-            let c1_g1_i = group_matrix_vector_mul_msm(&z_i_sum, &r);
-            c1_g1.push(c1_g1_i);
+            let z_i = level_affine_combination(&self.mac, i, id, &pk.z_g1, n, self.k)?;
+            c1_g1.push(group_matrix_vector_mul_msm(&z_i, &r)?);
         }
-        
-        let k_g1 = vector_dot_g1(&r, &pk.z_prime_g1);
+
+        let k_g1 = vector_dot_g1(&r, &pk.z_prime_g1)?;
         let k_t = pairing(&k_g1, &G2::generator());
 
         let ct = HIBKEM2Ciphertext { c0_g1, c1_g1 };
 
-        (k_t, ct)
+        Ok((k_t, ct))
     }
 
-    pub fn decrypt(&self, usk: &HIBKEM2UserSecretKey, ct: &HIBKEM2Ciphertext) -> GTElement {
+    pub fn decrypt(
+        &self,
+        usk: &HIBKEM2UserSecretKey,
+        ct: &HIBKEM2Ciphertext,
+    ) -> Result<GTElement, IbeError> {
         let p = usk.t_g2.len();
         
         let mut v_u_g2 = usk.v_g2.clone();
@@ -277,10 +313,13 @@ impl HIBKEM2 {
         let c0_g1 = &ct.c0_g1;
         let c1_g1 = &ct.c1_g1;
 
-        assert_ne!(c0_g1.len(), 0);
-        assert_ne!(c1_g1.len(), 0);
-        assert_eq!(c0_g1.len(), v_u_g2.len());
-        assert_eq!(c1_g1.len(), usk.t_g2.len());
+        check_non_empty(c0_g1)?;
+        check_non_empty(c1_g1)?;
+        check_dim(v_u_g2.len(), c0_g1.len())?;
+        check_dim(usk.t_g2.len(), c1_g1.len())?;
+        for (c1_i, t_i) in c1_g1.iter().zip(usk.t_g2.iter()) {
+            check_dim(t_i.len(), c1_i.len())?;
+        }
 
         let first_term: Vec<_> = (0..c0_g1.len())
             .map(|i| (c0_g1[i].clone(), v_u_g2[i].clone()))
@@ -297,6 +336,268 @@ impl HIBKEM2 {
             second_term.extend(level_term);
         }
 
-        multi_pairing(&first_term) / multi_pairing(&second_term)
+        Ok(multi_pairing(&first_term) / multi_pairing(&second_term))
+    }
+}
+
+impl<M: HIBKEM2Mac> HIBKEM for HIBKEM2<M> {
+    type PK = HIBKEM2PublicKey;
+    type DK = HIBKEM2DelegationKey;
+    type SK = HIBKEM2SecretKey<M>;
+    type USK = HIBKEM2UserSecretKey;
+    type CT = HIBKEM2Ciphertext;
+
+    fn name(&self) -> String {
+        String::from(M::NAME)
+    }
+
+    fn setup<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(HIBKEM2PublicKey, HIBKEM2DelegationKey, HIBKEM2SecretKey<M>), IbeError> {
+        HIBKEM2::setup(self, rng)
+    }
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &HIBKEM2SecretKey<M>,
+        identity: &[Vec<u8>],
+    ) -> Result<HIBKEM2UserSecretKey, IbeError> {
+        HIBKEM2::extract(self, rng, sk, identity)
+    }
+
+    fn delegate<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        dk: &HIBKEM2DelegationKey,
+        usk: &HIBKEM2UserSecretKey,
+        id_prefix: &[Vec<u8>],
+        id_next: Vec<u8>,
+    ) -> Result<HIBKEM2UserSecretKey, IbeError> {
+        HIBKEM2::delegate(self, rng, dk, usk, id_prefix, id_next)
+    }
+
+    fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &HIBKEM2PublicKey,
+        identity: &[Vec<u8>],
+    ) -> Result<(HIBKEM2Ciphertext, GTElement), IbeError> {
+        let (key, ct) = HIBKEM2::encrypt(self, rng, pk, identity)?;
+        Ok((ct, key))
+    }
+
+    fn decrypt(
+        &self,
+        usk: &HIBKEM2UserSecretKey,
+        ct: &HIBKEM2Ciphertext,
+    ) -> Result<GTElement, IbeError> {
+        HIBKEM2::decrypt(self, usk, ct)
+    }
+}
+
+impl<M: LevelAffineMac> KemParams for HIBKEM2<M> {
+    fn dimensions(&self) -> KemDimensions {
+        KemDimensions {
+            k: dim(self.k),
+            msg_len: 0,
+            max_levels: dim(self.max_levels),
+            identity_len: dim(self.identity_len),
+            lambda: 0,
+        }
+    }
+}
+
+impl<M: HIBKEM2Mac> KemSerialize<HIBKEM2<M>> for HIBKEM2PublicKey {
+    const KIND: u8 = M::PUBLIC_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM2<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        write_matrix(&self.a_g1, k + eta, k, &mut writer, compress)?;
+        write_levels(
+            &self.z_g1,
+            1,
+            kem.max_levels,
+            kem.identity_len,
+            &mut writer,
+            |z, w| write_matrix(z, n, k, w, compress),
+        )?;
+        write_vector(&self.z_prime_g1, k, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM2<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        let a_g1 = read_matrix(k + eta, k, &mut reader, compress, validate)?;
+        let z_g1 = read_levels(1, kem.max_levels, kem.identity_len, &mut reader, |r| {
+            read_matrix(n, k, r, compress, validate)
+        })?;
+        let z_prime_g1 = read_vector(k, &mut reader, compress, validate)?;
+        Ok(HIBKEM2PublicKey {
+            a_g1,
+            z_g1,
+            z_prime_g1,
+        })
+    }
+}
+
+impl<M: HIBKEM2Mac> KemSerialize<HIBKEM2<M>> for HIBKEM2DelegationKey {
+    const KIND: u8 = M::DELEGATION_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM2<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        write_matrix(&self.b_g2, n, k, &mut writer, compress)?;
+        for (levels, rows) in [(&self.d_g2, eta), (&self.e_g2, k)] {
+            write_levels(
+                levels,
+                1,
+                kem.max_levels,
+                kem.identity_len,
+                &mut writer,
+                |m, w| write_matrix(m, rows, k, w, compress),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM2<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        let b_g2 = read_matrix(n, k, &mut reader, compress, validate)?;
+        let d_g2 = read_levels(1, kem.max_levels, kem.identity_len, &mut reader, |r| {
+            read_matrix(eta, k, r, compress, validate)
+        })?;
+        let e_g2 = read_levels(1, kem.max_levels, kem.identity_len, &mut reader, |r| {
+            read_matrix(k, k, r, compress, validate)
+        })?;
+        Ok(HIBKEM2DelegationKey { b_g2, d_g2, e_g2 })
+    }
+}
+
+impl<M: HIBKEM2Mac> KemSerialize<HIBKEM2<M>> for HIBKEM2SecretKey<M>
+where
+    M::SecretKey: KemSerialize<M>,
+{
+    const KIND: u8 = M::SECRET_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM2<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n) = (kem.k, kem.mac.n());
+        self.sk_mac
+            .serialize_body(&kem.mac, &mut writer, compress)?;
+        write_levels(
+            &self.y_matrices,
+            1,
+            kem.max_levels,
+            kem.identity_len,
+            &mut writer,
+            |y, w| write_matrix(y, k, n, w, compress),
+        )?;
+        write_vector(&self.y_prime, k, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM2<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n) = (kem.k, kem.mac.n());
+        let sk_mac =
+            M::SecretKey::deserialize_body(&kem.mac, &mut reader, compress, validate)?;
+        let y_matrices = read_levels(1, kem.max_levels, kem.identity_len, &mut reader, |r| {
+            read_matrix(k, n, r, compress, validate)
+        })?;
+        let y_prime = read_vector(k, &mut reader, compress, validate)?;
+        Ok(HIBKEM2SecretKey {
+            sk_mac,
+            y_matrices,
+            y_prime,
+        })
+    }
+}
+
+// User secret keys and ciphertexts hold one t (resp. c1) vector per level, so the body starts
+// with the depth p.
+impl<M: HIBKEM2Mac> KemSerialize<HIBKEM2<M>> for HIBKEM2UserSecretKey {
+    const KIND: u8 = M::USER_SECRET_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM2<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        write_len(self.t_g2.len(), &mut writer)?;
+        write_matrix(&self.t_g2, self.t_g2.len(), n, &mut writer, compress)?;
+        write_vector(&self.u_g2, eta, &mut writer, compress)?;
+        write_vector(&self.v_g2, k, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM2<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        let depth = read_depth(kem.max_levels, &mut reader)?;
+        let t_g2 = read_matrix(depth, n, &mut reader, compress, validate)?;
+        let u_g2 = read_vector(eta, &mut reader, compress, validate)?;
+        let v_g2 = read_vector(k, &mut reader, compress, validate)?;
+        Ok(HIBKEM2UserSecretKey { t_g2, u_g2, v_g2 })
+    }
+}
+
+impl<M: HIBKEM2Mac> KemSerialize<HIBKEM2<M>> for HIBKEM2Ciphertext {
+    const KIND: u8 = M::CIPHERTEXT_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &HIBKEM2<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        write_len(self.c1_g1.len(), &mut writer)?;
+        write_vector(&self.c0_g1, k + eta, &mut writer, compress)?;
+        write_matrix(&self.c1_g1, self.c1_g1.len(), n, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        kem: &HIBKEM2<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, eta) = (kem.k, kem.mac.n(), kem.mac.eta());
+        let depth = read_depth(kem.max_levels, &mut reader)?;
+        let c0_g1 = read_vector(k + eta, &mut reader, compress, validate)?;
+        let c1_g1 = read_matrix(depth, n, &mut reader, compress, validate)?;
+        Ok(HIBKEM2Ciphertext { c0_g1, c1_g1 })
     }
 }
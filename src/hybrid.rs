@@ -0,0 +1,233 @@
+use ark_serialize::SerializationError;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::{CryptoRng, RngCore};
+
+use crate::envelope::{EnvelopeScheme, open, seal};
+use crate::error::IbeError;
+use crate::kdf::{KdfContext, derive_key};
+use crate::kem::{HIBKEM, IBKEM};
+use crate::types::GTElement;
+
+// Hybrid ciphertext layout:
+//
+//   magic (4) || version (1) || identity || kem envelope (4 + n) || nonce (12)
+//   || aead ciphertext || tag (16)
+//
+// The identity is a level count (1) followed by every level (4 + n); identities of the
+// non-hierarchical KEMs have a single level. Lengths are little-endian. Everything in front
+// of the AEAD ciphertext is authenticated as associated data, and the DEM key is derived
+// from the encapsulated key bound to the scheme, the identity and the KEM envelope.
+
+pub const HYBRID_MAGIC: [u8; 4] = *b"IBEH";
+pub const HYBRID_VERSION: u8 = 1;
+pub const HYBRID_NONCE_LEN: usize = 12;
+pub const HYBRID_TAG_LEN: usize = 16;
+
+const DEM_KEY_LEN: usize = 32;
+
+struct HybridParts<'a> {
+    identity: Vec<Vec<u8>>,
+    kem_part: &'a [u8],
+    nonce: &'a [u8],
+    // magic up to and including the nonce, authenticated as associated data
+    header: &'a [u8],
+    payload: &'a [u8],
+}
+
+pub(crate) fn malformed() -> IbeError {
+    IbeError::MalformedEncoding(SerializationError::InvalidData)
+}
+
+pub(crate) fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], IbeError> {
+    if bytes.len() < n {
+        return Err(malformed());
+    }
+    let (head, tail) = bytes.split_at(n);
+    *bytes = tail;
+    Ok(head)
+}
+
+pub(crate) fn write_prefixed(bytes: &[u8], out: &mut Vec<u8>) -> Result<(), IbeError> {
+    let len = u32::try_from(bytes.len()).map_err(|_| malformed())?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+pub(crate) fn read_prefixed<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], IbeError> {
+    let len = u32::from_le_bytes(take(bytes, 4)?.try_into().unwrap()) as usize;
+    take(bytes, len)
+}
+
+pub(crate) fn write_identity(identity: &[Vec<u8>], out: &mut Vec<u8>) -> Result<(), IbeError> {
+    let levels = u8::try_from(identity.len()).map_err(|_| malformed())?;
+    out.push(levels);
+    for level in identity {
+        write_prefixed(level, out)?;
+    }
+    Ok(())
+}
+
+fn write_header(identity: &[Vec<u8>], kem_part: &[u8], nonce: &[u8]) -> Result<Vec<u8>, IbeError> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&HYBRID_MAGIC);
+    out.push(HYBRID_VERSION);
+    write_identity(identity, &mut out)?;
+    write_prefixed(kem_part, &mut out)?;
+    out.extend_from_slice(nonce);
+    Ok(out)
+}
+
+fn parse(blob: &[u8]) -> Result<HybridParts<'_>, IbeError> {
+    let mut bytes = blob;
+    if take(&mut bytes, HYBRID_MAGIC.len())? != HYBRID_MAGIC {
+        return Err(malformed());
+    }
+    if take(&mut bytes, 1)?[0] != HYBRID_VERSION {
+        return Err(malformed());
+    }
+
+    let levels = take(&mut bytes, 1)?[0] as usize;
+    let mut identity = Vec::with_capacity(levels);
+    for _ in 0..levels {
+        identity.push(read_prefixed(&mut bytes)?.to_vec());
+    }
+    let kem_part = read_prefixed(&mut bytes)?;
+    let nonce = take(&mut bytes, HYBRID_NONCE_LEN)?;
+    if bytes.len() < HYBRID_TAG_LEN {
+        return Err(malformed());
+    }
+
+    let header = &blob[..blob.len() - bytes.len()];
+    Ok(HybridParts {
+        identity,
+        kem_part,
+        nonce,
+        header,
+        payload: bytes,
+    })
+}
+
+fn dem_cipher(key: &GTElement, context: &KdfContext) -> ChaCha20Poly1305 {
+    let dem_key = derive_key(key, context, DEM_KEY_LEN);
+    ChaCha20Poly1305::new(Key::from_slice(&dem_key))
+}
+
+fn dem_seal<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    key: &GTElement,
+    context: &KdfContext,
+    identity: &[Vec<u8>],
+    kem_part: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, IbeError> {
+    let mut nonce = [0u8; HYBRID_NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+
+    let mut blob = write_header(identity, kem_part, &nonce)?;
+    let payload = Payload {
+        msg: plaintext,
+        aad: &blob,
+    };
+    let sealed = dem_cipher(key, context)
+        .encrypt(Nonce::from_slice(&nonce), payload)
+        .expect("plaintext within the ChaCha20-Poly1305 length limit");
+    blob.extend_from_slice(&sealed);
+    Ok(blob)
+}
+
+fn dem_open(
+    key: &GTElement,
+    context: &KdfContext,
+    parts: &HybridParts<'_>,
+) -> Result<Vec<u8>, IbeError> {
+    let payload = Payload {
+        msg: parts.payload,
+        aad: parts.header,
+    };
+    dem_cipher(key, context)
+        .decrypt(Nonce::from_slice(parts.nonce), payload)
+        .map_err(|_| IbeError::DecryptionFailed)
+}
+
+/// Returns the identity levels a hybrid ciphertext is addressed to.
+pub fn recipient(blob: &[u8]) -> Result<Vec<Vec<u8>>, IbeError> {
+    Ok(parse(blob)?.identity)
+}
+
+/// Encrypts `plaintext` of any length to `identity`, using `kem` for the key and
+/// ChaCha20-Poly1305 for the data.
+pub fn encrypt_bytes<K, R>(
+    kem: &K,
+    rng: &mut R,
+    pk: &K::PK,
+    identity: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, IbeError>
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+    R: RngCore + CryptoRng,
+{
+    let (ct, key) = kem.encrypt(rng, pk, identity)?;
+    let kem_part = seal(kem, &ct)?;
+    let context = KdfContext::new(K::SCHEME_ID, identity, &kem_part);
+    dem_seal(
+        rng,
+        &key,
+        &context,
+        &[identity.to_vec()],
+        &kem_part,
+        plaintext,
+    )
+}
+
+/// Decrypts a blob produced by [`encrypt_bytes`]. The public key is needed by KEMs that
+/// check a proof before decapsulating (IBKEM2).
+pub fn decrypt_bytes<K>(kem: &K, pk: &K::PK, usk: &K::USK, blob: &[u8]) -> Result<Vec<u8>, IbeError>
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+{
+    let parts = parse(blob)?;
+    let [identity] = parts.identity.as_slice() else {
+        return Err(malformed());
+    };
+    let ct = open(kem, parts.kem_part)?;
+    let key = kem.decrypt(pk, usk, identity, &ct)?;
+    let context = KdfContext::new(K::SCHEME_ID, identity, parts.kem_part);
+    dem_open(&key, &context, &parts)
+}
+
+/// Hierarchical counterpart of [`encrypt_bytes`].
+pub fn encrypt_bytes_hierarchical<K, R>(
+    kem: &K,
+    rng: &mut R,
+    pk: &K::PK,
+    identity: &[Vec<u8>],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, IbeError>
+where
+    K: HIBKEM + EnvelopeScheme<Ciphertext = <K as HIBKEM>::CT>,
+    R: RngCore + CryptoRng,
+{
+    let (ct, key) = kem.encrypt(rng, pk, identity)?;
+    let kem_part = seal(kem, &ct)?;
+    let context = KdfContext::hierarchical(K::SCHEME_ID, identity, &kem_part);
+    dem_seal(rng, &key, &context, identity, &kem_part, plaintext)
+}
+
+/// Decrypts a blob produced by [`encrypt_bytes_hierarchical`].
+pub fn decrypt_bytes_hierarchical<K>(
+    kem: &K,
+    usk: &K::USK,
+    blob: &[u8],
+) -> Result<Vec<u8>, IbeError>
+where
+    K: HIBKEM + EnvelopeScheme<Ciphertext = <K as HIBKEM>::CT>,
+{
+    let parts = parse(blob)?;
+    let ct = open(kem, parts.kem_part)?;
+    let key = kem.decrypt(usk, &ct)?;
+    let context = KdfContext::hierarchical(K::SCHEME_ID, &parts.identity, parts.kem_part);
+    dem_open(&key, &context, &parts)
+}
@@ -1,21 +1,28 @@
-use crate::affine_mac::{AffineMAC, SecretKey as MACSecretKey};
-use crate::bit_utils::bit_at;
+use crate::affine_mac::{AffineMAC, AffineMac};
+use crate::affine_mac_nr::AffineMacNr;
+use crate::error::{IbeError, check_dim, check_identity_len, check_non_empty};
 use crate::field_utils::*;
 use crate::group_functions::{multi_pairing, pairing};
+use crate::kem::IBKEM;
+use crate::serialization::*;
 use crate::types::*;
 
 use ark_bls12_381::{G1Projective as G1, G2Projective as G2};
 use ark_ec::PrimeGroup;
+use ark_serialize::{Compress, SerializationError, Validate};
+use rand::{CryptoRng, RngCore};
+use std::io::{Read, Write};
 
 pub struct IBKEM1PublicKey {
     pub m_matrix: Matrix<G1>,
-    // z_matrices = [z_{0,0}, z_{0,1}, z_{1,0}, z_{1,1}, ..., z_{l-1,0}, z_{l-1,1}]
+    // z_matrices = [z_0, ..., z_{l-1}], one per f_i(m) of the MAC
     pub z_matrices: Vec<Matrix<G1>>,
+    // one z'_j per f'_j(m) of the MAC
     pub z_prime_vectors: Matrix<G1>,
 }
 
-pub struct IBKEM1SecretKey {
-    pub mac_sk: MACSecretKey,
+pub struct IBKEM1SecretKey<M: AffineMac = AffineMAC> {
+    pub mac_sk: M::SecretKey,
     pub y_matrices: Vec<Matrix<FieldElement>>,
     pub y_prime_vectors: Vec<Vector>,
 }
@@ -26,160 +33,454 @@ pub struct IBKEM1UserSecretKey {
     pub v_g2: Vec<G2>,
 }
 
+#[derive(Clone, PartialEq)]
 pub struct IBKEM1Ciphertext {
     pub c0_g1: Vec<G1>,
     pub c1_g1: Vec<G1>,
 }
 
-pub struct IBKEM1 {
+/// The IBKEM transform of an affine MAC `M`: every MAC key `x_i` (`x'_j`) is hidden behind a
+/// fresh `y_i` (`y'_j`) as `z_i = (y_i^T || x_i^T) M`, and user keys are MAC tags on the identity.
+/// Only the affine functions and shapes of [`AffineMac`] are used, so any such MAC plugs in.
+pub struct IBKEM1<M: AffineMac = AffineMAC> {
     pub k: usize,
     pub msg_len: usize,
-    pub mac: AffineMAC,
+    pub mac: M,
+}
+
+/// The BKP14 IBKEM of Blazy–Kiltz–Pan (Crypto 2014), the transform over [`AffineMacNr`]. With
+/// `eta = 1`, `M` is a `(k + 1) x k` matrix and ciphertexts have `2k + 2` elements of `G1`.
+pub type BKP14 = IBKEM1<AffineMacNr>;
+pub type BKP14PublicKey = IBKEM1PublicKey;
+pub type BKP14SecretKey = IBKEM1SecretKey<AffineMacNr>;
+pub type BKP14UserSecretKey = IBKEM1UserSecretKey;
+pub type BKP14Ciphertext = IBKEM1Ciphertext;
+
+/// A MAC with a serializable [`IBKEM1`] instance: the name of the resulting IBKEM and the kinds
+/// of its objects.
+pub trait IBKEM1Mac: AffineMac + KemParams {
+    const NAME: &'static str;
+    const PUBLIC_KEY_KIND: u8;
+    const SECRET_KEY_KIND: u8;
+    const USER_SECRET_KEY_KIND: u8;
+    const CIPHERTEXT_KIND: u8;
+}
+
+impl IBKEM1Mac for AffineMAC {
+    const NAME: &'static str = "ibkem1";
+    const PUBLIC_KEY_KIND: u8 = KIND_IBKEM1_PUBLIC_KEY;
+    const SECRET_KEY_KIND: u8 = KIND_IBKEM1_SECRET_KEY;
+    const USER_SECRET_KEY_KIND: u8 = KIND_IBKEM1_USER_SECRET_KEY;
+    const CIPHERTEXT_KIND: u8 = KIND_IBKEM1_CIPHERTEXT;
+}
+
+impl IBKEM1Mac for AffineMacNr {
+    const NAME: &'static str = "bkp14";
+    const PUBLIC_KEY_KIND: u8 = KIND_BKP14_PUBLIC_KEY;
+    const SECRET_KEY_KIND: u8 = KIND_BKP14_SECRET_KEY;
+    const USER_SECRET_KEY_KIND: u8 = KIND_BKP14_USER_SECRET_KEY;
+    const CIPHERTEXT_KIND: u8 = KIND_BKP14_CIPHERTEXT;
 }
 
 impl IBKEM1 {
     pub fn new(k: usize, msg_len: usize) -> Self {
+        Self::with_mac(AffineMAC::new(k, msg_len))
+    }
+}
+
+impl<M: AffineMac> IBKEM1<M> {
+    pub fn with_mac(mac: M) -> Self {
         Self {
-            k,
-            msg_len,
-            mac: AffineMAC::new(k, msg_len),
+            k: mac.k(),
+            msg_len: mac.msg_len(),
+            mac,
         }
     }
 
-    pub fn setup(&self) -> (IBKEM1PublicKey, IBKEM1SecretKey) {
-        // we fix eta = 2k s.t. matrix formats for (y^T || x^T) * M
-        let eta = 2 * self.k;
-        let m_matrix = random_matrix(self.k + eta, self.k);
-        let mac_sk = self.mac.gen_mac();
+    pub fn setup<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(IBKEM1PublicKey, IBKEM1SecretKey<M>), IbeError> {
+        setup_keys(&self.mac, rng)
+    }
 
-        let l = mac_sk.x_matrices.len();
+    pub fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &IBKEM1SecretKey<M>,
+        identity: &[u8],
+    ) -> Result<IBKEM1UserSecretKey, IbeError> {
+        extract_key(
+            &self.mac,
+            rng,
+            &sk.mac_sk,
+            &sk.y_matrices,
+            &sk.y_prime_vectors,
+            identity,
+        )
+    }
 
-        let mut y_matrices = Vec::with_capacity(l);
-        let mut z_matrices = Vec::with_capacity(l);
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &IBKEM1PublicKey,
+        identity: &[u8],
+    ) -> Result<(IBKEM1Ciphertext, GTElement), IbeError> {
+        let (_, c0_g1, c1_g1, k_gt) = encapsulate(
+            &self.mac,
+            rng,
+            &pk.m_matrix,
+            &pk.z_matrices,
+            &pk.z_prime_vectors,
+            identity,
+        )?;
+        Ok((IBKEM1Ciphertext { c0_g1, c1_g1 }, k_gt))
+    }
 
-        for i in 0..l {
-            // we use (k x k) instead of (k x n) to ensure format of y_i and x_i matches for concat
-            let y_i = random_matrix(self.k, self.k);
-            let y_i_transposed = matrix_transpose(&y_i);
-            let x_i_transposed = matrix_transpose(&mac_sk.x_matrices[i]);
-            let z_i = matrix_concat_multiply(&y_i_transposed, &x_i_transposed, &m_matrix);
+    pub fn decrypt(
+        &self,
+        usk: &IBKEM1UserSecretKey,
+        ciphertext: &IBKEM1Ciphertext,
+    ) -> Result<GTElement, IbeError> {
+        decapsulate(
+            &usk.t_g2,
+            &usk.u_g2,
+            &usk.v_g2,
+            &ciphertext.c0_g1,
+            &ciphertext.c1_g1,
+        )
+    }
+}
 
-            y_matrices.push(y_i);
-            z_matrices.push(z_i);
-        }
+// The transform itself, shared with IBKEM2 which adds a QANIZK proof on top.
+
+pub(crate) fn setup_keys<M: AffineMac, R: RngCore + CryptoRng>(
+    mac: &M,
+    rng: &mut R,
+) -> Result<(IBKEM1PublicKey, IBKEM1SecretKey<M>), IbeError> {
+    let (k, n, eta) = (mac.k(), mac.n(), mac.eta());
+    // M is (k + eta) x k s.t. (y^T || x^T) * M is defined
+    let m_matrix = random_matrix(rng, k + eta, k);
+    let mac_sk = mac.gen_mac(rng);
+
+    let x_matrices = mac.x_matrices(&mac_sk);
+    let mut y_matrices = Vec::with_capacity(x_matrices.len());
+    let mut z_matrices = Vec::with_capacity(x_matrices.len());
+
+    for x_i in x_matrices {
+        // y_i is k x n like x_i is eta x n, so that their transposes concatenate
+        let y_i = random_matrix(rng, k, n);
+        let y_i_transposed = matrix_transpose(&y_i)?;
+        let x_i_transposed = matrix_transpose(x_i)?;
+        let z_i = matrix_concat_multiply(&y_i_transposed, &x_i_transposed, &m_matrix)?;
+
+        y_matrices.push(y_i);
+        z_matrices.push(z_i);
+    }
 
-        // specialized to l_prime = 0 based on the MAC we use
-        let l_prime = 0;
-        let mut y_prime_vectors = Vec::with_capacity(l_prime + 1);
-        let mut z_prime_vectors = Vec::with_capacity(l_prime + 1);
+    // one (y'_j, z'_j) per affine function f'_j(m) of the MAC, j = 0..=l_prime
+    let x_prime = mac.x_prime(&mac_sk);
+    let mut y_prime_vectors = Vec::with_capacity(x_prime.len());
+    let mut z_prime_vectors = Vec::with_capacity(x_prime.len());
 
-        for i in 0..=l_prime {
-            let y_prime_i = random_vector(self.k);
-            let combined = vector_concat(&y_prime_i, &mac_sk.x_prime[i]);
-            let z_prime_i = matrix_transpose_vector_mul(&m_matrix, &combined);
+    for x_prime_j in x_prime {
+        let y_prime_j = random_vector(rng, k);
+        let combined = vector_concat(&y_prime_j, x_prime_j);
+        let z_prime_j = matrix_transpose_vector_mul(&m_matrix, &combined)?;
 
-            y_prime_vectors.push(y_prime_i);
-            z_prime_vectors.push(z_prime_i);
-        }
+        y_prime_vectors.push(y_prime_j);
+        z_prime_vectors.push(z_prime_j);
+    }
 
-        let m_g1 = matrix_lift_g1(&m_matrix);
+    let pk = IBKEM1PublicKey {
+        m_matrix: matrix_lift_g1(&m_matrix),
+        z_matrices: z_matrices.iter().map(matrix_lift_g1).collect(),
+        z_prime_vectors: matrix_lift_g1(&z_prime_vectors),
+    };
 
-        let z_matrices_g1: Vec<Matrix<G1>> = z_matrices
-            .iter()
-            .map(|matrix| matrix_lift_g1(&matrix))
-            .collect();
+    let sk = IBKEM1SecretKey {
+        mac_sk,
+        y_matrices,
+        y_prime_vectors,
+    };
 
-        let z_prime_vectors_g1 = matrix_lift_g1(&z_prime_vectors);
+    Ok((pk, sk))
+}
 
-        let pk = IBKEM1PublicKey {
-            m_matrix: m_g1,
-            z_matrices: z_matrices_g1,
-            z_prime_vectors: z_prime_vectors_g1,
-        };
+pub(crate) fn extract_key<M: AffineMac, R: RngCore + CryptoRng>(
+    mac: &M,
+    rng: &mut R,
+    mac_sk: &M::SecretKey,
+    y_matrices: &[Matrix<FieldElement>],
+    y_prime_vectors: &Matrix<FieldElement>,
+    identity: &[u8],
+) -> Result<IBKEM1UserSecretKey, IbeError> {
+    check_identity_len(identity, mac.msg_len())?;
+    check_dim(mac.l_prime() + 1, y_prime_vectors.len())?;
+
+    // t stays here: it only enters v, and the user key gets [t]_2
+    let (tag, context) = mac.tag_with_context(rng, mac_sk, identity)?;
+
+    // (sum_i f_i(m) * y_i) * t + sum_j f'_j(m) * y'_j
+    let y_m = matrix_affine_combination(&mac.f(identity), y_matrices, mac.k(), mac.n())?;
+    let y_prime = matrix_transpose_vector_mul(y_prime_vectors, &mac.f_prime(identity))?;
+    let v_field = vector_add(&matrix_vector_mul(&y_m, context.t_field()), &y_prime);
+
+    Ok(IBKEM1UserSecretKey {
+        t_g2: tag.t_g2,
+        u_g2: tag.u_g2,
+        v_g2: vector_lift_g2(&v_field),
+    })
+}
 
-        let sk = IBKEM1SecretKey {
-            mac_sk,
-            y_matrices,
-            y_prime_vectors,
-        };
+/// Also returns the randomness `r`, which IBKEM2 proves knowledge of.
+pub(crate) fn encapsulate<M: AffineMac, R: RngCore + CryptoRng>(
+    mac: &M,
+    rng: &mut R,
+    m_matrix: &Matrix<G1>,
+    z_matrices: &[Matrix<G1>],
+    z_prime_vectors: &Matrix<G1>,
+    identity: &[u8],
+) -> Result<(Vector, Vec<G1>, Vec<G1>, GTElement), IbeError> {
+    check_identity_len(identity, mac.msg_len())?;
+    check_dim(mac.l(), z_matrices.len())?;
+    check_dim(mac.l_prime() + 1, z_prime_vectors.len())?;
+
+    let r = random_vector(rng, mac.k());
+    let c0_g1 = group_matrix_vector_mul_msm(m_matrix, &r)?;
+
+    // (sum_i f_i(m) * z_i) * r
+    let z_m = matrix_affine_combination(&mac.f(identity), z_matrices, mac.n(), mac.k())?;
+    let c1_g1 = group_matrix_vector_mul_msm(&z_m, &r)?;
+
+    // sum_j f'_j(m) * z'_j
+    let f_prime = mac.f_prime(identity);
+    let z_prime = group_matrix_transpose_vector_mul_msm(z_prime_vectors, &f_prime)?;
+    let k_g1 = vector_dot_g1(&r, &z_prime)?;
+
+    let k_gt = pairing(&k_g1, &G2::generator());
+
+    Ok((r, c0_g1, c1_g1, k_gt))
+}
 
-        (pk, sk)
-    }
+pub(crate) fn decapsulate(
+    t_g2: &[G2],
+    u_g2: &[G2],
+    v_g2: &[G2],
+    c0_g1: &[G1],
+    c1_g1: &[G1],
+) -> Result<GTElement, IbeError> {
+    let mut w_g2 = v_g2.to_vec();
+    w_g2.extend_from_slice(u_g2);
+
+    check_non_empty(c0_g1)?;
+    check_non_empty(c1_g1)?;
+    check_dim(w_g2.len(), c0_g1.len())?;
+    check_dim(t_g2.len(), c1_g1.len())?;
+
+    let first_term: Vec<_> = (0..c0_g1.len())
+        .map(|i| (c0_g1[i].clone(), w_g2[i].clone()))
+        .collect();
+
+    let second_term: Vec<_> = (0..c1_g1.len())
+        .map(|i| (c1_g1[i].clone(), t_g2[i].clone()))
+        .collect();
+
+    Ok(multi_pairing(&first_term) / multi_pairing(&second_term))
+}
 
-    pub fn extract(&self, sk: &IBKEM1SecretKey, identity: &[u8]) -> IBKEM1UserSecretKey {
-        assert_eq!(identity.len() * 8, self.msg_len);
+impl<M: IBKEM1Mac> IBKEM for IBKEM1<M> {
+    type PK = IBKEM1PublicKey;
+    type SK = IBKEM1SecretKey<M>;
+    type USK = IBKEM1UserSecretKey;
+    type CT = IBKEM1Ciphertext;
 
-        let tag = self.mac.tag(&sk.mac_sk, identity);
+    fn name(&self) -> String {
+        String::from(M::NAME)
+    }
 
-        // f_i(m) is specialized to the MAC we use
-        let mut v_field = vector_zero::<FieldElement>(self.k);
-        for i in 0..self.msg_len {
-            let b = bit_at(i, identity);
-            let y_i = &sk.y_matrices[2 * i + b];
+    fn setup<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(IBKEM1PublicKey, IBKEM1SecretKey<M>), IbeError> {
+        IBKEM1::setup(self, rng)
+    }
 
-            let y_i_t = matrix_vector_mul(&y_i, &tag.t_field);
-            v_field = vector_add(&v_field, &y_i_t);
-        }
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &IBKEM1SecretKey<M>,
+        identity: &[u8],
+    ) -> Result<IBKEM1UserSecretKey, IbeError> {
+        IBKEM1::extract(self, rng, sk, identity)
+    }
 
-        // Specialized to l_prime = 0 and f'_0(m) = 1 based on the MAC we use
-        let y_prime = &sk.y_prime_vectors[0];
-        v_field = vector_add(&v_field, &y_prime);
+    fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &IBKEM1PublicKey,
+        identity: &[u8],
+    ) -> Result<(IBKEM1Ciphertext, GTElement), IbeError> {
+        IBKEM1::encrypt(self, rng, pk, identity)
+    }
 
-        let v_g2 = vector_lift_g2(&v_field);
+    fn decrypt(
+        &self,
+        _pk: &IBKEM1PublicKey,
+        usk: &IBKEM1UserSecretKey,
+        _identity: &[u8],
+        ct: &IBKEM1Ciphertext,
+    ) -> Result<GTElement, IbeError> {
+        IBKEM1::decrypt(self, usk, ct)
+    }
+}
 
-        IBKEM1UserSecretKey {
-            t_g2: tag.t_g2,
-            u_g2: tag.u_g2,
-            v_g2,
+impl<M: AffineMac> KemParams for IBKEM1<M> {
+    fn dimensions(&self) -> KemDimensions {
+        KemDimensions {
+            k: dim(self.k),
+            msg_len: dim(self.msg_len),
+            max_levels: 0,
+            identity_len: 0,
+            lambda: 0,
         }
     }
+}
 
-    pub fn encrypt(&self, pk: &IBKEM1PublicKey, identity: &[u8]) -> (IBKEM1Ciphertext, GTElement) {
-        assert_eq!(identity.len() * 8, self.msg_len);
-
-        let r = random_vector(self.k);
-        let c0_g1 = group_matrix_vector_mul_msm(&pk.m_matrix, &r);
-
-        let n = pk.z_matrices[0].len();
-        let mut z_i_sum = matrix_zero::<G1>(n, self.k);
-
-        // f_i(m) is specialized to the MAC we use here
-        for i in 0..self.msg_len {
-            let b = bit_at(i, identity);
-            let z_i = &pk.z_matrices[2 * i + b];
-            z_i_sum = matrix_add(&z_i_sum, &z_i);
+impl<M: IBKEM1Mac> KemSerialize<IBKEM1<M>> for IBKEM1PublicKey {
+    const KIND: u8 = M::PUBLIC_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &IBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, mac) = (kem.k, kem.mac.n(), &kem.mac);
+        write_matrix(&self.m_matrix, k + mac.eta(), k, &mut writer, compress)?;
+        check_len(self.z_matrices.len(), mac.l())?;
+        for z in &self.z_matrices {
+            write_matrix(z, n, k, &mut writer, compress)?;
         }
-        let c1_g1 = group_matrix_vector_mul_msm(&z_i_sum, &r);
-
-        // Specialized to l_prime = 0 and f'_0(m) = 1 based on the MAC we use
-        let z_prime = &pk.z_prime_vectors[0];
-        let k_g1 = vector_dot_g1(&r, &z_prime);
+        write_matrix(&self.z_prime_vectors, mac.l_prime() + 1, k, &mut writer, compress)
+    }
 
-        let k_gt = pairing(&k_g1, &G2::generator());
+    fn deserialize_body<R: Read>(
+        kem: &IBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, mac) = (kem.k, kem.mac.n(), &kem.mac);
+        let m_matrix = read_matrix(k + mac.eta(), k, &mut reader, compress, validate)?;
+        let z_matrices = (0..mac.l())
+            .map(|_| read_matrix(n, k, &mut reader, compress, validate))
+            .collect::<Result<_, _>>()?;
+        let z_prime_vectors =
+            read_matrix(mac.l_prime() + 1, k, &mut reader, compress, validate)?;
+        Ok(IBKEM1PublicKey {
+            m_matrix,
+            z_matrices,
+            z_prime_vectors,
+        })
+    }
+}
 
-        let ciphertext = IBKEM1Ciphertext { c0_g1, c1_g1 };
-        (ciphertext, k_gt)
+impl<M: IBKEM1Mac> KemSerialize<IBKEM1<M>> for IBKEM1SecretKey<M>
+where
+    M::SecretKey: KemSerialize<M>,
+{
+    const KIND: u8 = M::SECRET_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &IBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let (k, n, mac) = (kem.k, kem.mac.n(), &kem.mac);
+        self.mac_sk
+            .serialize_body(mac, &mut writer, compress)?;
+        check_len(self.y_matrices.len(), mac.l())?;
+        for y in &self.y_matrices {
+            write_matrix(y, k, n, &mut writer, compress)?;
+        }
+        write_matrix(&self.y_prime_vectors, mac.l_prime() + 1, k, &mut writer, compress)
     }
 
-    pub fn decrypt(&self, usk: &IBKEM1UserSecretKey, ciphertext: &IBKEM1Ciphertext) -> GTElement {
-        let mut w_g2 = usk.v_g2.clone();
-        w_g2.extend_from_slice(&usk.u_g2);
+    fn deserialize_body<R: Read>(
+        kem: &IBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let (k, n, mac) = (kem.k, kem.mac.n(), &kem.mac);
+        let mac_sk = M::SecretKey::deserialize_body(mac, &mut reader, compress, validate)?;
+        let y_matrices = (0..mac.l())
+            .map(|_| read_matrix(k, n, &mut reader, compress, validate))
+            .collect::<Result<_, _>>()?;
+        let y_prime_vectors =
+            read_matrix(mac.l_prime() + 1, k, &mut reader, compress, validate)?;
+        Ok(IBKEM1SecretKey {
+            mac_sk,
+            y_matrices,
+            y_prime_vectors,
+        })
+    }
+}
 
-        let c0_g1 = &ciphertext.c0_g1;
-        let c1_g1 = &ciphertext.c1_g1;
-        assert_ne!(c0_g1.len(), 0);
-        assert_ne!(c1_g1.len(), 0);
-        assert_eq!(c0_g1.len(), w_g2.len());
-        assert_eq!(c1_g1.len(), usk.t_g2.len());
+impl<M: IBKEM1Mac> KemSerialize<IBKEM1<M>> for IBKEM1UserSecretKey {
+    const KIND: u8 = M::USER_SECRET_KEY_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &IBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = kem.k;
+        write_vector(&self.t_g2, kem.mac.n(), &mut writer, compress)?;
+        write_vector(&self.u_g2, kem.mac.eta(), &mut writer, compress)?;
+        write_vector(&self.v_g2, k, &mut writer, compress)
+    }
 
-        let first_term: Vec<_> = (0..c0_g1.len())
-            .map(|i| (c0_g1[i].clone(), w_g2[i].clone()))
-            .collect();
+    fn deserialize_body<R: Read>(
+        kem: &IBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = kem.k;
+        let t_g2 = read_vector(kem.mac.n(), &mut reader, compress, validate)?;
+        let u_g2 = read_vector(kem.mac.eta(), &mut reader, compress, validate)?;
+        let v_g2 = read_vector(k, &mut reader, compress, validate)?;
+        Ok(IBKEM1UserSecretKey { t_g2, u_g2, v_g2 })
+    }
+}
 
-        let second_term: Vec<_> = (0..c1_g1.len())
-            .map(|i| (c1_g1[i].clone(), usk.t_g2[i].clone()))
-            .collect();
+impl<M: IBKEM1Mac> KemSerialize<IBKEM1<M>> for IBKEM1Ciphertext {
+    const KIND: u8 = M::CIPHERTEXT_KIND;
+
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &IBKEM1<M>,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = kem.k;
+        write_vector(&self.c0_g1, k + kem.mac.eta(), &mut writer, compress)?;
+        write_vector(&self.c1_g1, kem.mac.n(), &mut writer, compress)
+    }
 
-        multi_pairing(&first_term) / multi_pairing(&second_term)
+    fn deserialize_body<R: Read>(
+        kem: &IBKEM1<M>,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = kem.k;
+        let c0_g1 = read_vector(k + kem.mac.eta(), &mut reader, compress, validate)?;
+        let c1_g1 = read_vector(kem.mac.n(), &mut reader, compress, validate)?;
+        Ok(IBKEM1Ciphertext { c0_g1, c1_g1 })
     }
 }
@@ -1,24 +1,28 @@
-use crate::affine_mac::{AffineMAC, SecretKey as MACSecretKey};
-use crate::bit_utils::bit_at;
-use crate::field_utils::*;
-use crate::group_functions::{multi_pairing, pairing};
+use crate::affine_mac::{AffineMAC, AffineMac, SecretKey as MACSecretKey};
+use crate::error::IbeError;
+use crate::ibkem1::{decapsulate, encapsulate, extract_key, setup_keys};
+use crate::kem::IBKEM;
 use crate::qanizk::{CRS, QANIZK, QANIZKProof as Proof};
+use crate::serialization::*;
 use crate::types::*;
 
 use ark_bls12_381::{G1Projective as G1, G2Projective as G2};
-use ark_ec::{CurveGroup, PrimeGroup};
+use ark_ec::CurveGroup;
 use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{Compress, SerializationError, Validate};
+use rand::{CryptoRng, RngCore};
+use std::io::{Read, Write};
 
 pub struct IBKEM2PublicKey {
     pub m_matrix: Matrix<G1>,
-    // z_matrices = [z_{0,0}, z_{0,1}, z_{1,0}, z_{1,1}, ..., z_{l-1,0}, z_{l-1,1}]
+    // z_matrices = [z_0, ..., z_{l-1}], one per f_i(m) of the MAC
     pub z_matrices: Vec<Matrix<G1>>,
     pub z_prime_vectors: Matrix<G1>,
     pub crs: CRS,
 }
 
-pub struct IBKEM2SecretKey {
-    pub mac_sk: MACSecretKey,
+pub struct IBKEM2SecretKey<M: AffineMac = AffineMAC> {
+    pub mac_sk: M::SecretKey,
     pub y_matrices: Vec<Matrix<FieldElement>>,
     pub y_prime_vectors: Vec<Vector>,
 }
@@ -29,203 +33,343 @@ pub struct IBKEM2UserSecretKey {
     pub v_g2: Vec<G2>,
 }
 
+#[derive(Clone, PartialEq)]
 pub struct IBKEM2Ciphertext {
     pub c0_g1: Vec<G1>,
     pub c1_g1: Vec<G1>,
     pub proof: Proof,
 }
 
-pub struct IBKEM2 {
+/// The transform of [`crate::IBKEM1`] with a QANIZK proof that `c0` lies in the span of `M`,
+/// bound to the identity, which makes it CCA secure.
+pub struct IBKEM2<M: AffineMac = AffineMAC> {
     pub k: usize,
     pub msg_len: usize,
-    pub mac: AffineMAC,
+    pub mac: M,
     pub qanizk: QANIZK,
 }
 
 impl IBKEM2 {
     pub fn new(k: usize, msg_len: usize, lambda: usize) -> Self {
-        Self {
-            k,
-            msg_len,
-            mac: AffineMAC::new(k, msg_len),
-            qanizk: QANIZK::new(k, lambda),
-        }
+        Self::with_mac(AffineMAC::new(k, msg_len), lambda)
     }
+}
 
-    pub fn setup(&self) -> (IBKEM2PublicKey, IBKEM2SecretKey) {
-        // we fix eta = 2k s.t. matrix formats for (y^T || x^T) * M
-        let eta = 2 * self.k;
-        let m_matrix = random_matrix(self.k + eta, self.k);
-        let mac_sk = self.mac.gen_mac();
-
-        let l = mac_sk.x_matrices.len();
-
-        let mut y_matrices = Vec::with_capacity(l);
-        let mut z_matrices = Vec::with_capacity(l);
-
-        for i in 0..l {
-            // we use (k x k) instead of (k x n) to ensure format of y_i and x_i matches for concat
-            let y_i = random_matrix(self.k, self.k);
-            let y_i_transposed = matrix_transpose(&y_i);
-            let x_i_transposed = matrix_transpose(&mac_sk.x_matrices[i]);
-            let z_i = matrix_concat_multiply(&y_i_transposed, &x_i_transposed, &m_matrix);
-
-            y_matrices.push(y_i);
-            z_matrices.push(z_i);
-        }
-
-        // specialized to l_prime = 0 based on the MAC we use
-        let l_prime = 0;
-        let mut y_prime_vectors = Vec::with_capacity(l_prime + 1);
-        let mut z_prime_vectors = Vec::with_capacity(l_prime + 1);
-
-        for i in 0..=l_prime {
-            let y_prime_i = random_vector(self.k);
-            let combined = vector_concat(&y_prime_i, &mac_sk.x_prime[i]);
-            let z_prime_i = matrix_transpose_vector_mul(&m_matrix, &combined);
+// tag (identity || c0) of the QANIZK proof
+fn proof_tag(identity: &[u8], c0_g1: &[G1]) -> Vec<u8> {
+    let mut tag = Vec::new();
+    tag.extend_from_slice(identity);
+    for point in c0_g1 {
+        let affine = point.into_affine();
+        tag.extend_from_slice(&affine.x.into_bigint().to_bytes_le());
+        tag.extend_from_slice(&affine.y.into_bigint().to_bytes_le());
+    }
+    tag
+}
 
-            y_prime_vectors.push(y_prime_i);
-            z_prime_vectors.push(z_prime_i);
+impl<M: AffineMac> IBKEM2<M> {
+    pub fn with_mac(mac: M, lambda: usize) -> Self {
+        Self {
+            k: mac.k(),
+            msg_len: mac.msg_len(),
+            qanizk: QANIZK::new(mac.k(), lambda),
+            mac,
         }
+    }
 
-        let m_g1 = matrix_lift_g1(&m_matrix);
-
-        let z_matrices_g1: Vec<Matrix<G1>> = z_matrices
-            .iter()
-            .map(|matrix| matrix_lift_g1(&matrix))
-            .collect();
-
-        let z_prime_vectors_g1 = matrix_lift_g1(&z_prime_vectors);
+    pub fn setup<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(IBKEM2PublicKey, IBKEM2SecretKey<M>), IbeError> {
+        let (pk, sk) = setup_keys(&self.mac, rng)?;
 
-        let (crs, _) = self.qanizk.gen_crs(&m_g1);
+        let (crs, _) = self.qanizk.gen_crs(rng, &pk.m_matrix)?;
 
         let pk = IBKEM2PublicKey {
-            m_matrix: m_g1,
-            z_matrices: z_matrices_g1,
-            z_prime_vectors: z_prime_vectors_g1,
+            m_matrix: pk.m_matrix,
+            z_matrices: pk.z_matrices,
+            z_prime_vectors: pk.z_prime_vectors,
             crs,
         };
 
         let sk = IBKEM2SecretKey {
-            mac_sk,
-            y_matrices,
-            y_prime_vectors,
+            mac_sk: sk.mac_sk,
+            y_matrices: sk.y_matrices,
+            y_prime_vectors: sk.y_prime_vectors,
         };
 
-        (pk, sk)
+        Ok((pk, sk))
     }
 
-    pub fn extract(&self, sk: &IBKEM2SecretKey, identity: &[u8]) -> IBKEM2UserSecretKey {
-        assert_eq!(identity.len() * 8, self.msg_len);
-
-        let tag = self.mac.tag(&sk.mac_sk, identity);
-
-        // f_i(m) is specialized to the MAC we use
-        let mut v_field = vector_zero::<FieldElement>(self.k);
-        for i in 0..self.msg_len {
-            let b = bit_at(i, identity);
-            let y_i = &sk.y_matrices[2 * i + b];
+    pub fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &IBKEM2SecretKey<M>,
+        identity: &[u8],
+    ) -> Result<IBKEM2UserSecretKey, IbeError> {
+        let usk = extract_key(
+            &self.mac,
+            rng,
+            &sk.mac_sk,
+            &sk.y_matrices,
+            &sk.y_prime_vectors,
+            identity,
+        )?;
+
+        Ok(IBKEM2UserSecretKey {
+            t_g2: usk.t_g2,
+            u_g2: usk.u_g2,
+            v_g2: usk.v_g2,
+        })
+    }
 
-            let y_i_t = matrix_vector_mul(&y_i, &tag.t_field);
-            v_field = vector_add(&v_field, &y_i_t);
-        }
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &IBKEM2PublicKey,
+        identity: &[u8],
+    ) -> Result<(IBKEM2Ciphertext, GTElement), IbeError> {
+        let (r, c0_g1, c1_g1, k_gt) = encapsulate(
+            &self.mac,
+            rng,
+            &pk.m_matrix,
+            &pk.z_matrices,
+            &pk.z_prime_vectors,
+            identity,
+        )?;
+
+        let tag = proof_tag(identity, &c0_g1);
+        let proof = self.qanizk.prove(rng, &pk.crs, &tag, &c0_g1, &r)?;
 
-        // Specialized to l_prime = 0 and f'_0(m) = 1 based on the MAC we use
-        let y_prime = &sk.y_prime_vectors[0];
-        v_field = vector_add(&v_field, &y_prime);
+        let ciphertext = IBKEM2Ciphertext {
+            c0_g1,
+            c1_g1,
+            proof,
+        };
 
-        let v_g2 = vector_lift_g2(&v_field);
+        Ok((ciphertext, k_gt))
+    }
 
-        IBKEM2UserSecretKey {
-            t_g2: tag.t_g2,
-            u_g2: tag.u_g2,
-            v_g2,
-        }
+    pub fn decrypt(
+        &self,
+        pk: &IBKEM2PublicKey,
+        usk: &IBKEM2UserSecretKey,
+        identity: &[u8],
+        ciphertext: &IBKEM2Ciphertext,
+    ) -> Result<GTElement, IbeError> {
+        let tag = proof_tag(identity, &ciphertext.c0_g1);
+        self.qanizk
+            .verify(&pk.crs, &tag, &ciphertext.c0_g1, &ciphertext.proof)?;
+
+        decapsulate(
+            &usk.t_g2,
+            &usk.u_g2,
+            &usk.v_g2,
+            &ciphertext.c0_g1,
+            &ciphertext.c1_g1,
+        )
     }
+}
 
-    pub fn encrypt(&self, pk: &IBKEM2PublicKey, identity: &[u8]) -> (IBKEM2Ciphertext, GTElement) {
-        assert_eq!(identity.len() * 8, self.msg_len);
+impl IBKEM for IBKEM2 {
+    type PK = IBKEM2PublicKey;
+    type SK = IBKEM2SecretKey;
+    type USK = IBKEM2UserSecretKey;
+    type CT = IBKEM2Ciphertext;
 
-        let r = random_vector(self.k);
-        let c0_g1 = group_matrix_vector_mul_msm(&pk.m_matrix, &r);
+    fn name(&self) -> String {
+        String::from("ibkem2")
+    }
 
-        let n = pk.z_matrices[0].len();
-        let mut z_i_sum = matrix_zero::<G1>(n, self.k);
+    fn setup<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(IBKEM2PublicKey, IBKEM2SecretKey), IbeError> {
+        IBKEM2::setup(self, rng)
+    }
 
-        // f_i(m) is specialized to the MAC we use here
-        for i in 0..self.msg_len {
-            let b = bit_at(i, identity);
-            let z_i = &pk.z_matrices[2 * i + b];
-            z_i_sum = matrix_add(&z_i_sum, &z_i);
-        }
-        let c1_g1 = group_matrix_vector_mul_msm(&z_i_sum, &r);
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &IBKEM2SecretKey,
+        identity: &[u8],
+    ) -> Result<IBKEM2UserSecretKey, IbeError> {
+        IBKEM2::extract(self, rng, sk, identity)
+    }
 
-        // Specialized to l_prime = 0 and f'_0(m) = 1 based on the MAC we use
-        let z_prime = &pk.z_prime_vectors[0];
-        let k_g1 = vector_dot_g1(&r, &z_prime);
+    fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &IBKEM2PublicKey,
+        identity: &[u8],
+    ) -> Result<(IBKEM2Ciphertext, GTElement), IbeError> {
+        IBKEM2::encrypt(self, rng, pk, identity)
+    }
 
-        let k_gt = pairing(&k_g1, &G2::generator());
+    fn decrypt(
+        &self,
+        pk: &IBKEM2PublicKey,
+        usk: &IBKEM2UserSecretKey,
+        identity: &[u8],
+        ct: &IBKEM2Ciphertext,
+    ) -> Result<GTElement, IbeError> {
+        IBKEM2::decrypt(self, pk, usk, identity, ct)
+    }
+}
 
-        let mut tag = Vec::new();
-        tag.extend_from_slice(identity);
-        for point in &c0_g1 {
-            let affine = point.into_affine();
-            tag.extend_from_slice(&affine.x.into_bigint().to_bytes_le());
-            tag.extend_from_slice(&affine.y.into_bigint().to_bytes_le());
+impl KemParams for IBKEM2 {
+    fn dimensions(&self) -> KemDimensions {
+        KemDimensions {
+            k: dim(self.k),
+            msg_len: dim(self.msg_len),
+            max_levels: 0,
+            identity_len: 0,
+            lambda: dim(self.qanizk.lambda),
         }
+    }
+}
 
-        let proof = self.qanizk.prove(&pk.crs, &tag, &c0_g1, &r);
+impl KemSerialize<IBKEM2> for IBKEM2PublicKey {
+    const KIND: u8 = KIND_IBKEM2_PUBLIC_KEY;
 
-        let ciphertext = IBKEM2Ciphertext {
-            c0_g1,
-            c1_g1,
-            proof,
-        };
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &IBKEM2,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = kem.k;
+        write_matrix(&self.m_matrix, 3 * k, k, &mut writer, compress)?;
+        check_len(self.z_matrices.len(), 2 * kem.msg_len)?;
+        for z in &self.z_matrices {
+            write_matrix(z, k, k, &mut writer, compress)?;
+        }
+        write_matrix(&self.z_prime_vectors, 1, k, &mut writer, compress)?;
+        self.crs.serialize_body(&kem.qanizk, &mut writer, compress)
+    }
 
-        (ciphertext, k_gt)
+    fn deserialize_body<R: Read>(
+        kem: &IBKEM2,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = kem.k;
+        let m_matrix = read_matrix(3 * k, k, &mut reader, compress, validate)?;
+        let z_matrices = (0..2 * kem.msg_len)
+            .map(|_| read_matrix(k, k, &mut reader, compress, validate))
+            .collect::<Result<_, _>>()?;
+        let z_prime_vectors = read_matrix(1, k, &mut reader, compress, validate)?;
+        let crs = CRS::deserialize_body(&kem.qanizk, &mut reader, compress, validate)?;
+        // the CRS is generated for [M]_1, which has 3k rows
+        check_len(crs.ka_g2.len(), 3 * k)?;
+        Ok(IBKEM2PublicKey {
+            m_matrix,
+            z_matrices,
+            z_prime_vectors,
+            crs,
+        })
     }
+}
 
-    pub fn decrypt(
+impl KemSerialize<IBKEM2> for IBKEM2SecretKey {
+    const KIND: u8 = KIND_IBKEM2_SECRET_KEY;
+
+    fn serialize_body<W: Write>(
         &self,
-        pk: &IBKEM2PublicKey,
-        usk: &IBKEM2UserSecretKey,
-        identity: &[u8],
-        ciphertext: &IBKEM2Ciphertext,
-    ) -> Option<GTElement> {
-        let crs = &pk.crs;
-        // tag (identity || c0)
-        let mut tag = Vec::new();
-        tag.extend_from_slice(identity);
-        for point in &ciphertext.c0_g1 {
-            let affine = point.into_affine();
-            tag.extend_from_slice(&affine.x.into_bigint().to_bytes_le());
-            tag.extend_from_slice(&affine.y.into_bigint().to_bytes_le());
+        kem: &IBKEM2,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = kem.k;
+        self.mac_sk
+            .serialize_body(&kem.mac, &mut writer, compress)?;
+        check_len(self.y_matrices.len(), 2 * kem.msg_len)?;
+        for y in &self.y_matrices {
+            write_matrix(y, k, k, &mut writer, compress)?;
         }
+        write_matrix(&self.y_prime_vectors, 1, k, &mut writer, compress)
+    }
 
-        let c0_g1 = &ciphertext.c0_g1;
-        let c1_g1 = &ciphertext.c1_g1;
+    fn deserialize_body<R: Read>(
+        kem: &IBKEM2,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = kem.k;
+        let mac_sk = MACSecretKey::deserialize_body(&kem.mac, &mut reader, compress, validate)?;
+        let y_matrices = (0..2 * kem.msg_len)
+            .map(|_| read_matrix(k, k, &mut reader, compress, validate))
+            .collect::<Result<_, _>>()?;
+        let y_prime_vectors = read_matrix(1, k, &mut reader, compress, validate)?;
+        Ok(IBKEM2SecretKey {
+            mac_sk,
+            y_matrices,
+            y_prime_vectors,
+        })
+    }
+}
 
-        let is_valid = self.qanizk.verify(crs, &tag, c0_g1, &ciphertext.proof);
-        if !is_valid {
-            return None;
-        }
+impl KemSerialize<IBKEM2> for IBKEM2UserSecretKey {
+    const KIND: u8 = KIND_IBKEM2_USER_SECRET_KEY;
 
-        let mut w_g2 = usk.v_g2.clone();
-        w_g2.extend_from_slice(&usk.u_g2);
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &IBKEM2,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = kem.k;
+        write_vector(&self.t_g2, k, &mut writer, compress)?;
+        write_vector(&self.u_g2, 2 * k, &mut writer, compress)?;
+        write_vector(&self.v_g2, k, &mut writer, compress)
+    }
 
-        assert_ne!(c0_g1.len(), 0);
-        assert_ne!(c1_g1.len(), 0);
-        assert_eq!(c0_g1.len(), w_g2.len());
-        assert_eq!(c1_g1.len(), usk.t_g2.len());
+    fn deserialize_body<R: Read>(
+        kem: &IBKEM2,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = kem.k;
+        let t_g2 = read_vector(k, &mut reader, compress, validate)?;
+        let u_g2 = read_vector(2 * k, &mut reader, compress, validate)?;
+        let v_g2 = read_vector(k, &mut reader, compress, validate)?;
+        Ok(IBKEM2UserSecretKey { t_g2, u_g2, v_g2 })
+    }
+}
 
-        let first_term: Vec<_> = (0..c0_g1.len())
-            .map(|i| (c0_g1[i].clone(), w_g2[i].clone()))
-            .collect();
+impl KemSerialize<IBKEM2> for IBKEM2Ciphertext {
+    const KIND: u8 = KIND_IBKEM2_CIPHERTEXT;
 
-        let second_term: Vec<_> = (0..c1_g1.len())
-            .map(|i| (c1_g1[i].clone(), usk.t_g2[i].clone()))
-            .collect();
+    fn serialize_body<W: Write>(
+        &self,
+        kem: &IBKEM2,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = kem.k;
+        write_vector(&self.c0_g1, 3 * k, &mut writer, compress)?;
+        write_vector(&self.c1_g1, k, &mut writer, compress)?;
+        self.proof
+            .serialize_body(&kem.qanizk, &mut writer, compress)
+    }
 
-        Some(multi_pairing(&first_term) / multi_pairing(&second_term))
+    fn deserialize_body<R: Read>(
+        kem: &IBKEM2,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = kem.k;
+        let c0_g1 = read_vector(3 * k, &mut reader, compress, validate)?;
+        let c1_g1 = read_vector(k, &mut reader, compress, validate)?;
+        let proof = Proof::deserialize_body(&kem.qanizk, &mut reader, compress, validate)?;
+        Ok(IBKEM2Ciphertext {
+            c0_g1,
+            c1_g1,
+            proof,
+        })
     }
 }
@@ -0,0 +1,70 @@
+//! Key derivation for the `GTElement` session keys returned by the KEMs.
+//!
+//! Domain separation policy:
+//! - BLAKE3 runs in derive_key mode under the fixed context [`KDF_CONTEXT`], so the output is
+//!   independent of every other use of BLAKE3 in this crate (identity and tag hashing).
+//! - The hashed input binds the scheme name, the identity and a hash of the ciphertext, in that
+//!   order and each with a length prefix, followed by the compressed session key. A key derived
+//!   for one scheme, identity or ciphertext is therefore unrelated to any other.
+//! - Hierarchical identities are encoded level by level, each level with its own length prefix,
+//!   so `["ab", "c"]` and `["a", "bc"]` give different keys.
+//! - The output length is not part of the input: a shorter request yields a prefix of a longer
+//!   one. Callers that need independent keys of different lengths should use separate contexts.
+
+use ark_serialize::CanonicalSerialize;
+use digest::Update;
+
+use crate::blake3::Blake3;
+use crate::hashing::blake3_hash_bytes;
+use crate::types::GTElement;
+
+/// Context string of the BLAKE3 derive_key mode; changing it changes every derived key.
+pub const KDF_CONTEXT: &str = "ibe_schemes 2024-10-01 GTElement session key derivation v1";
+
+/// Everything a derived key is bound to besides the session key itself.
+pub struct KdfContext {
+    pub scheme: String,
+    pub identity: Vec<u8>,
+    pub ciphertext_hash: Vec<u8>,
+}
+
+impl KdfContext {
+    pub fn new(scheme: &str, identity: &[u8], ciphertext: &[u8]) -> Self {
+        Self {
+            scheme: String::from(scheme),
+            identity: identity.to_vec(),
+            ciphertext_hash: blake3_hash_bytes(ciphertext),
+        }
+    }
+
+    pub fn hierarchical(scheme: &str, identity: &[Vec<u8>], ciphertext: &[u8]) -> Self {
+        let mut encoded = Vec::new();
+        for level in identity {
+            encoded.extend_from_slice(&(level.len() as u64).to_le_bytes());
+            encoded.extend_from_slice(level);
+        }
+        Self::new(scheme, &encoded, ciphertext)
+    }
+}
+
+fn update_prefixed(hasher: &mut Blake3, bytes: &[u8]) {
+    hasher.update(&(bytes.len() as u64).to_le_bytes());
+    hasher.update(bytes);
+}
+
+/// Derives `len` bytes of key material from a KEM session key.
+pub fn derive_key(key: &GTElement, context: &KdfContext, len: usize) -> Vec<u8> {
+    let mut key_bytes = Vec::new();
+    key.serialize_compressed(&mut key_bytes)
+        .expect("serializing into a Vec does not fail");
+
+    let mut hasher = Blake3::new_derive_key(KDF_CONTEXT);
+    update_prefixed(&mut hasher, context.scheme.as_bytes());
+    update_prefixed(&mut hasher, &context.identity);
+    update_prefixed(&mut hasher, &context.ciphertext_hash);
+    hasher.update(&key_bytes);
+
+    let mut out = vec![0u8; len];
+    hasher.finalize_xof_into(&mut out);
+    out
+}
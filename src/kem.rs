@@ -0,0 +1,158 @@
+use rand::{CryptoRng, RngCore};
+
+use crate::IbeError;
+use crate::kdf::{KdfContext, derive_key};
+use crate::serialization::{KemParams, KemSerialize};
+use crate::types::GTElement;
+
+/// Identity-based KEM over bit-string identities, implemented by the MAC-based constructions.
+///
+/// `encrypt` returns `(ciphertext, key)`. `decrypt` receives the public key and the identity as
+/// well, since some schemes (IBKEM2) check a proof bound to them before decapsulating.
+pub trait IBKEM {
+    type PK;
+    type SK;
+    type USK;
+    type CT;
+
+    fn name(&self) -> String;
+
+    fn setup<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(Self::PK, Self::SK), IbeError>;
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &Self::SK,
+        identity: &[u8],
+    ) -> Result<Self::USK, IbeError>;
+
+    fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &Self::PK,
+        identity: &[u8],
+    ) -> Result<(Self::CT, GTElement), IbeError>;
+
+    fn decrypt(
+        &self,
+        pk: &Self::PK,
+        usk: &Self::USK,
+        identity: &[u8],
+        ct: &Self::CT,
+    ) -> Result<GTElement, IbeError>;
+
+    /// Like `encrypt`, but derives `key_len` bytes of key material bound to the scheme, the
+    /// identity and the ciphertext (see [`crate::kdf`]).
+    fn encapsulate_bytes<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &Self::PK,
+        identity: &[u8],
+        key_len: usize,
+    ) -> Result<(Self::CT, Vec<u8>), IbeError>
+    where
+        Self: KemParams + Sized,
+        Self::CT: KemSerialize<Self>,
+    {
+        let (ct, key) = self.encrypt(rng, pk, identity)?;
+        let context = KdfContext::new(&self.name(), identity, &ct.to_bytes(self)?);
+        Ok((ct, derive_key(&key, &context, key_len)))
+    }
+
+    fn decapsulate_bytes(
+        &self,
+        pk: &Self::PK,
+        usk: &Self::USK,
+        identity: &[u8],
+        ct: &Self::CT,
+        key_len: usize,
+    ) -> Result<Vec<u8>, IbeError>
+    where
+        Self: KemParams + Sized,
+        Self::CT: KemSerialize<Self>,
+    {
+        let key = self.decrypt(pk, usk, identity, ct)?;
+        let context = KdfContext::new(&self.name(), identity, &ct.to_bytes(self)?);
+        Ok(derive_key(&key, &context, key_len))
+    }
+}
+
+/// Output of [`HIBKEM::setup`]: public key, delegation key and master secret key.
+pub type HIBKEMSetup<T> = (<T as HIBKEM>::PK, <T as HIBKEM>::DK, <T as HIBKEM>::SK);
+
+/// Hierarchical identity-based KEM, one bit-string per level.
+///
+/// `USK` holds everything a user needs to decrypt and to delegate further (for HIBKEM1 this
+/// includes the user delegation key). `encrypt` returns `(ciphertext, key)` like [`IBKEM`].
+pub trait HIBKEM {
+    type PK;
+    type DK;
+    type SK;
+    type USK;
+    type CT;
+
+    fn name(&self) -> String;
+
+    fn setup<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<HIBKEMSetup<Self>, IbeError>;
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &Self::SK,
+        identity: &[Vec<u8>],
+    ) -> Result<Self::USK, IbeError>;
+
+    fn delegate<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        dk: &Self::DK,
+        usk: &Self::USK,
+        id_prefix: &[Vec<u8>],
+        id_next: Vec<u8>,
+    ) -> Result<Self::USK, IbeError>;
+
+    fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &Self::PK,
+        identity: &[Vec<u8>],
+    ) -> Result<(Self::CT, GTElement), IbeError>;
+
+    fn decrypt(&self, usk: &Self::USK, ct: &Self::CT) -> Result<GTElement, IbeError>;
+
+    /// Like `encrypt`, but derives `key_len` bytes of key material bound to the scheme, the
+    /// identity and the ciphertext (see [`crate::kdf`]).
+    fn encapsulate_bytes<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &Self::PK,
+        identity: &[Vec<u8>],
+        key_len: usize,
+    ) -> Result<(Self::CT, Vec<u8>), IbeError>
+    where
+        Self: KemParams + Sized,
+        Self::CT: KemSerialize<Self>,
+    {
+        let (ct, key) = self.encrypt(rng, pk, identity)?;
+        let context = KdfContext::hierarchical(&self.name(), identity, &ct.to_bytes(self)?);
+        Ok((ct, derive_key(&key, &context, key_len)))
+    }
+
+    /// The ciphertext does not carry the identity, so the caller passes the one it was
+    /// encrypted to.
+    fn decapsulate_bytes(
+        &self,
+        usk: &Self::USK,
+        identity: &[Vec<u8>],
+        ct: &Self::CT,
+        key_len: usize,
+    ) -> Result<Vec<u8>, IbeError>
+    where
+        Self: KemParams + Sized,
+        Self::CT: KemSerialize<Self>,
+    {
+        let key = self.decrypt(usk, ct)?;
+        let context = KdfContext::hierarchical(&self.name(), identity, &ct.to_bytes(self)?);
+        Ok(derive_key(&key, &context, key_len))
+    }
+}
@@ -1,30 +1,53 @@
 pub mod affine_mac;
 pub mod affine_mac_levels1;
 pub mod affine_mac_levels2;
+pub mod affine_mac_nr;
+pub mod armor;
 pub mod bit_utils;
 pub mod blake3;
+pub mod envelope;
+pub mod error;
 pub mod field_utils;
+pub mod games;
 pub mod group_functions;
 pub mod hashing;
 pub mod hibkem1;
 pub mod hibkem2;
+pub mod hybrid;
 pub mod ibkem1;
 pub mod ibkem2;
+pub mod kdf;
+pub mod kem;
+pub mod multi_recipient;
+pub mod ots;
 pub mod pes;
 pub mod qanizk;
+pub mod serialization;
+pub mod stream;
 pub mod types;
 
 pub use affine_mac::*;
 pub use affine_mac_levels1::*;
 pub use affine_mac_levels2::*;
+pub use affine_mac_nr::*;
+pub use armor::*;
 pub use bit_utils::*;
 pub use blake3::*;
+pub use envelope::*;
+pub use error::*;
 pub use field_utils::*;
 pub use group_functions::*;
 pub use hashing::*;
 pub use hibkem1::*;
 pub use hibkem2::*;
+pub use hybrid::*;
 pub use ibkem1::*;
 pub use ibkem2::*;
+pub use kdf::*;
+pub use kem::*;
+pub use multi_recipient::*;
+pub use ots::*;
 pub use qanizk::*;
+pub use serialization::*;
+pub use stream::*;
 pub use types::*;
@@ -0,0 +1,261 @@
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::{CryptoRng, RngCore};
+
+use crate::envelope::{EnvelopeScheme, open, seal};
+use crate::error::{IbeError, check_non_empty};
+use crate::hybrid::{
+    HYBRID_NONCE_LEN, HYBRID_TAG_LEN, malformed, read_prefixed, take, write_prefixed,
+};
+use crate::kdf::{KdfContext, derive_key};
+use crate::kem::IBKEM;
+use crate::types::GTElement;
+
+// Multi-recipient ciphertext layout:
+//
+//   magic (4) || version (1) || slot count (2) || slots || nonce (12) || aead ciphertext
+//   || tag (16)
+//
+// with every slot being identity (4 + n) || kem envelope (4 + n) || wrapped data key (32 + 16).
+// Lengths are little-endian. The payload is sealed once under a random data key, with
+// everything in front of the AEAD ciphertext as associated data. Each slot wraps the data key
+// under a key derived from the slot's encapsulated key, bound to the scheme, the identity and
+// the slot's KEM envelope. Wrapping keys are used once, so the wrap uses an all-zero nonce.
+//
+// Every recipient learns the data key and could produce a ciphertext for the others: the
+// format protects against outsiders, it does not authenticate the sender.
+
+pub const MULTI_MAGIC: [u8; 4] = *b"IBEM";
+pub const MULTI_VERSION: u8 = 1;
+pub const MULTI_WRAPPED_KEY_LEN: usize = DATA_KEY_LEN + HYBRID_TAG_LEN;
+
+const DATA_KEY_LEN: usize = 32;
+
+/// KEMs that can encapsulate one key to many identities with shared encryption randomness,
+/// which is cheaper than an independent encapsulation per identity.
+///
+/// Only implement this where sharing is secure. `PesKem<BF>` qualifies (see
+/// [`crate::pes::bf::BF::encrypt_shared`]); BB does not: with a shared `s`, two components
+/// `(b_0 + x_i * b_1) * s * g1` determine `b_0 * s * g1` and `b_1 * s * g1`, hence the
+/// ciphertext for every identity, and any non-recipient key decrypts.
+pub trait SharedRandomnessIBKEM: IBKEM {
+    /// Returns one ciphertext per identity, all encapsulating the returned key.
+    fn encrypt_shared<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &Self::PK,
+        identities: &[Vec<u8>],
+    ) -> Result<(Vec<Self::CT>, GTElement), IbeError>;
+}
+
+struct Slot<'a> {
+    identity: &'a [u8],
+    kem_part: &'a [u8],
+    wrapped: &'a [u8],
+}
+
+struct MultiParts<'a> {
+    slots: Vec<Slot<'a>>,
+    nonce: &'a [u8],
+    // magic up to and including the nonce, authenticated as associated data
+    header: &'a [u8],
+    payload: &'a [u8],
+}
+
+fn parse(blob: &[u8]) -> Result<MultiParts<'_>, IbeError> {
+    let mut bytes = blob;
+    if take(&mut bytes, MULTI_MAGIC.len())? != MULTI_MAGIC {
+        return Err(malformed());
+    }
+    if take(&mut bytes, 1)?[0] != MULTI_VERSION {
+        return Err(malformed());
+    }
+
+    let count = u16::from_le_bytes(take(&mut bytes, 2)?.try_into().unwrap()) as usize;
+    let mut slots = Vec::with_capacity(count);
+    for _ in 0..count {
+        slots.push(Slot {
+            identity: read_prefixed(&mut bytes)?,
+            kem_part: read_prefixed(&mut bytes)?,
+            wrapped: take(&mut bytes, MULTI_WRAPPED_KEY_LEN)?,
+        });
+    }
+    let nonce = take(&mut bytes, HYBRID_NONCE_LEN)?;
+    if bytes.len() < HYBRID_TAG_LEN {
+        return Err(malformed());
+    }
+
+    let header = &blob[..blob.len() - bytes.len()];
+    Ok(MultiParts {
+        slots,
+        nonce,
+        header,
+        payload: bytes,
+    })
+}
+
+fn wrap_cipher(key: &GTElement, context: &KdfContext) -> ChaCha20Poly1305 {
+    let wrap_key = derive_key(key, context, DATA_KEY_LEN);
+    ChaCha20Poly1305::new(Key::from_slice(&wrap_key))
+}
+
+fn seal_multi<K, R>(
+    kem: &K,
+    rng: &mut R,
+    recipients: Vec<(&[u8], K::Ciphertext, GTElement)>,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, IbeError>
+where
+    K: EnvelopeScheme,
+    R: RngCore + CryptoRng,
+{
+    let mut data_key = [0u8; DATA_KEY_LEN];
+    rng.fill_bytes(&mut data_key);
+
+    let mut blob = Vec::new();
+    blob.extend_from_slice(&MULTI_MAGIC);
+    blob.push(MULTI_VERSION);
+    let count = u16::try_from(recipients.len()).map_err(|_| malformed())?;
+    blob.extend_from_slice(&count.to_le_bytes());
+
+    for (identity, ct, key) in recipients {
+        let kem_part = seal(kem, &ct)?;
+        let context = KdfContext::new(K::SCHEME_ID, identity, &kem_part);
+        let wrapped = wrap_cipher(&key, &context)
+            .encrypt(&Nonce::default(), data_key.as_slice())
+            .expect("data key within the ChaCha20-Poly1305 length limit");
+        write_prefixed(identity, &mut blob)?;
+        write_prefixed(&kem_part, &mut blob)?;
+        blob.extend_from_slice(&wrapped);
+    }
+
+    let mut nonce = [0u8; HYBRID_NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    blob.extend_from_slice(&nonce);
+
+    let payload = Payload {
+        msg: plaintext,
+        aad: &blob,
+    };
+    let sealed = ChaCha20Poly1305::new(Key::from_slice(&data_key))
+        .encrypt(Nonce::from_slice(&nonce), payload)
+        .expect("plaintext within the ChaCha20-Poly1305 length limit");
+    blob.extend_from_slice(&sealed);
+    Ok(blob)
+}
+
+fn open_slot<K>(
+    kem: &K,
+    pk: &K::PK,
+    usk: &K::USK,
+    parts: &MultiParts<'_>,
+    index: usize,
+) -> Result<Vec<u8>, IbeError>
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+{
+    let slot = parts.slots.get(index).ok_or(IbeError::RecipientNotFound)?;
+    let ct = open(kem, slot.kem_part)?;
+    let key = kem.decrypt(pk, usk, slot.identity, &ct)?;
+    let context = KdfContext::new(K::SCHEME_ID, slot.identity, slot.kem_part);
+    let data_key = wrap_cipher(&key, &context)
+        .decrypt(&Nonce::default(), slot.wrapped)
+        .map_err(|_| IbeError::DecryptionFailed)?;
+
+    let payload = Payload {
+        msg: parts.payload,
+        aad: parts.header,
+    };
+    ChaCha20Poly1305::new(Key::from_slice(&data_key))
+        .decrypt(Nonce::from_slice(parts.nonce), payload)
+        .map_err(|_| IbeError::DecryptionFailed)
+}
+
+/// Returns the identities of a multi-recipient ciphertext in slot order.
+pub fn multi_recipients(blob: &[u8]) -> Result<Vec<Vec<u8>>, IbeError> {
+    Ok(parse(blob)?
+        .slots
+        .iter()
+        .map(|slot| slot.identity.to_vec())
+        .collect())
+}
+
+/// Encrypts `plaintext` once and makes it readable by every identity in `identities`, with
+/// an independent encapsulation per identity.
+pub fn encrypt_multi<K, R>(
+    kem: &K,
+    rng: &mut R,
+    pk: &K::PK,
+    identities: &[Vec<u8>],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, IbeError>
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+    R: RngCore + CryptoRng,
+{
+    check_non_empty(identities)?;
+    let mut recipients = Vec::with_capacity(identities.len());
+    for identity in identities {
+        let (ct, key) = kem.encrypt(rng, pk, identity)?;
+        recipients.push((identity.as_slice(), ct, key));
+    }
+    seal_multi(kem, rng, recipients, plaintext)
+}
+
+/// Like [`encrypt_multi`], but encapsulates with randomness shared between the identities.
+/// The result has the same format and is decrypted with [`decrypt_multi`].
+pub fn encrypt_multi_shared<K, R>(
+    kem: &K,
+    rng: &mut R,
+    pk: &K::PK,
+    identities: &[Vec<u8>],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, IbeError>
+where
+    K: SharedRandomnessIBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+    R: RngCore + CryptoRng,
+{
+    check_non_empty(identities)?;
+    let (cts, key) = kem.encrypt_shared(rng, pk, identities)?;
+    let recipients = identities
+        .iter()
+        .zip(cts)
+        .map(|(identity, ct)| (identity.as_slice(), ct, key))
+        .collect();
+    seal_multi(kem, rng, recipients, plaintext)
+}
+
+/// Decrypts a multi-recipient ciphertext with the key of `identity`, using the first slot
+/// addressed to it.
+pub fn decrypt_multi<K>(
+    kem: &K,
+    pk: &K::PK,
+    usk: &K::USK,
+    identity: &[u8],
+    blob: &[u8],
+) -> Result<Vec<u8>, IbeError>
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+{
+    let parts = parse(blob)?;
+    let index = parts
+        .slots
+        .iter()
+        .position(|slot| slot.identity == identity)
+        .ok_or(IbeError::RecipientNotFound)?;
+    open_slot(kem, pk, usk, &parts, index)
+}
+
+/// Decrypts a multi-recipient ciphertext using the slot at `index`.
+pub fn decrypt_multi_slot<K>(
+    kem: &K,
+    pk: &K::PK,
+    usk: &K::USK,
+    index: usize,
+    blob: &[u8],
+) -> Result<Vec<u8>, IbeError>
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+{
+    open_slot(kem, pk, usk, &parse(blob)?, index)
+}
@@ -0,0 +1,79 @@
+use ark_bls12_381::{Fr, G1Projective as G1};
+use ark_ec::PrimeGroup;
+use ark_ff::UniformRand;
+use ark_ff::field_hashers::{DefaultFieldHasher, HashToField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::{CryptoRng, RngCore};
+
+use crate::blake3::Blake3;
+
+// One-time signature from discrete logarithms: the signing key is (x, y), the verifying key
+// (x * g1, y * g1) and the signature on m is x + h * y with h = H(vk, m). A second signature
+// under the same key reveals x and y, which is why signing consumes the key. Each message has
+// exactly one valid signature, so the scheme is strongly unforgeable as long as H is
+// collision resistant.
+
+const OTS_DOMAIN: &str = "OTS";
+
+pub struct OtsSigningKey {
+    x: Fr,
+    y: Fr,
+    vk: OtsVerifyingKey,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
+pub struct OtsVerifyingKey {
+    pub x: G1,
+    pub y: G1,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
+pub struct OtsSignature {
+    pub sigma: Fr,
+}
+
+fn challenge(vk: &OtsVerifyingKey, message: &[u8]) -> Fr {
+    let mut input = Vec::new();
+    vk.serialize_compressed(&mut input)
+        .expect("serializing into a Vec does not fail");
+    input.extend_from_slice(message);
+
+    let hasher = <DefaultFieldHasher<Blake3> as HashToField<Fr>>::new(OTS_DOMAIN.as_bytes());
+    hasher.hash_to_field::<1>(&input)[0]
+}
+
+impl OtsSigningKey {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let g1 = G1::generator();
+        let x = Fr::rand(rng);
+        let y = Fr::rand(rng);
+
+        Self {
+            x,
+            y,
+            vk: OtsVerifyingKey {
+                x: g1 * x,
+                y: g1 * y,
+            },
+        }
+    }
+
+    pub fn verifying_key(&self) -> &OtsVerifyingKey {
+        &self.vk
+    }
+
+    /// Signs `message`. The key is consumed because it must not sign twice.
+    pub fn sign(self, message: &[u8]) -> OtsSignature {
+        let h = challenge(&self.vk, message);
+        OtsSignature {
+            sigma: self.x + h * self.y,
+        }
+    }
+}
+
+impl OtsVerifyingKey {
+    pub fn verify(&self, message: &[u8], signature: &OtsSignature) -> bool {
+        let h = challenge(self, message);
+        G1::generator() * signature.sigma == self.x + self.y * h
+    }
+}
@@ -2,18 +2,21 @@ use ark_bls12_381::{Bls12_381, Fq12 as Gt, Fr, G1Projective as G1, G2Projective
 use ark_ec::PrimeGroup;
 use ark_ec::pairing::Pairing;
 use ark_ff::{Field, PrimeField, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
 
 use super::IBEScheme;
 
-use crate::hash_to_fr;
+use crate::{IbeError, hash_to_fr};
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct MSK {
     pub alpha: Fr,
     pub b_0: Fr,
     pub b_1: Fr,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct MPK {
     pub a: Gt,
     pub b_0_g1: G1,
@@ -22,12 +25,14 @@ pub struct MPK {
     pub b_1_g2: G2,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct USK {
     pub identity: String,
     pub r: G2,
     pub k: G2,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
 pub struct CT {
     pub identity: String,
     pub msg: Gt,
@@ -35,12 +40,40 @@ pub struct CT {
     pub c: G1,
 }
 
+#[derive(Default)]
 pub struct BB {}
 
 impl BB {
     pub fn new() -> BB {
         Self {}
     }
+
+    /// Recomputes the master public key belonging to `msk`.
+    pub fn public_key(&self, msk: &MSK) -> MPK {
+        let g1 = G1::generator();
+        let g2 = G2::generator();
+
+        MPK {
+            a: Bls12_381::pairing(g1 * msk.alpha, g2).0,
+            b_0_g1: g1 * msk.b_0,
+            b_0_g2: g2 * msk.b_0,
+            b_1_g1: g1 * msk.b_1,
+            b_1_g2: g2 * msk.b_1,
+        }
+    }
+
+    /// Deterministic encryption with the exponent `s` supplied by the caller.
+    pub fn encrypt_with_randomness(&self, s: Fr, msg: &Gt, mpk: &MPK, identity: String) -> CT {
+        let g1 = G1::generator();
+        let xid = hash_to_fr(&identity);
+
+        CT {
+            identity,
+            msg: mpk.a.pow(s.into_bigint()) * msg,
+            s: g1 * s,
+            c: mpk.b_0_g1 * s + mpk.b_1_g1 * (s * xid),
+        }
+    }
 }
 
 impl IBEScheme for BB {
@@ -58,53 +91,40 @@ impl IBEScheme for BB {
         let b_0 = Fr::rand(&mut rng);
         let b_1 = Fr::rand(&mut rng);
         let msk = MSK { alpha, b_0, b_1 };
-
-        let g1 = G1::generator();
-        let g2 = G2::generator();
-
-        let mpk = MPK {
-            a: Bls12_381::pairing(g1 * alpha, g2).0,
-            b_0_g1: g1 * b_0,
-            b_0_g2: g2 * b_0,
-            b_1_g1: g1 * b_1,
-            b_1_g2: g2 * b_1,
-        };
+        let mpk = self.public_key(&msk);
 
         (msk, mpk)
     }
 
-    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: String) -> USK {
+    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: String) -> Result<USK, IbeError> {
         let g2 = G2::generator();
         let r = Fr::rand(&mut rng);
         let xid = hash_to_fr(&identity);
 
-        USK {
+        Ok(USK {
             identity: identity.clone(),
             r: g2 * r,
             k: g2 * (msk.alpha + r * (msk.b_0 + xid * msk.b_1)),
-        }
+        })
     }
 
-    fn encrypt(&self, mut rng: impl Rng, msg: &Gt, mpk: &MPK, identity: String) -> CT {
-        let g1 = G1::generator();
-
+    fn encrypt(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identity: String,
+    ) -> Result<CT, IbeError> {
         let s = Fr::rand(&mut rng);
-        let xid = hash_to_fr(&identity);
-
-        CT {
-            identity: identity.clone(),
-            msg: mpk.a.pow(s.into_bigint()) * msg,
-            s: g1 * s,
-            c: mpk.b_0_g1 * s + mpk.b_1_g1 * (s * xid),
-        }
+        Ok(self.encrypt_with_randomness(s, msg, mpk, identity))
     }
 
-    fn decrypt(&self, usk: &USK, ct: &CT) -> Option<Gt> {
+    fn decrypt(&self, usk: &USK, ct: &CT) -> Result<Gt, IbeError> {
         if &usk.identity != &ct.identity {
-            return None;
+            return Err(IbeError::IdentityMismatch);
         }
 
         let result = Bls12_381::pairing(ct.s, usk.k).0 * Bls12_381::pairing(-ct.c, usk.r).0;
-        Some(ct.msg / result)
+        Ok(ct.msg / result)
     }
 }
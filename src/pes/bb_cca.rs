@@ -0,0 +1,10 @@
+use super::bb::{self, BB};
+use super::fo::{self, Fo};
+
+pub type MSK = bb::MSK;
+pub type MPK = bb::MPK;
+pub type USK = fo::USK<BB>;
+pub type CT = fo::CT<BB>;
+
+/// [`BB`] made CCA secure with the Fujisaki–Okamoto transform, see [`Fo`].
+pub type BBCca = Fo<BB>;
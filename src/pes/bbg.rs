@@ -0,0 +1,207 @@
+use ark_bls12_381::{Bls12_381, Fq12 as Gt, Fr, G1Projective as G1, G2Projective as G2};
+use ark_ec::PrimeGroup;
+use ark_ec::pairing::Pairing;
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+
+use crate::{IbeError, check_dim, check_identity_non_empty, hash_to_fr, pes::HIBEScheme};
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct MSK {
+    pub alpha: Fr,
+    pub h: Vec<Fr>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct MPK {
+    pub a: Gt,
+    pub h_g1: Vec<G1>,
+    pub h_g2: Vec<G2>,
+}
+
+/// `b` holds `r * h_j` for the levels below the key, `j = depth + 1..=max_depth`.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct USK {
+    pub identity: Vec<String>,
+    pub k: G2,
+    pub r: G2,
+    pub b: Vec<G2>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
+pub struct CT {
+    pub identity: Vec<String>,
+    pub msg: Gt,
+    pub s: G1,
+    pub c: G1,
+}
+
+/// Boneh–Boyen–Goh HIBE (Eurocrypt 2005) with ciphertexts of three group elements at any
+/// depth. Identity levels are hashed to `I_i`, and an identity of depth `d` maps to
+/// `h_0 + sum_{i <= d} I_i * h_i` in the exponent.
+pub struct BBG {
+    pub max_depth: usize,
+}
+
+impl BBG {
+    pub fn new(max_depth: usize) -> BBG {
+        Self { max_depth }
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<(), IbeError> {
+        if depth > self.max_depth {
+            return Err(IbeError::DepthOutOfRange {
+                depth,
+                max: self.max_depth,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_usk(&self, usk: &USK) -> Result<(), IbeError> {
+        check_identity_non_empty(&usk.identity)?;
+        self.check_depth(usk.identity.len())?;
+        check_dim(self.max_depth - usk.identity.len(), usk.b.len())
+    }
+}
+
+impl HIBEScheme for BBG {
+    type MPK = MPK;
+    type MSK = MSK;
+    type USK = USK;
+    type CT = CT;
+
+    fn name(&self) -> String {
+        String::from("bbg")
+    }
+
+    fn setup(&self, mut rng: impl Rng) -> (MSK, MPK) {
+        let alpha = Fr::rand(&mut rng);
+        let h = (0..=self.max_depth)
+            .map(|_| Fr::rand(&mut rng))
+            .collect::<Vec<_>>();
+
+        let g1 = G1::generator();
+        let g2 = G2::generator();
+
+        let mpk = MPK {
+            a: Bls12_381::pairing(g1 * alpha, g2).0,
+            h_g1: h.iter().map(|h_i| g1 * h_i).collect(),
+            h_g2: h.iter().map(|h_i| g2 * h_i).collect(),
+        };
+        let msk = MSK { alpha, h };
+
+        (msk, mpk)
+    }
+
+    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: Vec<String>) -> Result<USK, IbeError> {
+        check_identity_non_empty(&identity)?;
+        self.check_depth(identity.len())?;
+        check_dim(self.max_depth + 1, msk.h.len())?;
+        let n_k = identity.len();
+
+        let g2 = G2::generator();
+        let r = Fr::rand(&mut rng);
+
+        let mut f_id = msk.h[0];
+        for (id, h_i) in identity.iter().zip(&msk.h[1..]) {
+            f_id += hash_to_fr(id) * h_i;
+        }
+
+        Ok(USK {
+            identity,
+            k: g2 * (msk.alpha + r * f_id),
+            r: g2 * r,
+            b: msk.h[n_k + 1..].iter().map(|h_j| g2 * (r * h_j)).collect(),
+        })
+    }
+
+    fn encrypt(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identity: Vec<String>,
+    ) -> Result<CT, IbeError> {
+        check_identity_non_empty(&identity)?;
+        self.check_depth(identity.len())?;
+        check_dim(self.max_depth + 1, mpk.h_g1.len())?;
+
+        let g1 = G1::generator();
+        let s = Fr::rand(&mut rng);
+
+        let mut f_id = mpk.h_g1[0];
+        for (id, h_i) in identity.iter().zip(&mpk.h_g1[1..]) {
+            f_id += *h_i * hash_to_fr(id);
+        }
+
+        Ok(CT {
+            identity,
+            msg: mpk.a.pow(s.into_bigint()) * msg,
+            s: g1 * s,
+            c: f_id * s,
+        })
+    }
+
+    fn decrypt(&self, usk: &USK, ct: &CT) -> Result<Gt, IbeError> {
+        self.check_usk(usk)?;
+        let n_k = usk.identity.len();
+
+        if !can_decrypt(&usk.identity, &ct.identity) {
+            return Err(IbeError::IdentityMismatch);
+        }
+        self.check_depth(ct.identity.len())?;
+
+        // a superior key is extended to the ciphertext's identity without re-randomizing
+        let mut k = usk.k;
+        for (b_j, id) in usk.b.iter().zip(&ct.identity[n_k..]) {
+            k += *b_j * hash_to_fr(id);
+        }
+
+        let result = Bls12_381::pairing(ct.s, k).0 * Bls12_381::pairing(-ct.c, usk.r).0;
+        Ok(ct.msg / result)
+    }
+
+    fn delegate(
+        &self,
+        mut rng: impl Rng,
+        mpk: &MPK,
+        usk: &USK,
+        identity_extension: String,
+    ) -> Result<USK, IbeError> {
+        self.check_usk(usk)?;
+        check_dim(self.max_depth + 1, mpk.h_g2.len())?;
+        let n_k = usk.identity.len() + 1;
+        self.check_depth(n_k)?;
+
+        let g2 = G2::generator();
+        let t = Fr::rand(&mut rng);
+
+        let mut new_identity = usk.identity.clone();
+        new_identity.push(identity_extension);
+
+        let mut f_id = mpk.h_g2[0];
+        for (id, h_i) in new_identity.iter().zip(&mpk.h_g2[1..]) {
+            f_id += *h_i * hash_to_fr(id);
+        }
+        let x_ext = hash_to_fr(&new_identity[n_k - 1]);
+
+        Ok(USK {
+            identity: new_identity,
+            k: usk.k + usk.b[0] * x_ext + f_id * t,
+            r: usk.r + g2 * t,
+            b: usk.b[1..]
+                .iter()
+                .zip(&mpk.h_g2[n_k + 1..])
+                .map(|(b_j, h_j)| *b_j + *h_j * t)
+                .collect(),
+        })
+    }
+}
+
+fn can_decrypt(key: &[String], ct: &[String]) -> bool {
+    let is_shorter = key.len() <= ct.len();
+    let prefix_matches = key.iter().zip(ct.iter()).all(|(x, y)| x == y);
+    is_shorter && prefix_matches
+}
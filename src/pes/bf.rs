@@ -2,24 +2,29 @@ use ark_bls12_381::{Bls12_381, Fq12 as Gt, Fr, G1Projective as G1, G2Projective
 use ark_ec::PrimeGroup;
 use ark_ec::pairing::Pairing;
 use ark_ff::{Field, PrimeField, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
 
-use crate::{hash_to_g1, pes::IBEScheme};
+use crate::{IbeError, hash_to_g1, pes::IBEScheme};
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct MSK {
     pub alpha: Fr,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct MPK {
     pub a: Gt,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct USK {
     pub identity: String,
     pub r: G2,
     pub k: G1,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
 pub struct CT {
     pub identity: String,
     pub msg: Gt,
@@ -27,12 +32,67 @@ pub struct CT {
     pub c: G1,
 }
 
+#[derive(Default)]
 pub struct BF {}
 
 impl BF {
     pub fn new() -> BF {
         Self {}
     }
+
+    /// Recomputes the master public key belonging to `msk`.
+    pub fn public_key(&self, msk: &MSK) -> MPK {
+        let g1 = G1::generator();
+        let g2 = G2::generator();
+
+        MPK {
+            a: Bls12_381::pairing(g1 * msk.alpha, g2).0,
+        }
+    }
+
+    /// Deterministic encryption with the exponent `s` supplied by the caller.
+    pub fn encrypt_with_randomness(&self, s: Fr, msg: &Gt, mpk: &MPK, identity: String) -> CT {
+        let g2 = G2::generator();
+        let bid = hash_to_g1(&identity);
+
+        CT {
+            identity,
+            msg: mpk.a.pow(s.into_bigint()) * msg,
+            s: g2 * s,
+            c: bid * s,
+        }
+    }
+
+    /// Encrypts `msg` to every identity with a single exponent `s`, so `a^s` and `g2^s` are
+    /// computed once and each further recipient costs one `G1` multiplication. Every returned
+    /// ciphertext decrypts with [`IBEScheme::decrypt`] as usual.
+    ///
+    /// Sharing `s` is safe here: the ciphertexts only add `H(id_i)^s` for random-oracle points,
+    /// and computing `H(id)^s` for any other identity from them is a CDH instance (this is the
+    /// multi-receiver construction of Baek, Safavi-Naini and Susilo).
+    pub fn encrypt_shared(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identities: Vec<String>,
+    ) -> Vec<CT> {
+        let g2 = G2::generator();
+
+        let s = Fr::rand(&mut rng);
+        let blinded = mpk.a.pow(s.into_bigint()) * msg;
+        let s_g2 = g2 * s;
+
+        identities
+            .into_iter()
+            .map(|identity| CT {
+                c: hash_to_g1(&identity) * s,
+                identity,
+                msg: blinded,
+                s: s_g2,
+            })
+            .collect()
+    }
 }
 
 impl IBEScheme for BF {
@@ -48,50 +108,41 @@ impl IBEScheme for BF {
     fn setup(&self, mut rng: impl Rng) -> (MSK, MPK) {
         let alpha = Fr::rand(&mut rng);
         let msk = MSK { alpha };
-
-        let g1 = G1::generator();
-        let g2 = G2::generator();
-
-        let mpk = MPK {
-            a: Bls12_381::pairing(g1 * alpha, g2).0,
-        };
+        let mpk = self.public_key(&msk);
 
         (msk, mpk)
     }
 
-    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: String) -> USK {
+    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: String) -> Result<USK, IbeError> {
         let g1 = G1::generator();
         let g2 = G2::generator();
         let r = Fr::rand(&mut rng);
         let bid = hash_to_g1(&identity);
 
-        USK {
+        Ok(USK {
             identity: identity.clone(),
             r: g2 * r,
             k: g1 * msk.alpha + bid * r,
-        }
+        })
     }
 
-    fn encrypt(&self, mut rng: impl Rng, msg: &Gt, mpk: &MPK, identity: String) -> CT {
-        let g2 = G2::generator();
-
+    fn encrypt(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identity: String,
+    ) -> Result<CT, IbeError> {
         let s = Fr::rand(&mut rng);
-        let bid = hash_to_g1(&identity);
-
-        CT {
-            identity: identity.clone(),
-            msg: mpk.a.pow(s.into_bigint()) * msg,
-            s: g2 * s,
-            c: bid * s,
-        }
+        Ok(self.encrypt_with_randomness(s, msg, mpk, identity))
     }
 
-    fn decrypt(&self, usk: &USK, ct: &CT) -> Option<Gt> {
+    fn decrypt(&self, usk: &USK, ct: &CT) -> Result<Gt, IbeError> {
         if &usk.identity != &ct.identity {
-            return None;
+            return Err(IbeError::IdentityMismatch);
         }
 
         let result = Bls12_381::pairing(usk.k, ct.s).0 * Bls12_381::pairing(-ct.c, usk.r).0;
-        Some(ct.msg / result)
+        Ok(ct.msg / result)
     }
 }
@@ -0,0 +1,10 @@
+use super::bf::{self, BF};
+use super::fo::{self, Fo};
+
+pub type MSK = bf::MSK;
+pub type MPK = bf::MPK;
+pub type USK = fo::USK<BF>;
+pub type CT = fo::CT<BF>;
+
+/// [`BF`] made CCA secure with the Fujisaki–Okamoto transform, see [`Fo`].
+pub type BFCca = Fo<BF>;
@@ -0,0 +1,100 @@
+use ark_bls12_381::Fq12 as Gt;
+use ark_serialize::CanonicalSerialize;
+use rand::{CryptoRng, RngCore};
+
+use super::HIBEScheme;
+
+use crate::{IbeError, OtsSignature, OtsSigningKey, OtsVerifyingKey};
+
+/// First level of every identity. Only the second level, the verifying key of a fresh
+/// one-time signature, differs between ciphertexts.
+const CHK_ROOT: &str = "chk";
+
+/// The Canetti–Halevi–Katz transform: CCA-secure public-key encryption from any CPA-secure
+/// HIBE with two levels.
+///
+/// A key pair is a HIBE instance together with the user key for the fixed first-level
+/// identity. Encryption draws a one-time signature key, encrypts to `(root, vk)` and signs the
+/// HIBE ciphertext. Decryption verifies the signature, delegates the user key to `vk` and
+/// decrypts, so every ciphertext is opened with its own key.
+pub struct Chk<T> {
+    pub scheme: T,
+}
+
+/// The master public key doubles as the delegation key of the pes schemes.
+pub struct SecretKey<T: HIBEScheme> {
+    pub mpk: T::MPK,
+    pub usk: T::USK,
+}
+
+pub struct Ciphertext<T: HIBEScheme> {
+    pub vk: OtsVerifyingKey,
+    pub ct: T::CT,
+    pub signature: OtsSignature,
+}
+
+fn vk_identity(vk: &OtsVerifyingKey) -> String {
+    let mut bytes = Vec::new();
+    vk.serialize_compressed(&mut bytes)
+        .expect("serializing into a Vec does not fail");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn signed_bytes<T: HIBEScheme>(ct: &T::CT) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ct.serialize_compressed(&mut bytes)
+        .expect("serializing into a Vec does not fail");
+    bytes
+}
+
+impl<T: HIBEScheme> Chk<T> {
+    pub fn new(scheme: T) -> Self {
+        Self { scheme }
+    }
+
+    pub fn keygen<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(T::MPK, SecretKey<T>), IbeError>
+    where
+        T::MPK: Clone,
+    {
+        let (msk, mpk) = self.scheme.setup(&mut *rng);
+        let usk = self
+            .scheme
+            .keygen(&mut *rng, &msk, vec![String::from(CHK_ROOT)])?;
+        Ok((mpk.clone(), SecretKey { mpk, usk }))
+    }
+
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &T::MPK,
+        msg: &Gt,
+    ) -> Result<Ciphertext<T>, IbeError> {
+        let signing_key = OtsSigningKey::generate(rng);
+        let vk = signing_key.verifying_key().clone();
+        let identity = vec![String::from(CHK_ROOT), vk_identity(&vk)];
+
+        let ct = self.scheme.encrypt(&mut *rng, msg, pk, identity)?;
+        let signature = signing_key.sign(&signed_bytes::<T>(&ct));
+        Ok(Ciphertext { vk, ct, signature })
+    }
+
+    /// Delegation is randomized in the pes schemes, hence the `rng`.
+    pub fn decrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &SecretKey<T>,
+        ct: &Ciphertext<T>,
+    ) -> Result<Gt, IbeError> {
+        if !ct.vk.verify(&signed_bytes::<T>(&ct.ct), &ct.signature) {
+            return Err(IbeError::DecryptionFailed);
+        }
+
+        let usk = self
+            .scheme
+            .delegate(&mut *rng, &sk.mpk, &sk.usk, vk_identity(&ct.vk))?;
+        self.scheme.decrypt(&usk, &ct.ct)
+    }
+}
@@ -0,0 +1,188 @@
+use ark_bls12_381::{Fq12 as Gt, Fr, G1Projective as G1, G2Projective as G2};
+use ark_ff::{Field, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+
+use super::IBEScheme;
+
+use crate::field_utils::*;
+use crate::group_functions::{multi_pairing, pairing, scalar_mul_g1, scalar_mul_g2};
+use crate::types::{FieldElement, Matrix, Vector};
+use crate::{IbeError, check_dim, hash_to_fr};
+
+/// SXDH is the matrix Diffie–Hellman assumption for k = 1.
+const K: usize = 1;
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct MSK {
+    pub k: Vector,
+    pub b: Matrix<FieldElement>,
+    pub w_0: Matrix<FieldElement>,
+    pub w_1: Matrix<FieldElement>,
+}
+
+/// `w_i_a` holds `W_i^T A`, and `k_a` holds `A^T k` in `Gt`.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct MPK {
+    pub a: Matrix<G1>,
+    pub w_0_a: Matrix<G1>,
+    pub w_1_a: Matrix<G1>,
+    pub k_a: Vec<Gt>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct USK {
+    pub identity: String,
+    pub k_0: Vec<G2>,
+    pub k_1: Vec<G2>,
+}
+
+/// The ciphertext does not carry the identity: under SXDH it hides the recipient.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
+pub struct CT {
+    pub msg: Gt,
+    pub c_0: Vec<G1>,
+    pub c_1: Vec<G1>,
+}
+
+/// Chen–Wee dual system IBE (Crypto 2013) in its matrix Diffie–Hellman form, instantiated
+/// with k = 1. With `W_id = W_0 + H(id) * W_1`, a user key is `(B r, k + W_id B r)` in `G2`
+/// and a ciphertext is `(A s, W_id^T A s)` in `G1` together with `msg * [s^T A^T k]_T`.
+///
+/// Ciphertexts are anonymous, so decrypting with the key of another identity does not fail
+/// but yields an unrelated message.
+#[derive(Default)]
+pub struct CW13 {}
+
+/// `L^T R` for matrices with the same number of rows. `setup` only multiplies matrices it
+/// has just sampled, whose shapes are fixed by `K`, so this cannot fail.
+fn transpose_multiply(
+    left: &Matrix<FieldElement>,
+    right: &Matrix<FieldElement>,
+) -> Matrix<FieldElement> {
+    (0..left[0].len())
+        .map(|i| {
+            (0..right[0].len())
+                .map(|j| left.iter().zip(right).map(|(l, r)| l[i] * r[j]).sum())
+                .collect()
+        })
+        .collect()
+}
+
+fn check_matrix_dims<T>(rows: usize, cols: usize, matrix: &Matrix<T>) -> Result<(), IbeError> {
+    check_dim(rows, matrix.len())?;
+    matrix.iter().try_for_each(|row| check_dim(cols, row.len()))
+}
+
+impl CW13 {
+    pub fn new() -> CW13 {
+        Self {}
+    }
+}
+
+impl IBEScheme for CW13 {
+    type MPK = MPK;
+    type MSK = MSK;
+    type USK = USK;
+    type CT = CT;
+
+    fn name(&self) -> String {
+        String::from("cw13")
+    }
+
+    fn setup(&self, mut rng: impl Rng) -> (MSK, MPK) {
+        let a = random_matrix(&mut rng, K + 1, K);
+        let b = random_matrix(&mut rng, K + 1, K);
+        let w_0 = random_matrix(&mut rng, K + 1, K + 1);
+        let w_1 = random_matrix(&mut rng, K + 1, K + 1);
+        let k = random_vector(&mut rng, K + 1);
+
+        let k_column = k.iter().map(|&x| vec![x]).collect();
+        let gt = pairing(&scalar_mul_g1(Fr::ONE), &scalar_mul_g2(Fr::ONE));
+
+        let mpk = MPK {
+            a: matrix_lift_g1(&a),
+            w_0_a: matrix_lift_g1(&transpose_multiply(&w_0, &a)),
+            w_1_a: matrix_lift_g1(&transpose_multiply(&w_1, &a)),
+            k_a: transpose_multiply(&a, &k_column)
+                .iter()
+                .map(|row| gt.pow(row[0].into_bigint()))
+                .collect(),
+        };
+        let msk = MSK { k, b, w_0, w_1 };
+
+        (msk, mpk)
+    }
+
+    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: String) -> Result<USK, IbeError> {
+        check_dim(K + 1, msk.k.len())?;
+        check_matrix_dims(K + 1, K, &msk.b)?;
+        check_matrix_dims(K + 1, K + 1, &msk.w_0)?;
+        check_matrix_dims(K + 1, K + 1, &msk.w_1)?;
+
+        let id = hash_to_fr(&identity);
+        let r = random_vector(&mut rng, K);
+
+        let w_1_id = msk
+            .w_1
+            .iter()
+            .map(|row| scalar_vector_mul(id, row))
+            .collect();
+        let w_id = matrix_add(&msk.w_0, &w_1_id)?;
+        let br = matrix_vector_mul(&msk.b, &r);
+
+        Ok(USK {
+            identity,
+            k_0: vector_lift_g2(&br),
+            k_1: vector_lift_g2(&vector_add(&msk.k, &matrix_vector_mul(&w_id, &br))),
+        })
+    }
+
+    fn encrypt(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identity: String,
+    ) -> Result<CT, IbeError> {
+        check_matrix_dims(K + 1, K, &mpk.a)?;
+        check_matrix_dims(K + 1, K, &mpk.w_0_a)?;
+        check_matrix_dims(K + 1, K, &mpk.w_1_a)?;
+        check_dim(K, mpk.k_a.len())?;
+
+        let id = hash_to_fr(&identity);
+        let s = random_vector(&mut rng, K);
+
+        let w_id_a = matrix_add(&mpk.w_0_a, &matrix_multiply_scalar(&mpk.w_1_a, id)?)?;
+        let mask = mpk
+            .k_a
+            .iter()
+            .zip(&s)
+            .map(|(k_a_i, s_i)| k_a_i.pow(s_i.into_bigint()))
+            .product::<Gt>();
+
+        Ok(CT {
+            msg: mask * msg,
+            c_0: group_matrix_vector_mul_msm(&mpk.a, &s)?,
+            c_1: group_matrix_vector_mul_msm(&w_id_a, &s)?,
+        })
+    }
+
+    fn decrypt(&self, usk: &USK, ct: &CT) -> Result<Gt, IbeError> {
+        check_dim(K + 1, usk.k_0.len())?;
+        check_dim(K + 1, usk.k_1.len())?;
+        check_dim(K + 1, ct.c_0.len())?;
+        check_dim(K + 1, ct.c_1.len())?;
+
+        // e(A s, k + W_id B r) / e(W_id^T A s, B r) = [s^T A^T k]_T
+        let pairs = ct
+            .c_0
+            .iter()
+            .zip(&usk.k_1)
+            .map(|(c, k)| (*c, *k))
+            .chain(ct.c_1.iter().zip(&usk.k_0).map(|(c, k)| (-*c, *k)))
+            .collect::<Vec<(G1, G2)>>();
+
+        Ok(ct.msg / multi_pairing(&pairs))
+    }
+}
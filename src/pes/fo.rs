@@ -0,0 +1,200 @@
+use ark_bls12_381::{Fq12 as Gt, Fr};
+use ark_ff::UniformRand;
+use ark_ff::field_hashers::{DefaultFieldHasher, HashToField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+
+use super::IBEScheme;
+use super::bb::{self, BB};
+use super::bf::{self, BF};
+use crate::IbeError;
+use crate::blake3::Blake3;
+
+// Random oracles of the Fujisaki–Okamoto transform. The CCA schemes encrypt a random `sigma`
+// under the exponent `s = H(sigma, msg, identity)` and pad the message with `G(sigma)`.
+
+const RANDOMNESS_DOMAIN: &str = "FO-RANDOMNESS";
+const MASK_DOMAIN: &str = "FO-MASK";
+
+fn to_bytes(element: &Gt, out: &mut Vec<u8>) {
+    element
+        .serialize_compressed(out)
+        .expect("serializing into a Vec does not fail");
+}
+
+/// `H(sigma, msg, identity)`. Both group elements have a fixed encoding length, so the
+/// identity can follow without a length prefix.
+pub(crate) fn randomness(sigma: &Gt, msg: &Gt, identity: &str) -> Fr {
+    let mut input = Vec::new();
+    to_bytes(sigma, &mut input);
+    to_bytes(msg, &mut input);
+    input.extend_from_slice(identity.as_bytes());
+
+    let hasher = <DefaultFieldHasher<Blake3> as HashToField<Fr>>::new(RANDOMNESS_DOMAIN.as_bytes());
+    hasher.hash_to_field::<1>(&input)[0]
+}
+
+/// `G(sigma)`, a one-time pad for the message.
+pub(crate) fn mask(sigma: &Gt) -> Gt {
+    let mut input = Vec::new();
+    to_bytes(sigma, &mut input);
+
+    let hasher = <DefaultFieldHasher<Blake3> as HashToField<Gt>>::new(MASK_DOMAIN.as_bytes());
+    hasher.hash_to_field::<1>(&input)[0]
+}
+
+/// A CPA scheme the transform applies to. Encryption has to be deterministic once the
+/// exponent `s` is fixed, so that decryption can re-encrypt and compare.
+pub trait FoScheme: IBEScheme {
+    fn public_key(&self, msk: &Self::MSK) -> Self::MPK;
+
+    fn encrypt_with_randomness(
+        &self,
+        s: Fr,
+        msg: &Gt,
+        mpk: &Self::MPK,
+        identity: String,
+    ) -> Self::CT;
+
+    /// The identity a ciphertext is addressed to.
+    fn ciphertext_identity(ct: &Self::CT) -> &str;
+}
+
+/// The user key carries the master public key, which decryption needs to re-encrypt.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct USK<S: IBEScheme> {
+    pub inner: S::USK,
+    pub mpk: S::MPK,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct CT<S: IBEScheme> {
+    pub inner: S::CT,
+    pub masked: Gt,
+}
+
+// derived impls would require `S: Clone` and `S: PartialEq` of the scheme itself
+impl<S: IBEScheme> Clone for CT<S>
+where
+    S::CT: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            masked: self.masked,
+        }
+    }
+}
+
+impl<S: IBEScheme> PartialEq for CT<S>
+where
+    S::CT: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner && self.masked == other.masked
+    }
+}
+
+/// `S` made CCA secure with the Fujisaki–Okamoto transform. `encrypt` encrypts a random
+/// `sigma` with the exponent `s = H(sigma, msg, identity)` and pads `msg` with `G(sigma)`;
+/// `decrypt` recovers `sigma` and `msg`, recomputes `s` and rejects the ciphertext unless
+/// encrypting again reproduces it exactly.
+#[derive(Default)]
+pub struct Fo<S> {
+    pub scheme: S,
+}
+
+impl<S: FoScheme + Default> Fo<S> {
+    pub fn new() -> Self {
+        Self {
+            scheme: S::default(),
+        }
+    }
+}
+
+impl<S: FoScheme> IBEScheme for Fo<S>
+where
+    S::CT: PartialEq,
+{
+    type MPK = S::MPK;
+    type MSK = S::MSK;
+    type USK = USK<S>;
+    type CT = CT<S>;
+
+    fn name(&self) -> String {
+        format!("{}_cca", self.scheme.name())
+    }
+
+    fn setup(&self, rng: impl Rng) -> (S::MSK, S::MPK) {
+        self.scheme.setup(rng)
+    }
+
+    fn keygen(&self, rng: impl Rng, msk: &S::MSK, identity: String) -> Result<USK<S>, IbeError> {
+        Ok(USK {
+            inner: self.scheme.keygen(rng, msk, identity)?,
+            mpk: self.scheme.public_key(msk),
+        })
+    }
+
+    fn encrypt(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &S::MPK,
+        identity: String,
+    ) -> Result<CT<S>, IbeError> {
+        let sigma = Gt::rand(&mut rng);
+        let s = randomness(&sigma, msg, &identity);
+
+        Ok(CT {
+            inner: self
+                .scheme
+                .encrypt_with_randomness(s, &sigma, mpk, identity),
+            masked: mask(&sigma) * msg,
+        })
+    }
+
+    fn decrypt(&self, usk: &USK<S>, ct: &CT<S>) -> Result<Gt, IbeError> {
+        let sigma = self.scheme.decrypt(&usk.inner, &ct.inner)?;
+        let msg = ct.masked / mask(&sigma);
+
+        let identity = S::ciphertext_identity(&ct.inner).to_string();
+        let s = randomness(&sigma, &msg, &identity);
+        if self
+            .scheme
+            .encrypt_with_randomness(s, &sigma, &usk.mpk, identity)
+            != ct.inner
+        {
+            return Err(IbeError::DecryptionFailed);
+        }
+        Ok(msg)
+    }
+}
+
+impl FoScheme for BF {
+    fn public_key(&self, msk: &bf::MSK) -> bf::MPK {
+        BF::public_key(self, msk)
+    }
+
+    fn encrypt_with_randomness(&self, s: Fr, msg: &Gt, mpk: &bf::MPK, identity: String) -> bf::CT {
+        BF::encrypt_with_randomness(self, s, msg, mpk, identity)
+    }
+
+    fn ciphertext_identity(ct: &bf::CT) -> &str {
+        &ct.identity
+    }
+}
+
+impl FoScheme for BB {
+    fn public_key(&self, msk: &bb::MSK) -> bb::MPK {
+        BB::public_key(self, msk)
+    }
+
+    fn encrypt_with_randomness(&self, s: Fr, msg: &Gt, mpk: &bb::MPK, identity: String) -> bb::CT {
+        BB::encrypt_with_randomness(self, s, msg, mpk, identity)
+    }
+
+    fn ciphertext_identity(ct: &bb::CT) -> &str {
+        &ct.identity
+    }
+}
@@ -2,19 +2,25 @@ use ark_bls12_381::{Bls12_381, Fq12 as Gt, Fr, G1Affine, G1Projective as G1, G2P
 use ark_ec::pairing::Pairing;
 use ark_ec::{PrimeGroup, VariableBaseMSM};
 use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
 
 use crate::pes::HIBEScheme;
-use crate::{hash_to_fr, hash_to_g1};
+use crate::{
+    IbeError, check_dim, check_identity_non_empty, check_non_empty, hash_to_fr, hash_to_g1,
+};
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct MSK {
     pub alpha: Fr,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct MPK {
     pub a: Gt,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct USK {
     pub identity: Vec<String>,
     pub k_1: G1,
@@ -23,6 +29,7 @@ pub struct USK {
     pub k_check: Vec<G2>,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
 pub struct CT {
     pub identity: Vec<String>,
     pub msg: Gt,
@@ -42,6 +49,12 @@ impl HiberlaDec {
     fn iota(&self, i: usize) -> usize {
         i / self.l
     }
+
+    fn check_usk(&self, usk: &USK) -> Result<(), IbeError> {
+        check_identity_non_empty(&usk.identity)?;
+        check_dim(ceil_div(usk.identity.len(), self.l), usk.k_check.len())?;
+        check_dim(usk.k_2_0.len(), usk.k_2_1.len())
+    }
 }
 
 impl HIBEScheme for HiberlaDec {
@@ -67,9 +80,9 @@ impl HIBEScheme for HiberlaDec {
         (msk, mpk)
     }
 
-    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: Vec<String>) -> USK {
+    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: Vec<String>) -> Result<USK, IbeError> {
+        check_identity_non_empty(&identity)?;
         let n_k = identity.len();
-        assert!(n_k > 0);
 
         let m_k = ceil_div(n_k, self.l);
         let rs = sample_fr(&mut rng, m_k);
@@ -99,13 +112,13 @@ impl HIBEScheme for HiberlaDec {
 
         let k_check = rs.iter().map(|r| G2::generator() * r).collect();
 
-        USK {
+        Ok(USK {
             identity: identity.clone(),
             k_1,
             k_2_0,
             k_2_1,
             k_check,
-        }
+        })
     }
 
     fn delegate(
@@ -114,9 +127,9 @@ impl HIBEScheme for HiberlaDec {
         _mpk: &MPK, // not needed, but kept for trait compliance
         usk: &USK,
         identity_extension: String,
-    ) -> USK {
+    ) -> Result<USK, IbeError> {
+        self.check_usk(usk)?;
         let n_k = usk.identity.len();
-        assert!(n_k > 0);
 
         let m_k = ceil_div(n_k, self.l);
 
@@ -126,6 +139,7 @@ impl HIBEScheme for HiberlaDec {
         new_identity.push(identity_extension.clone());
 
         if n_k + 1 <= self.l * m_k {
+            check_non_empty(&usk.k_2_0)?;
             let xid = hash_to_fr(&identity_extension);
             let mut new_k_1 = usk.k_1 + usk.k_2_0[0] + usk.k_2_1[0] * xid;
             for i in 0..n_k + 1 {
@@ -159,13 +173,13 @@ impl HIBEScheme for HiberlaDec {
                 new_k_check[i] = new_k_check[i] + g2 * r;
             }
 
-            USK {
+            Ok(USK {
                 identity: new_identity,
                 k_1: new_k_1,
                 k_2_0: new_k_2_0,
                 k_2_1: new_k_2_1,
                 k_check: new_k_check,
-            }
+            })
         } else {
             let mut new_k_1 = usk.k_1;
             for i in 0..n_k + 1 {
@@ -196,19 +210,25 @@ impl HIBEScheme for HiberlaDec {
             }
             new_k_check.push(g2 * rs[m_k]);
 
-            USK {
+            Ok(USK {
                 identity: new_identity,
                 k_1: new_k_1,
                 k_2_0: new_k_2_0,
                 k_2_1: new_k_2_1,
                 k_check: new_k_check,
-            }
+            })
         }
     }
 
-    fn encrypt(&self, mut rng: impl Rng, msg: &Gt, mpk: &MPK, identity: Vec<String>) -> CT {
+    fn encrypt(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identity: Vec<String>,
+    ) -> Result<CT, IbeError> {
+        check_identity_non_empty(&identity)?;
         let n_c = identity.len();
-        assert!(n_c > 0);
 
         let s = Fr::rand(&mut rng);
 
@@ -221,21 +241,22 @@ impl HIBEScheme for HiberlaDec {
             c_i.push(tmp);
         }
 
-        CT {
+        Ok(CT {
             identity: identity.clone(),
             msg: mpk.a.pow(s.into_bigint()) * msg,
             c: G2::generator() * s,
             c_i,
-        }
+        })
     }
 
-    fn decrypt(&self, usk: &USK, ct: &CT) -> Option<Gt> {
+    fn decrypt(&self, usk: &USK, ct: &CT) -> Result<Gt, IbeError> {
+        self.check_usk(usk)?;
         let n_k = usk.identity.len();
-        assert!(n_k > 0);
 
         if !can_decrypt(&usk.identity, &ct.identity) {
-            return None;
+            return Err(IbeError::IdentityMismatch);
         }
+        check_dim(ct.identity.len(), ct.c_i.len())?;
 
         let mut result = Bls12_381::pairing(usk.k_1, ct.c).0;
 
@@ -273,7 +294,7 @@ impl HIBEScheme for HiberlaDec {
             result *= Bls12_381::pairing(-c, k).0;
         }
 
-        Some(ct.msg / result)
+        Ok(ct.msg / result)
     }
 }
 
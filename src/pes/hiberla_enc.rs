@@ -4,18 +4,25 @@ use ark_bls12_381::{Bls12_381, Fq12 as Gt, Fr, G1Affine, G1Projective as G1, G2P
 use ark_ec::PrimeGroup;
 use ark_ec::pairing::Pairing;
 use ark_ff::{Field, PrimeField, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
 
-use crate::{hash_to_fr, hash_to_g1, pes::HIBEScheme};
+use crate::{
+    IbeError, check_dim, check_identity_non_empty, check_non_empty, hash_to_fr, hash_to_g1,
+    pes::HIBEScheme,
+};
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct MSK {
     pub alpha: Fr,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct MPK {
     pub a: Gt,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct USK {
     pub identity: Vec<String>,
     pub k_1: G1,
@@ -23,6 +30,7 @@ pub struct USK {
     pub k_check: Vec<G2>,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
 pub struct CT {
     pub identity: Vec<String>,
     pub msg: Gt,
@@ -38,6 +46,11 @@ impl HiberlaEnc {
     pub fn new(l: usize) -> HiberlaEnc {
         Self { l }
     }
+
+    fn check_usk(&self, usk: &USK) -> Result<(), IbeError> {
+        check_identity_non_empty(&usk.identity)?;
+        check_dim(ceil_div(usk.identity.len(), self.l), usk.k_check.len())
+    }
 }
 
 impl HIBEScheme for HiberlaEnc {
@@ -63,9 +76,9 @@ impl HIBEScheme for HiberlaEnc {
         (msk, mpk)
     }
 
-    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: Vec<String>) -> USK {
+    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: Vec<String>) -> Result<USK, IbeError> {
+        check_identity_non_empty(&identity)?;
         let n_k = identity.len();
-        assert!(n_k > 0);
 
         let m_k = ceil_div(n_k, self.l);
         let rs = sample_fr(&mut rng, m_k);
@@ -93,17 +106,23 @@ impl HIBEScheme for HiberlaEnc {
 
         let k_check = rs.iter().map(|r| G2::generator() * r).collect();
 
-        USK {
+        Ok(USK {
             identity: identity.clone(),
             k_1,
             k_2,
             k_check,
-        }
+        })
     }
 
-    fn encrypt(&self, mut rng: impl Rng, msg: &Gt, mpk: &MPK, identity: Vec<String>) -> CT {
+    fn encrypt(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identity: Vec<String>,
+    ) -> Result<CT, IbeError> {
+        check_identity_non_empty(&identity)?;
         let n_c = identity.len();
-        assert!(n_c > 0);
 
         let s = Fr::rand(&mut rng);
 
@@ -120,12 +139,12 @@ impl HIBEScheme for HiberlaEnc {
             c_i.push(b_prime_l * s);
         }
 
-        CT {
+        Ok(CT {
             identity: identity.clone(),
             msg: mpk.a.pow(s.into_bigint()) * msg,
             c: G2::generator() * s,
             c_i,
-        }
+        })
     }
 
     fn delegate(
@@ -134,9 +153,9 @@ impl HIBEScheme for HiberlaEnc {
         _mpk: &MPK, // not needed, but kept for trait compliance
         usk: &USK,
         identity_extension: String,
-    ) -> USK {
+    ) -> Result<USK, IbeError> {
+        self.check_usk(usk)?;
         let n_k = usk.identity.len();
-        assert!(n_k > 0);
 
         let m_k = ceil_div(n_k, self.l);
 
@@ -146,6 +165,7 @@ impl HIBEScheme for HiberlaEnc {
         new_identity.push(identity_extension.clone());
 
         if n_k + 1 <= self.l * m_k {
+            check_non_empty(&usk.k_2)?;
             let xid = hash_to_fr(&identity_extension);
             let mut new_k_1 = usk.k_1 + usk.k_2[0] * xid;
             for (i, (l, h)) in chunks(n_k + 1, self.l) {
@@ -177,12 +197,12 @@ impl HIBEScheme for HiberlaEnc {
                 new_k_check[i] = new_k_check[i] + g2 * r;
             }
 
-            USK {
+            Ok(USK {
                 identity: new_identity.clone(),
                 k_1: new_k_1,
                 k_2: new_k_2,
                 k_check: new_k_check,
-            }
+            })
         } else {
             let mut new_k_1 = usk.k_1;
             for (i, (l, h)) in chunks(n_k + 1, self.l) {
@@ -213,26 +233,27 @@ impl HIBEScheme for HiberlaEnc {
             }
             new_k_check.push(g2 * rs[m_k]);
 
-            USK {
+            Ok(USK {
                 identity: new_identity.clone(),
                 k_1: new_k_1,
                 k_2: new_k_2,
                 k_check: new_k_check,
-            }
+            })
         }
     }
 
-    fn decrypt(&self, usk: &USK, ct: &CT) -> Option<Gt> {
+    fn decrypt(&self, usk: &USK, ct: &CT) -> Result<Gt, IbeError> {
+        self.check_usk(usk)?;
         let n_k = usk.identity.len();
-        assert!(n_k > 0);
 
         let n_c = ct.identity.len();
         let m_k = ceil_div(n_k, self.l);
         let m_c = ceil_div(n_c, self.l);
 
         if !can_decrypt(&usk.identity, &ct.identity) {
-            return None;
+            return Err(IbeError::IdentityMismatch);
         }
+        check_dim(m_c, ct.c_i.len())?;
 
         // Delegation is only required if
         // 1) n_c > n_k and
@@ -261,6 +282,12 @@ impl HIBEScheme for HiberlaEnc {
                 x_c = n_c % self.l;
             }
             let diff = x_c - x_k;
+            if usk.k_2.len() < diff {
+                return Err(IbeError::DimensionMismatch {
+                    expected: diff,
+                    found: usk.k_2.len(),
+                });
+            }
             let mut j = 0;
             // TODO: maybe one could use MSM for the loop? A bit tricky, since size is not constant/known?
             for i in n_k..n_k + diff {
@@ -274,7 +301,7 @@ impl HIBEScheme for HiberlaEnc {
         for l in 0..m_k {
             result *= Bls12_381::pairing(-ct.c_i[l], usk.k_check[l]).0;
         }
-        Some(ct.msg / result)
+        Ok(ct.msg / result)
     }
 }
 
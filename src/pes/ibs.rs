@@ -0,0 +1,120 @@
+use ark_bls12_381::{Bls12_381, Fq12 as Gt, G1Projective as G1, G2Projective as G2};
+use ark_ec::PrimeGroup;
+use ark_ec::pairing::Pairing;
+use ark_ff::UniformRand;
+use rand::{CryptoRng, RngCore};
+
+use super::IBEScheme;
+use super::bb::{self, BB};
+use super::bf::{self, BF};
+
+use crate::{IbeError, blake3_hash_bytes, hash_to_fr, hash_to_g1};
+
+/// Prefix of the identities messages are signed as, so they never coincide with the
+/// identities of ordinary user keys.
+const MESSAGE_IDENTITY_PREFIX: &str = "naor:";
+
+/// A signature is the user key for the identity of the signed message.
+pub type Signature<T> = <T as IBEScheme>::USK;
+
+/// Identity under which `message` is signed.
+pub fn message_identity(message: &[u8]) -> String {
+    let digest: String = blake3_hash_bytes(message)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("{}{}", MESSAGE_IDENTITY_PREFIX, digest)
+}
+
+/// Signing half of the Naor transform: signing `m` extracts the user key for the identity of
+/// `m`. The master secret key is the signing key, so it belongs to a signer-specific instance
+/// rather than to the PKG that issues decryption keys.
+pub struct Signer<T: IBEScheme> {
+    pub scheme: T,
+    pub msk: T::MSK,
+}
+
+/// Verifying half of the Naor transform, holding the signer's master public key.
+pub struct Verifier<T: IBEScheme> {
+    pub scheme: T,
+    pub mpk: T::MPK,
+}
+
+/// Schemes whose user keys can be checked against the master public key directly.
+pub trait KeyCheck: IBEScheme {
+    fn check_key(&self, mpk: &Self::MPK, usk: &Self::USK, identity: &str) -> bool;
+}
+
+impl<T: IBEScheme> Signer<T> {
+    pub fn new(scheme: T, msk: T::MSK) -> Self {
+        Self { scheme, msk }
+    }
+
+    pub fn sign<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        message: &[u8],
+    ) -> Result<Signature<T>, IbeError> {
+        self.scheme
+            .keygen(rng, &self.msk, message_identity(message))
+    }
+}
+
+impl<T: IBEScheme> Verifier<T> {
+    pub fn new(scheme: T, mpk: T::MPK) -> Self {
+        Self { scheme, mpk }
+    }
+
+    /// Works for every scheme: encrypts a random `Gt` element to the identity of `message` and
+    /// checks that the signature decrypts it.
+    pub fn verify_by_decryption<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        message: &[u8],
+        signature: &Signature<T>,
+    ) -> bool {
+        let challenge = Gt::rand(rng);
+        let Ok(ct) = self
+            .scheme
+            .encrypt(rng, &challenge, &self.mpk, message_identity(message))
+        else {
+            return false;
+        };
+        matches!(self.scheme.decrypt(signature, &ct), Ok(msg) if msg == challenge)
+    }
+}
+
+impl<T: KeyCheck> Verifier<T> {
+    /// Deterministic verification with the pairing check of the scheme.
+    pub fn verify(&self, message: &[u8], signature: &Signature<T>) -> bool {
+        self.scheme
+            .check_key(&self.mpk, signature, &message_identity(message))
+    }
+}
+
+// e(k, g2) = a * e(H(id), r), as for BLS signatures
+impl KeyCheck for BF {
+    fn check_key(&self, mpk: &bf::MPK, usk: &bf::USK, identity: &str) -> bool {
+        if usk.identity != identity {
+            return false;
+        }
+
+        let lhs = Bls12_381::pairing(usk.k, G2::generator()).0;
+        let rhs = mpk.a * Bls12_381::pairing(hash_to_g1(identity), usk.r).0;
+        lhs == rhs
+    }
+}
+
+// e(g1, k) = a * e(b_0 * g1 + x_id * b_1 * g1, r)
+impl KeyCheck for BB {
+    fn check_key(&self, mpk: &bb::MPK, usk: &bb::USK, identity: &str) -> bool {
+        if usk.identity != identity {
+            return false;
+        }
+
+        let xid = hash_to_fr(identity);
+        let lhs = Bls12_381::pairing(G1::generator(), usk.k).0;
+        let rhs = mpk.a * Bls12_381::pairing(mpk.b_0_g1 + mpk.b_1_g1 * xid, usk.r).0;
+        lhs == rhs
+    }
+}
@@ -0,0 +1,156 @@
+use ark_bls12_381::Fq12 as Gt;
+use ark_ff::UniformRand;
+use rand::{CryptoRng, RngCore};
+
+use crate::pes::bf::BF;
+use crate::pes::{HIBEScheme, IBEScheme};
+use crate::{HIBKEM, HIBKEMSetup, IBKEM, IbeError, SharedRandomnessIBKEM};
+
+/// Turns a pes scheme into a KEM: `encrypt` samples a uniform `Gt` element, encrypts it and
+/// returns it as the encapsulated key.
+///
+/// The KEM traits take bit-string identities, while the pes schemes hash `String` identities.
+/// Every identity (level) is therefore passed on as its lowercase hex encoding, which keeps the
+/// mapping injective for arbitrary bytes.
+pub struct PesKem<T> {
+    pub scheme: T,
+}
+
+impl<T> PesKem<T> {
+    pub fn new(scheme: T) -> Self {
+        Self { scheme }
+    }
+}
+
+pub(crate) fn identity_to_string(identity: &[u8]) -> String {
+    identity.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hierarchical_identity_to_strings(identity: &[Vec<u8>]) -> Vec<String> {
+    identity.iter().map(|id| identity_to_string(id)).collect()
+}
+
+impl<T: IBEScheme> IBKEM for PesKem<T> {
+    type PK = T::MPK;
+    type SK = T::MSK;
+    type USK = T::USK;
+    type CT = T::CT;
+
+    fn name(&self) -> String {
+        self.scheme.name()
+    }
+
+    fn setup<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(T::MPK, T::MSK), IbeError> {
+        let (msk, mpk) = self.scheme.setup(rng);
+        Ok((mpk, msk))
+    }
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &T::MSK,
+        identity: &[u8],
+    ) -> Result<T::USK, IbeError> {
+        self.scheme.keygen(rng, sk, identity_to_string(identity))
+    }
+
+    fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &T::MPK,
+        identity: &[u8],
+    ) -> Result<(T::CT, Gt), IbeError> {
+        let key = Gt::rand(rng);
+        let ct = self
+            .scheme
+            .encrypt(&mut *rng, &key, pk, identity_to_string(identity))?;
+        Ok((ct, key))
+    }
+
+    fn decrypt(
+        &self,
+        _pk: &T::MPK,
+        usk: &T::USK,
+        _identity: &[u8],
+        ct: &T::CT,
+    ) -> Result<Gt, IbeError> {
+        self.scheme.decrypt(usk, ct)
+    }
+}
+
+impl SharedRandomnessIBKEM for PesKem<BF> {
+    fn encrypt_shared<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &<BF as IBEScheme>::MPK,
+        identities: &[Vec<u8>],
+    ) -> Result<(Vec<<BF as IBEScheme>::CT>, Gt), IbeError> {
+        let key = Gt::rand(rng);
+        let identities = identities.iter().map(|id| identity_to_string(id)).collect();
+        let cts = self.scheme.encrypt_shared(&mut *rng, &key, pk, identities);
+        Ok((cts, key))
+    }
+}
+
+// the pes schemes delegate with the master public key, so it doubles as the delegation key
+impl<T: HIBEScheme> HIBKEM for PesKem<T>
+where
+    T::MPK: Clone,
+{
+    type PK = T::MPK;
+    type DK = T::MPK;
+    type SK = T::MSK;
+    type USK = T::USK;
+    type CT = T::CT;
+
+    fn name(&self) -> String {
+        self.scheme.name()
+    }
+
+    fn setup<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<HIBKEMSetup<Self>, IbeError> {
+        let (msk, mpk) = self.scheme.setup(rng);
+        Ok((mpk.clone(), mpk, msk))
+    }
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &T::MSK,
+        identity: &[Vec<u8>],
+    ) -> Result<T::USK, IbeError> {
+        self.scheme
+            .keygen(rng, sk, hierarchical_identity_to_strings(identity))
+    }
+
+    fn delegate<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        dk: &T::MPK,
+        usk: &T::USK,
+        _id_prefix: &[Vec<u8>],
+        id_next: Vec<u8>,
+    ) -> Result<T::USK, IbeError> {
+        self.scheme
+            .delegate(rng, dk, usk, identity_to_string(&id_next))
+    }
+
+    fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &T::MPK,
+        identity: &[Vec<u8>],
+    ) -> Result<(T::CT, Gt), IbeError> {
+        let key = Gt::rand(rng);
+        let ct = self.scheme.encrypt(
+            &mut *rng,
+            &key,
+            pk,
+            hierarchical_identity_to_strings(identity),
+        )?;
+        Ok((ct, key))
+    }
+
+    fn decrypt(&self, usk: &T::USK, ct: &T::CT) -> Result<Gt, IbeError> {
+        self.scheme.decrypt(usk, ct)
+    }
+}
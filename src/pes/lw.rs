@@ -2,10 +2,12 @@ use ark_bls12_381::{Bls12_381, Fq12 as Gt, Fr, G1Projective as G1, G2Projective
 use ark_ec::PrimeGroup;
 use ark_ec::pairing::Pairing;
 use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
 
-use crate::{hash_to_fr, pes::HIBEScheme};
+use crate::{IbeError, check_dim, check_identity_non_empty, hash_to_fr, pes::HIBEScheme};
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct MSK {
     pub alpha: Fr,
     pub b: Fr,
@@ -13,6 +15,7 @@ pub struct MSK {
     pub b_1: Fr,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct MPK {
     pub a: Gt,
     pub b_g1: G1,
@@ -23,6 +26,7 @@ pub struct MPK {
     pub b_1_g2: G2,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct USK {
     pub identity: Vec<String>,
     pub k: Vec<G2>,
@@ -30,6 +34,7 @@ pub struct USK {
     pub k_2: Vec<G2>,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
 pub struct CT {
     pub identity: Vec<String>,
     pub msg: Gt,
@@ -79,9 +84,9 @@ impl HIBEScheme for LW {
         (msk, mpk)
     }
 
-    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: Vec<String>) -> USK {
+    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: Vec<String>) -> Result<USK, IbeError> {
+        check_identity_non_empty(&identity)?;
         let n_k = identity.len();
-        assert!(n_k > 0);
 
         let g2 = G2::generator();
         let rs = sample_fr(&mut rng, n_k);
@@ -102,17 +107,23 @@ impl HIBEScheme for LW {
             k_2.push(g2 * e_2);
         }
 
-        USK {
+        Ok(USK {
             identity: identity.clone(),
             k,
             k_1,
             k_2,
-        }
+        })
     }
 
-    fn encrypt(&self, mut rng: impl Rng, msg: &Gt, mpk: &MPK, identity: Vec<String>) -> CT {
+    fn encrypt(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identity: Vec<String>,
+    ) -> Result<CT, IbeError> {
+        check_identity_non_empty(&identity)?;
         let n_c = identity.len();
-        assert!(n_c > 0);
 
         let g1 = G1::generator();
         let s = Fr::rand(&mut rng);
@@ -126,18 +137,24 @@ impl HIBEScheme for LW {
             c_i.push(mpk.b_g1 * s + (mpk.b_0_g1 + mpk.b_1_g1 * xid) * ss[i]);
         }
 
-        CT {
+        Ok(CT {
             identity: identity.clone(),
             msg: mpk.a.pow(s.into_bigint()) * msg,
             c: g1 * s,
             c_i,
             c_i_alt,
-        }
+        })
     }
 
-    fn delegate(&self, mut rng: impl Rng, mpk: &MPK, usk: &USK, identity_extension: String) -> USK {
+    fn delegate(
+        &self,
+        mut rng: impl Rng,
+        mpk: &MPK,
+        usk: &USK,
+        identity_extension: String,
+    ) -> Result<USK, IbeError> {
+        check_usk(usk)?;
         let n_k = usk.identity.len();
-        assert!(n_k > 0);
 
         let lambdas = sample_fr(&mut rng, n_k);
         let rs = sample_fr(&mut rng, n_k + 1);
@@ -149,21 +166,23 @@ impl HIBEScheme for LW {
         let new_k1 = update_k1(&mpk, &usk, &rs, &lambdas);
         let new_k2 = update_k2(&mpk, &usk, &rs, &new_identity, &identity_extension);
 
-        USK {
+        Ok(USK {
             identity: new_identity,
             k: new_k,
             k_1: new_k1,
             k_2: new_k2,
-        }
+        })
     }
 
-    fn decrypt(&self, usk: &USK, ct: &CT) -> Option<Gt> {
+    fn decrypt(&self, usk: &USK, ct: &CT) -> Result<Gt, IbeError> {
+        check_usk(usk)?;
         let n_k = usk.identity.len();
-        assert!(n_k > 0);
 
         if !can_decrypt(&usk.identity, &ct.identity) {
-            return None;
+            return Err(IbeError::IdentityMismatch);
         }
+        check_dim(ct.identity.len(), ct.c_i.len())?;
+        check_dim(ct.identity.len(), ct.c_i_alt.len())?;
 
         let sum: G2 = usk.k_1.iter().sum();
         let mut result = Bls12_381::pairing(ct.c, sum).0;
@@ -173,10 +192,18 @@ impl HIBEScheme for LW {
             result *= Bls12_381::pairing(ct.c_i_alt[i], usk.k_2[i]).0;
         }
 
-        Some(ct.msg / result)
+        Ok(ct.msg / result)
     }
 }
 
+fn check_usk(usk: &USK) -> Result<(), IbeError> {
+    let n_k = usk.identity.len();
+    check_identity_non_empty(&usk.identity)?;
+    check_dim(n_k, usk.k.len())?;
+    check_dim(n_k, usk.k_1.len())?;
+    check_dim(n_k, usk.k_2.len())
+}
+
 fn sample_fr(mut rng: impl Rng, n: usize) -> Vec<Fr> {
     let mut result = Vec::with_capacity(n);
     for _ in 0..n {
@@ -1,38 +1,63 @@
 use ark_bls12_381::Fq12 as Gt;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use rand::Rng;
 
+use crate::IbeError;
+
 pub trait IBEScheme {
-    type MPK;
-    type MSK;
-    type USK;
-    type CT;
+    type MPK: CanonicalSerialize + CanonicalDeserialize;
+    type MSK: CanonicalSerialize + CanonicalDeserialize;
+    type USK: CanonicalSerialize + CanonicalDeserialize;
+    type CT: CanonicalSerialize + CanonicalDeserialize;
 
     fn name(&self) -> String;
 
     fn setup(&self, rng: impl Rng) -> (Self::MSK, Self::MPK);
 
-    fn keygen(&self, rng: impl Rng, msk: &Self::MSK, identity: String) -> Self::USK;
+    fn keygen(
+        &self,
+        rng: impl Rng,
+        msk: &Self::MSK,
+        identity: String,
+    ) -> Result<Self::USK, IbeError>;
 
-    fn encrypt(&self, rng: impl Rng, msg: &Gt, mpk: &Self::MPK, identity: String) -> Self::CT;
+    fn encrypt(
+        &self,
+        rng: impl Rng,
+        msg: &Gt,
+        mpk: &Self::MPK,
+        identity: String,
+    ) -> Result<Self::CT, IbeError>;
 
-    fn decrypt(&self, usk: &Self::USK, ct: &Self::CT) -> Option<Gt>;
+    fn decrypt(&self, usk: &Self::USK, ct: &Self::CT) -> Result<Gt, IbeError>;
 }
 
 pub trait HIBEScheme {
-    type MPK;
-    type MSK;
-    type USK;
-    type CT;
+    type MPK: CanonicalSerialize + CanonicalDeserialize;
+    type MSK: CanonicalSerialize + CanonicalDeserialize;
+    type USK: CanonicalSerialize + CanonicalDeserialize;
+    type CT: CanonicalSerialize + CanonicalDeserialize;
 
     fn name(&self) -> String;
 
     fn setup(&self, rng: impl Rng) -> (Self::MSK, Self::MPK);
 
-    fn keygen(&self, rng: impl Rng, msk: &Self::MSK, identity: Vec<String>) -> Self::USK;
+    fn keygen(
+        &self,
+        rng: impl Rng,
+        msk: &Self::MSK,
+        identity: Vec<String>,
+    ) -> Result<Self::USK, IbeError>;
 
-    fn encrypt(&self, rng: impl Rng, msg: &Gt, mpk: &Self::MPK, identity: Vec<String>) -> Self::CT;
+    fn encrypt(
+        &self,
+        rng: impl Rng,
+        msg: &Gt,
+        mpk: &Self::MPK,
+        identity: Vec<String>,
+    ) -> Result<Self::CT, IbeError>;
 
-    fn decrypt(&self, usk: &Self::USK, ct: &Self::CT) -> Option<Gt>;
+    fn decrypt(&self, usk: &Self::USK, ct: &Self::CT) -> Result<Gt, IbeError>;
 
     fn delegate(
         &self,
@@ -40,11 +65,21 @@ pub trait HIBEScheme {
         mpk: &Self::MPK,
         usk: &Self::USK,
         identity_extension: String,
-    ) -> Self::USK;
+    ) -> Result<Self::USK, IbeError>;
 }
 
 pub mod bb;
+pub mod bb_cca;
+pub mod bbg;
 pub mod bf;
+pub mod bf_cca;
+pub mod chk;
+pub mod cw13;
+pub mod fo;
 pub mod hiberla_dec;
 pub mod hiberla_enc;
+pub mod ibs;
+pub mod kem;
 pub mod lw;
+pub mod sakai_kasahara;
+pub mod waters;
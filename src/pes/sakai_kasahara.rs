@@ -0,0 +1,183 @@
+use ark_bls12_381::{Bls12_381, Fq12 as Gt, Fr, G1Projective as G1, G2Projective as G2};
+use ark_ec::PrimeGroup;
+use ark_ec::pairing::Pairing;
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+use rand::{CryptoRng, RngCore};
+
+use super::IBEScheme;
+use super::kem::identity_to_string;
+
+use crate::{IBKEM, IbeError, hash_to_fr};
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct MSK {
+    pub z: Fr,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct MPK {
+    pub a: Gt,
+    pub z_g1: G1,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct USK {
+    pub identity: String,
+    pub d: G2,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
+pub struct CT {
+    pub identity: String,
+    pub msg: Gt,
+    pub r: G1,
+}
+
+/// Ciphertext of [`SakaiKasaharaKem`], a single group element.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
+pub struct KemCT {
+    pub r: G1,
+}
+
+/// Sakai–Kasahara IBE as standardized in IEEE 1363.3 (SK-KEM). User keys are obtained by
+/// exponent inversion, `d = g2 / (z + H(id))`, and a ciphertext `r = s * (z * g1 + H(id) * g1)`
+/// pairs with `d` to the key `e(g1, g2)^s`.
+#[derive(Default)]
+pub struct SakaiKasahara {}
+
+impl SakaiKasahara {
+    pub fn new() -> SakaiKasahara {
+        Self {}
+    }
+
+    /// Fails with [`IbeError::InvalidIdentity`] if `z + H(id) = 0`, which an attacker can only
+    /// arrange by knowing `z`.
+    fn extract_key(&self, msk: &MSK, identity: String) -> Result<USK, IbeError> {
+        let exponent = (msk.z + hash_to_fr(&identity))
+            .inverse()
+            .ok_or(IbeError::InvalidIdentity)?;
+
+        Ok(USK {
+            identity,
+            d: G2::generator() * exponent,
+        })
+    }
+
+    fn encapsulate(&self, mut rng: impl Rng, mpk: &MPK, identity: &str) -> (G1, Gt) {
+        let s = Fr::rand(&mut rng);
+        let q_id = mpk.z_g1 + G1::generator() * hash_to_fr(identity);
+
+        (q_id * s, mpk.a.pow(s.into_bigint()))
+    }
+}
+
+impl IBEScheme for SakaiKasahara {
+    type MPK = MPK;
+    type MSK = MSK;
+    type USK = USK;
+    type CT = CT;
+
+    fn name(&self) -> String {
+        String::from("sakai_kasahara")
+    }
+
+    fn setup(&self, mut rng: impl Rng) -> (MSK, MPK) {
+        let z = Fr::rand(&mut rng);
+        let msk = MSK { z };
+
+        let g1 = G1::generator();
+        let g2 = G2::generator();
+
+        let mpk = MPK {
+            a: Bls12_381::pairing(g1, g2).0,
+            z_g1: g1 * z,
+        };
+
+        (msk, mpk)
+    }
+
+    fn keygen(&self, _rng: impl Rng, msk: &MSK, identity: String) -> Result<USK, IbeError> {
+        self.extract_key(msk, identity)
+    }
+
+    fn encrypt(
+        &self,
+        rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identity: String,
+    ) -> Result<CT, IbeError> {
+        let (r, key) = self.encapsulate(rng, mpk, &identity);
+
+        Ok(CT {
+            identity,
+            msg: key * msg,
+            r,
+        })
+    }
+
+    fn decrypt(&self, usk: &USK, ct: &CT) -> Result<Gt, IbeError> {
+        if usk.identity != ct.identity {
+            return Err(IbeError::IdentityMismatch);
+        }
+
+        Ok(ct.msg / Bls12_381::pairing(ct.r, usk.d).0)
+    }
+}
+
+/// [`SakaiKasahara`] as a native KEM: the key is `e(g1, g2)^s` itself, so the ciphertext is a
+/// single `G1` element. Identities are hex encoded as in [`super::kem::PesKem`], so user keys
+/// are interchangeable with those of the `IBEScheme` view.
+#[derive(Default)]
+pub struct SakaiKasaharaKem {}
+
+impl SakaiKasaharaKem {
+    pub fn new() -> SakaiKasaharaKem {
+        Self {}
+    }
+}
+
+impl IBKEM for SakaiKasaharaKem {
+    type PK = MPK;
+    type SK = MSK;
+    type USK = USK;
+    type CT = KemCT;
+
+    fn name(&self) -> String {
+        String::from("sakai_kasahara_kem")
+    }
+
+    fn setup<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<(MPK, MSK), IbeError> {
+        let (msk, mpk) = SakaiKasahara::new().setup(rng);
+        Ok((mpk, msk))
+    }
+
+    fn extract<R: RngCore + CryptoRng>(
+        &self,
+        _rng: &mut R,
+        sk: &MSK,
+        identity: &[u8],
+    ) -> Result<USK, IbeError> {
+        SakaiKasahara::new().extract_key(sk, identity_to_string(identity))
+    }
+
+    fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &MPK,
+        identity: &[u8],
+    ) -> Result<(KemCT, Gt), IbeError> {
+        let (r, key) = SakaiKasahara::new().encapsulate(rng, pk, &identity_to_string(identity));
+        Ok((KemCT { r }, key))
+    }
+
+    fn decrypt(&self, _pk: &MPK, usk: &USK, identity: &[u8], ct: &KemCT) -> Result<Gt, IbeError> {
+        if usk.identity != identity_to_string(identity) {
+            return Err(IbeError::IdentityMismatch);
+        }
+
+        Ok(Bls12_381::pairing(ct.r, usk.d).0)
+    }
+}
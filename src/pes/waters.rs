@@ -0,0 +1,141 @@
+use ark_bls12_381::{Bls12_381, Fq12 as Gt, Fr, G1Projective as G1, G2Projective as G2};
+use ark_ec::PrimeGroup;
+use ark_ec::pairing::Pairing;
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+use bit_vec::BitVec;
+
+use super::IBEScheme;
+
+use crate::{IbeError, blake3_hash_to_bits, check_dim};
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct MSK {
+    pub alpha: Fr,
+    pub u: Vec<Fr>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct MPK {
+    pub a: Gt,
+    pub u_g1: Vec<G1>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct USK {
+    pub identity: String,
+    pub r: G2,
+    pub k: G2,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq)]
+pub struct CT {
+    pub identity: String,
+    pub msg: Gt,
+    pub s: G1,
+    pub c: G1,
+}
+
+/// Waters IBE (Eurocrypt 2005), adaptively secure in the standard model. Identities are
+/// hashed to `n` bits `b_i` and mapped to `u_0 + sum b_i * u_i` in the exponent, with
+/// `u_0` playing the role of `u'`.
+pub struct Waters {
+    pub n: usize, // identity length in bits, at most 256
+}
+
+impl Waters {
+    pub fn new(n: usize) -> Waters {
+        assert!(n <= 256);
+        Self { n }
+    }
+
+    fn identity_bits(&self, identity: &str) -> BitVec {
+        blake3_hash_to_bits(identity.as_bytes(), self.n)
+    }
+}
+
+impl IBEScheme for Waters {
+    type MPK = MPK;
+    type MSK = MSK;
+    type USK = USK;
+    type CT = CT;
+
+    fn name(&self) -> String {
+        String::from("waters")
+    }
+
+    fn setup(&self, mut rng: impl Rng) -> (MSK, MPK) {
+        let alpha = Fr::rand(&mut rng);
+        let u = (0..=self.n).map(|_| Fr::rand(&mut rng)).collect::<Vec<_>>();
+
+        let g1 = G1::generator();
+        let g2 = G2::generator();
+
+        let mpk = MPK {
+            a: Bls12_381::pairing(g1 * alpha, g2).0,
+            u_g1: u.iter().map(|u_i| g1 * u_i).collect(),
+        };
+        let msk = MSK { alpha, u };
+
+        (msk, mpk)
+    }
+
+    fn keygen(&self, mut rng: impl Rng, msk: &MSK, identity: String) -> Result<USK, IbeError> {
+        check_dim(self.n + 1, msk.u.len())?;
+        let g2 = G2::generator();
+        let r = Fr::rand(&mut rng);
+
+        let bits = self.identity_bits(&identity);
+        let f_id: Fr = msk.u[0]
+            + bits
+                .iter()
+                .zip(&msk.u[1..])
+                .filter(|(b, _)| *b)
+                .map(|(_, u_i)| u_i)
+                .sum::<Fr>();
+
+        Ok(USK {
+            identity,
+            r: g2 * r,
+            k: g2 * (msk.alpha + r * f_id),
+        })
+    }
+
+    fn encrypt(
+        &self,
+        mut rng: impl Rng,
+        msg: &Gt,
+        mpk: &MPK,
+        identity: String,
+    ) -> Result<CT, IbeError> {
+        check_dim(self.n + 1, mpk.u_g1.len())?;
+        let g1 = G1::generator();
+        let s = Fr::rand(&mut rng);
+
+        let bits = self.identity_bits(&identity);
+        let f_id: G1 = mpk.u_g1[0]
+            + bits
+                .iter()
+                .zip(&mpk.u_g1[1..])
+                .filter(|(b, _)| *b)
+                .map(|(_, u_i)| u_i)
+                .sum::<G1>();
+
+        Ok(CT {
+            identity,
+            msg: mpk.a.pow(s.into_bigint()) * msg,
+            s: g1 * s,
+            c: f_id * s,
+        })
+    }
+
+    fn decrypt(&self, usk: &USK, ct: &CT) -> Result<Gt, IbeError> {
+        if usk.identity != ct.identity {
+            return Err(IbeError::IdentityMismatch);
+        }
+
+        let result = Bls12_381::pairing(ct.s, usk.k).0 * Bls12_381::pairing(-ct.c, usk.r).0;
+        Ok(ct.msg / result)
+    }
+}
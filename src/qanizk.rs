@@ -1,11 +1,16 @@
+use crate::error::{IbeError, check_dim, check_non_empty};
 use crate::field_utils::*;
 use crate::group_functions::multi_pairing;
 use crate::hashing::*;
+use crate::serialization::*;
 use crate::types::*;
 
 use ark_bls12_381::{G1Affine, G1Projective as G1, G2Projective as G2};
 use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::{BigInteger, One, PrimeField};
+use ark_serialize::{Compress, SerializationError, Validate};
+use rand::{CryptoRng, RngCore};
+use std::io::{Read, Write};
 
 use bit_vec::BitVec;
 
@@ -22,6 +27,7 @@ pub struct Trapdoor {
     pub k_matrix: Matrix<FieldElement>,
 }
 
+#[derive(Clone, PartialEq)]
 pub struct QANIZKProof {
     pub t1_g1: Vec<G1>,
     pub u1_g1: Vec<G1>,
@@ -37,16 +43,20 @@ impl QANIZK {
         Self { k, lambda }
     }
 
-    pub fn gen_crs(&self, m1_matrix: &Matrix<G1>) -> (CRS, Trapdoor) {
-        let a_matrix = random_matrix(self.k + 1, self.k);
-        let b_matrix = random_matrix(self.k, self.k);
-        let k_matrix = random_matrix(m1_matrix.len(), self.k + 1);
+    pub fn gen_crs<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        m1_matrix: &Matrix<G1>,
+    ) -> Result<(CRS, Trapdoor), IbeError> {
+        let a_matrix = random_matrix(rng, self.k + 1, self.k);
+        let b_matrix = random_matrix(rng, self.k, self.k);
+        let k_matrix = random_matrix(rng, m1_matrix.len(), self.k + 1);
 
         let a_g2: Matrix<G2> = matrix_lift_g2(&a_matrix);
-        let ka_g2 = matrix_multiply_lift_g2(&k_matrix, &a_matrix);
+        let ka_g2 = matrix_multiply_lift_g2(&k_matrix, &a_matrix)?;
         let b_g1: Matrix<G1> = matrix_lift_g1(&b_matrix);
 
-        let mk_g1 = g1_matrix_transpose_multiply(&m1_matrix, &k_matrix);
+        let mk_g1 = g1_matrix_transpose_multiply(&m1_matrix, &k_matrix)?;
 
         let mut kjb_a_g2 = Vec::with_capacity(self.lambda);
         let mut b_kjb_g1 = Vec::with_capacity(self.lambda);
@@ -56,11 +66,11 @@ impl QANIZK {
             let mut b_kjb_row = Vec::with_capacity(2);
 
             for _ in 0..2 {
-                let kjb_matrix = random_matrix(self.k, self.k + 1);
-                let kjb_row_a_g2 = matrix_multiply_lift_g2(&kjb_matrix, &a_matrix);
+                let kjb_matrix = random_matrix(rng, self.k, self.k + 1);
+                let kjb_row_a_g2 = matrix_multiply_lift_g2(&kjb_matrix, &a_matrix)?;
                 kjb_row_a.push(kjb_row_a_g2);
 
-                let b_kjb_row_g1 = matrix_transpose_multiply_lift_g1(&b_matrix, &kjb_matrix);
+                let b_kjb_row_g1 = matrix_transpose_multiply_lift_g1(&b_matrix, &kjb_matrix)?;
                 b_kjb_row.push(b_kjb_row_g1);
             }
 
@@ -78,7 +88,7 @@ impl QANIZK {
         };
         let trapdoor = Trapdoor { k_matrix };
 
-        (crs, trapdoor)
+        Ok((crs, trapdoor))
     }
 
     fn hash_tag_c0_t1(&self, tag: &[u8], c0_g1: &[G1], t1: &[G1]) -> Vec<u8> {
@@ -104,8 +114,17 @@ impl QANIZK {
         s: &Vector,
         b_kjb_g1: &Vec<Vec<Matrix<G1>>>,
         tau: &BitVec,
-    ) -> Vec<G1> {
+    ) -> Result<Vec<G1>, IbeError> {
         let lambda = tau.len();
+        check_dim(lambda, b_kjb_g1.len())?;
+        check_non_empty(b_kjb_g1)?;
+        for b_kjb in b_kjb_g1 {
+            check_dim(2, b_kjb.len())?;
+            for m in b_kjb {
+                check_dim(s.len(), m.len())?;
+            }
+        }
+        check_non_empty(&b_kjb_g1[0][0][0])?;
         let cols = b_kjb_g1[0][0][0].len();
 
         let mut result = vector_zero::<G1>(cols);
@@ -117,37 +136,59 @@ impl QANIZK {
             for j in 0..lambda {
                 let tau_j = tau[j] as usize;
                 for row in 0..s.len() {
+                    check_dim(cols, b_kjb_g1[j][tau_j][row].len())?;
                     bases.push(b_kjb_g1[j][tau_j][row][col]);
                     scalars.push(s[row]);
                 }
             }
 
             let bases_affine: Vec<G1Affine> = bases.iter().map(|g| g.into_affine()).collect();
-            result[col] = G1::msm(&bases_affine, &scalars).unwrap();
+            result[col] = G1::msm(&bases_affine, &scalars).map_err(|_| {
+                IbeError::DimensionMismatch {
+                    expected: bases.len(),
+                    found: scalars.len(),
+                }
+            })?;
         }
 
-        result
+        Ok(result)
     }
 
-    pub fn prove(&self, crs: &CRS, tag: &[u8], c0_g1: &Vec<G1>, r: &Vector) -> QANIZKProof {
-        let s = random_vector(self.k);
-        let t1_g1 = group_matrix_vector_mul_msm(&crs.b_g1, &s);
+    pub fn prove<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        crs: &CRS,
+        tag: &[u8],
+        c0_g1: &Vec<G1>,
+        r: &Vector,
+    ) -> Result<QANIZKProof, IbeError> {
+        let s = random_vector(rng, self.k);
+        let t1_g1 = group_matrix_vector_mul_msm(&crs.b_g1, &s)?;
 
         let hash_input = self.hash_tag_c0_t1(tag, c0_g1, &t1_g1);
         let tau = blake3_hash_to_bits(&hash_input, self.lambda);
 
-        let r_mk = group_matrix_transpose_vector_mul_msm(&crs.mk_g1, &r);
-        let s_b_k_tau = self.compute_s_times_b_k_tau(&s, &crs.b_kjb_g1, &tau);
+        let r_mk = group_matrix_transpose_vector_mul_msm(&crs.mk_g1, &r)?;
+        let s_b_k_tau = self.compute_s_times_b_k_tau(&s, &crs.b_kjb_g1, &tau)?;
+        check_dim(r_mk.len(), s_b_k_tau.len())?;
 
         let u1_g1 = vector_add_g1(&r_mk, &s_b_k_tau);
 
-        QANIZKProof { t1_g1, u1_g1 }
+        Ok(QANIZKProof { t1_g1, u1_g1 })
     }
 
-    fn compute_k_tau_a_from_crs(&self, kjb_a_g2: &[Vec<Matrix<G2>>], tau: &BitVec) -> Matrix<G2> {
+    fn compute_k_tau_a_from_crs(
+        &self,
+        kjb_a_g2: &[Vec<Matrix<G2>>],
+        tau: &BitVec,
+    ) -> Result<Matrix<G2>, IbeError> {
         let lambda = tau.len();
-        assert_eq!(kjb_a_g2.len(), lambda);
-        assert_ne!(lambda, 0);
+        check_dim(lambda, kjb_a_g2.len())?;
+        check_non_empty(kjb_a_g2)?;
+        kjb_a_g2
+            .iter()
+            .try_for_each(|kjb_a| check_dim(2, kjb_a.len()))?;
+        check_non_empty(&kjb_a_g2[0][0])?;
 
         let rows = kjb_a_g2[0][0].len();
         let cols = kjb_a_g2[0][0][0].len();
@@ -156,27 +197,32 @@ impl QANIZK {
         for j in 0..lambda {
             let tau_j = tau[j] as usize;
             let kj_tauj_a = &kjb_a_g2[j][tau_j];
-            k_tau_a = matrix_add(&k_tau_a, kj_tauj_a);
+            k_tau_a = matrix_add(&k_tau_a, kj_tauj_a)?;
         }
-        k_tau_a
+        Ok(k_tau_a)
     }
 
-    pub fn verify(&self, crs: &CRS, tag: &[u8], c0_g1: &Vec<G1>, pi: &QANIZKProof) -> bool {
+    pub fn verify(
+        &self,
+        crs: &CRS,
+        tag: &[u8],
+        c0_g1: &Vec<G1>,
+        pi: &QANIZKProof,
+    ) -> Result<(), IbeError> {
         let t1_g1 = &pi.t1_g1;
         let u1_g1 = &pi.u1_g1;
 
         let hash_input = self.hash_tag_c0_t1(tag, c0_g1, t1_g1);
         let tau = blake3_hash_to_bits(&hash_input, self.lambda);
 
-        assert_eq!(u1_g1.len(), self.k + 1);
-        assert_eq!(t1_g1.len(), self.k);
-        assert_eq!(c0_g1.len(), crs.ka_g2.len());
-        assert_eq!(tau.len(), self.lambda);
-        assert_eq!(crs.kjb_a_g2.len(), self.lambda);
-        assert_eq!(crs.a_g2.len(), self.k + 1);
-        assert_eq!(crs.a_g2[0].len(), self.k);
+        check_dim(self.k + 1, u1_g1.len())?;
+        check_dim(self.k, t1_g1.len())?;
+        check_dim(crs.ka_g2.len(), c0_g1.len())?;
+        check_dim(self.lambda, tau.len())?;
+        check_dim(self.lambda, crs.kjb_a_g2.len())?;
+        check_dim(self.k + 1, crs.a_g2.len())?;
 
-        let k_tau_a = self.compute_k_tau_a_from_crs(&crs.kjb_a_g2, &tau);
+        let k_tau_a = self.compute_k_tau_a_from_crs(&crs.kjb_a_g2, &tau)?;
 
         let mut all_pairings = Vec::new();
         for (i, &u1_elem) in u1_g1.iter().enumerate() {
@@ -191,14 +237,120 @@ impl QANIZK {
             }
         }
 
-        assert_eq!(k_tau_a.len(), t1_g1.len());
+        check_dim(t1_g1.len(), k_tau_a.len())?;
         for (i, &t1_elem) in t1_g1.iter().enumerate() {
             for &ktau_elem in k_tau_a[i].iter() {
                 all_pairings.push((-t1_elem, ktau_elem));
             }
         }
 
-        assert!(!all_pairings.is_empty());
-        multi_pairing(&all_pairings) == GTElement::one()
+        if multi_pairing(&all_pairings) != GTElement::one() {
+            return Err(IbeError::InvalidProof);
+        }
+        Ok(())
+    }
+}
+
+impl KemParams for QANIZK {
+    fn dimensions(&self) -> KemDimensions {
+        KemDimensions {
+            k: dim(self.k),
+            msg_len: 0,
+            max_levels: 0,
+            identity_len: 0,
+            lambda: dim(self.lambda),
+        }
+    }
+}
+
+// The number of rows of `[M]_1` is not a QANIZK parameter, so the CRS body starts with it.
+impl KemSerialize<QANIZK> for CRS {
+    const KIND: u8 = KIND_QANIZK_CRS;
+
+    fn serialize_body<W: Write>(
+        &self,
+        qanizk: &QANIZK,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let k = qanizk.k;
+        let n = self.ka_g2.len();
+        write_len(n, &mut writer)?;
+        write_matrix(&self.a_g2, k + 1, k, &mut writer, compress)?;
+        write_matrix(&self.ka_g2, n, k, &mut writer, compress)?;
+        write_matrix(&self.b_g1, k, k, &mut writer, compress)?;
+        write_matrix(&self.mk_g1, k, k + 1, &mut writer, compress)?;
+        check_len(self.kjb_a_g2.len(), qanizk.lambda)?;
+        check_len(self.b_kjb_g1.len(), qanizk.lambda)?;
+        for (kjb_a, b_kjb) in self.kjb_a_g2.iter().zip(&self.b_kjb_g1) {
+            check_len(kjb_a.len(), 2)?;
+            check_len(b_kjb.len(), 2)?;
+            for (m_g2, m_g1) in kjb_a.iter().zip(b_kjb) {
+                write_matrix(m_g2, k, k, &mut writer, compress)?;
+                write_matrix(m_g1, k, k + 1, &mut writer, compress)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn deserialize_body<R: Read>(
+        qanizk: &QANIZK,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let k = qanizk.k;
+        let n = read_len(&mut reader)?;
+        let a_g2 = read_matrix(k + 1, k, &mut reader, compress, validate)?;
+        let ka_g2 = read_matrix(n, k, &mut reader, compress, validate)?;
+        let b_g1 = read_matrix(k, k, &mut reader, compress, validate)?;
+        let mk_g1 = read_matrix(k, k + 1, &mut reader, compress, validate)?;
+
+        let mut kjb_a_g2 = Vec::with_capacity(qanizk.lambda);
+        let mut b_kjb_g1 = Vec::with_capacity(qanizk.lambda);
+        for _ in 0..qanizk.lambda {
+            let mut kjb_a = Vec::with_capacity(2);
+            let mut b_kjb = Vec::with_capacity(2);
+            for _ in 0..2 {
+                kjb_a.push(read_matrix(k, k, &mut reader, compress, validate)?);
+                b_kjb.push(read_matrix(k, k + 1, &mut reader, compress, validate)?);
+            }
+            kjb_a_g2.push(kjb_a);
+            b_kjb_g1.push(b_kjb);
+        }
+
+        Ok(CRS {
+            a_g2,
+            ka_g2,
+            b_g1,
+            mk_g1,
+            kjb_a_g2,
+            b_kjb_g1,
+        })
+    }
+}
+
+impl KemSerialize<QANIZK> for QANIZKProof {
+    const KIND: u8 = KIND_QANIZK_PROOF;
+
+    fn serialize_body<W: Write>(
+        &self,
+        qanizk: &QANIZK,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        write_vector(&self.t1_g1, qanizk.k, &mut writer, compress)?;
+        write_vector(&self.u1_g1, qanizk.k + 1, &mut writer, compress)
+    }
+
+    fn deserialize_body<R: Read>(
+        qanizk: &QANIZK,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let t1_g1 = read_vector(qanizk.k, &mut reader, compress, validate)?;
+        let u1_g1 = read_vector(qanizk.k + 1, &mut reader, compress, validate)?;
+        Ok(QANIZKProof { t1_g1, u1_g1 })
     }
 }
@@ -0,0 +1,262 @@
+use std::io::{self, Read, Write};
+
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate,
+};
+
+use crate::types::Matrix;
+
+// Object kinds written in front of every header, so that e.g. a ciphertext
+// can not be loaded as a public key of the same instance.
+pub const KIND_AFFINE_MAC_SECRET_KEY: u8 = 0x01;
+pub const KIND_AFFINE_MAC_LEVELS1_SECRET_KEY: u8 = 0x02;
+pub const KIND_AFFINE_MAC_LEVELS2_SECRET_KEY: u8 = 0x03;
+pub const KIND_AFFINE_MAC_NR_SECRET_KEY: u8 = 0x04;
+pub const KIND_QANIZK_CRS: u8 = 0x08;
+pub const KIND_QANIZK_PROOF: u8 = 0x09;
+pub const KIND_IBKEM1_PUBLIC_KEY: u8 = 0x10;
+pub const KIND_IBKEM1_SECRET_KEY: u8 = 0x11;
+pub const KIND_IBKEM1_USER_SECRET_KEY: u8 = 0x12;
+pub const KIND_IBKEM1_CIPHERTEXT: u8 = 0x13;
+pub const KIND_IBKEM2_PUBLIC_KEY: u8 = 0x20;
+pub const KIND_IBKEM2_SECRET_KEY: u8 = 0x21;
+pub const KIND_IBKEM2_USER_SECRET_KEY: u8 = 0x22;
+pub const KIND_IBKEM2_CIPHERTEXT: u8 = 0x23;
+pub const KIND_HIBKEM1_PUBLIC_KEY: u8 = 0x30;
+pub const KIND_HIBKEM1_DELEGATION_KEY: u8 = 0x31;
+pub const KIND_HIBKEM1_SECRET_KEY: u8 = 0x32;
+pub const KIND_HIBKEM1_USER_SECRET_KEY: u8 = 0x33;
+pub const KIND_HIBKEM1_USER_DELEGATION_KEY: u8 = 0x34;
+pub const KIND_HIBKEM1_CIPHERTEXT: u8 = 0x35;
+pub const KIND_HIBKEM2_PUBLIC_KEY: u8 = 0x40;
+pub const KIND_HIBKEM2_DELEGATION_KEY: u8 = 0x41;
+pub const KIND_HIBKEM2_SECRET_KEY: u8 = 0x42;
+pub const KIND_HIBKEM2_USER_SECRET_KEY: u8 = 0x43;
+pub const KIND_HIBKEM2_CIPHERTEXT: u8 = 0x44;
+pub const KIND_BKP14_PUBLIC_KEY: u8 = 0x50;
+pub const KIND_BKP14_SECRET_KEY: u8 = 0x51;
+pub const KIND_BKP14_USER_SECRET_KEY: u8 = 0x52;
+pub const KIND_BKP14_CIPHERTEXT: u8 = 0x53;
+
+/// Dimensions of a KEM (or MAC/QANIZK) instance; dimensions a scheme does not have are zero.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KemDimensions {
+    pub k: u32,
+    pub msg_len: u32,
+    pub max_levels: u32,
+    pub identity_len: u32,
+    pub lambda: u32,
+}
+
+pub trait KemParams {
+    fn dimensions(&self) -> KemDimensions;
+}
+
+/// Compact encoding for objects whose shape is fully determined by the parameters `P`.
+///
+/// The encoding is `kind || dimensions || body`, where the body contains the group and
+/// field elements without any per-vector length prefixes. Loading checks the kind and
+/// the dimensions against the instance the object is loaded into.
+pub trait KemSerialize<P: KemParams>: Sized {
+    const KIND: u8;
+
+    fn serialize_body<W: Write>(
+        &self,
+        params: &P,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError>;
+
+    fn deserialize_body<R: Read>(
+        params: &P,
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError>;
+
+    fn serialize_with_params<W: Write>(
+        &self,
+        params: &P,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        Self::KIND.serialize_compressed(&mut writer)?;
+        params.dimensions().serialize_compressed(&mut writer)?;
+        self.serialize_body(params, writer, compress)
+    }
+
+    fn deserialize_with_params<R: Read>(
+        params: &P,
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let kind = u8::deserialize_compressed(&mut reader)?;
+        if kind != Self::KIND {
+            return Err(invalid_data(format!(
+                "unexpected object kind {:#04x}, expected {:#04x}",
+                kind,
+                Self::KIND
+            )));
+        }
+
+        let encoded = KemDimensions::deserialize_compressed(&mut reader)?;
+        let expected = params.dimensions();
+        if encoded != expected {
+            return Err(invalid_data(format!(
+                "dimension mismatch: encoded {:?}, instance {:?}",
+                encoded, expected
+            )));
+        }
+
+        Self::deserialize_body(params, reader, compress, validate)
+    }
+
+    fn to_bytes(&self, params: &P) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = Vec::new();
+        self.serialize_with_params(params, &mut bytes, Compress::Yes)?;
+        Ok(bytes)
+    }
+
+    fn from_bytes(params: &P, bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut reader = bytes;
+        let value =
+            Self::deserialize_with_params(params, &mut reader, Compress::Yes, Validate::Yes)?;
+        if !reader.is_empty() {
+            return Err(invalid_data(format!("{} trailing bytes", reader.len())));
+        }
+        Ok(value)
+    }
+}
+
+pub(crate) fn invalid_data(msg: String) -> SerializationError {
+    SerializationError::IoError(io::Error::new(io::ErrorKind::InvalidData, msg))
+}
+
+pub(crate) fn check_len(actual: usize, expected: usize) -> Result<(), SerializationError> {
+    if actual != expected {
+        return Err(invalid_data(format!(
+            "expected {} elements, found {}",
+            expected, actual
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn write_vector<T: CanonicalSerialize, W: Write>(
+    v: &[T],
+    len: usize,
+    mut writer: W,
+    compress: Compress,
+) -> Result<(), SerializationError> {
+    check_len(v.len(), len)?;
+    for e in v {
+        e.serialize_with_mode(&mut writer, compress)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_vector<T: CanonicalDeserialize, R: Read>(
+    len: usize,
+    mut reader: R,
+    compress: Compress,
+    validate: Validate,
+) -> Result<Vec<T>, SerializationError> {
+    // no pre-allocation, `len` may stem from untrusted input
+    (0..len)
+        .map(|_| T::deserialize_with_mode(&mut reader, compress, validate))
+        .collect()
+}
+
+pub(crate) fn write_matrix<T: CanonicalSerialize, W: Write>(
+    m: &Matrix<T>,
+    rows: usize,
+    cols: usize,
+    mut writer: W,
+    compress: Compress,
+) -> Result<(), SerializationError> {
+    check_len(m.len(), rows)?;
+    for row in m {
+        write_vector(row, cols, &mut writer, compress)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_matrix<T: CanonicalDeserialize, R: Read>(
+    rows: usize,
+    cols: usize,
+    mut reader: R,
+    compress: Compress,
+    validate: Validate,
+) -> Result<Matrix<T>, SerializationError> {
+    (0..rows)
+        .map(|_| read_vector(cols, &mut reader, compress, validate))
+        .collect()
+}
+
+/// Writes `[i][j][b]` collections as used by the hierarchical schemes, where level `i`
+/// (counted from `first_level`) holds `i * identity_len` pairs `b = 0, 1`.
+pub(crate) fn write_levels<T, W: Write>(
+    levels: &[Vec<Vec<T>>],
+    first_level: usize,
+    max_levels: usize,
+    identity_len: usize,
+    mut writer: W,
+    mut write_item: impl FnMut(&T, &mut W) -> Result<(), SerializationError>,
+) -> Result<(), SerializationError> {
+    check_len(levels.len(), max_levels + 1 - first_level)?;
+    for (level, i) in levels.iter().zip(first_level..=max_levels) {
+        check_len(level.len(), i * identity_len)?;
+        for pair in level {
+            check_len(pair.len(), 2)?;
+            for item in pair {
+                write_item(item, &mut writer)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_levels<T, R: Read>(
+    first_level: usize,
+    max_levels: usize,
+    identity_len: usize,
+    mut reader: R,
+    mut read_item: impl FnMut(&mut R) -> Result<T, SerializationError>,
+) -> Result<Vec<Vec<Vec<T>>>, SerializationError> {
+    (first_level..=max_levels)
+        .map(|i| {
+            (0..i * identity_len)
+                .map(|_| (0..2).map(|_| read_item(&mut reader)).collect())
+                .collect()
+        })
+        .collect()
+}
+
+pub(crate) fn write_len<W: Write>(len: usize, writer: W) -> Result<(), SerializationError> {
+    let len = u32::try_from(len).map_err(|_| invalid_data(format!("length {} too large", len)))?;
+    len.serialize_compressed(writer)
+}
+
+pub(crate) fn read_len<R: Read>(reader: R) -> Result<usize, SerializationError> {
+    Ok(u32::deserialize_compressed(reader)? as usize)
+}
+
+/// Reads the depth of a hierarchical key or ciphertext, which must lie in `1..=max_levels`.
+pub(crate) fn read_depth<R: Read>(
+    max_levels: usize,
+    reader: R,
+) -> Result<usize, SerializationError> {
+    let depth = read_len(reader)?;
+    if depth == 0 || depth > max_levels {
+        return Err(invalid_data(format!(
+            "depth {} outside of 1..={}",
+            depth, max_levels
+        )));
+    }
+    Ok(depth)
+}
+
+pub(crate) fn dim(value: usize) -> u32 {
+    u32::try_from(value).expect("dimension fits into u32")
+}
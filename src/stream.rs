@@ -0,0 +1,414 @@
+use std::cmp::min;
+use std::io::{self, Read, Write};
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::{CryptoRng, RngCore};
+
+use crate::envelope::{EnvelopeScheme, open, seal};
+use crate::error::IbeError;
+use crate::hybrid::{malformed, write_identity, write_prefixed};
+use crate::kdf::{KdfContext, derive_key};
+use crate::kem::{HIBKEM, IBKEM};
+use crate::types::GTElement;
+
+// Stream layout:
+//
+//   magic (4) || version (1) || identity || kem envelope (4 + n) || chunk size (4)
+//   || nonce prefix (7) || chunk_0 || ... || chunk_last
+//
+// The header fields are encoded as in the hybrid layout. Every chunk but the last holds
+// exactly `chunk size` bytes of plaintext plus a 16 byte tag; the last one holds between 0
+// and `chunk size` bytes plus the tag and is always present. Chunk i is sealed under the
+// nonce `prefix || i (4, big-endian) || last (1)` (the STREAM construction), so dropping,
+// reordering or appending chunks fails authentication. The DEM key is derived from the
+// encapsulated key bound to the scheme, the identity and the whole header.
+
+pub const STREAM_MAGIC: [u8; 4] = *b"IBES";
+pub const STREAM_VERSION: u8 = 1;
+pub const STREAM_NONCE_PREFIX_LEN: usize = 7;
+pub const STREAM_TAG_LEN: usize = 16;
+pub const STREAM_DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+pub const STREAM_MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+const DEM_KEY_LEN: usize = 32;
+// bounds the header allocations when reading untrusted input
+const MAX_FIELD_LEN: usize = 16 * 1024 * 1024;
+
+fn check_chunk_size(size: usize) -> Result<(), IbeError> {
+    if size == 0 || size > STREAM_MAX_CHUNK_SIZE {
+        return Err(IbeError::InvalidChunkSize {
+            size,
+            max: STREAM_MAX_CHUNK_SIZE,
+        });
+    }
+    Ok(())
+}
+
+fn io_error(e: io::Error) -> IbeError {
+    IbeError::MalformedEncoding(e.into())
+}
+
+fn stream_error(e: IbeError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn chunk_nonce(prefix: &[u8; STREAM_NONCE_PREFIX_LEN], counter: u32, last: bool) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[..STREAM_NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[STREAM_NONCE_PREFIX_LEN..STREAM_NONCE_PREFIX_LEN + 4]
+        .copy_from_slice(&counter.to_be_bytes());
+    nonce[STREAM_NONCE_PREFIX_LEN + 4] = last as u8;
+    nonce
+}
+
+fn dem_cipher(key: &GTElement, context: &KdfContext) -> ChaCha20Poly1305 {
+    let dem_key = derive_key(key, context, DEM_KEY_LEN);
+    ChaCha20Poly1305::new(Key::from_slice(&dem_key))
+}
+
+struct StreamHeader {
+    identity: Vec<Vec<u8>>,
+    kem_part: Vec<u8>,
+    chunk_size: usize,
+    nonce_prefix: [u8; STREAM_NONCE_PREFIX_LEN],
+    // the encoded header, bound into the DEM key
+    bytes: Vec<u8>,
+}
+
+impl StreamHeader {
+    fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        identity: &[Vec<u8>],
+        kem_part: Vec<u8>,
+        chunk_size: usize,
+    ) -> Result<Self, IbeError> {
+        check_chunk_size(chunk_size)?;
+        let mut nonce_prefix = [0u8; STREAM_NONCE_PREFIX_LEN];
+        rng.fill_bytes(&mut nonce_prefix);
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&STREAM_MAGIC);
+        bytes.push(STREAM_VERSION);
+        write_identity(identity, &mut bytes)?;
+        write_prefixed(&kem_part, &mut bytes)?;
+        bytes.extend_from_slice(&(chunk_size as u32).to_le_bytes());
+        bytes.extend_from_slice(&nonce_prefix);
+
+        Ok(Self {
+            identity: identity.to_vec(),
+            kem_part,
+            chunk_size,
+            nonce_prefix,
+            bytes,
+        })
+    }
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self, IbeError> {
+        let mut input = RecordingReader {
+            reader,
+            bytes: Vec::new(),
+        };
+
+        if input.take(STREAM_MAGIC.len())? != STREAM_MAGIC || input.take(1)?[0] != STREAM_VERSION {
+            return Err(malformed());
+        }
+        let levels = input.take(1)?[0] as usize;
+        let mut identity = Vec::with_capacity(levels);
+        for _ in 0..levels {
+            identity.push(input.take_prefixed()?);
+        }
+        let kem_part = input.take_prefixed()?;
+        let chunk_size = input.take_u32()? as usize;
+        check_chunk_size(chunk_size)?;
+        let nonce_prefix = input.take(STREAM_NONCE_PREFIX_LEN)?.try_into().unwrap();
+
+        Ok(Self {
+            identity,
+            kem_part,
+            chunk_size,
+            nonce_prefix,
+            bytes: input.bytes,
+        })
+    }
+}
+
+// Reads the header field by field and keeps a copy of everything read.
+struct RecordingReader<'a, R: Read> {
+    reader: &'a mut R,
+    bytes: Vec<u8>,
+}
+
+impl<R: Read> RecordingReader<'_, R> {
+    fn take(&mut self, n: usize) -> Result<Vec<u8>, IbeError> {
+        let mut buf = vec![0u8; n];
+        self.reader.read_exact(&mut buf).map_err(io_error)?;
+        self.bytes.extend_from_slice(&buf);
+        Ok(buf)
+    }
+
+    fn take_u32(&mut self) -> Result<u32, IbeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn take_prefixed(&mut self) -> Result<Vec<u8>, IbeError> {
+        let len = self.take_u32()? as usize;
+        if len > MAX_FIELD_LEN {
+            return Err(malformed());
+        }
+        self.take(len)
+    }
+}
+
+/// Encrypting writer returned by [`encrypt_stream`]. Plaintext written to it is sealed chunk by
+/// chunk; [`StreamEncryptor::finish`] has to be called to write the last chunk, otherwise the
+/// stream is rejected as truncated.
+pub struct StreamEncryptor<W: Write> {
+    inner: W,
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; STREAM_NONCE_PREFIX_LEN],
+    counter: u32,
+    chunk_size: usize,
+    // holds up to one byte more than a chunk, so the last chunk may be a full one
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> StreamEncryptor<W> {
+    fn new(
+        mut inner: W,
+        key: &GTElement,
+        context: &KdfContext,
+        header: &StreamHeader,
+    ) -> Result<Self, IbeError> {
+        inner.write_all(&header.bytes).map_err(io_error)?;
+        Ok(Self {
+            inner,
+            cipher: dem_cipher(key, context),
+            nonce_prefix: header.nonce_prefix,
+            counter: 0,
+            chunk_size: header.chunk_size,
+            buffer: Vec::with_capacity(header.chunk_size + 1),
+        })
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        let len = min(self.buffer.len(), self.chunk_size);
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        let sealed = self
+            .cipher
+            .encrypt(&nonce, &self.buffer[..len])
+            .expect("chunk within the ChaCha20-Poly1305 length limit");
+        self.inner.write_all(&sealed)?;
+        self.buffer.drain(..len);
+
+        if !last {
+            self.counter = self
+                .counter
+                .checked_add(1)
+                .ok_or_else(|| io::Error::other("stream exceeds the maximum number of chunks"))?;
+        }
+        Ok(())
+    }
+
+    /// Seals the last chunk and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for StreamEncryptor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = min(buf.len(), self.chunk_size + 1 - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        if self.buffer.len() > self.chunk_size {
+            self.seal_chunk(false)?;
+        }
+        Ok(n)
+    }
+
+    // a partial chunk can not be sealed before more data or `finish` decides whether it is
+    // the last one, so this only flushes what has been sealed so far
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypting reader returned by [`decrypt_stream`]. Reads fail with
+/// [`io::ErrorKind::InvalidData`] as soon as a chunk does not authenticate, and a stream that
+/// ends without its last chunk is rejected.
+pub struct StreamDecryptor<R: Read> {
+    inner: R,
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; STREAM_NONCE_PREFIX_LEN],
+    counter: u32,
+    chunk_size: usize,
+    // sealed input, read one byte past a full chunk to tell whether the chunk is the last
+    sealed: Vec<u8>,
+    plaintext: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+
+impl<R: Read> StreamDecryptor<R> {
+    fn new(inner: R, key: &GTElement, context: &KdfContext, header: &StreamHeader) -> Self {
+        Self {
+            inner,
+            cipher: dem_cipher(key, context),
+            nonce_prefix: header.nonce_prefix,
+            counter: 0,
+            chunk_size: header.chunk_size,
+            sealed: Vec::with_capacity(header.chunk_size + STREAM_TAG_LEN + 1),
+            plaintext: Vec::new(),
+            pos: 0,
+            finished: false,
+        }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let want = self.chunk_size + STREAM_TAG_LEN + 1;
+        while self.sealed.len() < want {
+            let start = self.sealed.len();
+            self.sealed.resize(want, 0);
+            match self.inner.read(&mut self.sealed[start..]) {
+                Ok(0) => {
+                    self.sealed.truncate(start);
+                    break;
+                }
+                Ok(n) => self.sealed.truncate(start + n),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => self.sealed.truncate(start),
+                Err(e) => {
+                    self.sealed.truncate(start);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn open_chunk(&mut self) -> io::Result<()> {
+        self.fill()?;
+        let full = self.chunk_size + STREAM_TAG_LEN;
+        let last = self.sealed.len() <= full;
+        let len = min(self.sealed.len(), full);
+        if len < STREAM_TAG_LEN {
+            return Err(stream_error(IbeError::DecryptionFailed));
+        }
+
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        self.plaintext = self
+            .cipher
+            .decrypt(&nonce, &self.sealed[..len])
+            .map_err(|_| stream_error(IbeError::DecryptionFailed))?;
+        self.sealed.drain(..len);
+        self.pos = 0;
+
+        if last {
+            self.finished = true;
+        } else {
+            self.counter = self
+                .counter
+                .checked_add(1)
+                .ok_or_else(|| stream_error(IbeError::DecryptionFailed))?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamDecryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plaintext.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.open_chunk()?;
+        }
+        let n = min(buf.len(), self.plaintext.len() - self.pos);
+        buf[..n].copy_from_slice(&self.plaintext[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Encapsulates a key for `identity`, writes the stream header to `writer` and returns a
+/// writer that encrypts everything written to it in chunks of `chunk_size` bytes.
+pub fn encrypt_stream<K, R, W>(
+    kem: &K,
+    rng: &mut R,
+    pk: &K::PK,
+    identity: &[u8],
+    chunk_size: usize,
+    writer: W,
+) -> Result<StreamEncryptor<W>, IbeError>
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+    R: RngCore + CryptoRng,
+    W: Write,
+{
+    check_chunk_size(chunk_size)?;
+    let (ct, key) = kem.encrypt(rng, pk, identity)?;
+    let header = StreamHeader::new(rng, &[identity.to_vec()], seal(kem, &ct)?, chunk_size)?;
+    let context = KdfContext::new(K::SCHEME_ID, identity, &header.bytes);
+    StreamEncryptor::new(writer, &key, &context, &header)
+}
+
+/// Reads the stream header from `reader`, decapsulates the key and returns a reader yielding
+/// the plaintext of a stream produced by [`encrypt_stream`].
+pub fn decrypt_stream<K, R>(
+    kem: &K,
+    pk: &K::PK,
+    usk: &K::USK,
+    mut reader: R,
+) -> Result<StreamDecryptor<R>, IbeError>
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+    R: Read,
+{
+    let header = StreamHeader::read(&mut reader)?;
+    let [identity] = header.identity.as_slice() else {
+        return Err(malformed());
+    };
+    let ct = open(kem, &header.kem_part)?;
+    let key = kem.decrypt(pk, usk, identity, &ct)?;
+    let context = KdfContext::new(K::SCHEME_ID, identity, &header.bytes);
+    Ok(StreamDecryptor::new(reader, &key, &context, &header))
+}
+
+/// Hierarchical counterpart of [`encrypt_stream`].
+pub fn encrypt_stream_hierarchical<K, R, W>(
+    kem: &K,
+    rng: &mut R,
+    pk: &K::PK,
+    identity: &[Vec<u8>],
+    chunk_size: usize,
+    writer: W,
+) -> Result<StreamEncryptor<W>, IbeError>
+where
+    K: HIBKEM + EnvelopeScheme<Ciphertext = <K as HIBKEM>::CT>,
+    R: RngCore + CryptoRng,
+    W: Write,
+{
+    check_chunk_size(chunk_size)?;
+    let (ct, key) = kem.encrypt(rng, pk, identity)?;
+    let header = StreamHeader::new(rng, identity, seal(kem, &ct)?, chunk_size)?;
+    let context = KdfContext::hierarchical(K::SCHEME_ID, identity, &header.bytes);
+    StreamEncryptor::new(writer, &key, &context, &header)
+}
+
+/// Hierarchical counterpart of [`decrypt_stream`].
+pub fn decrypt_stream_hierarchical<K, R>(
+    kem: &K,
+    usk: &K::USK,
+    mut reader: R,
+) -> Result<StreamDecryptor<R>, IbeError>
+where
+    K: HIBKEM + EnvelopeScheme<Ciphertext = <K as HIBKEM>::CT>,
+    R: Read,
+{
+    let header = StreamHeader::read(&mut reader)?;
+    let ct = open(kem, &header.kem_part)?;
+    let key = kem.decrypt(usk, &ct)?;
+    let context = KdfContext::hierarchical(K::SCHEME_ID, &header.identity, &header.bytes);
+    Ok(StreamDecryptor::new(reader, &key, &context, &header))
+}
@@ -1,55 +1,103 @@
-use ibe_schemes::{AffineMAC, generate_random_message_128};
+use ibe_schemes::{AffineMAC, AffineMac, IbeError, generate_random_message_128};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
 
 #[test]
 fn affine_mac_small_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 8;
     let mac = AffineMAC::new(k, msg_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let message = vec![0b10110011u8];
-    let tag = mac.tag(&sk, &message);
-    let check = mac.verify(&sk, &message, &tag);
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
+    let check = mac.verify(&sk, &message, &tag).is_ok();
     assert!(check);
 }
 
 #[test]
 fn affine_mac_small_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 8;
     let mac = AffineMAC::new(k, msg_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let message = vec![0b11010100u8];
-    let tag = mac.tag(&sk, &message);
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
     let new_message = vec![0b10010001u8];
-    let check = mac.verify(&sk, &new_message, &tag);
+    let check = mac.verify(&sk, &new_message, &tag).is_ok();
     assert!(!check);
 }
 
 #[test]
 fn affine_mac_large_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let mac = AffineMAC::new(k, msg_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let message = generate_random_message_128();
-    let tag = mac.tag(&sk, &message);
-    let check = mac.verify(&sk, &message, &tag);
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
+    let check = mac.verify(&sk, &message, &tag).is_ok();
     assert!(check);
 }
 
 #[test]
 fn affine_mac_large_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let mac = AffineMAC::new(k, msg_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let message = generate_random_message_128();
-    let tag = mac.tag(&sk, &message);
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
     let new_message = generate_random_message_128();
-    let check = mac.verify(&sk, &new_message, &tag);
+    let check = mac.verify(&sk, &new_message, &tag).is_ok();
     assert!(!check);
 }
+
+#[test]
+fn affine_mac_selects_one_function_per_bit() {
+    let mac = AffineMAC::new(2, 8);
+    assert_eq!((mac.n(), mac.eta(), mac.l(), mac.l_prime()), (2, 4, 16, 0));
+
+    let f = mac.f(&[0b10000000u8]);
+    assert_eq!(f.len(), mac.l());
+    assert_eq!((f[0], f[1]), (0u64.into(), 1u64.into()));
+    assert_eq!((f[2], f[3]), (1u64.into(), 0u64.into()));
+    assert_eq!(mac.f_prime(&[0u8]), vec![1u64.into()]);
+}
+
+#[test]
+fn affine_mac_verify_reports_why_a_tag_is_rejected() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = AffineMAC::new(2, 8);
+    let sk = mac.gen_mac(&mut rng);
+
+    let mut tag = mac.tag(&mut rng, &sk, &[0b10110011u8]).unwrap();
+    let other = mac.tag(&mut rng, &sk, &[0b00110011u8]).unwrap();
+
+    assert!(matches!(
+        mac.verify(&sk, &[0b10110011u8, 0], &tag),
+        Err(IbeError::InvalidIdentityLength { .. })
+    ));
+
+    // u of another message does not fit t
+    tag.u_g2 = other.u_g2;
+    assert!(matches!(
+        mac.verify(&sk, &[0b10110011u8], &tag),
+        Err(IbeError::InvalidTag)
+    ));
+
+    tag.u_g2.pop();
+    assert!(matches!(
+        mac.verify(&sk, &[0b10110011u8], &tag),
+        Err(IbeError::DimensionMismatch { .. })
+    ));
+}
@@ -1,61 +1,69 @@
+use ark_ff::{One, Zero};
 use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
 
 #[test]
 fn affine_mac_levels1_single_level_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 3;
     let identity_len = 8;
     let mac = AffineMacLevels1::new(k, max_levels, identity_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let messages = vec![generate_random_message_bits(identity_len)];
-    let tag = mac.tag(&sk, &messages);
-    let check = mac.verify(&sk, &messages, &tag);
+    let tag = mac.tag(&mut rng, &sk, &messages).unwrap();
+    let check = mac.verify(&sk, &messages, &tag).is_ok();
     assert!(check);
 }
 
 #[test]
 fn affine_mac_levels1_single_level_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 3;
     let identity_len = 8;
     let mac = AffineMacLevels1::new(k, max_levels, identity_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let messages = vec![generate_random_message_bits(identity_len)];
-    let tag = mac.tag(&sk, &messages);
+    let tag = mac.tag(&mut rng, &sk, &messages).unwrap();
 
     let wrong_messages = vec![generate_random_message_bits(identity_len)];
-    let check = mac.verify(&sk, &wrong_messages, &tag);
+    let check = mac.verify(&sk, &wrong_messages, &tag).is_ok();
     assert!(!check);
 }
 
 #[test]
 fn affine_mac_levels1_max_depth_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
     let mac = AffineMacLevels1::new(k, max_levels, identity_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let messages = vec![
         generate_random_message_bits(identity_len), 
         generate_random_message_bits(identity_len), 
         generate_random_message_bits(identity_len)
     ];
-    let tag = mac.tag(&sk, &messages);
-    let check = mac.verify(&sk, &messages, &tag);
+    let tag = mac.tag(&mut rng, &sk, &messages).unwrap();
+    let check = mac.verify(&sk, &messages, &tag).is_ok();
     assert!(check);
 }
 
 #[test]
-#[should_panic(expected = "Invalid depth p")]
 fn affine_mac_levels1_max_depth_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 3;
     let identity_len = 8;
     let mac = AffineMacLevels1::new(k, max_levels, identity_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let messages = vec![
         generate_random_message_bits(identity_len), 
@@ -64,46 +72,52 @@ fn affine_mac_levels1_max_depth_fail() {
         generate_random_message_bits(identity_len)
     ];
 
-    mac.tag(&sk, &messages);
+    assert!(matches!(
+        mac.tag(&mut rng, &sk, &messages),
+        Err(IbeError::DepthOutOfRange { depth: 4, max: 3 })
+    ));
 }
 
 #[test]
 fn affine_mac_levels1_hierarchy_mismatch_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 3;
     let identity_len = 8;
     let mac = AffineMacLevels1::new(k, max_levels, identity_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let messages = vec![generate_random_message_bits(identity_len), generate_random_message_bits(identity_len)];
-    let tag = mac.tag(&sk, &messages);
+    let tag = mac.tag(&mut rng, &sk, &messages).unwrap();
 
     let wrong_messages = vec![messages[0].clone()];
-    let check = mac.verify(&sk, &wrong_messages, &tag);
+    let check = mac.verify(&sk, &wrong_messages, &tag).is_ok();
     assert!(!check);
 }
 
 #[test]
 fn affine_mac_levels1_longer_identity_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 3;
     let identity_len = 128;
     let mac = AffineMacLevels1::new(k, max_levels, identity_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let messages = vec![generate_random_message_bits(identity_len)];
-    let tag = mac.tag(&sk, &messages);
-    let check = mac.verify(&sk, &messages, &tag);
+    let tag = mac.tag(&mut rng, &sk, &messages).unwrap();
+    let check = mac.verify(&sk, &messages, &tag).is_ok();
     assert!(check);
 }
 
 #[test]
 fn affine_mac_levels1_prefix_mismatch_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 3;
     let identity_len = 8;
     let mac = AffineMacLevels1::new(k, max_levels, identity_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let id1 = generate_random_message_bits(identity_len);
     let id2 = generate_random_message_bits(identity_len);
@@ -112,25 +126,65 @@ fn affine_mac_levels1_prefix_mismatch_fail() {
     let messages_2 = vec![id1.clone(), id2.clone()];
     let messages_3 = vec![id1.clone(), id2.clone(), id3.clone()];
 
-    let tag_2 = mac.tag(&sk, &messages_2);
+    let tag_2 = mac.tag(&mut rng, &sk, &messages_2).unwrap();
     
-    assert!(!mac.verify(&sk, &messages_3, &tag_2));
+    assert!(mac.verify(&sk, &messages_3, &tag_2).is_err());
 }
 
 #[test]
 fn affine_mac_levels1_randomness_check() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 2;
     let identity_len = 8;
     let mac = AffineMacLevels1::new(k, max_levels, identity_len);
-    let sk = mac.gen_mac();
+    let sk = mac.gen_mac(&mut rng);
 
     let messages = vec![generate_random_message_bits(identity_len)];
-    let tag1 = mac.tag(&sk, &messages);
-    let tag2 = mac.tag(&sk, &messages);
+    let tag1 = mac.tag(&mut rng, &sk, &messages).unwrap();
+    let tag2 = mac.tag(&mut rng, &sk, &messages).unwrap();
 
-    assert!(mac.verify(&sk, &messages, &tag1));
-    assert!(mac.verify(&sk, &messages, &tag2));
+    assert!(mac.verify(&sk, &messages, &tag1).is_ok());
+    assert!(mac.verify(&sk, &messages, &tag2).is_ok());
     assert_ne!(tag1.t_g2[0], tag2.t_g2[0]);
     assert_ne!(tag1.u_g2[0], tag2.u_g2[0]);
 }
+
+#[test]
+fn affine_mac_levels1_mixed_tag_is_invalid() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = AffineMacLevels1::new(2, 3, 8);
+    let sk = mac.gen_mac(&mut rng);
+
+    let messages = vec![vec![0x5a], vec![0xa5]];
+    let mut tag = mac.tag(&mut rng, &sk, &messages).unwrap();
+    let other = mac.tag(&mut rng, &sk, &messages).unwrap();
+    assert!(mac.verify(&sk, &messages, &other).is_ok());
+
+    // t of one tag with u of another
+    tag.u_g2 = other.u_g2;
+    assert!(matches!(
+        mac.verify(&sk, &messages, &tag),
+        Err(IbeError::InvalidTag)
+    ));
+}
+
+#[test]
+fn affine_mac_levels1_level_functions_select_one_per_bit() {
+    let mac = AffineMacLevels1::new(2, 3, 8);
+    let messages = vec![vec![0b1000_0001], vec![0xff]];
+
+    for level in 1..=messages.len() {
+        let f = mac.f(level, &messages);
+        assert_eq!(f.len(), mac.l(level));
+        assert_eq!(f.iter().filter(|x| x.is_one()).count(), level * 8);
+    }
+
+    // level 2 covers the bits of both levels: the first bit is set, the second is not
+    let f = mac.f(2, &messages);
+    assert!(f[0].is_zero() && f[1].is_one());
+    assert!(f[2].is_one() && f[3].is_zero());
+    // and every bit of the second level is
+    assert!(f[16..].iter().step_by(2).all(|x| x.is_zero()));
+    assert!(f[17..].iter().step_by(2).all(|x| x.is_one()));
+}
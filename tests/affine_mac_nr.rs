@@ -0,0 +1,83 @@
+use ibe_schemes::{
+    AffineMac, AffineMacNr, AffineMacNrSecretKey, KemSerialize, generate_random_message_128,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+#[test]
+fn affine_mac_nr_small_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = AffineMacNr::new(2, 8);
+    let sk = mac.gen_mac(&mut rng);
+
+    let message = vec![0b10110011u8];
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
+    assert!(mac.verify(&sk, &message, &tag).is_ok());
+}
+
+#[test]
+fn affine_mac_nr_small_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = AffineMacNr::new(2, 8);
+    let sk = mac.gen_mac(&mut rng);
+
+    let message = vec![0b11010100u8];
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
+    assert!(mac.verify(&sk, &[0b10010001u8], &tag).is_err());
+}
+
+#[test]
+fn affine_mac_nr_large_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = AffineMacNr::new(2, 128);
+    let sk = mac.gen_mac(&mut rng);
+
+    let message = generate_random_message_128();
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
+    assert!(mac.verify(&sk, &message, &tag).is_ok());
+}
+
+#[test]
+fn affine_mac_nr_large_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = AffineMacNr::new(2, 128);
+    let sk = mac.gen_mac(&mut rng);
+
+    let message = generate_random_message_128();
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
+    let new_message = generate_random_message_128();
+    assert!(mac.verify(&sk, &new_message, &tag).is_err());
+}
+
+#[test]
+fn affine_mac_nr_has_only_the_constant_f_prime() {
+    let mac = AffineMacNr::new(1, 8);
+
+    assert_eq!(mac.l_prime(), 0);
+    assert_eq!(mac.f_prime(&[0b10000000u8]), vec![1u64.into()]);
+}
+
+#[test]
+fn affine_mac_nr_tag_has_scalar_u() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = AffineMacNr::new(2, 8);
+    let sk = mac.gen_mac(&mut rng);
+
+    let tag = mac.tag(&mut rng, &sk, &[0u8]).unwrap();
+    assert_eq!(tag.t_g2.len(), 3);
+    assert_eq!(tag.u_g2.len(), 1);
+}
+
+#[test]
+fn affine_mac_nr_serialize_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = AffineMacNr::new(2, 8);
+    let sk = mac.gen_mac(&mut rng);
+    let sk = AffineMacNrSecretKey::from_bytes(&mac, &sk.to_bytes(&mac).unwrap()).unwrap();
+
+    let message = vec![0b01100110u8];
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
+    assert!(mac.verify(&sk, &message, &tag).is_ok());
+}
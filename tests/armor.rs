@@ -0,0 +1,127 @@
+use ark_bls12_381::Fq12 as Gt;
+use ark_ff::UniformRand;
+use rand::thread_rng;
+
+use ibe_schemes::pes::bb::{self, BB};
+use ibe_schemes::pes::lw::{self, LW};
+use ibe_schemes::pes::{HIBEScheme, IBEScheme};
+use ibe_schemes::*;
+
+fn bb_user_key() -> bb::USK {
+    let mut rng = thread_rng();
+    let scheme = BB::new();
+    let (msk, _) = scheme.setup(&mut rng);
+    scheme
+        .keygen(&mut rng, &msk, String::from("alice@example.com"))
+        .unwrap()
+}
+
+#[test]
+fn armor_bb_user_key_roundtrip() {
+    let usk = bb_user_key();
+    let text = to_armor(&usk).unwrap();
+
+    assert!(text.starts_with("-----BEGIN IBE BB USER KEY-----\n"));
+    assert!(text.ends_with("-----END IBE BB USER KEY-----\n"));
+    assert!(text.lines().all(|line| line.len() <= 64));
+
+    let decoded: bb::USK = from_armor(&text).unwrap();
+    assert_eq!(decoded.identity, usk.identity);
+    assert_eq!(decoded.r, usk.r);
+    assert_eq!(decoded.k, usk.k);
+}
+
+#[test]
+fn armor_lw_ciphertext_with_surrounding_text() {
+    let mut rng = thread_rng();
+    let scheme = LW::new();
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+    let identity = vec![String::from("A"), String::from("B")];
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, identity.clone())
+        .unwrap();
+
+    let text = format!(
+        "Please decrypt the following:\n\n{}\nThanks!\n",
+        to_armor(&ct).unwrap()
+    );
+    let ct: lw::CT = from_armor(&text).unwrap();
+
+    let usk = scheme.keygen(&mut rng, &msk, identity).unwrap();
+    assert_eq!(scheme.decrypt(&usk, &ct).unwrap(), msg);
+}
+
+#[test]
+fn armor_hibkem1_public_key_roundtrip() {
+    let mut rng = thread_rng();
+    let hibkem = HIBKEM1::new(2, 2, 8);
+    let (pk, _, sk) = hibkem.setup(&mut rng).unwrap();
+    let (_, id) = generate_hierarchical_identity(2, 8);
+
+    let text = to_armor_with_params(&pk, &hibkem).unwrap();
+    assert!(text.starts_with("-----BEGIN HIBKEM1 PUBLIC KEY-----\n"));
+    let pk: HIBKEM1PublicKey = from_armor_with_params(&hibkem, &text).unwrap();
+
+    let (usk, _) = hibkem.extract(&mut rng, &sk, &id).unwrap();
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id).unwrap();
+    assert_eq!(k_enc, hibkem.decrypt(&usk, &ct).unwrap());
+
+    let other = HIBKEM1::new(2, 3, 8);
+    assert!(matches!(
+        from_armor_with_params::<_, HIBKEM1PublicKey>(&other, &text),
+        Err(ArmorError::Malformed(_))
+    ));
+}
+
+#[test]
+fn armor_rejects_wrong_label() {
+    let text = to_armor(&bb_user_key()).unwrap();
+
+    let err = from_armor::<bb::CT>(&text).err().unwrap();
+    match err {
+        ArmorError::LabelMismatch { expected, found } => {
+            assert_eq!(expected, "IBE BB CIPHERTEXT");
+            assert_eq!(found, "IBE BB USER KEY");
+        }
+        e => panic!("unexpected error {}", e),
+    }
+}
+
+#[test]
+fn armor_rejects_corrupted_body() {
+    let text = to_armor(&bb_user_key()).unwrap();
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+
+    // swap two characters of the body, keeping it valid base64
+    let mut corrupted = lines.clone();
+    let body: Vec<char> = corrupted[1].chars().collect();
+    let (i, j) = (0..body.len() - 1)
+        .find_map(|i| (body[i] != body[i + 1]).then_some((i, i + 1)))
+        .unwrap();
+    let mut body = body;
+    body.swap(i, j);
+    corrupted[1] = body.into_iter().collect();
+    assert!(matches!(
+        from_armor::<bb::USK>(&corrupted.join("\n")),
+        Err(ArmorError::ChecksumMismatch { .. })
+    ));
+
+    let mut invalid = lines.clone();
+    invalid[1].replace_range(0..1, "*");
+    assert!(matches!(
+        from_armor::<bb::USK>(&invalid.join("\n")),
+        Err(ArmorError::InvalidBase64 { line: 2 })
+    ));
+
+    lines.pop();
+    assert!(matches!(
+        from_armor::<bb::USK>(&lines.join("\n")),
+        Err(ArmorError::MissingEnd { .. })
+    ));
+
+    assert!(matches!(
+        from_armor::<bb::USK>("no armor here"),
+        Err(ArmorError::MissingBegin)
+    ));
+}
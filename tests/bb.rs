@@ -1,6 +1,8 @@
 mod common;
 pub use common::*;
 
+use ark_serialize::Compress;
+
 use ibe_schemes::pes::bb::BB;
 
 #[test]
@@ -26,3 +28,15 @@ fn bb_longer_fail() {
     let scheme = BB::new();
     test_ibe_decrypt_fail(scheme, "ABCDEFG", "ABCDeFG");
 }
+
+#[test]
+fn bb_serialize_compressed_ok() {
+    let scheme = BB::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::Yes);
+}
+
+#[test]
+fn bb_serialize_uncompressed_ok() {
+    let scheme = BB::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::No);
+}
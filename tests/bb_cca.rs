@@ -0,0 +1,63 @@
+mod common;
+pub use common::*;
+
+use ark_bls12_381::{Fq12 as Gt, G1Projective as G1};
+use ark_ec::PrimeGroup;
+use ark_ff::One;
+use ark_serialize::Compress;
+
+use ibe_schemes::pes::bb_cca::BBCca;
+
+#[test]
+fn bb_cca_minimal_ok() {
+    let scheme = BBCca::new();
+    test_ibe_decrypt_ok(scheme, "A", "A");
+}
+
+#[test]
+fn bb_cca_longer_ok() {
+    let scheme = BBCca::new();
+    test_ibe_decrypt_ok(scheme, "ABCDEFG", "ABCDEFG");
+}
+
+#[test]
+fn bb_cca_minimal_fail() {
+    let scheme = BBCca::new();
+    test_ibe_decrypt_fail(scheme, "A", "B");
+}
+
+#[test]
+fn bb_cca_serialize_compressed_ok() {
+    let scheme = BBCca::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::Yes);
+}
+
+#[test]
+fn bb_cca_serialize_uncompressed_ok() {
+    let scheme = BBCca::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::No);
+}
+
+#[test]
+fn bb_cca_rejects_modified_c() {
+    test_fo_rejects_tampering(BBCca::new(), |ct| ct.inner.c += G1::generator());
+}
+
+#[test]
+fn bb_cca_rejects_modified_s() {
+    test_fo_rejects_tampering(BBCca::new(), |ct| ct.inner.s += G1::generator());
+}
+
+#[test]
+fn bb_cca_rejects_mauled_message_parts() {
+    // multiplying either part by a known factor is exactly what the CPA scheme lets through
+    let factor = Gt::one() + Gt::one();
+
+    test_fo_rejects_tampering(BBCca::new(), |ct| ct.inner.msg *= factor);
+    test_fo_rejects_tampering(BBCca::new(), |ct| ct.masked *= factor);
+}
+
+#[test]
+fn bb_cca_envelope_and_armor_roundtrip() {
+    test_fo_envelope_and_armor_roundtrip(BBCca::new());
+}
@@ -0,0 +1,198 @@
+mod common;
+pub use common::*;
+
+use ark_bls12_381::Fq12 as Gt;
+use ark_ff::UniformRand;
+use ark_serialize::{CanonicalSerialize, Compress};
+use rand::thread_rng;
+
+use ibe_schemes::pes::HIBEScheme;
+use ibe_schemes::*;
+
+use ibe_schemes::pes::bbg::{self, BBG};
+
+const MAX_DEPTH: usize = 5;
+
+#[test]
+fn bbg_minimal_ok() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_decrypt_ok(scheme, "A", "A");
+}
+
+#[test]
+fn bbg_exact_match_ok() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_decrypt_ok(scheme, "A.B.C.D", "A.B.C.D");
+}
+
+#[test]
+fn bbg_superior_ok() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_decrypt_ok(scheme, "A.B.C", "A.B.C.D");
+}
+
+#[test]
+fn bbg_root_ok() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_decrypt_ok(scheme, "A", "A.B.C");
+}
+
+#[test]
+fn bbg_minimal_fail() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_decrypt_fail(scheme, "A", "B");
+}
+
+#[test]
+fn bbg_hierarchy_mismatch_fail() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_decrypt_fail(scheme, "A.B.C.D", "A.b.C.D");
+}
+
+#[test]
+fn bbg_inferior_fail() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_decrypt_fail(scheme, "A.B.C.D", "A.B.C");
+}
+
+#[test]
+fn bbg_empty_identity_fail() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_empty_identity_fail(scheme);
+}
+
+#[test]
+fn bbg_delegate_ok() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_delegate_ok(scheme, "A.B.C", "A.B.C.D", "D");
+}
+
+#[test]
+fn bbg_delegate_minimal_ok() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_delegate_ok(scheme, "A", "A.B", "B");
+}
+
+#[test]
+fn bbg_delegate_superior_ok() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_delegate_ok(scheme, "A.B", "A.B.C.D", "C");
+}
+
+#[test]
+fn bbg_delegate_hierarchy_mismatch_fail() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_delegate_fail(scheme, "A.b.C", "A.B.C.D", "D");
+}
+
+#[test]
+fn bbg_serialize_compressed_ok() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_serialize_roundtrip(scheme, "A.B", "A.B.C", "C", Compress::Yes);
+}
+
+#[test]
+fn bbg_serialize_uncompressed_ok() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_serialize_roundtrip(scheme, "A.B", "A.B.C", "C", Compress::No);
+}
+
+#[test]
+fn bbg_depth_limit() {
+    let scheme = BBG::new(MAX_DEPTH);
+    test_hibe_decrypt_ok(scheme, "A.B.C.D.E", "A.B.C.D.E");
+
+    let mut rng = thread_rng();
+    let scheme = BBG::new(MAX_DEPTH);
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let too_deep: Vec<String> = "A.B.C.D.E.F".split('.').map(String::from).collect();
+    let usk = scheme
+        .keygen(&mut rng, &msk, too_deep[..5].to_vec())
+        .unwrap();
+    let msg = Gt::rand(&mut rng);
+
+    assert!(matches!(
+        scheme.keygen(&mut rng, &msk, too_deep.clone()),
+        Err(IbeError::DepthOutOfRange { depth: 6, max: 5 })
+    ));
+    assert!(matches!(
+        scheme.encrypt(&mut rng, &msg, &mpk, too_deep),
+        Err(IbeError::DepthOutOfRange { depth: 6, max: 5 })
+    ));
+    assert!(matches!(
+        scheme.delegate(&mut rng, &mpk, &usk, String::from("F")),
+        Err(IbeError::DepthOutOfRange { depth: 6, max: 5 })
+    ));
+}
+
+#[test]
+fn bbg_ciphertext_size_is_constant() {
+    let mut rng = thread_rng();
+    let scheme = BBG::new(MAX_DEPTH);
+    let (_, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    // the identity strings are part of the ciphertext, so use empty levels
+    let sizes: Vec<usize> = (1..=MAX_DEPTH)
+        .map(|depth| {
+            let ct = scheme
+                .encrypt(&mut rng, &msg, &mpk, vec![String::new(); depth])
+                .unwrap();
+            ct.serialized_size(Compress::Yes) - ct.identity.serialized_size(Compress::Yes)
+        })
+        .collect();
+    assert!(sizes.iter().all(|size| *size == sizes[0]));
+}
+
+fn identity(id: &str) -> Vec<String> {
+    id.split('.').map(String::from).collect()
+}
+
+#[test]
+fn bbg_envelope_roundtrip() {
+    let mut rng = thread_rng();
+    let scheme = BBG::new(MAX_DEPTH);
+    assert_eq!(BBG::SCHEME_ID, scheme.name());
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, identity("A.B.C"))
+        .unwrap();
+
+    let bytes = seal(&scheme, &ct).unwrap();
+    let AnyCiphertext::Bbg(opened, ct) = open_any(&bytes).unwrap() else {
+        panic!("expected a bbg ciphertext");
+    };
+    assert_eq!(opened.max_depth, MAX_DEPTH);
+    assert!(matches!(
+        open(&BBG::new(MAX_DEPTH + 1), &bytes),
+        Err(EnvelopeError::ParamsMismatch { .. })
+    ));
+
+    let usk = opened.keygen(&mut rng, &msk, identity("A.B")).unwrap();
+    assert_eq!(opened.decrypt(&usk, &ct).unwrap(), msg);
+}
+
+#[test]
+fn bbg_armor_roundtrip() {
+    let mut rng = thread_rng();
+    let scheme = BBG::new(MAX_DEPTH);
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    let text = to_armor(&mpk).unwrap();
+    assert!(text.starts_with("-----BEGIN HIBE BBG PUBLIC KEY-----\n"));
+    let mpk: bbg::MPK = from_armor(&text).unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, identity("A.B.C"))
+        .unwrap();
+    let ct: bbg::CT = from_armor(&to_armor(&ct).unwrap()).unwrap();
+
+    let msk: bbg::MSK = from_armor(&to_armor(&msk).unwrap()).unwrap();
+    let usk = scheme.keygen(&mut rng, &msk, identity("A")).unwrap();
+    let usk: bbg::USK = from_armor(&to_armor(&usk).unwrap()).unwrap();
+    let usk = scheme
+        .delegate(&mut rng, &mpk, &usk, String::from("B"))
+        .unwrap();
+    assert_eq!(scheme.decrypt(&usk, &ct).unwrap(), msg);
+}
@@ -1,6 +1,8 @@
 mod common;
 pub use common::*;
 
+use ark_serialize::Compress;
+
 use ibe_schemes::pes::bf::BF;
 
 #[test]
@@ -26,3 +28,15 @@ fn bf_longer_fail() {
     let scheme = BF::new();
     test_ibe_decrypt_fail(scheme, "ABCDEFG", "ABCDeFG");
 }
+
+#[test]
+fn bf_serialize_compressed_ok() {
+    let scheme = BF::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::Yes);
+}
+
+#[test]
+fn bf_serialize_uncompressed_ok() {
+    let scheme = BF::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::No);
+}
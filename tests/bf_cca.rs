@@ -0,0 +1,63 @@
+mod common;
+pub use common::*;
+
+use ark_bls12_381::{Fq12 as Gt, G1Projective as G1, G2Projective as G2};
+use ark_ec::PrimeGroup;
+use ark_ff::One;
+use ark_serialize::Compress;
+
+use ibe_schemes::pes::bf_cca::BFCca;
+
+#[test]
+fn bf_cca_minimal_ok() {
+    let scheme = BFCca::new();
+    test_ibe_decrypt_ok(scheme, "A", "A");
+}
+
+#[test]
+fn bf_cca_longer_ok() {
+    let scheme = BFCca::new();
+    test_ibe_decrypt_ok(scheme, "ABCDEFG", "ABCDEFG");
+}
+
+#[test]
+fn bf_cca_minimal_fail() {
+    let scheme = BFCca::new();
+    test_ibe_decrypt_fail(scheme, "A", "B");
+}
+
+#[test]
+fn bf_cca_serialize_compressed_ok() {
+    let scheme = BFCca::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::Yes);
+}
+
+#[test]
+fn bf_cca_serialize_uncompressed_ok() {
+    let scheme = BFCca::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::No);
+}
+
+#[test]
+fn bf_cca_rejects_modified_c() {
+    test_fo_rejects_tampering(BFCca::new(), |ct| ct.inner.c += G1::generator());
+}
+
+#[test]
+fn bf_cca_rejects_modified_s() {
+    test_fo_rejects_tampering(BFCca::new(), |ct| ct.inner.s += G2::generator());
+}
+
+#[test]
+fn bf_cca_rejects_mauled_message_parts() {
+    // multiplying either part by a known factor is exactly what the CPA scheme lets through
+    let factor = Gt::one() + Gt::one();
+
+    test_fo_rejects_tampering(BFCca::new(), |ct| ct.inner.msg *= factor);
+    test_fo_rejects_tampering(BFCca::new(), |ct| ct.masked *= factor);
+}
+
+#[test]
+fn bf_cca_envelope_and_armor_roundtrip() {
+    test_fo_envelope_and_armor_roundtrip(BFCca::new());
+}
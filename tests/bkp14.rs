@@ -0,0 +1,171 @@
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+#[test]
+fn test_bkp14_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let k = 2;
+    let msg_len = 128;
+    let ibkem = BKP14::with_mac(AffineMacNr::new(k, msg_len));
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    let k_dec = ibkem.decrypt(&usk, &ct).unwrap();
+
+    assert_eq!(k_dec, k)
+}
+
+#[test]
+fn test_bkp14_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let k = 2;
+    let msg_len = 128;
+    let ibkem = BKP14::with_mac(AffineMacNr::new(k, msg_len));
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+
+    let (_, new_identity) = generate_email_and_hash_identity(128);
+    let new_usk = ibkem.extract(&mut rng, &sk, &new_identity).unwrap();
+    let k_dec = ibkem.decrypt(&new_usk, &ct).unwrap();
+
+    assert_ne!(k_dec, k);
+}
+
+#[test]
+fn test_bkp14_seeded_rng_is_reproducible() {
+    let ibkem = BKP14::with_mac(AffineMacNr::new(2, 128));
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    let run = || {
+        let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+        let (pk, _) = ibkem.setup(&mut rng).unwrap();
+        let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+        (
+            pk.to_bytes(&ibkem).unwrap(),
+            ct.to_bytes(&ibkem).unwrap(),
+            k,
+        )
+    };
+
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn test_bkp14_rejects_wrong_identity_length() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = BKP14::with_mac(AffineMacNr::new(2, 128));
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+
+    let (_, short_identity) = generate_email_and_hash_identity(64);
+
+    assert!(matches!(
+        ibkem.extract(&mut rng, &sk, &short_identity),
+        Err(IbeError::InvalidIdentityLength {
+            expected: 128,
+            found: 64
+        })
+    ));
+    assert!(matches!(
+        ibkem.encrypt(&mut rng, &pk, &short_identity),
+        Err(IbeError::InvalidIdentityLength { .. })
+    ));
+}
+
+#[test]
+fn test_bkp14_serialize_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = BKP14::with_mac(AffineMacNr::new(2, 128));
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    let pk = BKP14PublicKey::from_bytes(&ibkem, &pk.to_bytes(&ibkem).unwrap()).unwrap();
+    let sk = BKP14SecretKey::from_bytes(&ibkem, &sk.to_bytes(&ibkem).unwrap()).unwrap();
+
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let usk = BKP14UserSecretKey::from_bytes(&ibkem, &usk.to_bytes(&ibkem).unwrap()).unwrap();
+
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    let ct_bytes = ct.to_bytes(&ibkem).unwrap();
+    let ct = BKP14Ciphertext::from_bytes(&ibkem, &ct_bytes).unwrap();
+
+    assert_eq!(ct.to_bytes(&ibkem).unwrap(), ct_bytes);
+    assert_eq!(ibkem.decrypt(&usk, &ct).unwrap(), k);
+}
+
+#[test]
+fn test_bkp14_deserialize_rejects_other_instance() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = BKP14::with_mac(AffineMacNr::new(2, 128));
+    let other = BKP14::with_mac(AffineMacNr::new(1, 128));
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+
+    assert!(BKP14PublicKey::from_bytes(&other, &pk.to_bytes(&ibkem).unwrap()).is_err());
+    assert!(BKP14Ciphertext::from_bytes(&other, &ct.to_bytes(&ibkem).unwrap()).is_err());
+}
+
+#[test]
+fn test_bkp14_deserialize_rejects_wrong_kind() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = BKP14::with_mac(AffineMacNr::new(2, 128));
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+
+    let mut ct_bytes = ct.to_bytes(&ibkem).unwrap();
+    assert!(BKP14UserSecretKey::from_bytes(&ibkem, &ct_bytes).is_err());
+
+    ct_bytes.push(0);
+    assert!(BKP14Ciphertext::from_bytes(&ibkem, &ct_bytes).is_err());
+}
+
+#[test]
+fn test_bkp14_armor_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = BKP14::with_mac(AffineMacNr::new(2, 128));
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    let text = to_armor_with_params(&sk, &ibkem).unwrap();
+    assert!(text.starts_with("-----BEGIN IBKEM1 SECRET KEY-----\n"));
+    let sk: BKP14SecretKey = from_armor_with_params(&ibkem, &text).unwrap();
+
+    let pk_text = to_armor_with_params(&pk, &ibkem).unwrap();
+    let pk: BKP14PublicKey = from_armor_with_params(&ibkem, &pk_text).unwrap();
+    let other = IBKEM1::new(2, 128);
+    assert!(matches!(
+        from_armor_with_params::<_, IBKEM1PublicKey>(&other, &pk_text),
+        Err(ArmorError::Malformed(_))
+    ));
+
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let usk_text = to_armor_with_params(&usk, &ibkem).unwrap();
+    let usk: BKP14UserSecretKey = from_armor_with_params(&ibkem, &usk_text).unwrap();
+
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    let ct_text = to_armor_with_params(&ct, &ibkem).unwrap();
+    let ct: BKP14Ciphertext = from_armor_with_params(&ibkem, &ct_text).unwrap();
+    assert_eq!(ibkem.decrypt(&usk, &ct).unwrap(), k);
+}
+
+#[test]
+fn test_bkp14_ciphertext_size() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = BKP14::with_mac(AffineMacNr::new(2, 128));
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    assert_eq!(ct.c0_g1.len(), 3);
+    assert_eq!(ct.c1_g1.len(), 3);
+    assert_eq!(pk.z_prime_vectors.len(), 1);
+}
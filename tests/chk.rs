@@ -0,0 +1,96 @@
+use ark_bls12_381::{Fq12 as Gt, Fr};
+use ark_ff::UniformRand;
+use ark_serialize::CanonicalSerialize;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use ibe_schemes::pes::HIBEScheme;
+use ibe_schemes::pes::chk::Chk;
+use ibe_schemes::pes::hiberla_dec::HiberlaDec;
+use ibe_schemes::pes::hiberla_enc::HiberlaEnc;
+use ibe_schemes::pes::lw::LW;
+use ibe_schemes::{IbeError, OtsSigningKey};
+
+const SEED: u64 = 42;
+
+fn chk_roundtrip<T: HIBEScheme>(scheme: T)
+where
+    T::MPK: Clone,
+{
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let chk = Chk::new(scheme);
+    let (pk, sk) = chk.keygen(&mut rng).unwrap();
+
+    let msg = Gt::rand(&mut rng);
+    let ct = chk.encrypt(&mut rng, &pk, &msg).unwrap();
+    assert_eq!(chk.decrypt(&mut rng, &sk, &ct).unwrap(), msg);
+}
+
+#[test]
+fn chk_lw() {
+    chk_roundtrip(LW::new());
+}
+
+#[test]
+fn chk_hiberla_enc() {
+    chk_roundtrip(HiberlaEnc::new(2));
+}
+
+#[test]
+fn chk_hiberla_dec() {
+    chk_roundtrip(HiberlaDec::new(2));
+}
+
+#[test]
+fn chk_ciphertexts_use_fresh_keys() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let chk = Chk::new(LW::new());
+    let (pk, _) = chk.keygen(&mut rng).unwrap();
+    let msg = Gt::rand(&mut rng);
+
+    let ct_1 = chk.encrypt(&mut rng, &pk, &msg).unwrap();
+    let ct_2 = chk.encrypt(&mut rng, &pk, &msg).unwrap();
+    assert!(ct_1.vk != ct_2.vk);
+}
+
+#[test]
+fn chk_rejects_modified_ciphertext() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let chk = Chk::new(LW::new());
+    let (pk, sk) = chk.keygen(&mut rng).unwrap();
+    let msg = Gt::rand(&mut rng);
+
+    let mut ct = chk.encrypt(&mut rng, &pk, &msg).unwrap();
+    ct.ct.msg *= Gt::rand(&mut rng);
+    assert!(matches!(
+        chk.decrypt(&mut rng, &sk, &ct),
+        Err(IbeError::DecryptionFailed)
+    ));
+
+    let mut ct = chk.encrypt(&mut rng, &pk, &msg).unwrap();
+    ct.signature.sigma += Fr::from(1u64);
+    assert!(matches!(
+        chk.decrypt(&mut rng, &sk, &ct),
+        Err(IbeError::DecryptionFailed)
+    ));
+}
+
+#[test]
+fn chk_rejects_resigned_ciphertext() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let chk = Chk::new(LW::new());
+    let (pk, sk) = chk.keygen(&mut rng).unwrap();
+    let msg = Gt::rand(&mut rng);
+    let mut ct = chk.encrypt(&mut rng, &pk, &msg).unwrap();
+
+    // a valid signature under a new key does not help: the HIBE ciphertext names the old one
+    let mut bytes = Vec::new();
+    ct.ct.serialize_compressed(&mut bytes).unwrap();
+    let signing_key = OtsSigningKey::generate(&mut rng);
+    ct.vk = signing_key.verifying_key().clone();
+    ct.signature = signing_key.sign(&bytes);
+    assert!(matches!(
+        chk.decrypt(&mut rng, &sk, &ct),
+        Err(IbeError::IdentityMismatch)
+    ));
+}
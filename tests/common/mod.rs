@@ -1,7 +1,10 @@
 use ark_bls12_381::Fq12 as Gt;
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
 
+use ibe_schemes::pes::fo::{self, Fo, FoScheme};
 use ibe_schemes::pes::{HIBEScheme, IBEScheme};
+use ibe_schemes::{Armored, EnvelopeScheme, IbeError, from_armor, open, open_any, seal, to_armor};
 
 use rand::thread_rng;
 
@@ -10,12 +13,16 @@ fn run_ibe_scheme<T: IBEScheme>(
     msg_in: Gt,
     user_identity: &str,
     ct_identity: &str,
-) -> Option<Gt> {
+) -> Result<Gt, IbeError> {
     let mut rng = thread_rng();
 
     let (msk, mpk) = scheme.setup(&mut rng);
-    let usk = scheme.keygen(&mut rng, &msk, String::from(user_identity));
-    let ct = scheme.encrypt(&mut rng, &msg_in, &mpk, String::from(ct_identity));
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from(user_identity))
+        .unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg_in, &mpk, String::from(ct_identity))
+        .unwrap();
     let msg_out = scheme.decrypt(&usk, &ct);
 
     return msg_out;
@@ -26,7 +33,7 @@ pub fn test_ibe_decrypt_ok<T: IBEScheme>(scheme: T, user_identity: &str, ct_iden
     let msg_in = Gt::rand(&mut rng);
 
     let msg_out = run_ibe_scheme(scheme, msg_in, user_identity, ct_identity);
-    assert!(msg_out.is_some_and(|msg| msg == msg_in));
+    assert!(msg_out.is_ok_and(|msg| msg == msg_in));
 }
 
 pub fn test_ibe_decrypt_fail<T: IBEScheme>(scheme: T, user_identity: &str, ct_identity: &str) {
@@ -34,7 +41,97 @@ pub fn test_ibe_decrypt_fail<T: IBEScheme>(scheme: T, user_identity: &str, ct_id
     let msg_in = Gt::rand(&mut rng);
 
     let msg_out = run_ibe_scheme(scheme, msg_in, user_identity, ct_identity);
-    assert!(msg_out.is_none());
+    assert!(matches!(msg_out, Err(IbeError::IdentityMismatch)));
+}
+
+fn roundtrip<T: CanonicalSerialize + CanonicalDeserialize>(value: &T, compress: Compress) -> T {
+    let mut bytes = Vec::new();
+    value.serialize_with_mode(&mut bytes, compress).unwrap();
+    assert_eq!(bytes.len(), value.serialized_size(compress));
+
+    let decoded = T::deserialize_with_mode(bytes.as_slice(), compress, Validate::Yes).unwrap();
+
+    let mut bytes_again = Vec::new();
+    decoded
+        .serialize_with_mode(&mut bytes_again, compress)
+        .unwrap();
+    assert_eq!(bytes, bytes_again);
+
+    decoded
+}
+
+pub fn test_ibe_serialize_roundtrip<T: IBEScheme>(scheme: T, identity: &str, compress: Compress) {
+    let mut rng = thread_rng();
+    let msg_in = Gt::rand(&mut rng);
+
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msk = roundtrip(&msk, compress);
+    let mpk = roundtrip(&mpk, compress);
+
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from(identity))
+        .unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg_in, &mpk, String::from(identity))
+        .unwrap();
+    let usk = roundtrip(&usk, compress);
+    let ct = roundtrip(&ct, compress);
+
+    let msg_out = scheme.decrypt(&usk, &ct);
+    assert!(msg_out.is_ok_and(|msg| msg == msg_in));
+}
+
+pub fn test_fo_rejects_tampering<S: FoScheme>(scheme: Fo<S>, tamper: impl Fn(&mut fo::CT<S>))
+where
+    S::CT: PartialEq,
+{
+    let mut rng = thread_rng();
+    let msg_in = Gt::rand(&mut rng);
+
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from("alice"))
+        .unwrap();
+    let mut ct = scheme
+        .encrypt(&mut rng, &msg_in, &mpk, String::from("alice"))
+        .unwrap();
+    assert!(scheme.decrypt(&usk, &ct).is_ok_and(|msg| msg == msg_in));
+
+    tamper(&mut ct);
+    assert!(matches!(
+        scheme.decrypt(&usk, &ct),
+        Err(IbeError::DecryptionFailed)
+    ));
+}
+
+pub fn test_fo_envelope_and_armor_roundtrip<S: FoScheme>(scheme: Fo<S>)
+where
+    S::CT: PartialEq,
+    Fo<S>: EnvelopeScheme<Ciphertext = fo::CT<S>>,
+    fo::USK<S>: Armored,
+    fo::CT<S>: Armored,
+{
+    let mut rng = thread_rng();
+    let msg_in = Gt::rand(&mut rng);
+    assert_eq!(Fo::<S>::SCHEME_ID, scheme.name());
+
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg_in, &mpk, String::from("alice"))
+        .unwrap();
+    let bytes = seal(&scheme, &ct).unwrap();
+    assert_eq!(open_any(&bytes).unwrap().scheme_id(), Fo::<S>::SCHEME_ID);
+    assert!(open(&scheme, &bytes).unwrap() == ct);
+
+    let text = to_armor(&ct).unwrap();
+    assert!(text.starts_with(&format!("-----BEGIN {}-----\n", fo::CT::<S>::LABEL)));
+    let ct: fo::CT<S> = from_armor(&text).unwrap();
+
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from("alice"))
+        .unwrap();
+    let usk: fo::USK<S> = from_armor(&to_armor(&usk).unwrap()).unwrap();
+    assert!(scheme.decrypt(&usk, &ct).is_ok_and(|msg| msg == msg_in));
 }
 
 fn parse_identity(id: &str) -> Vec<String> {
@@ -46,15 +143,19 @@ fn run_hibe_scheme<T: HIBEScheme>(
     msg_in: Gt,
     user_identity: &str,
     ct_identity: &str,
-) -> Option<Gt> {
+) -> Result<Gt, IbeError> {
     let mut rng = thread_rng();
 
     let ct_identity = parse_identity(ct_identity);
     let user_identity = parse_identity(user_identity);
 
     let (msk, mpk) = scheme.setup(&mut rng);
-    let ct = scheme.encrypt(&mut rng, &msg_in, &mpk, ct_identity);
-    let usk = scheme.keygen(&mut rng, &msk, user_identity.clone());
+    let ct = scheme
+        .encrypt(&mut rng, &msg_in, &mpk, ct_identity)
+        .unwrap();
+    let usk = scheme
+        .keygen(&mut rng, &msk, user_identity.clone())
+        .unwrap();
     let msg_out = scheme.decrypt(&usk, &ct);
 
     return msg_out;
@@ -65,7 +166,7 @@ pub fn test_hibe_decrypt_ok<T: HIBEScheme>(scheme: T, user_identity: &str, ct_id
     let msg_in = Gt::rand(&mut rng);
 
     let msg_out = run_hibe_scheme(scheme, msg_in, user_identity, ct_identity);
-    assert!(msg_out.is_some_and(|msg| msg == msg_in));
+    assert!(msg_out.is_ok_and(|msg| msg == msg_in));
 }
 
 pub fn test_hibe_decrypt_fail<T: HIBEScheme>(scheme: T, user_identity: &str, ct_identity: &str) {
@@ -73,7 +174,19 @@ pub fn test_hibe_decrypt_fail<T: HIBEScheme>(scheme: T, user_identity: &str, ct_
     let msg_in = Gt::rand(&mut rng);
 
     let msg_out = run_hibe_scheme(scheme, msg_in, user_identity, ct_identity);
-    assert!(msg_out.is_none());
+    assert!(matches!(msg_out, Err(IbeError::IdentityMismatch)));
+}
+
+pub fn test_hibe_empty_identity_fail<T: HIBEScheme>(scheme: T) {
+    let mut rng = thread_rng();
+    let msg_in = Gt::rand(&mut rng);
+
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let usk = scheme.keygen(&mut rng, &msk, Vec::new());
+    let ct = scheme.encrypt(&mut rng, &msg_in, &mpk, Vec::new());
+
+    assert!(matches!(usk, Err(IbeError::EmptyIdentity)));
+    assert!(matches!(ct, Err(IbeError::EmptyIdentity)));
 }
 
 pub fn test_hibe_delegate_ok<T: HIBEScheme>(
@@ -90,23 +203,31 @@ pub fn test_hibe_delegate_ok<T: HIBEScheme>(
 
     let (msk, mpk) = scheme.setup(&mut rng);
 
-    let usk = scheme.keygen(&mut rng, &msk, user_identity.clone());
-    let usk_del = scheme.delegate(&mut rng, &mpk, &usk, String::from(identity_extension));
-    let ct = scheme.encrypt(&mut rng, &msg_in, &mpk, ct_identity);
+    let usk = scheme
+        .keygen(&mut rng, &msk, user_identity.clone())
+        .unwrap();
+    let usk_del = scheme
+        .delegate(&mut rng, &mpk, &usk, String::from(identity_extension))
+        .unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg_in, &mpk, ct_identity)
+        .unwrap();
 
     // actual check: delegated key can decrypt as expected
     let msg_out = scheme.decrypt(&usk_del, &ct);
-    assert!(msg_out.is_some_and(|msg| msg == msg_in));
+    assert!(msg_out.is_ok_and(|msg| msg == msg_in));
 
     // sanity check: superior can always decrypt messages of subordinates
     let msg_out = scheme.decrypt(&usk, &ct);
-    assert!(msg_out.is_some_and(|msg| msg == msg_in));
+    assert!(msg_out.is_ok_and(|msg| msg == msg_in));
 
     // additional check: ensures usk_del != usk which is not caught with the above test
     let parent_identity = user_identity.clone();
-    let ct_parent = scheme.encrypt(&mut rng, &msg_in, &mpk, parent_identity);
+    let ct_parent = scheme
+        .encrypt(&mut rng, &msg_in, &mpk, parent_identity)
+        .unwrap();
     let msg_out = scheme.decrypt(&usk_del, &ct_parent);
-    assert!(msg_out.is_none());
+    assert!(matches!(msg_out, Err(IbeError::IdentityMismatch)));
 }
 
 pub fn test_hibe_delegate_fail<T: HIBEScheme>(
@@ -123,11 +244,50 @@ pub fn test_hibe_delegate_fail<T: HIBEScheme>(
 
     let (msk, mpk) = scheme.setup(&mut rng);
 
-    let usk = scheme.keygen(&mut rng, &msk, user_identity.clone());
-    let usk_del = scheme.delegate(&mut rng, &mpk, &usk, String::from(identity_extension));
-    let ct = scheme.encrypt(&mut rng, &msg_in, &mpk, ct_identity);
+    let usk = scheme
+        .keygen(&mut rng, &msk, user_identity.clone())
+        .unwrap();
+    let usk_del = scheme
+        .delegate(&mut rng, &mpk, &usk, String::from(identity_extension))
+        .unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg_in, &mpk, ct_identity)
+        .unwrap();
 
     // actual check: delegated key can not decrypt as expected
     let msg_out = scheme.decrypt(&usk_del, &ct);
-    assert!(msg_out.is_none());
+    assert!(matches!(msg_out, Err(IbeError::IdentityMismatch)));
+}
+
+pub fn test_hibe_serialize_roundtrip<T: HIBEScheme>(
+    scheme: T,
+    user_identity: &str,
+    ct_identity: &str,
+    identity_extension: &str,
+    compress: Compress,
+) {
+    let mut rng = thread_rng();
+    let msg_in = Gt::rand(&mut rng);
+
+    let user_identity = parse_identity(user_identity);
+    let ct_identity = parse_identity(ct_identity);
+
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msk = roundtrip(&msk, compress);
+    let mpk = roundtrip(&mpk, compress);
+
+    let usk = scheme.keygen(&mut rng, &msk, user_identity).unwrap();
+    let usk = roundtrip(&usk, compress);
+    let usk_del = scheme
+        .delegate(&mut rng, &mpk, &usk, String::from(identity_extension))
+        .unwrap();
+    let usk_del = roundtrip(&usk_del, compress);
+
+    let ct = scheme
+        .encrypt(&mut rng, &msg_in, &mpk, ct_identity)
+        .unwrap();
+    let ct = roundtrip(&ct, compress);
+
+    let msg_out = scheme.decrypt(&usk_del, &ct);
+    assert!(msg_out.is_ok_and(|msg| msg == msg_in));
 }
@@ -0,0 +1,132 @@
+mod common;
+pub use common::*;
+
+use ark_bls12_381::Fq12 as Gt;
+use ark_ff::UniformRand;
+use ark_serialize::{CanonicalSerialize, Compress};
+
+use ibe_schemes::pes::IBEScheme;
+use ibe_schemes::pes::cw13::{self, CW13};
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+#[test]
+fn cw13_minimal_ok() {
+    let scheme = CW13::new();
+    test_ibe_decrypt_ok(scheme, "A", "A");
+}
+
+#[test]
+fn cw13_longer_ok() {
+    let scheme = CW13::new();
+    test_ibe_decrypt_ok(scheme, "ABCDEFG", "ABCDEFG");
+}
+
+// anonymous ciphertexts cannot be matched against the key, so decryption yields garbage
+#[test]
+fn cw13_other_identity_fails() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scheme = CW13::new();
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from("ABCDeFG"))
+        .unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("ABCDEFG"))
+        .unwrap();
+    assert_ne!(scheme.decrypt(&usk, &ct).unwrap(), msg);
+}
+
+#[test]
+fn cw13_serialize_compressed_ok() {
+    let scheme = CW13::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::Yes);
+}
+
+#[test]
+fn cw13_serialize_uncompressed_ok() {
+    let scheme = CW13::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::No);
+}
+
+#[test]
+fn cw13_ciphertext_size_is_independent_of_identity() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scheme = CW13::new();
+    let (_, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    let short = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("A"))
+        .unwrap();
+    let long = scheme
+        .encrypt(&mut rng, &msg, &mpk, "A".repeat(1000))
+        .unwrap();
+    assert_eq!(short.compressed_size(), long.compressed_size());
+}
+
+#[test]
+fn cw13_rejects_malformed_key() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scheme = CW13::new();
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    let mut usk = scheme.keygen(&mut rng, &msk, String::from("A")).unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("A"))
+        .unwrap();
+    usk.k_0.pop();
+    assert!(scheme.decrypt(&usk, &ct).is_err());
+}
+
+#[test]
+fn cw13_envelope_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scheme = CW13::new();
+    assert_eq!(CW13::SCHEME_ID, scheme.name());
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("alice"))
+        .unwrap();
+
+    let bytes = seal(&scheme, &ct).unwrap();
+    let AnyCiphertext::Cw13(opened, opened_ct) = open_any(&bytes).unwrap() else {
+        panic!("expected a cw13 ciphertext");
+    };
+    assert!(opened_ct == ct);
+
+    let usk = opened
+        .keygen(&mut rng, &msk, String::from("alice"))
+        .unwrap();
+    assert_eq!(opened.decrypt(&usk, &opened_ct).unwrap(), msg);
+}
+
+#[test]
+fn cw13_armor_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scheme = CW13::new();
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    let text = to_armor(&mpk).unwrap();
+    assert!(text.starts_with("-----BEGIN IBE CW13 PUBLIC KEY-----\n"));
+    let mpk: cw13::MPK = from_armor(&text).unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("alice"))
+        .unwrap();
+    let ct: cw13::CT = from_armor(&to_armor(&ct).unwrap()).unwrap();
+
+    let msk: cw13::MSK = from_armor(&to_armor(&msk).unwrap()).unwrap();
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from("alice"))
+        .unwrap();
+    let usk: cw13::USK = from_armor(&to_armor(&usk).unwrap()).unwrap();
+    assert_eq!(scheme.decrypt(&usk, &ct).unwrap(), msg);
+}
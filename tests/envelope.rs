@@ -0,0 +1,154 @@
+use ark_bls12_381::Fq12 as Gt;
+use ark_ff::UniformRand;
+use rand::thread_rng;
+
+use ibe_schemes::pes::bf::BF;
+use ibe_schemes::pes::hiberla_enc::HiberlaEnc;
+use ibe_schemes::pes::lw::LW;
+use ibe_schemes::pes::{HIBEScheme, IBEScheme};
+use ibe_schemes::*;
+
+fn identity(id: &str) -> Vec<String> {
+    id.split('.').map(String::from).collect()
+}
+
+#[test]
+fn envelope_scheme_ids_match_names() {
+    assert_eq!(BF::SCHEME_ID, BF::new().name());
+    assert_eq!(LW::SCHEME_ID, LW::new().name());
+    assert_eq!(HiberlaEnc::SCHEME_ID, HiberlaEnc::new(2).name());
+}
+
+#[test]
+fn envelope_bf_roundtrip_ok() {
+    let mut rng = thread_rng();
+    let scheme = BF::new();
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("A"))
+        .unwrap();
+    let bytes = seal(&scheme, &ct).unwrap();
+
+    let header = parse_header(&bytes).unwrap().0;
+    assert_eq!(header.version, ENVELOPE_VERSION);
+    assert_eq!(header.scheme_id, "bf");
+    assert_eq!(header.curve_id, CURVE_BLS12_381);
+
+    let ct = open(&scheme, &bytes).unwrap();
+    let usk = scheme.keygen(&mut rng, &msk, String::from("A")).unwrap();
+    assert_eq!(scheme.decrypt(&usk, &ct).unwrap(), msg);
+}
+
+#[test]
+fn envelope_open_any_dispatches_hiberla() {
+    let mut rng = thread_rng();
+    let scheme = HiberlaEnc::new(2);
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, identity("A.B.C"))
+        .unwrap();
+    let bytes = seal(&scheme, &ct).unwrap();
+
+    let AnyCiphertext::HiberlaEnc(opened, ct) = open_any(&bytes).unwrap() else {
+        panic!("expected a hiberla_enc ciphertext");
+    };
+    assert_eq!(opened.l, 2);
+
+    let usk = opened.keygen(&mut rng, &msk, identity("A.B.C")).unwrap();
+    assert_eq!(opened.decrypt(&usk, &ct).unwrap(), msg);
+}
+
+#[test]
+fn envelope_open_any_dispatches_ibkem1() {
+    let mut rng = thread_rng();
+    let ibkem = IBKEM1::new(2, 128);
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, id) = generate_email_and_hash_identity(128);
+
+    let (ct, key) = ibkem.encrypt(&mut rng, &pk, &id).unwrap();
+    let bytes = seal(&ibkem, &ct).unwrap();
+
+    let any = open_any(&bytes).unwrap();
+    assert_eq!(any.scheme_id(), "ibkem1");
+    let AnyCiphertext::Ibkem1(opened, ct) = any else {
+        panic!("expected an ibkem1 ciphertext");
+    };
+    assert_eq!((opened.k, opened.msg_len), (2, 128));
+
+    let usk = ibkem.extract(&mut rng, &sk, &id).unwrap();
+    assert_eq!(ibkem.decrypt(&usk, &ct).unwrap(), key);
+}
+
+#[test]
+fn envelope_rejects_other_scheme() {
+    let mut rng = thread_rng();
+    let scheme = BF::new();
+    let (_, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("A"))
+        .unwrap();
+    let bytes = seal(&scheme, &ct).unwrap();
+
+    assert!(matches!(
+        open(&LW::new(), &bytes),
+        Err(EnvelopeError::SchemeMismatch { .. })
+    ));
+}
+
+#[test]
+fn envelope_rejects_other_params() {
+    let mut rng = thread_rng();
+    let scheme = HiberlaEnc::new(2);
+    let (_, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, identity("A.B"))
+        .unwrap();
+    let bytes = seal(&scheme, &ct).unwrap();
+
+    assert!(matches!(
+        open(&HiberlaEnc::new(3), &bytes),
+        Err(EnvelopeError::ParamsMismatch { .. })
+    ));
+}
+
+#[test]
+fn envelope_rejects_malformed_header() {
+    let mut rng = thread_rng();
+    let scheme = BF::new();
+    let (_, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("A"))
+        .unwrap();
+    let bytes = seal(&scheme, &ct).unwrap();
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] ^= 1;
+    assert!(matches!(open_any(&bad_magic), Err(EnvelopeError::BadMagic)));
+
+    let mut bad_version = bytes.clone();
+    bad_version[4] = ENVELOPE_VERSION + 1;
+    assert!(matches!(
+        open_any(&bad_version),
+        Err(EnvelopeError::UnsupportedVersion(_))
+    ));
+
+    // "bf" -> "bx"
+    let mut unknown = bytes.clone();
+    unknown[7] = b'x';
+    assert!(matches!(
+        open_any(&unknown),
+        Err(EnvelopeError::UnknownScheme(_))
+    ));
+
+    assert!(matches!(
+        open_any(&bytes[..bytes.len() - 1]),
+        Err(EnvelopeError::Malformed(_))
+    ));
+}
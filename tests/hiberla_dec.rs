@@ -1,6 +1,8 @@
 mod common;
 pub use common::*;
 
+use ark_serialize::Compress;
+
 use ibe_schemes::pes::hiberla_dec::HiberlaDec;
 
 #[test]
@@ -122,6 +124,13 @@ fn hiberla_inferior_large_parition_fail() {
     test_hibe_decrypt_fail(scheme, "A.B.C.D", "A.B.C");
 }
 
+#[test]
+fn hiberla_empty_identity_fail() {
+    const PARTITION_SIZE: usize = 3;
+    let scheme = HiberlaDec::new(PARTITION_SIZE);
+    test_hibe_empty_identity_fail(scheme);
+}
+
 #[test]
 fn hiberla_delegate_single_partition_space_left_ok() {
     const PARTITION_SIZE: usize = 6;
@@ -170,3 +179,17 @@ fn hiberla_delegate_hierarchy_mismatch_fail() {
     let scheme = HiberlaDec::new(PARTITION_SIZE);
     test_hibe_delegate_fail(scheme, "A.b.C", "A.B.C.D", "D");
 }
+
+#[test]
+fn hiberla_serialize_compressed_ok() {
+    const PARTITION_SIZE: usize = 2;
+    let scheme = HiberlaDec::new(PARTITION_SIZE);
+    test_hibe_serialize_roundtrip(scheme, "A.B.C", "A.B.C.D.E", "D", Compress::Yes);
+}
+
+#[test]
+fn hiberla_serialize_uncompressed_ok() {
+    const PARTITION_SIZE: usize = 2;
+    let scheme = HiberlaDec::new(PARTITION_SIZE);
+    test_hibe_serialize_roundtrip(scheme, "A.B.C", "A.B.C.D.E", "D", Compress::No);
+}
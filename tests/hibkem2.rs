@@ -1,218 +1,411 @@
 use ibe_schemes::*;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+/// [`AffineMacLevels2`] on the complement of every identity: it selects `x_{i,j,1-b}` for a bit
+/// `b`, so it is a second MAC to plug into the generic HIBKEM2.
+struct ComplementMac(AffineMacLevels2);
+
+fn complement(message: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    message
+        .iter()
+        .map(|level| level.iter().map(|byte| !byte).collect())
+        .collect()
+}
+
+impl LevelAffineMac for ComplementMac {
+    type SecretKey = AffineMacLevels2SecretKey;
+    type Tag = AffineMacLevels2Tag;
+    type TagContext = AffineMacLevels2TagContext;
+
+    fn k(&self) -> usize {
+        self.0.k
+    }
+
+    fn max_levels(&self) -> usize {
+        self.0.max_levels
+    }
+
+    fn identity_len(&self) -> usize {
+        self.0.identity_len
+    }
+
+    fn n(&self) -> usize {
+        self.0.n()
+    }
+
+    fn eta(&self) -> usize {
+        self.0.eta()
+    }
+
+    fn l(&self, level: usize) -> usize {
+        self.0.l(level)
+    }
+
+    fn f(&self, level: usize, message: &[Vec<u8>]) -> Vector {
+        self.0.f(level, &complement(message))
+    }
+
+    fn b<'a>(&self, sk: &'a AffineMacLevels2SecretKey) -> &'a Matrix<FieldElement> {
+        self.0.b(sk)
+    }
+
+    fn x_matrices<'a>(
+        &self,
+        sk: &'a AffineMacLevels2SecretKey,
+        level: usize,
+    ) -> &'a [Vec<Matrix<FieldElement>>] {
+        self.0.x_matrices(sk, level)
+    }
+
+    fn x_prime<'a>(&self, sk: &'a AffineMacLevels2SecretKey) -> &'a Vector {
+        self.0.x_prime(sk)
+    }
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> AffineMacLevels2SecretKey {
+        self.0.gen_mac(rng)
+    }
+
+    fn tag_with_context<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &AffineMacLevels2SecretKey,
+        message: &[Vec<u8>],
+    ) -> Result<(AffineMacLevels2Tag, AffineMacLevels2TagContext), IbeError> {
+        self.0.tag_with_context(rng, sk, &complement(message))
+    }
+
+    fn verify(
+        &self,
+        sk: &AffineMacLevels2SecretKey,
+        message: &[Vec<u8>],
+        tag: &AffineMacLevels2Tag,
+    ) -> Result<(), IbeError> {
+        self.0.verify(sk, &complement(message), tag)
+    }
+}
 
 #[test]
 fn hibkem2_enc_dec_level_1() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, _dk, sk) = hibkem.setup();
+    let (pk, _dk, sk) = hibkem.setup(&mut rng).unwrap();
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l1 = id[0..1].to_vec();
-    let usk = hibkem.extract(&sk, &id_l1);
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_l1);
-    let k_dec = hibkem.decrypt(&usk, &ct);
+    let usk = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l1).unwrap();
+    let k_dec = hibkem.decrypt(&usk, &ct).unwrap();
     assert_eq!(k_enc, k_dec);
 }
 
 #[test]
 fn hibkem2_enc_dec_level_2() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, _dk, sk) = hibkem.setup();
+    let (pk, _dk, sk) = hibkem.setup(&mut rng).unwrap();
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l2 = id[0..2].to_vec();
-    let usk = hibkem.extract(&sk, &id_l2);
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_l2);
-    let k_dec = hibkem.decrypt(&usk, &ct);
+    let usk = hibkem.extract(&mut rng, &sk, &id_l2).unwrap();
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l2).unwrap();
+    let k_dec = hibkem.decrypt(&usk, &ct).unwrap();
     assert_eq!(k_enc, k_dec);
 }
 
 #[test]
 fn hibkem2_enc_dec_level_3() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, _dk, sk) = hibkem.setup();
+    let (pk, _dk, sk) = hibkem.setup(&mut rng).unwrap();
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l3 = id[0..3].to_vec();
-    let usk = hibkem.extract(&sk, &id_l3);
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_l3);
-    let k_dec = hibkem.decrypt(&usk, &ct);
+    let usk = hibkem.extract(&mut rng, &sk, &id_l3).unwrap();
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l3).unwrap();
+    let k_dec = hibkem.decrypt(&usk, &ct).unwrap();
     assert_eq!(k_enc, k_dec);
 }
 
 #[test]
 fn hibkem2_enc_dec_level_4() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, _dk, sk) = hibkem.setup();
+    let (pk, _dk, sk) = hibkem.setup(&mut rng).unwrap();
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l = id[0..4].to_vec();
-    let usk = hibkem.extract(&sk, &id_l);
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_l);
-    let k_dec = hibkem.decrypt(&usk, &ct);
+    let usk = hibkem.extract(&mut rng, &sk, &id_l).unwrap();
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l).unwrap();
+    let k_dec = hibkem.decrypt(&usk, &ct).unwrap();
     assert_eq!(k_enc, k_dec);
 }
 
 #[test]
 fn hibkem2_delegation_l1_to_l2() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, dk, sk) = hibkem.setup();
+    let (pk, dk, sk) = hibkem.setup(&mut rng).unwrap();
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l1 = id[0..1].to_vec();
-    let usk1 = hibkem.extract(&sk, &id_l1);
-    let usk2 = hibkem.delegate(&dk, &usk1, &id_l1, id[1].clone());
+    let usk1 = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
+    let usk2 = hibkem
+        .delegate(&mut rng, &dk, &usk1, &id_l1, id[1].clone())
+        .unwrap();
 
     let id_l2 = id[0..2].to_vec();
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_l2);
-    let k_dec = hibkem.decrypt(&usk2, &ct);
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l2).unwrap();
+    let k_dec = hibkem.decrypt(&usk2, &ct).unwrap();
     assert_eq!(k_enc, k_dec);
 }
 
 #[test]
 fn hibkem2_delegation_chained_l1_to_l4() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, dk, sk) = hibkem.setup();
+    let (pk, dk, sk) = hibkem.setup(&mut rng).unwrap();
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l1 = id[0..1].to_vec();
-    let usk1 = hibkem.extract(&sk, &id_l1);
+    let usk1 = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
 
     let id_l2 = id[0..2].to_vec();
-    let usk2 = hibkem.delegate(&dk, &usk1, &id_l1, id[1].clone());
+    let usk2 = hibkem
+        .delegate(&mut rng, &dk, &usk1, &id_l1, id[1].clone())
+        .unwrap();
 
     let id_l3 = id[0..3].to_vec();
-    let usk3 = hibkem.delegate(&dk, &usk2, &id_l2, id[2].clone());
+    let usk3 = hibkem
+        .delegate(&mut rng, &dk, &usk2, &id_l2, id[2].clone())
+        .unwrap();
 
-    let usk4 = hibkem.delegate(&dk, &usk3, &id_l3, id[3].clone());
+    let usk4 = hibkem
+        .delegate(&mut rng, &dk, &usk3, &id_l3, id[3].clone())
+        .unwrap();
 
     let id_l4 = id[0..4].to_vec();
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_l4);
-    let k_dec = hibkem.decrypt(&usk4, &ct);
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l4).unwrap();
+    let k_dec = hibkem.decrypt(&usk4, &ct).unwrap();
     assert_eq!(k_enc, k_dec);
 }
 
 #[test]
 fn hibkem2_extract_and_delegated_key_both_decrypt() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, dk, sk) = hibkem.setup();
+    let (pk, dk, sk) = hibkem.setup(&mut rng).unwrap();
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l2 = id[0..2].to_vec();
 
-    let usk_direct = hibkem.extract(&sk, &id_l2);
+    let usk_direct = hibkem.extract(&mut rng, &sk, &id_l2).unwrap();
 
     let id_l1 = id[0..1].to_vec();
-    let usk1 = hibkem.extract(&sk, &id_l1);
-    let usk_delegated = hibkem.delegate(&dk, &usk1, &id_l1, id[1].clone());
-
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_l2);
-    assert_eq!(k_enc, hibkem.decrypt(&usk_direct, &ct));
-    assert_eq!(k_enc, hibkem.decrypt(&usk_delegated, &ct));
+    let usk1 = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
+    let usk_delegated = hibkem
+        .delegate(&mut rng, &dk, &usk1, &id_l1, id[1].clone())
+        .unwrap();
+
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l2).unwrap();
+    assert_eq!(k_enc, hibkem.decrypt(&usk_direct, &ct).unwrap());
+    assert_eq!(k_enc, hibkem.decrypt(&usk_delegated, &ct).unwrap());
 }
 
 #[test]
 fn hibkem2_wrong_identity_cannot_decrypt() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, _dk, sk) = hibkem.setup();
+    let (pk, _dk, sk) = hibkem.setup(&mut rng).unwrap();
     let (_, id_a) = generate_hierarchical_identity(max_levels, identity_len);
     let (_, id_b) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l2_a = id_a[0..2].to_vec();
-    let usk_a = hibkem.extract(&sk, &id_l2_a);
+    let usk_a = hibkem.extract(&mut rng, &sk, &id_l2_a).unwrap();
 
     let id_wrong = vec![id_a[0].clone(), id_b[1].clone()];
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_wrong);
-    let k_dec = hibkem.decrypt(&usk_a, &ct);
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_wrong).unwrap();
+    let k_dec = hibkem.decrypt(&usk_a, &ct).unwrap();
     assert_ne!(k_enc, k_dec);
 }
 
 #[test]
-#[should_panic]
 fn hibkem2_parent_cannot_decrypt_child_ciphertext() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
 
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, _, sk) = hibkem.setup();
+    let (pk, _, sk) = hibkem.setup(&mut rng).unwrap();
 
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l1 = id[0..1].to_vec();
     let id_l2 = id[0..2].to_vec();
 
-    let usk1 = hibkem.extract(&sk, &id_l1);
+    let usk1 = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
 
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_l2);
-    let k_dec = hibkem.decrypt(&usk1, &ct);
+    let (_, ct) = hibkem.encrypt(&mut rng, &pk, &id_l2).unwrap();
 
-    assert_ne!(k_enc, k_dec);
+    assert!(matches!(
+        hibkem.decrypt(&usk1, &ct),
+        Err(IbeError::DimensionMismatch { .. })
+    ));
 }
 
 #[test]
-#[should_panic]
 fn hibkem2_wrong_depth_decrypt_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
 
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, _, sk) = hibkem.setup();
+    let (pk, _, sk) = hibkem.setup(&mut rng).unwrap();
 
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l1 = id[0..1].to_vec();
     let id_l2 = id[0..2].to_vec();
 
-    let usk = hibkem.extract(&sk, &id_l1);
+    let usk = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
 
-    let (k_enc, ct) = hibkem.encrypt(&pk, &id_l2);
+    let (_, ct) = hibkem.encrypt(&mut rng, &pk, &id_l2).unwrap();
 
-    let k_dec = hibkem.decrypt(&usk, &ct);
-
-    assert_ne!(k_enc, k_dec);
+    assert!(matches!(
+        hibkem.decrypt(&usk, &ct),
+        Err(IbeError::DimensionMismatch { .. })
+    ));
 }
 
 #[test]
 fn hibkem2_encryption_randomness() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let max_levels = 4;
     let identity_len = 8;
 
     let hibkem = HIBKEM2::new(k, max_levels, identity_len);
-    let (pk, _, _) = hibkem.setup();
+    let (pk, _, _) = hibkem.setup(&mut rng).unwrap();
 
     let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
 
     let id_l = id[0..2].to_vec();
 
-    let (k1, ct1) = hibkem.encrypt(&pk, &id_l);
-    let (k2, ct2) = hibkem.encrypt(&pk, &id_l);
+    let (k1, ct1) = hibkem.encrypt(&mut rng, &pk, &id_l).unwrap();
+    let (k2, ct2) = hibkem.encrypt(&mut rng, &pk, &id_l).unwrap();
 
     assert_ne!(ct1.c0_g1, ct2.c0_g1);
     assert_ne!(k1, k2);
 }
+
+#[test]
+fn hibkem2_serialize_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let k = 2;
+    let max_levels = 3;
+    let identity_len = 8;
+
+    let hibkem = HIBKEM2::new(k, max_levels, identity_len);
+    let (pk, dk, sk) = hibkem.setup(&mut rng).unwrap();
+    let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
+
+    let pk = HIBKEM2PublicKey::from_bytes(&hibkem, &pk.to_bytes(&hibkem).unwrap()).unwrap();
+    let dk = HIBKEM2DelegationKey::from_bytes(&hibkem, &dk.to_bytes(&hibkem).unwrap()).unwrap();
+    let sk = HIBKEM2SecretKey::from_bytes(&hibkem, &sk.to_bytes(&hibkem).unwrap()).unwrap();
+
+    let id_l1 = id[0..1].to_vec();
+    let usk1 = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
+    let usk1 = HIBKEM2UserSecretKey::from_bytes(&hibkem, &usk1.to_bytes(&hibkem).unwrap()).unwrap();
+
+    let usk2 = hibkem
+        .delegate(&mut rng, &dk, &usk1, &id_l1, id[1].clone())
+        .unwrap();
+    let usk2_bytes = usk2.to_bytes(&hibkem).unwrap();
+    let usk2 = HIBKEM2UserSecretKey::from_bytes(&hibkem, &usk2_bytes).unwrap();
+    assert_eq!(usk2.to_bytes(&hibkem).unwrap(), usk2_bytes);
+    assert_eq!(usk2.t_g2.len(), 2);
+
+    let id_l2 = id[0..2].to_vec();
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l2).unwrap();
+    let ct = HIBKEM2Ciphertext::from_bytes(&hibkem, &ct.to_bytes(&hibkem).unwrap()).unwrap();
+    assert_eq!(ct.c1_g1.len(), 2);
+    assert_eq!(k_enc, hibkem.decrypt(&usk2, &ct).unwrap());
+}
+
+#[test]
+fn hibkem2_deserialize_rejects_other_instance() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let hibkem = HIBKEM2::new(2, 3, 8);
+    let other = HIBKEM2::new(2, 3, 16);
+    let (pk, _, _) = hibkem.setup(&mut rng).unwrap();
+    let (_, id) = generate_hierarchical_identity(3, 8);
+    let (_, ct) = hibkem.encrypt(&mut rng, &pk, &id[0..2]).unwrap();
+
+    assert!(HIBKEM2PublicKey::from_bytes(&other, &pk.to_bytes(&hibkem).unwrap()).is_err());
+    assert!(HIBKEM2Ciphertext::from_bytes(&other, &ct.to_bytes(&hibkem).unwrap()).is_err());
+}
+
+#[test]
+fn hibkem2_deserialize_rejects_wrong_kind() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let hibkem = HIBKEM2::new(2, 3, 8);
+    let (pk, _, sk) = hibkem.setup(&mut rng).unwrap();
+    let (_, id) = generate_hierarchical_identity(3, 8);
+    let usk = hibkem.extract(&mut rng, &sk, &id[0..1]).unwrap();
+    let (_, ct) = hibkem.encrypt(&mut rng, &pk, &id[0..1]).unwrap();
+
+    assert!(HIBKEM2Ciphertext::from_bytes(&hibkem, &usk.to_bytes(&hibkem).unwrap()).is_err());
+    assert!(HIBKEM2UserSecretKey::from_bytes(&hibkem, &ct.to_bytes(&hibkem).unwrap()).is_err());
+}
+
+#[test]
+fn hibkem2_with_another_level_mac() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let max_levels = 3;
+    let identity_len = 8;
+    let mac = ComplementMac(AffineMacLevels2::new(2, max_levels, identity_len));
+    let hibkem = HIBKEM2::with_mac(mac);
+    let (pk, dk, sk) = hibkem.setup(&mut rng).unwrap();
+    let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
+
+    let id_l1 = id[0..1].to_vec();
+    let usk1 = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
+    let usk2 = hibkem
+        .delegate(&mut rng, &dk, &usk1, &id_l1, id[1].clone())
+        .unwrap();
+
+    let id_l2 = id[0..2].to_vec();
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l2).unwrap();
+    assert_eq!(k_enc, hibkem.decrypt(&usk2, &ct).unwrap());
+
+    let (k_enc, ct) = hibkem.encrypt(&mut rng, &pk, &id_l1).unwrap();
+    assert_eq!(k_enc, hibkem.decrypt(&usk1, &ct).unwrap());
+}
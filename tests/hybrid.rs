@@ -0,0 +1,202 @@
+use ibe_schemes::pes::bb::BB;
+use ibe_schemes::pes::bf::BF;
+use ibe_schemes::pes::hiberla_enc::HiberlaEnc;
+use ibe_schemes::pes::kem::PesKem;
+use ibe_schemes::pes::lw::LW;
+use ibe_schemes::pes::sakai_kasahara::SakaiKasaharaKem;
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+const MESSAGE: &[u8] = b"the quarterly report is attached";
+
+fn ibkem_roundtrip<K>(kem: K)
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+{
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, &identity).unwrap();
+
+    let blob = encrypt_bytes(&kem, &mut rng, &pk, &identity, MESSAGE).unwrap();
+    assert_eq!(recipient(&blob).unwrap(), vec![identity]);
+    assert_eq!(decrypt_bytes(&kem, &pk, &usk, &blob).unwrap(), MESSAGE);
+}
+
+fn hibkem_roundtrip<K>(kem: K)
+where
+    K: HIBKEM + EnvelopeScheme<Ciphertext = <K as HIBKEM>::CT>,
+{
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (pk, dk, sk) = HIBKEM::setup(&kem, &mut rng).unwrap();
+    let (_, id) = generate_hierarchical_identity(2, 8);
+    let usk_l1 = HIBKEM::extract(&kem, &mut rng, &sk, &id[0..1]).unwrap();
+    let usk_l2 = HIBKEM::delegate(&kem, &mut rng, &dk, &usk_l1, &id[0..1], id[1].clone()).unwrap();
+
+    let blob = encrypt_bytes_hierarchical(&kem, &mut rng, &pk, &id, MESSAGE).unwrap();
+    assert_eq!(recipient(&blob).unwrap(), id);
+    assert_eq!(
+        decrypt_bytes_hierarchical(&kem, &usk_l2, &blob).unwrap(),
+        MESSAGE
+    );
+}
+
+#[test]
+fn hybrid_ibkem1() {
+    ibkem_roundtrip(IBKEM1::new(2, 128));
+}
+
+#[test]
+fn hybrid_ibkem2() {
+    ibkem_roundtrip(IBKEM2::new(2, 128, 128));
+}
+
+#[test]
+fn hybrid_bkp14() {
+    ibkem_roundtrip(BKP14::with_mac(AffineMacNr::new(2, 128)));
+}
+
+#[test]
+fn hybrid_pes_bf() {
+    ibkem_roundtrip(PesKem::new(BF::new()));
+}
+
+#[test]
+fn hybrid_pes_bb() {
+    ibkem_roundtrip(PesKem::new(BB::new()));
+}
+
+#[test]
+fn hybrid_sakai_kasahara_kem() {
+    ibkem_roundtrip(SakaiKasaharaKem::new());
+}
+
+#[test]
+fn hybrid_hibkem1() {
+    hibkem_roundtrip(HIBKEM1::new(2, 2, 8));
+}
+
+#[test]
+fn hybrid_hibkem2() {
+    hibkem_roundtrip(HIBKEM2::new(2, 2, 8));
+}
+
+#[test]
+fn hybrid_pes_lw() {
+    hibkem_roundtrip(PesKem::new(LW::new()));
+}
+
+#[test]
+fn hybrid_pes_hiberla_enc() {
+    hibkem_roundtrip(PesKem::new(HiberlaEnc::new(2)));
+}
+
+#[test]
+fn hybrid_message_lengths() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = PesKem::new(BF::new());
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, b"alice").unwrap();
+
+    for len in [0, 1, 64, 100_000] {
+        let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let blob = encrypt_bytes(&kem, &mut rng, &pk, b"alice", &plaintext).unwrap();
+        assert_eq!(decrypt_bytes(&kem, &pk, &usk, &blob).unwrap(), plaintext);
+    }
+}
+
+#[test]
+fn hybrid_detects_tampering_everywhere() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = IBKEM1::new(2, 128);
+    let (pk, sk) = kem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let usk = kem.extract(&mut rng, &sk, &identity).unwrap();
+    let blob = encrypt_bytes(&kem, &mut rng, &pk, &identity, MESSAGE).unwrap();
+
+    // covers magic, identity, KEM envelope, nonce, AEAD payload and tag
+    for i in 0..blob.len() {
+        let mut tampered = blob.clone();
+        tampered[i] ^= 0x01;
+        assert!(
+            decrypt_bytes(&kem, &pk, &usk, &tampered).is_err(),
+            "flipping byte {} went unnoticed",
+            i
+        );
+    }
+}
+
+#[test]
+fn hybrid_detects_payload_tampering() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = PesKem::new(BB::new());
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, b"alice").unwrap();
+    let blob = encrypt_bytes(&kem, &mut rng, &pk, b"alice", MESSAGE).unwrap();
+
+    let mut tampered = blob.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 0x80;
+    assert!(matches!(
+        decrypt_bytes(&kem, &pk, &usk, &tampered),
+        Err(IbeError::DecryptionFailed)
+    ));
+
+    let mut tampered = blob.clone();
+    tampered[last - HYBRID_TAG_LEN] ^= 0x80;
+    assert!(matches!(
+        decrypt_bytes(&kem, &pk, &usk, &tampered),
+        Err(IbeError::DecryptionFailed)
+    ));
+}
+
+#[test]
+fn hybrid_rejects_truncation_and_extension() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = PesKem::new(BF::new());
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, b"alice").unwrap();
+    let blob = encrypt_bytes(&kem, &mut rng, &pk, b"alice", MESSAGE).unwrap();
+
+    for len in [0, 4, 5, blob.len() - HYBRID_TAG_LEN, blob.len() - 1] {
+        assert!(decrypt_bytes(&kem, &pk, &usk, &blob[..len]).is_err());
+    }
+    let mut extended = blob.clone();
+    extended.push(0);
+    assert!(decrypt_bytes(&kem, &pk, &usk, &extended).is_err());
+}
+
+#[test]
+fn hybrid_wrong_key_fails() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = IBKEM1::new(2, 128);
+    let (pk, sk) = kem.setup(&mut rng).unwrap();
+    let (_, alice) = generate_email_and_hash_identity(128);
+    let mut bob = alice.clone();
+    bob[0] ^= 1;
+    let usk_bob = kem.extract(&mut rng, &sk, &bob).unwrap();
+
+    let blob = encrypt_bytes(&kem, &mut rng, &pk, &alice, MESSAGE).unwrap();
+    assert!(matches!(
+        decrypt_bytes(&kem, &pk, &usk_bob, &blob),
+        Err(IbeError::DecryptionFailed)
+    ));
+}
+
+#[test]
+fn hybrid_rejects_blob_of_other_scheme() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let bf = PesKem::new(BF::new());
+    let bb = PesKem::new(BB::new());
+    let (pk, _) = IBKEM::setup(&bf, &mut rng).unwrap();
+    let (pk_bb, sk_bb) = IBKEM::setup(&bb, &mut rng).unwrap();
+    let usk_bb = IBKEM::extract(&bb, &mut rng, &sk_bb, b"alice").unwrap();
+
+    let blob = encrypt_bytes(&bf, &mut rng, &pk, b"alice", MESSAGE).unwrap();
+    assert!(matches!(
+        decrypt_bytes(&bb, &pk_bb, &usk_bb, &blob),
+        Err(IbeError::Envelope(EnvelopeError::SchemeMismatch { .. }))
+    ));
+}
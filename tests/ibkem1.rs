@@ -1,34 +1,267 @@
+use ark_bls12_381::G2Projective as G2;
 use ibe_schemes::*;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
 
 #[test]
 fn test_ibkem1_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let ibkem = IBKEM1::new(k, msg_len);
-    let (pk, sk) = ibkem.setup();
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
 
     let (_, identity) = generate_email_and_hash_identity(128);
 
-    let usk = ibkem.extract(&sk, &identity);
-    let (ct, k) = ibkem.encrypt(&pk, &identity);
-    let k_dec = ibkem.decrypt(&usk, &ct);
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    let k_dec = ibkem.decrypt(&usk, &ct).unwrap();
 
     assert_eq!(k_dec, k)
 }
 
 #[test]
 fn test_ibkem1_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let ibkem = IBKEM1::new(k, msg_len);
-    let (pk, sk) = ibkem.setup();
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
 
     let (_, identity) = generate_email_and_hash_identity(128);
-    let (ct, k) = ibkem.encrypt(&pk, &identity);
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
 
     let (_, new_identity) = generate_email_and_hash_identity(128);
-    let new_usk = ibkem.extract(&sk, &new_identity);
-    let k_dec = ibkem.decrypt(&new_usk, &ct);
+    let new_usk = ibkem.extract(&mut rng, &sk, &new_identity).unwrap();
+    let k_dec = ibkem.decrypt(&new_usk, &ct).unwrap();
 
     assert_ne!(k_dec, k);
 }
+
+#[test]
+fn test_ibkem1_seeded_rng_is_reproducible() {
+    let ibkem = IBKEM1::new(2, 128);
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    let run = || {
+        let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+        let (pk, _) = ibkem.setup(&mut rng).unwrap();
+        let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+        (
+            pk.to_bytes(&ibkem).unwrap(),
+            ct.to_bytes(&ibkem).unwrap(),
+            k,
+        )
+    };
+
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn test_ibkem1_rejects_wrong_identity_length() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM1::new(2, 128);
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+
+    let (_, short_identity) = generate_email_and_hash_identity(64);
+
+    assert!(matches!(
+        ibkem.extract(&mut rng, &sk, &short_identity),
+        Err(IbeError::InvalidIdentityLength {
+            expected: 128,
+            found: 64
+        })
+    ));
+    assert!(matches!(
+        ibkem.encrypt(&mut rng, &pk, &short_identity),
+        Err(IbeError::InvalidIdentityLength { .. })
+    ));
+}
+
+#[test]
+fn test_ibkem1_serialize_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM1::new(2, 128);
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    let pk = IBKEM1PublicKey::from_bytes(&ibkem, &pk.to_bytes(&ibkem).unwrap()).unwrap();
+    let sk = IBKEM1SecretKey::from_bytes(&ibkem, &sk.to_bytes(&ibkem).unwrap()).unwrap();
+
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let usk = IBKEM1UserSecretKey::from_bytes(&ibkem, &usk.to_bytes(&ibkem).unwrap()).unwrap();
+
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    let ct_bytes = ct.to_bytes(&ibkem).unwrap();
+    let ct = IBKEM1Ciphertext::from_bytes(&ibkem, &ct_bytes).unwrap();
+
+    assert_eq!(ct.to_bytes(&ibkem).unwrap(), ct_bytes);
+    assert_eq!(ibkem.decrypt(&usk, &ct).unwrap(), k);
+}
+
+#[test]
+fn test_ibkem1_deserialize_rejects_other_instance() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM1::new(2, 128);
+    let other = IBKEM1::new(1, 128);
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+
+    assert!(IBKEM1PublicKey::from_bytes(&other, &pk.to_bytes(&ibkem).unwrap()).is_err());
+    assert!(IBKEM1Ciphertext::from_bytes(&other, &ct.to_bytes(&ibkem).unwrap()).is_err());
+}
+
+#[test]
+fn test_ibkem1_deserialize_rejects_wrong_kind() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM1::new(2, 128);
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+
+    let mut ct_bytes = ct.to_bytes(&ibkem).unwrap();
+    assert!(IBKEM1UserSecretKey::from_bytes(&ibkem, &ct_bytes).is_err());
+
+    ct_bytes.push(0);
+    assert!(IBKEM1Ciphertext::from_bytes(&ibkem, &ct_bytes).is_err());
+}
+
+/// MAC_NR with one extra affine function `f'_{i,b}(m) = [m_i = b]` per message bit and value,
+/// so `l' = 2 * msg_len`. It only exists to run the `f'_j(m)` terms of the transform.
+struct BitTermsMac {
+    nr: AffineMacNr,
+}
+
+struct BitTermsMacSecretKey {
+    nr: AffineMacNrSecretKey,
+    // x_prime = [x'_0 of MAC_NR, x'_{0,0}, x'_{0,1}, ..., x'_{l-1,0}, x'_{l-1,1}]
+    x_prime: Vec<Vector>,
+}
+
+impl BitTermsMac {
+    // sum_{j > 0} f'_j(m) x'_j, the part of u that MAC_NR does not compute
+    fn bit_terms(&self, sk: &BitTermsMacSecretKey, message: &[u8]) -> Vec<G2> {
+        let f_prime = self.f_prime(message);
+        let sum = f_prime[1..]
+            .iter()
+            .zip(&sk.x_prime[1..])
+            .map(|(f, x)| *f * x[0])
+            .sum();
+        vector_lift_g2(&vec![sum])
+    }
+}
+
+impl AffineMac for BitTermsMac {
+    type SecretKey = BitTermsMacSecretKey;
+
+    fn k(&self) -> usize {
+        self.nr.k()
+    }
+
+    fn msg_len(&self) -> usize {
+        self.nr.msg_len()
+    }
+
+    fn n(&self) -> usize {
+        self.nr.n()
+    }
+
+    fn eta(&self) -> usize {
+        self.nr.eta()
+    }
+
+    fn l(&self) -> usize {
+        self.nr.l()
+    }
+
+    fn l_prime(&self) -> usize {
+        2 * self.nr.msg_len
+    }
+
+    fn f(&self, message: &[u8]) -> Vector {
+        AffineMac::f(&self.nr, message)
+    }
+
+    fn f_prime(&self, message: &[u8]) -> Vector {
+        let mut f_prime = vec![FieldElement::from(1u64)];
+        for i in 0..self.nr.msg_len {
+            let b = bit_at(i, message);
+            f_prime.push(FieldElement::from((b == 0) as u64));
+            f_prime.push(FieldElement::from((b == 1) as u64));
+        }
+        f_prime
+    }
+
+    fn x_matrices<'a>(&self, sk: &'a BitTermsMacSecretKey) -> &'a [Matrix<FieldElement>] {
+        &sk.nr.x_matrices
+    }
+
+    fn x_prime<'a>(&self, sk: &'a BitTermsMacSecretKey) -> &'a [Vector] {
+        &sk.x_prime
+    }
+
+    fn gen_mac<R: RngCore + CryptoRng>(&self, rng: &mut R) -> BitTermsMacSecretKey {
+        let nr = self.nr.gen_mac(rng);
+        let mut x_prime = nr.x_prime.clone();
+        x_prime.extend(random_matrix(rng, 2 * self.nr.msg_len, 1));
+        BitTermsMacSecretKey { nr, x_prime }
+    }
+
+    fn tag_with_context<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        sk: &BitTermsMacSecretKey,
+        message: &[u8],
+    ) -> Result<(Tag, TagContext), IbeError> {
+        let (mut tag, context) = self.nr.tag_with_context(rng, &sk.nr, message)?;
+        tag.u_g2 = vector_add_g2(&tag.u_g2, &self.bit_terms(sk, message));
+        Ok((tag, context))
+    }
+
+    fn verify(&self, sk: &BitTermsMacSecretKey, message: &[u8], tag: &Tag) -> Result<(), IbeError> {
+        let bit_terms = self.bit_terms(sk, message);
+        let tag = Tag {
+            t_g2: tag.t_g2.clone(),
+            u_g2: vector_add_g2(&tag.u_g2, &bit_terms.iter().map(|u| -*u).collect()),
+        };
+        self.nr.verify(&sk.nr, message, &tag)
+    }
+}
+
+#[test]
+fn test_ibkem1_with_several_f_prime_terms() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = BitTermsMac {
+        nr: AffineMacNr::new(2, 128),
+    };
+    assert_eq!(mac.l_prime(), 256);
+
+    let ibkem = IBKEM1::with_mac(mac);
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    assert_eq!(pk.z_prime_vectors.len(), 257);
+
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    assert_eq!(ibkem.decrypt(&usk, &ct).unwrap(), k);
+
+    let (_, new_identity) = generate_email_and_hash_identity(128);
+    let new_usk = ibkem.extract(&mut rng, &sk, &new_identity).unwrap();
+    assert_ne!(ibkem.decrypt(&new_usk, &ct).unwrap(), k);
+}
+
+#[test]
+fn test_ibkem1_bit_terms_mac_tags_verify() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let mac = BitTermsMac {
+        nr: AffineMacNr::new(2, 8),
+    };
+    let sk = mac.gen_mac(&mut rng);
+
+    let tag = mac.tag(&mut rng, &sk, &[0b10110011u8]).unwrap();
+    assert!(mac.verify(&sk, &[0b10110011u8], &tag).is_ok());
+    assert!(mac.verify(&sk, &[0b10110010u8], &tag).is_err());
+}
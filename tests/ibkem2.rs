@@ -1,38 +1,111 @@
 use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
 
 #[test]
 fn test_ibkem2_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let lambda = 128;
 
     let ibkem = IBKEM2::new(k, msg_len, lambda);
-    let (pk, sk) = ibkem.setup();
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
 
     let (_, identity) = generate_email_and_hash_identity(128);
 
-    let usk = ibkem.extract(&sk, &identity);
-    let (ct, k) = ibkem.encrypt(&pk, &identity);
-    let k_dec = ibkem.decrypt(&pk, &usk, &identity, &ct);
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    let k_dec = ibkem.decrypt(&pk, &usk, &identity, &ct).unwrap();
 
-    assert!(k_dec.is_some_and(|k_dec| k_dec == k));
+    assert_eq!(k_dec, k);
 }
 
 #[test]
 fn test_ibkem2_fail() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let msg_len = 128;
     let lambda = 128;
 
     let ibkem = IBKEM2::new(k, msg_len, lambda);
-    let (pk, sk) = ibkem.setup();
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
 
     let (_, identity) = generate_email_and_hash_identity(128);
-    let (ct, _) = ibkem.encrypt(&pk, &identity);
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
 
     let (_, new_identity) = generate_email_and_hash_identity(128);
-    let new_usk = ibkem.extract(&sk, &new_identity);
+    let new_usk = ibkem.extract(&mut rng, &sk, &new_identity).unwrap();
     let k_dec = ibkem.decrypt(&pk, &new_usk, &new_identity, &ct);
 
-    assert!(k_dec.is_none());
+    assert!(matches!(k_dec, Err(IbeError::InvalidProof)));
+}
+
+#[test]
+fn test_ibkem2_over_affine_mac_nr() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM2::with_mac(AffineMacNr::new(2, 128), 128);
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    assert_eq!(ibkem.decrypt(&pk, &usk, &identity, &ct).unwrap(), k);
+
+    let (_, new_identity) = generate_email_and_hash_identity(128);
+    let new_usk = ibkem.extract(&mut rng, &sk, &new_identity).unwrap();
+    assert_ne!(ibkem.decrypt(&pk, &new_usk, &identity, &ct).unwrap(), k);
+}
+
+#[test]
+fn test_ibkem2_serialize_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM2::new(2, 128, 128);
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+
+    let pk = IBKEM2PublicKey::from_bytes(&ibkem, &pk.to_bytes(&ibkem).unwrap()).unwrap();
+    let sk = IBKEM2SecretKey::from_bytes(&ibkem, &sk.to_bytes(&ibkem).unwrap()).unwrap();
+
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let usk = IBKEM2UserSecretKey::from_bytes(&ibkem, &usk.to_bytes(&ibkem).unwrap()).unwrap();
+
+    let (ct, k) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    let ct_bytes = ct.to_bytes(&ibkem).unwrap();
+    let ct = IBKEM2Ciphertext::from_bytes(&ibkem, &ct_bytes).unwrap();
+
+    assert_eq!(ct.to_bytes(&ibkem).unwrap(), ct_bytes);
+    assert!(
+        ibkem
+            .decrypt(&pk, &usk, &identity, &ct)
+            .is_ok_and(|k_dec| k_dec == k)
+    );
+}
+
+#[test]
+fn test_ibkem2_deserialize_rejects_other_instance() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM2::new(2, 128, 128);
+    let other = IBKEM2::new(2, 128, 64);
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+
+    assert!(IBKEM2PublicKey::from_bytes(&other, &pk.to_bytes(&ibkem).unwrap()).is_err());
+    assert!(IBKEM2Ciphertext::from_bytes(&other, &ct.to_bytes(&ibkem).unwrap()).is_err());
+}
+
+#[test]
+fn test_ibkem2_deserialize_rejects_wrong_kind() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM2::new(2, 128, 128);
+    let (pk, _) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let (ct, _) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+
+    let ct_bytes = ct.to_bytes(&ibkem).unwrap();
+    assert!(IBKEM2UserSecretKey::from_bytes(&ibkem, &ct_bytes).is_err());
+    assert!(IBKEM2Ciphertext::from_bytes(&ibkem, &ct_bytes[..ct_bytes.len() - 1]).is_err());
 }
@@ -0,0 +1,97 @@
+use ark_bls12_381::{G1Projective as G1, G2Projective as G2};
+use ark_ec::PrimeGroup;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use ibe_schemes::pes::IBEScheme;
+use ibe_schemes::pes::bb::BB;
+use ibe_schemes::pes::bf::BF;
+use ibe_schemes::pes::bf_cca::BFCca;
+use ibe_schemes::pes::ibs::{KeyCheck, Signer, Verifier, message_identity};
+
+const SEED: u64 = 42;
+const MESSAGE: &[u8] = b"release v1.2.0";
+
+fn keypair<T: IBEScheme>(new: fn() -> T, rng: &mut ChaCha20Rng) -> (Signer<T>, Verifier<T>) {
+    let (msk, mpk) = new().setup(&mut *rng);
+    (Signer::new(new(), msk), Verifier::new(new(), mpk))
+}
+
+fn pairing_check_roundtrip<T: KeyCheck>(new: fn() -> T) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (signer, verifier) = keypair(new, &mut rng);
+    let (_, other_verifier) = keypair(new, &mut rng);
+
+    let signature = signer.sign(&mut rng, MESSAGE).unwrap();
+    assert!(verifier.verify(MESSAGE, &signature));
+    assert!(verifier.verify_by_decryption(&mut rng, MESSAGE, &signature));
+
+    assert!(!verifier.verify(b"release v1.2.1", &signature));
+    assert!(!verifier.verify_by_decryption(&mut rng, b"release v1.2.1", &signature));
+    assert!(!other_verifier.verify(MESSAGE, &signature));
+    assert!(!other_verifier.verify_by_decryption(&mut rng, MESSAGE, &signature));
+}
+
+#[test]
+fn ibs_bf() {
+    pairing_check_roundtrip(BF::new);
+}
+
+#[test]
+fn ibs_bb() {
+    pairing_check_roundtrip(BB::new);
+}
+
+#[test]
+fn ibs_by_decryption_for_any_scheme() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (signer, verifier) = keypair(BFCca::new, &mut rng);
+
+    let signature = signer.sign(&mut rng, MESSAGE).unwrap();
+    assert!(verifier.verify_by_decryption(&mut rng, MESSAGE, &signature));
+    assert!(!verifier.verify_by_decryption(&mut rng, b"", &signature));
+}
+
+#[test]
+fn ibs_bf_rejects_modified_signature() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (signer, verifier) = keypair(BF::new, &mut rng);
+
+    let mut signature = signer.sign(&mut rng, MESSAGE).unwrap();
+    signature.k += G1::generator();
+    assert!(!verifier.verify(MESSAGE, &signature));
+
+    let mut signature = signer.sign(&mut rng, MESSAGE).unwrap();
+    signature.r += G2::generator();
+    assert!(!verifier.verify(MESSAGE, &signature));
+}
+
+#[test]
+fn ibs_bb_rejects_modified_signature() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (signer, verifier) = keypair(BB::new, &mut rng);
+
+    let mut signature = signer.sign(&mut rng, MESSAGE).unwrap();
+    signature.k += G2::generator();
+    assert!(!verifier.verify(MESSAGE, &signature));
+    assert!(!verifier.verify_by_decryption(&mut rng, MESSAGE, &signature));
+}
+
+#[test]
+fn ibs_signs_under_prefixed_identity() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scheme = BF::new();
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let verifier = Verifier::new(BF::new(), mpk);
+
+    // a key for the message itself as identity does not verify, only the prefixed identity does
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from_utf8(MESSAGE.to_vec()).unwrap())
+        .unwrap();
+    assert!(!verifier.verify(MESSAGE, &usk));
+
+    let usk = scheme
+        .keygen(&mut rng, &msk, message_identity(MESSAGE))
+        .unwrap();
+    assert!(verifier.verify(MESSAGE, &usk));
+}
@@ -0,0 +1,390 @@
+use ark_bls12_381::{Fq12 as Gt, G1Projective as G1};
+use ark_ff::{Field, UniformRand};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use ibe_schemes::games::ind_id::*;
+use ibe_schemes::pes::bb::{self, BB};
+use ibe_schemes::pes::bb_cca::{self, BBCca};
+use ibe_schemes::pes::bbg::BBG;
+use ibe_schemes::pes::bf::{self, BF};
+use ibe_schemes::pes::bf_cca::{self, BFCca};
+use ibe_schemes::pes::cw13::CW13;
+use ibe_schemes::pes::hiberla_dec::HiberlaDec;
+use ibe_schemes::pes::hiberla_enc::HiberlaEnc;
+use ibe_schemes::pes::kem::PesKem;
+use ibe_schemes::pes::lw::LW;
+use ibe_schemes::pes::sakai_kasahara::{SakaiKasahara, SakaiKasaharaKem};
+use ibe_schemes::pes::waters::Waters;
+use ibe_schemes::*;
+
+const SEED: u64 = 42;
+
+fn rng() -> ChaCha20Rng {
+    ChaCha20Rng::seed_from_u64(SEED)
+}
+
+fn messages(rng: &mut ChaCha20Rng) -> [Gt; 2] {
+    [Gt::rand(rng), Gt::rand(rng)]
+}
+
+fn hierarchical(levels: &[&str]) -> Vec<String> {
+    levels.iter().map(|level| level.to_string()).collect()
+}
+
+// doubles both parts of an IBKEM1/IBKEM2 ciphertext, which squares its key
+fn double(c_g1: &[G1]) -> Vec<G1> {
+    c_g1.iter().map(|c| *c + c).collect()
+}
+
+fn assert_forbidden_queries_refused<S: IndIdScheme>(
+    scheme: &S,
+    identity: S::Identity,
+    query: S::ChallengeQuery,
+) {
+    let mut rng = rng();
+    for bit in [false, true] {
+        let mut challenger =
+            IndIdChallenger::with_bit(scheme, IndIdMode::Cca, bit, &mut rng).unwrap();
+        let (ct, _) = challenger.challenge(&identity, query.clone()).unwrap();
+        assert!(matches!(
+            challenger.extract_oracle(&identity),
+            Err(OracleError::ChallengeIdentity)
+        ));
+        assert!(matches!(
+            challenger.decrypt_oracle(&identity, &ct),
+            Err(OracleError::ChallengeCiphertext)
+        ));
+        assert!(matches!(
+            challenger.challenge(&identity, query.clone()),
+            Err(OracleError::ChallengeIssued)
+        ));
+    }
+}
+
+// the challenge ciphertext must not be decrypted under any prefix of the challenge identity
+fn assert_prefix_decryption_refused<S, L>(scheme: &S, identity: Vec<L>, query: S::ChallengeQuery)
+where
+    S: IndIdScheme<Identity = Vec<L>>,
+    L: Clone,
+{
+    let mut challenger = IndIdChallenger::with_bit(scheme, IndIdMode::Cca, false, rng()).unwrap();
+    let (ct, _) = challenger.challenge(&identity, query).unwrap();
+    for depth in 1..identity.len() {
+        assert!(matches!(
+            challenger.decrypt_oracle(&identity[..depth].to_vec(), &ct),
+            Err(OracleError::ChallengeCiphertext)
+        ));
+    }
+}
+
+#[test]
+fn ind_id_ibe_schemes_refuse_forbidden_queries() {
+    let mut rng = rng();
+    let alice = "alice".to_string();
+
+    assert_forbidden_queries_refused(&IbeGame::new(BF::new()), alice.clone(), messages(&mut rng));
+    assert_forbidden_queries_refused(&IbeGame::new(BB::new()), alice.clone(), messages(&mut rng));
+    assert_forbidden_queries_refused(
+        &IbeGame::new(BFCca::new()),
+        alice.clone(),
+        messages(&mut rng),
+    );
+    assert_forbidden_queries_refused(
+        &IbeGame::new(BBCca::new()),
+        alice.clone(),
+        messages(&mut rng),
+    );
+    assert_forbidden_queries_refused(
+        &IbeGame::new(Waters::new(128)),
+        alice.clone(),
+        messages(&mut rng),
+    );
+    assert_forbidden_queries_refused(
+        &IbeGame::new(SakaiKasahara::new()),
+        alice.clone(),
+        messages(&mut rng),
+    );
+    assert_forbidden_queries_refused(&IbeGame::new(CW13::new()), alice, messages(&mut rng));
+}
+
+#[test]
+fn ind_id_hibe_schemes_refuse_forbidden_queries() {
+    let mut rng = rng();
+    let identity = hierarchical(&["alice", "bob"]);
+
+    assert_forbidden_queries_refused(
+        &HibeGame::new(BBG::new(3)),
+        identity.clone(),
+        messages(&mut rng),
+    );
+    assert_forbidden_queries_refused(
+        &HibeGame::new(LW::new()),
+        identity.clone(),
+        messages(&mut rng),
+    );
+    assert_forbidden_queries_refused(
+        &HibeGame::new(HiberlaDec::new(2)),
+        identity.clone(),
+        messages(&mut rng),
+    );
+    assert_forbidden_queries_refused(
+        &HibeGame::new(HiberlaEnc::new(2)),
+        identity,
+        messages(&mut rng),
+    );
+}
+
+#[test]
+fn ind_id_ibkems_refuse_forbidden_queries() {
+    let identity = b"alice@example.co".to_vec();
+
+    assert_forbidden_queries_refused(&IbkemGame::new(IBKEM1::new(2, 128)), identity.clone(), ());
+    assert_forbidden_queries_refused(
+        &IbkemGame::new(BKP14::with_mac(AffineMacNr::new(2, 128))),
+        identity.clone(),
+        (),
+    );
+    assert_forbidden_queries_refused(
+        &IbkemGame::new(IBKEM2::new(2, 128, 128)),
+        identity.clone(),
+        (),
+    );
+    assert_forbidden_queries_refused(
+        &IbkemGame::new(SakaiKasaharaKem::new()),
+        identity.clone(),
+        (),
+    );
+    assert_forbidden_queries_refused(&IbkemGame::new(PesKem::new(BF::new())), identity, ());
+}
+
+#[test]
+fn ind_id_hibkems_refuse_forbidden_queries() {
+    let identity = vec![vec![0x61], vec![0x62]];
+
+    assert_forbidden_queries_refused(
+        &HibkemGame::new(HIBKEM1::new(2, 2, 8)),
+        identity.clone(),
+        (),
+    );
+    assert_forbidden_queries_refused(
+        &HibkemGame::new(HIBKEM2::new(2, 2, 8)),
+        identity.clone(),
+        (),
+    );
+    assert_forbidden_queries_refused(&HibkemGame::new(PesKem::new(LW::new())), identity, ());
+}
+
+#[test]
+fn ind_id_prefixes_of_the_challenge_identity_cannot_decrypt_the_challenge() {
+    let mut rng = rng();
+    let identity = hierarchical(&["alice", "bob", "carol"]);
+
+    assert_prefix_decryption_refused(&HibeGame::new(BBG::new(3)), identity, messages(&mut rng));
+    assert_prefix_decryption_refused(
+        &HibkemGame::new(HIBKEM1::new(2, 3, 8)),
+        vec![vec![0x12], vec![0x34], vec![0x56]],
+        (),
+    );
+}
+
+#[test]
+fn ind_id_keys_for_prefixes_of_the_challenge_identity_are_refused() {
+    let mut rng = rng();
+    let query = messages(&mut rng);
+    let scheme = HibeGame::new(BBG::new(3));
+    let mut challenger = IndIdChallenger::with_bit(&scheme, IndIdMode::Cpa, false, rng).unwrap();
+
+    // a key extracted before the challenge rules out the identity and its descendants
+    challenger
+        .extract_oracle(&hierarchical(&["alice"]))
+        .unwrap();
+    assert!(matches!(
+        challenger.challenge(&hierarchical(&["alice", "bob"]), query),
+        Err(OracleError::ChallengeIdentity)
+    ));
+
+    let challenge_identity = hierarchical(&["carol", "dave"]);
+    challenger.challenge(&challenge_identity, query).unwrap();
+    assert!(matches!(
+        challenger.extract_oracle(&hierarchical(&["carol"])),
+        Err(OracleError::ChallengeIdentity)
+    ));
+    assert!(matches!(
+        challenger.extract_oracle(&challenge_identity),
+        Err(OracleError::ChallengeIdentity)
+    ));
+    assert!(
+        challenger
+            .extract_oracle(&hierarchical(&["carol", "dave", "eve"]))
+            .is_ok()
+    );
+    assert!(
+        challenger
+            .extract_oracle(&hierarchical(&["carol", "eve"]))
+            .is_ok()
+    );
+    assert!(challenger.extract_oracle(&hierarchical(&["dave"])).is_ok());
+}
+
+#[test]
+fn ind_id_cpa_has_no_decryption_oracle() {
+    let identity = b"alice@example.co".to_vec();
+    let scheme = IbkemGame::new(IBKEM1::new(2, 128));
+    let mut challenger = IndIdChallenger::with_bit(&scheme, IndIdMode::Cpa, false, rng()).unwrap();
+
+    let (ct, _) = challenger.challenge(&identity, ()).unwrap();
+    assert!(matches!(
+        challenger.decrypt_oracle(&identity, &ct),
+        Err(OracleError::NoDecryptionOracle)
+    ));
+}
+
+#[test]
+fn ind_id_cca_decrypts_other_ciphertexts_for_the_challenge_identity() {
+    let mut encrypt_rng = ChaCha20Rng::seed_from_u64(SEED + 1);
+    let identity = b"alice@example.co".to_vec();
+    let scheme = IbkemGame::new(IBKEM1::new(2, 128));
+    let mut challenger = IndIdChallenger::with_bit(&scheme, IndIdMode::Cca, false, rng()).unwrap();
+
+    challenger.challenge(&identity, ()).unwrap();
+    let (ct, key) = scheme
+        .scheme
+        .encrypt(&mut encrypt_rng, challenger.pk(), &identity)
+        .unwrap();
+    assert_eq!(challenger.decrypt_oracle(&identity, &ct).unwrap(), key);
+}
+
+#[test]
+fn ind_id_cca_bf_ciphertexts_are_malleable() {
+    let mut rng = rng();
+    let delta = Gt::rand(&mut rng);
+    let scheme = IbeGame::new(BF::new());
+    let mut adversary = MaulingAdversary::<IbeGame<BF>, _, _> {
+        identity: "alice".to_string(),
+        query: messages(&mut rng),
+        maul: |ct: &bf::CT| bf::CT {
+            msg: ct.msg * delta,
+            ..ct.clone()
+        },
+        relate: |m: &Gt| *m * delta,
+    };
+
+    assert!(distinguishes(&scheme, IndIdMode::Cca, &mut adversary, &mut rng).unwrap());
+    assert!(!distinguishes(&scheme, IndIdMode::Cpa, &mut adversary, &mut rng).unwrap());
+}
+
+#[test]
+fn ind_id_cca_bb_ciphertexts_are_malleable() {
+    let mut rng = rng();
+    let delta = Gt::rand(&mut rng);
+    let scheme = IbeGame::new(BB::new());
+    let mut adversary = MaulingAdversary::<IbeGame<BB>, _, _> {
+        identity: "alice".to_string(),
+        query: messages(&mut rng),
+        maul: |ct: &bb::CT| bb::CT {
+            msg: ct.msg * delta,
+            ..ct.clone()
+        },
+        relate: |m: &Gt| *m * delta,
+    };
+
+    assert!(distinguishes(&scheme, IndIdMode::Cca, &mut adversary, &mut rng).unwrap());
+}
+
+#[test]
+fn ind_id_cca_fo_transform_rejects_mauled_ciphertexts() {
+    let mut rng = rng();
+    let delta = Gt::rand(&mut rng);
+    let alice = "alice".to_string();
+
+    let bf_cca = IbeGame::new(BFCca::new());
+    let mut adversary = MaulingAdversary::<IbeGame<BFCca>, _, _> {
+        identity: alice.clone(),
+        query: messages(&mut rng),
+        maul: |ct: &bf_cca::CT| bf_cca::CT {
+            inner: bf::CT {
+                msg: ct.inner.msg * delta,
+                ..ct.inner.clone()
+            },
+            masked: ct.masked,
+        },
+        relate: |m: &Gt| *m * delta,
+    };
+    assert!(!distinguishes(&bf_cca, IndIdMode::Cca, &mut adversary, &mut rng).unwrap());
+
+    let query = messages(&mut rng);
+    let mut challenger =
+        IndIdChallenger::with_bit(&bf_cca, IndIdMode::Cca, false, &mut rng).unwrap();
+    let (ct, _) = challenger.challenge(&alice, query).unwrap();
+    assert!(matches!(
+        challenger.decrypt_oracle(&alice, &(adversary.maul)(&ct)),
+        Err(OracleError::Scheme(IbeError::DecryptionFailed))
+    ));
+
+    let bb_cca = IbeGame::new(BBCca::new());
+    let mut adversary = MaulingAdversary::<IbeGame<BBCca>, _, _> {
+        identity: alice.clone(),
+        query: messages(&mut rng),
+        maul: |ct: &bb_cca::CT| bb_cca::CT {
+            inner: bb::CT {
+                msg: ct.inner.msg * delta,
+                ..ct.inner.clone()
+            },
+            masked: ct.masked,
+        },
+        relate: |m: &Gt| *m * delta,
+    };
+    assert!(!distinguishes(&bb_cca, IndIdMode::Cca, &mut adversary, &mut rng).unwrap());
+
+    let query = messages(&mut rng);
+    let mut challenger = IndIdChallenger::with_bit(&bb_cca, IndIdMode::Cca, false, rng).unwrap();
+    let (ct, _) = challenger.challenge(&alice, query).unwrap();
+    assert!(matches!(
+        challenger.decrypt_oracle(&alice, &(adversary.maul)(&ct)),
+        Err(OracleError::Scheme(IbeError::DecryptionFailed))
+    ));
+}
+
+#[test]
+fn ind_id_cca_ibkem1_ciphertexts_are_malleable() {
+    let mut rng = rng();
+    let scheme = IbkemGame::new(IBKEM1::new(2, 128));
+    let mut adversary = MaulingAdversary::<IbkemGame<IBKEM1>, _, _> {
+        identity: b"alice@example.co".to_vec(),
+        query: (),
+        maul: |ct: &IBKEM1Ciphertext| IBKEM1Ciphertext {
+            c0_g1: double(&ct.c0_g1),
+            c1_g1: double(&ct.c1_g1),
+        },
+        relate: |key: &Gt| key.square(),
+    };
+
+    assert!(distinguishes(&scheme, IndIdMode::Cca, &mut adversary, &mut rng).unwrap());
+}
+
+#[test]
+fn ind_id_cca_ibkem2_rejects_mauled_ciphertexts() {
+    let mut rng = rng();
+    let identity = b"alice@example.co".to_vec();
+    let scheme = IbkemGame::new(IBKEM2::new(2, 128, 128));
+    let mut adversary = MaulingAdversary::<IbkemGame<IBKEM2>, _, _> {
+        identity: identity.clone(),
+        query: (),
+        maul: |ct: &IBKEM2Ciphertext| IBKEM2Ciphertext {
+            c0_g1: double(&ct.c0_g1),
+            c1_g1: double(&ct.c1_g1),
+            proof: ct.proof.clone(),
+        },
+        relate: |key: &Gt| key.square(),
+    };
+
+    assert!(!distinguishes(&scheme, IndIdMode::Cca, &mut adversary, &mut rng).unwrap());
+
+    let mut challenger = IndIdChallenger::with_bit(&scheme, IndIdMode::Cca, false, rng).unwrap();
+    let (ct, _) = challenger.challenge(&identity, ()).unwrap();
+    assert!(matches!(
+        challenger.decrypt_oracle(&identity, &(adversary.maul)(&ct)),
+        Err(OracleError::Scheme(IbeError::InvalidProof))
+    ));
+}
@@ -0,0 +1,422 @@
+//! Known-answer tests.
+//!
+//! Every vector is derived from `KAT_SEED` and fixed identities, so a change to hashing, to the
+//! bit order of identities or to the layout of keys shows up as a byte difference against the
+//! JSON files in `tests/kat`. After an intentional change, regenerate them with
+//! `cargo test --release --test kat -- --ignored kat_generate`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use ark_bls12_381::{Fq12 as Gt, G1Projective as G1};
+use ark_ff::UniformRand;
+use ark_serialize::CanonicalSerialize;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde_json::{Map, Value};
+
+use ibe_schemes::pes::bb::BB;
+use ibe_schemes::pes::bf::BF;
+use ibe_schemes::pes::hiberla_dec::HiberlaDec;
+use ibe_schemes::pes::hiberla_enc::HiberlaEnc;
+use ibe_schemes::pes::lw::LW;
+use ibe_schemes::pes::{HIBEScheme, IBEScheme};
+use ibe_schemes::*;
+
+const KAT_SEED: u64 = 20240917;
+
+const IBE_IDENTITY: &str = "alice@example.com";
+const HIBE_IDENTITY: [&str; 3] = ["example.com", "eng", "alice"];
+
+// small dimensions keep the files reviewable; the KATs pin the math, not the security level
+const K: usize = 2;
+const MSG_LEN: usize = 16;
+const LAMBDA: usize = 16;
+const MAX_LEVELS: usize = 2;
+const IDENTITY_LEN: usize = 8;
+
+#[derive(Default)]
+struct Kat(Map<String, Value>);
+
+impl Kat {
+    fn new(scheme: &str) -> Self {
+        let mut kat = Kat::default();
+        kat.text("scheme", scheme);
+        kat.0.insert(String::from("seed"), Value::from(KAT_SEED));
+        kat
+    }
+
+    fn param(&mut self, name: &str, value: usize) {
+        self.0.insert(name.to_string(), Value::from(value));
+    }
+
+    fn text(&mut self, name: &str, value: &str) {
+        self.0.insert(name.to_string(), Value::from(value));
+    }
+
+    fn bytes(&mut self, name: &str, bytes: &[u8]) {
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        self.0.insert(name.to_string(), Value::from(hex));
+    }
+
+    fn canonical<T: CanonicalSerialize>(&mut self, name: &str, value: &T) {
+        let mut bytes = Vec::new();
+        value.serialize_compressed(&mut bytes).unwrap();
+        self.bytes(name, &bytes);
+    }
+
+    fn kem<P: KemParams, T: KemSerialize<P>>(&mut self, name: &str, value: &T, params: &P) {
+        self.bytes(name, &value.to_bytes(params).unwrap());
+    }
+
+    fn finish(self) -> Value {
+        Value::Object(self.0)
+    }
+}
+
+fn hibe_identity(depth: usize) -> Vec<String> {
+    HIBE_IDENTITY[..depth]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn hashed_identity(input: &str, bits: usize) -> Vec<u8> {
+    blake3_hash_to_bits(input.as_bytes(), bits).to_bytes()
+}
+
+fn hashed_hierarchical_identity(depth: usize) -> Vec<Vec<u8>> {
+    HIBE_IDENTITY[..depth]
+        .iter()
+        .map(|level| hashed_identity(level, IDENTITY_LEN))
+        .collect()
+}
+
+fn kat_ibe<T: IBEScheme>(scheme: T) -> Value {
+    let mut rng = ChaCha20Rng::seed_from_u64(KAT_SEED);
+    let mut kat = Kat::new(&scheme.name());
+    kat.text("identity", IBE_IDENTITY);
+
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from(IBE_IDENTITY))
+        .unwrap();
+    let msg = Gt::rand(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from(IBE_IDENTITY))
+        .unwrap();
+    assert_eq!(scheme.decrypt(&usk, &ct).unwrap(), msg);
+
+    kat.canonical("msk", &msk);
+    kat.canonical("mpk", &mpk);
+    kat.canonical("usk", &usk);
+    kat.canonical("msg", &msg);
+    kat.canonical("ct", &ct);
+    kat.finish()
+}
+
+fn kat_hibe<T: HIBEScheme>(scheme: T, mut kat: Kat) -> Value {
+    let mut rng = ChaCha20Rng::seed_from_u64(KAT_SEED);
+    kat.text("identity", &hibe_identity(3).join("."));
+
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let usk = scheme.keygen(&mut rng, &msk, hibe_identity(2)).unwrap();
+    let usk_delegated = scheme
+        .delegate(&mut rng, &mpk, &usk, String::from(HIBE_IDENTITY[2]))
+        .unwrap();
+    let msg = Gt::rand(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, hibe_identity(3))
+        .unwrap();
+    assert_eq!(scheme.decrypt(&usk, &ct).unwrap(), msg);
+    assert_eq!(scheme.decrypt(&usk_delegated, &ct).unwrap(), msg);
+
+    kat.canonical("msk", &msk);
+    kat.canonical("mpk", &mpk);
+    kat.canonical("usk", &usk);
+    kat.canonical("usk_delegated", &usk_delegated);
+    kat.canonical("msg", &msg);
+    kat.canonical("ct", &ct);
+    kat.finish()
+}
+
+fn kat_bf() -> Value {
+    kat_ibe(BF::new())
+}
+
+fn kat_bb() -> Value {
+    kat_ibe(BB::new())
+}
+
+fn kat_lw() -> Value {
+    kat_hibe(LW::new(), Kat::new("lw"))
+}
+
+fn kat_hiberla_enc() -> Value {
+    let mut kat = Kat::new("hiberla_enc");
+    kat.param("l", 2);
+    kat_hibe(HiberlaEnc::new(2), kat)
+}
+
+fn kat_hiberla_dec() -> Value {
+    let mut kat = Kat::new("hiberla_dec");
+    kat.param("l", 2);
+    kat_hibe(HiberlaDec::new(2), kat)
+}
+
+fn kat_ibkem1() -> Value {
+    let mut rng = ChaCha20Rng::seed_from_u64(KAT_SEED);
+    let ibkem = IBKEM1::new(K, MSG_LEN);
+    let identity = hashed_identity(IBE_IDENTITY, MSG_LEN);
+
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, key) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    assert_eq!(ibkem.decrypt(&usk, &ct).unwrap(), key);
+
+    let mut kat = Kat::new("ibkem1");
+    kat.param("k", K);
+    kat.param("msg_len", MSG_LEN);
+    kat.bytes("identity", &identity);
+    kat.kem("pk", &pk, &ibkem);
+    kat.kem("sk", &sk, &ibkem);
+    kat.kem("usk", &usk, &ibkem);
+    kat.kem("ct", &ct, &ibkem);
+    kat.canonical("key", &key);
+    kat.finish()
+}
+
+fn kat_ibkem2() -> Value {
+    let mut rng = ChaCha20Rng::seed_from_u64(KAT_SEED);
+    let ibkem = IBKEM2::new(K, MSG_LEN, LAMBDA);
+    let identity = hashed_identity(IBE_IDENTITY, MSG_LEN);
+
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+    let (ct, key) = ibkem.encrypt(&mut rng, &pk, &identity).unwrap();
+    assert_eq!(ibkem.decrypt(&pk, &usk, &identity, &ct).unwrap(), key);
+
+    let mut kat = Kat::new("ibkem2");
+    kat.param("k", K);
+    kat.param("msg_len", MSG_LEN);
+    kat.param("lambda", LAMBDA);
+    kat.bytes("identity", &identity);
+    kat.kem("pk", &pk, &ibkem);
+    kat.kem("sk", &sk, &ibkem);
+    kat.kem("usk", &usk, &ibkem);
+    kat.kem("ct", &ct, &ibkem);
+    kat.canonical("key", &key);
+    kat.finish()
+}
+
+fn kat_hibkem1() -> Value {
+    let mut rng = ChaCha20Rng::seed_from_u64(KAT_SEED);
+    let hibkem = HIBKEM1::new(K, MAX_LEVELS, IDENTITY_LEN);
+    let id_l1 = hashed_hierarchical_identity(1);
+    let id_l2 = hashed_hierarchical_identity(2);
+
+    let (pk, dk, sk) = hibkem.setup(&mut rng).unwrap();
+    let (usk, udk) = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
+    let (usk_delegated, udk_delegated) = hibkem
+        .delegate(&mut rng, &dk, &usk, &udk, &id_l1, id_l2[1].clone())
+        .unwrap();
+    let (key, ct) = hibkem.encrypt(&mut rng, &pk, &id_l2).unwrap();
+    assert_eq!(hibkem.decrypt(&usk_delegated, &ct).unwrap(), key);
+
+    let mut kat = Kat::new("hibkem1");
+    kat.param("k", K);
+    kat.param("max_levels", MAX_LEVELS);
+    kat.param("identity_len", IDENTITY_LEN);
+    kat.bytes("identity", &id_l2.concat());
+    kat.kem("pk", &pk, &hibkem);
+    kat.kem("dk", &dk, &hibkem);
+    kat.kem("sk", &sk, &hibkem);
+    kat.kem("usk", &usk, &hibkem);
+    kat.kem("udk", &udk, &hibkem);
+    kat.kem("usk_delegated", &usk_delegated, &hibkem);
+    kat.kem("udk_delegated", &udk_delegated, &hibkem);
+    kat.kem("ct", &ct, &hibkem);
+    kat.canonical("key", &key);
+    kat.finish()
+}
+
+fn kat_hibkem2() -> Value {
+    let mut rng = ChaCha20Rng::seed_from_u64(KAT_SEED);
+    let hibkem = HIBKEM2::new(K, MAX_LEVELS, IDENTITY_LEN);
+    let id_l1 = hashed_hierarchical_identity(1);
+    let id_l2 = hashed_hierarchical_identity(2);
+
+    let (pk, dk, sk) = hibkem.setup(&mut rng).unwrap();
+    let usk = hibkem.extract(&mut rng, &sk, &id_l1).unwrap();
+    let usk_delegated = hibkem
+        .delegate(&mut rng, &dk, &usk, &id_l1, id_l2[1].clone())
+        .unwrap();
+    let (key, ct) = hibkem.encrypt(&mut rng, &pk, &id_l2).unwrap();
+    assert_eq!(hibkem.decrypt(&usk_delegated, &ct).unwrap(), key);
+
+    let mut kat = Kat::new("hibkem2");
+    kat.param("k", K);
+    kat.param("max_levels", MAX_LEVELS);
+    kat.param("identity_len", IDENTITY_LEN);
+    kat.bytes("identity", &id_l2.concat());
+    kat.kem("pk", &pk, &hibkem);
+    kat.kem("dk", &dk, &hibkem);
+    kat.kem("sk", &sk, &hibkem);
+    kat.kem("usk", &usk, &hibkem);
+    kat.kem("usk_delegated", &usk_delegated, &hibkem);
+    kat.kem("ct", &ct, &hibkem);
+    kat.canonical("key", &key);
+    kat.finish()
+}
+
+fn kat_affine_mac() -> Value {
+    let mut rng = ChaCha20Rng::seed_from_u64(KAT_SEED);
+    let mac = AffineMAC::new(K, MSG_LEN);
+    let message = hashed_identity(IBE_IDENTITY, MSG_LEN);
+
+    let sk = mac.gen_mac(&mut rng);
+    let tag = mac.tag(&mut rng, &sk, &message).unwrap();
+    assert!(mac.verify(&sk, &message, &tag).is_ok());
+
+    let mut kat = Kat::new("affine_mac");
+    kat.param("k", K);
+    kat.param("msg_len", MSG_LEN);
+    kat.bytes("message", &message);
+    kat.kem("sk", &sk, &mac);
+    kat.canonical("tag_t", &tag.t_g2);
+    kat.canonical("tag_u", &tag.u_g2);
+    kat.finish()
+}
+
+fn kat_qanizk() -> Value {
+    let mut rng = ChaCha20Rng::seed_from_u64(KAT_SEED);
+    let qanizk = QANIZK::new(K, LAMBDA);
+    let tag = hashed_identity(IBE_IDENTITY, MSG_LEN);
+
+    let m_matrix = random_matrix(&mut rng, 3 * K, K);
+    let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
+    let (crs, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
+
+    let r = random_vector(&mut rng, K);
+    let c0_g1 = vector_lift_g1(&matrix_vector_mul(&m_matrix, &r));
+    let pi = qanizk.prove(&mut rng, &crs, &tag, &c0_g1, &r).unwrap();
+    qanizk.verify(&crs, &tag, &c0_g1, &pi).unwrap();
+
+    let mut kat = Kat::new("qanizk");
+    kat.param("k", K);
+    kat.param("lambda", LAMBDA);
+    kat.bytes("tag", &tag);
+    kat.canonical("m", &m_g1_matrix);
+    kat.kem("crs", &crs, &qanizk);
+    kat.canonical("c0", &c0_g1);
+    kat.kem("proof", &pi, &qanizk);
+    kat.finish()
+}
+
+fn kat_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/kat")
+        .join(format!("{}.json", name))
+}
+
+fn to_json(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap() + "\n"
+}
+
+fn check_kat(name: &str, generate: Generator) {
+    let file = fs::read_to_string(kat_path(name)).unwrap();
+    let expected: Value = serde_json::from_str(&file).unwrap();
+    let actual = generate();
+
+    // compare field by field first for a readable failure
+    for (field, value) in expected.as_object().unwrap() {
+        assert_eq!(
+            actual.get(field),
+            Some(value),
+            "{}: `{}` differs from the known answer",
+            name,
+            field
+        );
+    }
+    assert_eq!(to_json(&actual), file, "{}: vector file differs", name);
+}
+
+type Generator = fn() -> Value;
+
+const KATS: [(&str, Generator); 11] = [
+    ("bf", kat_bf),
+    ("bb", kat_bb),
+    ("lw", kat_lw),
+    ("hiberla_enc", kat_hiberla_enc),
+    ("hiberla_dec", kat_hiberla_dec),
+    ("ibkem1", kat_ibkem1),
+    ("ibkem2", kat_ibkem2),
+    ("hibkem1", kat_hibkem1),
+    ("hibkem2", kat_hibkem2),
+    ("affine_mac", kat_affine_mac),
+    ("qanizk", kat_qanizk),
+];
+
+#[test]
+#[ignore = "rewrites tests/kat; run after an intentional change"]
+fn kat_generate() {
+    fs::create_dir_all(kat_path("").parent().unwrap()).unwrap();
+    for (name, generate) in KATS {
+        fs::write(kat_path(name), to_json(&generate())).unwrap();
+    }
+}
+
+#[test]
+fn kat_bf_reproduces() {
+    check_kat("bf", kat_bf);
+}
+
+#[test]
+fn kat_bb_reproduces() {
+    check_kat("bb", kat_bb);
+}
+
+#[test]
+fn kat_lw_reproduces() {
+    check_kat("lw", kat_lw);
+}
+
+#[test]
+fn kat_hiberla_enc_reproduces() {
+    check_kat("hiberla_enc", kat_hiberla_enc);
+}
+
+#[test]
+fn kat_hiberla_dec_reproduces() {
+    check_kat("hiberla_dec", kat_hiberla_dec);
+}
+
+#[test]
+fn kat_ibkem1_reproduces() {
+    check_kat("ibkem1", kat_ibkem1);
+}
+
+#[test]
+fn kat_ibkem2_reproduces() {
+    check_kat("ibkem2", kat_ibkem2);
+}
+
+#[test]
+fn kat_hibkem1_reproduces() {
+    check_kat("hibkem1", kat_hibkem1);
+}
+
+#[test]
+fn kat_hibkem2_reproduces() {
+    check_kat("hibkem2", kat_hibkem2);
+}
+
+#[test]
+fn kat_affine_mac_reproduces() {
+    check_kat("affine_mac", kat_affine_mac);
+}
+
+#[test]
+fn kat_qanizk_reproduces() {
+    check_kat("qanizk", kat_qanizk);
+}
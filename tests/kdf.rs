@@ -0,0 +1,143 @@
+use ark_bls12_381::Fq12 as Gt;
+use ark_ff::{One, UniformRand};
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn kdf_known_answer() {
+    // pins KDF_CONTEXT and the input encoding
+    let context = KdfContext::new("ibkem1", b"alice", b"ciphertext");
+    assert_eq!(
+        hex(&derive_key(&Gt::one(), &context, 32)),
+        "3385c6f244e2dfdaeb4cf2d2dd310246aa54179deda88ed132a319695f3aa7d2"
+    );
+}
+
+#[test]
+fn kdf_any_output_length() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let key = Gt::rand(&mut rng);
+    let context = KdfContext::new("ibkem1", b"alice", b"ciphertext");
+
+    assert!(derive_key(&key, &context, 0).is_empty());
+    let long = derive_key(&key, &context, 1000);
+    assert_eq!(long.len(), 1000);
+    assert_eq!(derive_key(&key, &context, 16), long[..16]);
+}
+
+#[test]
+fn kdf_binds_every_context_field() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let key = Gt::rand(&mut rng);
+    let okm = derive_key(&key, &KdfContext::new("ibkem1", b"alice", b"ct"), 32);
+
+    let other_key = Gt::rand(&mut rng);
+    assert_ne!(
+        derive_key(&other_key, &KdfContext::new("ibkem1", b"alice", b"ct"), 32),
+        okm
+    );
+    assert_ne!(
+        derive_key(&key, &KdfContext::new("ibkem2", b"alice", b"ct"), 32),
+        okm
+    );
+    assert_ne!(
+        derive_key(&key, &KdfContext::new("ibkem1", b"bob", b"ct"), 32),
+        okm
+    );
+    assert_ne!(
+        derive_key(&key, &KdfContext::new("ibkem1", b"alice", b"ct2"), 32),
+        okm
+    );
+    // moving bytes between length-prefixed fields changes the key
+    assert_ne!(
+        derive_key(&key, &KdfContext::new("ibkem1a", b"lice", b"ct"), 32),
+        okm
+    );
+}
+
+#[test]
+fn kdf_separates_hierarchy_levels() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let key = Gt::rand(&mut rng);
+    let id_1 = vec![b"ab".to_vec(), b"c".to_vec()];
+    let id_2 = vec![b"a".to_vec(), b"bc".to_vec()];
+
+    assert_ne!(
+        derive_key(&key, &KdfContext::hierarchical("hibkem1", &id_1, b"ct"), 32),
+        derive_key(&key, &KdfContext::hierarchical("hibkem1", &id_2, b"ct"), 32)
+    );
+}
+
+#[test]
+fn ibkem1_encapsulate_bytes() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM1::new(2, 128);
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+
+    let (ct, okm) = ibkem
+        .encapsulate_bytes(&mut rng, &pk, &identity, 32)
+        .unwrap();
+    assert_eq!(okm.len(), 32);
+    let okm_dec = ibkem
+        .decapsulate_bytes(&pk, &usk, &identity, &ct, 32)
+        .unwrap();
+    assert_eq!(okm, okm_dec);
+}
+
+#[test]
+fn ibkem2_encapsulate_bytes() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let ibkem = IBKEM2::new(2, 128, 128);
+    let (pk, sk) = ibkem.setup(&mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(128);
+    let usk = ibkem.extract(&mut rng, &sk, &identity).unwrap();
+
+    let (ct, okm) = ibkem
+        .encapsulate_bytes(&mut rng, &pk, &identity, 64)
+        .unwrap();
+    let okm_dec = ibkem
+        .decapsulate_bytes(&pk, &usk, &identity, &ct, 64)
+        .unwrap();
+    assert_eq!(okm, okm_dec);
+}
+
+#[test]
+fn hibkem1_encapsulate_bytes() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let hibkem = HIBKEM1::new(2, 2, 8);
+    let (pk, _dk, sk) = HIBKEM::setup(&hibkem, &mut rng).unwrap();
+    let (_, id) = generate_hierarchical_identity(2, 8);
+    let usk = HIBKEM::extract(&hibkem, &mut rng, &sk, &id).unwrap();
+
+    let (ct, okm) = hibkem.encapsulate_bytes(&mut rng, &pk, &id, 32).unwrap();
+    let okm_dec = hibkem.decapsulate_bytes(&usk, &id, &ct, 32).unwrap();
+    assert_eq!(okm, okm_dec);
+}
+
+#[test]
+fn hibkem2_encapsulate_bytes_binds_identity() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let hibkem = HIBKEM2::new(2, 2, 8);
+    let (pk, _dk, sk) = HIBKEM::setup(&hibkem, &mut rng).unwrap();
+    let (_, id) = generate_hierarchical_identity(2, 8);
+    let usk = HIBKEM::extract(&hibkem, &mut rng, &sk, &id).unwrap();
+
+    let (ct, okm) = hibkem.encapsulate_bytes(&mut rng, &pk, &id, 32).unwrap();
+    let okm_dec = hibkem.decapsulate_bytes(&usk, &id, &ct, 32).unwrap();
+    assert_eq!(okm, okm_dec);
+
+    // the ciphertext does not carry the identity, a wrong one yields an unrelated key
+    let mut other = id.clone();
+    other[1][0] ^= 1;
+    let okm_other = hibkem.decapsulate_bytes(&usk, &other, &ct, 32).unwrap();
+    assert_ne!(okm, okm_other);
+}
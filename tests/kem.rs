@@ -0,0 +1,149 @@
+use ibe_schemes::pes::bb::BB;
+use ibe_schemes::pes::bf::BF;
+use ibe_schemes::pes::cw13::CW13;
+use ibe_schemes::pes::hiberla_dec::HiberlaDec;
+use ibe_schemes::pes::hiberla_enc::HiberlaEnc;
+use ibe_schemes::pes::kem::PesKem;
+use ibe_schemes::pes::lw::LW;
+use ibe_schemes::pes::sakai_kasahara::{SakaiKasahara, SakaiKasaharaKem};
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+fn ibkem_roundtrip<T: IBKEM>(kem: T, identity_len: usize) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(identity_len);
+
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, &identity).unwrap();
+    let (ct, k_enc) = IBKEM::encrypt(&kem, &mut rng, &pk, &identity).unwrap();
+    let k_dec = IBKEM::decrypt(&kem, &pk, &usk, &identity, &ct).unwrap();
+    assert_eq!(k_enc, k_dec);
+}
+
+fn ibkem_wrong_identity<T: IBKEM>(kem: T, identity_len: usize) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let (_, identity) = generate_email_and_hash_identity(identity_len);
+    let mut other = identity.clone();
+    other[0] ^= 1;
+
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, &other).unwrap();
+    let (ct, k_enc) = IBKEM::encrypt(&kem, &mut rng, &pk, &identity).unwrap();
+    // IBKEM1 yields a different key, IBKEM2 rejects the proof for the other identity
+    if let Ok(k_dec) = IBKEM::decrypt(&kem, &pk, &usk, &other, &ct) {
+        assert_ne!(k_enc, k_dec);
+    }
+}
+
+fn hibkem_delegate_roundtrip<T: HIBKEM>(kem: T, max_levels: usize, identity_len: usize) {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (pk, dk, sk) = HIBKEM::setup(&kem, &mut rng).unwrap();
+    let (_, id) = generate_hierarchical_identity(max_levels, identity_len);
+
+    let usk_l1 = HIBKEM::extract(&kem, &mut rng, &sk, &id[0..1]).unwrap();
+    let usk_l2 = HIBKEM::delegate(&kem, &mut rng, &dk, &usk_l1, &id[0..1], id[1].clone()).unwrap();
+
+    let (ct, k_enc) = HIBKEM::encrypt(&kem, &mut rng, &pk, &id[0..2]).unwrap();
+    assert_eq!(HIBKEM::decrypt(&kem, &usk_l2, &ct).unwrap(), k_enc);
+
+    let (ct, k_enc) = HIBKEM::encrypt(&kem, &mut rng, &pk, &id[0..1]).unwrap();
+    assert_eq!(HIBKEM::decrypt(&kem, &usk_l1, &ct).unwrap(), k_enc);
+}
+
+#[test]
+fn ibkem_trait_ibkem1() {
+    ibkem_roundtrip(IBKEM1::new(2, 128), 128);
+    ibkem_wrong_identity(IBKEM1::new(2, 128), 128);
+}
+
+#[test]
+fn ibkem_trait_ibkem2() {
+    ibkem_roundtrip(IBKEM2::new(2, 128, 128), 128);
+    ibkem_wrong_identity(IBKEM2::new(2, 128, 128), 128);
+}
+
+#[test]
+fn ibkem_trait_bkp14() {
+    ibkem_roundtrip(BKP14::with_mac(AffineMacNr::new(2, 128)), 128);
+    ibkem_wrong_identity(BKP14::with_mac(AffineMacNr::new(2, 128)), 128);
+}
+
+#[test]
+fn hibkem_trait_hibkem1() {
+    hibkem_delegate_roundtrip(HIBKEM1::new(2, 2, 8), 2, 8);
+}
+
+#[test]
+fn hibkem_trait_hibkem2() {
+    hibkem_delegate_roundtrip(HIBKEM2::new(2, 2, 8), 2, 8);
+}
+
+#[test]
+fn ibkem_trait_pes_bf() {
+    ibkem_roundtrip(PesKem::new(BF::new()), 128);
+    ibkem_wrong_identity(PesKem::new(BF::new()), 128);
+}
+
+#[test]
+fn ibkem_trait_pes_bb() {
+    ibkem_roundtrip(PesKem::new(BB::new()), 128);
+    ibkem_wrong_identity(PesKem::new(BB::new()), 128);
+}
+
+#[test]
+fn ibkem_trait_pes_cw13() {
+    ibkem_roundtrip(PesKem::new(CW13::new()), 128);
+    ibkem_wrong_identity(PesKem::new(CW13::new()), 128);
+}
+
+#[test]
+fn ibkem_trait_pes_sakai_kasahara() {
+    ibkem_roundtrip(PesKem::new(SakaiKasahara::new()), 128);
+    ibkem_wrong_identity(PesKem::new(SakaiKasahara::new()), 128);
+}
+
+#[test]
+fn ibkem_trait_sakai_kasahara_kem() {
+    ibkem_roundtrip(SakaiKasaharaKem::new(), 128);
+    ibkem_wrong_identity(SakaiKasaharaKem::new(), 128);
+}
+
+#[test]
+fn hibkem_trait_pes_lw() {
+    hibkem_delegate_roundtrip(PesKem::new(LW::new()), 2, 8);
+}
+
+#[test]
+fn hibkem_trait_pes_hiberla_enc() {
+    hibkem_delegate_roundtrip(PesKem::new(HiberlaEnc::new(2)), 2, 8);
+}
+
+#[test]
+fn hibkem_trait_pes_hiberla_dec() {
+    hibkem_delegate_roundtrip(PesKem::new(HiberlaDec::new(2)), 2, 8);
+}
+
+#[test]
+fn pes_kem_keys_are_fresh() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = PesKem::new(BF::new());
+    let (pk, _) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let (_, k_1) = IBKEM::encrypt(&kem, &mut rng, &pk, b"alice").unwrap();
+    let (_, k_2) = IBKEM::encrypt(&kem, &mut rng, &pk, b"alice").unwrap();
+    assert_ne!(k_1, k_2);
+}
+
+#[test]
+fn kem_trait_names() {
+    assert_eq!(IBKEM::name(&IBKEM1::new(2, 8)), "ibkem1");
+    assert_eq!(IBKEM::name(&IBKEM2::new(2, 8, 8)), "ibkem2");
+    assert_eq!(IBKEM::name(&BKP14::with_mac(AffineMacNr::new(2, 8))), "bkp14");
+    assert_eq!(HIBKEM::name(&HIBKEM1::new(2, 2, 8)), "hibkem1");
+    assert_eq!(HIBKEM::name(&HIBKEM2::new(2, 2, 8)), "hibkem2");
+    assert_eq!(IBKEM::name(&PesKem::new(BF::new())), "bf");
+    assert_eq!(IBKEM::name(&SakaiKasaharaKem::new()), "sakai_kasahara_kem");
+    assert_eq!(HIBKEM::name(&PesKem::new(LW::new())), "lw");
+}
@@ -1,6 +1,8 @@
 mod common;
 pub use common::*;
 
+use ark_serialize::Compress;
+
 use ibe_schemes::pes::lw::LW;
 
 #[test]
@@ -45,6 +47,12 @@ fn lw_inferior_fail() {
     test_hibe_decrypt_fail(scheme, "A.B.C.D", "A.B.C");
 }
 
+#[test]
+fn lw_empty_identity_fail() {
+    let scheme = LW::new();
+    test_hibe_empty_identity_fail(scheme);
+}
+
 #[test]
 fn lw_delegate_ok() {
     let scheme = LW::new();
@@ -68,3 +76,15 @@ fn lw_delegate_hierarchy_mismatch_fail() {
     let scheme = LW::new();
     test_hibe_delegate_fail(scheme, "A.b.C", "A.B.C.D", "D");
 }
+
+#[test]
+fn lw_serialize_compressed_ok() {
+    let scheme = LW::new();
+    test_hibe_serialize_roundtrip(scheme, "A.B", "A.B.C", "C", Compress::Yes);
+}
+
+#[test]
+fn lw_serialize_uncompressed_ok() {
+    let scheme = LW::new();
+    test_hibe_serialize_roundtrip(scheme, "A.B", "A.B.C", "C", Compress::No);
+}
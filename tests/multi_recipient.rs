@@ -0,0 +1,154 @@
+use ibe_schemes::pes::bb::BB;
+use ibe_schemes::pes::bf::BF;
+use ibe_schemes::pes::kem::PesKem;
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+const MESSAGE: &[u8] = b"minutes of the board meeting";
+
+fn identities(n: usize) -> Vec<Vec<u8>> {
+    (0..n)
+        .map(|i| blake3_hash_to_bits(format!("user{}@example.com", i).as_bytes(), 128).to_bytes())
+        .collect()
+}
+
+fn multi_roundtrip<K>(kem: K)
+where
+    K: IBKEM + EnvelopeScheme<Ciphertext = <K as IBKEM>::CT>,
+{
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let ids = identities(3);
+
+    let blob = encrypt_multi(&kem, &mut rng, &pk, &ids, MESSAGE).unwrap();
+    assert_eq!(multi_recipients(&blob).unwrap(), ids);
+    for (index, identity) in ids.iter().enumerate() {
+        let usk = IBKEM::extract(&kem, &mut rng, &sk, identity).unwrap();
+        assert_eq!(
+            decrypt_multi(&kem, &pk, &usk, identity, &blob).unwrap(),
+            MESSAGE
+        );
+        assert_eq!(
+            decrypt_multi_slot(&kem, &pk, &usk, index, &blob).unwrap(),
+            MESSAGE
+        );
+    }
+}
+
+#[test]
+fn multi_ibkem1() {
+    multi_roundtrip(IBKEM1::new(2, 128));
+}
+
+#[test]
+fn multi_ibkem2() {
+    multi_roundtrip(IBKEM2::new(2, 128, 128));
+}
+
+#[test]
+fn multi_pes_bf() {
+    multi_roundtrip(PesKem::new(BF::new()));
+}
+
+#[test]
+fn multi_pes_bb() {
+    multi_roundtrip(PesKem::new(BB::new()));
+}
+
+#[test]
+fn multi_shared_randomness_bf() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = PesKem::new(BF::new());
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let ids = identities(4);
+
+    let blob = encrypt_multi_shared(&kem, &mut rng, &pk, &ids, MESSAGE).unwrap();
+    assert_eq!(multi_recipients(&blob).unwrap(), ids);
+    for identity in &ids {
+        let usk = IBKEM::extract(&kem, &mut rng, &sk, identity).unwrap();
+        assert_eq!(
+            decrypt_multi(&kem, &pk, &usk, identity, &blob).unwrap(),
+            MESSAGE
+        );
+    }
+}
+
+#[test]
+fn bf_encrypt_shared_shares_s_only() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = PesKem::new(BF::new());
+    let (pk, _) = IBKEM::setup(&kem, &mut rng).unwrap();
+
+    let (cts, _) = kem.encrypt_shared(&mut rng, &pk, &identities(2)).unwrap();
+    assert_eq!(cts[0].s, cts[1].s);
+    assert_eq!(cts[0].msg, cts[1].msg);
+    assert_ne!(cts[0].c, cts[1].c);
+}
+
+#[test]
+fn multi_non_recipient_fails() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = IBKEM1::new(2, 128);
+    let (pk, sk) = kem.setup(&mut rng).unwrap();
+    let ids = identities(3);
+    let blob = encrypt_multi(&kem, &mut rng, &pk, &ids[..2], MESSAGE).unwrap();
+    let usk = kem.extract(&mut rng, &sk, &ids[2]).unwrap();
+
+    assert!(matches!(
+        decrypt_multi(&kem, &pk, &usk, &ids[2], &blob),
+        Err(IbeError::RecipientNotFound)
+    ));
+    assert!(matches!(
+        decrypt_multi_slot(&kem, &pk, &usk, 2, &blob),
+        Err(IbeError::RecipientNotFound)
+    ));
+    // using another recipient's slot yields an unrelated wrapping key
+    assert!(matches!(
+        decrypt_multi_slot(&kem, &pk, &usk, 0, &blob),
+        Err(IbeError::DecryptionFailed)
+    ));
+}
+
+#[test]
+fn multi_detects_tampering_everywhere() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = IBKEM1::new(2, 128);
+    let (pk, sk) = kem.setup(&mut rng).unwrap();
+    let ids = identities(2);
+    let usk = kem.extract(&mut rng, &sk, &ids[0]).unwrap();
+    let blob = encrypt_multi(&kem, &mut rng, &pk, &ids, MESSAGE).unwrap();
+
+    // includes the slot of the other recipient, which is authenticated with the payload
+    for i in 0..blob.len() {
+        let mut tampered = blob.clone();
+        tampered[i] ^= 0x01;
+        assert!(
+            decrypt_multi_slot(&kem, &pk, &usk, 0, &tampered).is_err(),
+            "flipping byte {} went unnoticed",
+            i
+        );
+    }
+}
+
+#[test]
+fn multi_rejects_truncation_and_empty_recipients() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = PesKem::new(BF::new());
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let ids = identities(2);
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, &ids[1]).unwrap();
+    let blob = encrypt_multi(&kem, &mut rng, &pk, &ids, MESSAGE).unwrap();
+
+    for len in [
+        0,
+        4,
+        7,
+        blob.len() - MESSAGE.len() - HYBRID_TAG_LEN,
+        blob.len() - 1,
+    ] {
+        assert!(decrypt_multi(&kem, &pk, &usk, &ids[1], &blob[..len]).is_err());
+    }
+    assert!(encrypt_multi(&kem, &mut rng, &pk, &[], MESSAGE).is_err());
+}
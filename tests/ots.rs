@@ -0,0 +1,41 @@
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+#[test]
+fn ots_sign_verify() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let signing_key = OtsSigningKey::generate(&mut rng);
+    let vk = signing_key.verifying_key().clone();
+
+    let signature = signing_key.sign(b"message");
+    assert!(vk.verify(b"message", &signature));
+}
+
+#[test]
+fn ots_rejects_other_message() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let signing_key = OtsSigningKey::generate(&mut rng);
+    let vk = signing_key.verifying_key().clone();
+
+    let signature = signing_key.sign(b"message");
+    assert!(!vk.verify(b"messagf", &signature));
+    assert!(!vk.verify(b"", &signature));
+}
+
+#[test]
+fn ots_rejects_other_key_and_modified_signature() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let signing_key = OtsSigningKey::generate(&mut rng);
+    let vk = signing_key.verifying_key().clone();
+    let other = OtsSigningKey::generate(&mut rng).verifying_key().clone();
+
+    let signature = signing_key.sign(b"message");
+    assert!(!other.verify(b"message", &signature));
+
+    let mut modified = signature.clone();
+    modified.sigma += ark_bls12_381::Fr::from(1u64);
+    assert!(!vk.verify(b"message", &modified));
+}
@@ -1,94 +1,104 @@
 use ark_bls12_381::G1Projective as G1;
 use ark_ec::PrimeGroup;
 use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
 
 #[test]
 fn qanizk_ok() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let lambda = 128;
     let qanizk = QANIZK::new(k, lambda);
-    let m_matrix = random_matrix(3 * k, k);
+    let m_matrix = random_matrix(&mut rng, 3 * k, k);
     let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
 
-    let (crs, _) = qanizk.gen_crs(&m_g1_matrix);
+    let (crs, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
 
     let tag = generate_random_message_128();
-    let r = random_vector(k);
+    let r = random_vector(&mut rng, k);
     let c0_field = matrix_vector_mul(&m_matrix, &r);
     let c0_g1: Vec<G1> = vector_lift_g1(&c0_field);
 
-    let pi = qanizk.prove(&crs, &tag, &c0_g1, &r);
-    let is_valid = qanizk.verify(&crs, &tag, &c0_g1, &pi);
+    let pi = qanizk.prove(&mut rng, &crs, &tag, &c0_g1, &r).unwrap();
+    let is_valid = qanizk.verify(&crs, &tag, &c0_g1, &pi).is_ok();
     assert!(is_valid);
 }
 
 #[test]
 fn qanizk_fail_wrong_tag() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let lambda = 128;
     let qanizk = QANIZK::new(k, lambda);
-    let m_matrix = random_matrix(3 * k, k);
+    let m_matrix = random_matrix(&mut rng, 3 * k, k);
     let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
 
-    let (crs, _) = qanizk.gen_crs(&m_g1_matrix);
+    let (crs, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
 
     let tag = generate_random_message_128();
-    let r = random_vector(k);
+    let r = random_vector(&mut rng, k);
     let c0_field = matrix_vector_mul(&m_matrix, &r);
     let c0_g1: Vec<G1> = vector_lift_g1(&c0_field);
 
-    let pi = qanizk.prove(&crs, &tag, &c0_g1, &r);
+    let pi = qanizk.prove(&mut rng, &crs, &tag, &c0_g1, &r).unwrap();
 
     let wrong_tag = generate_random_message_128();
-    let is_valid = qanizk.verify(&crs, &wrong_tag, &c0_g1, &pi);
+    let is_valid = qanizk.verify(&crs, &wrong_tag, &c0_g1, &pi).is_ok();
     assert!(!is_valid, "Verification should fail with wrong tag");
 }
 
 #[test]
 fn qanizk_fail_wrong_c0() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let lambda = 128;
     let qanizk = QANIZK::new(k, lambda);
-    let m_matrix = random_matrix(3 * k, k);
+    let m_matrix = random_matrix(&mut rng, 3 * k, k);
     let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
 
-    let (crs, _) = qanizk.gen_crs(&m_g1_matrix);
+    let (crs, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
 
     let tag = generate_random_message_128();
-    let r = random_vector(k);
+    let r = random_vector(&mut rng, k);
     let c0_field = matrix_vector_mul(&m_matrix, &r);
     let c0_g1: Vec<G1> = vector_lift_g1(&c0_field);
 
-    let pi = qanizk.prove(&crs, &tag, &c0_g1, &r);
+    let pi = qanizk.prove(&mut rng, &crs, &tag, &c0_g1, &r).unwrap();
 
-    let wrong_r = random_vector(k);
+    let wrong_r = random_vector(&mut rng, k);
     let wrong_c0_field = matrix_vector_mul(&m_matrix, &wrong_r);
     let wrong_c0_g1: Vec<G1> = vector_lift_g1(&wrong_c0_field);
 
-    let is_valid = qanizk.verify(&crs, &tag, &wrong_c0_g1, &pi);
+    let is_valid = qanizk.verify(&crs, &tag, &wrong_c0_g1, &pi).is_ok();
     assert!(!is_valid, "Verification should fail with wrong c0");
 }
 
 #[test]
 fn qanizk_fail_inconsistent_r() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let lambda = 128;
     let qanizk = QANIZK::new(k, lambda);
-    let m_matrix = random_matrix(3 * k, k);
+    let m_matrix = random_matrix(&mut rng, 3 * k, k);
     let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
 
-    let (crs, _) = qanizk.gen_crs(&m_g1_matrix);
+    let (crs, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
 
     let tag = generate_random_message_128();
-    let r = random_vector(k);
+    let r = random_vector(&mut rng, k);
     let c0_field = matrix_vector_mul(&m_matrix, &r);
     let c0_g1: Vec<G1> = vector_lift_g1(&c0_field);
 
-    let wrong_r = random_vector(k);
+    let wrong_r = random_vector(&mut rng, k);
 
-    let pi = qanizk.prove(&crs, &tag, &c0_g1, &wrong_r);
+    let pi = qanizk
+        .prove(&mut rng, &crs, &tag, &c0_g1, &wrong_r)
+        .unwrap();
 
-    let is_valid = qanizk.verify(&crs, &tag, &c0_g1, &pi);
+    let is_valid = qanizk.verify(&crs, &tag, &c0_g1, &pi).is_ok();
     assert!(
         !is_valid,
         "Verification should fail with inconsistent witness"
@@ -97,45 +107,52 @@ fn qanizk_fail_inconsistent_r() {
 
 #[test]
 fn qanizk_fail_modified_proof() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let lambda = 128;
     let qanizk = QANIZK::new(k, lambda);
-    let m_matrix = random_matrix(3 * k, k);
+    let m_matrix = random_matrix(&mut rng, 3 * k, k);
     let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
 
-    let (crs, _) = qanizk.gen_crs(&m_g1_matrix);
+    let (crs, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
 
     let tag = generate_random_message_128();
-    let r = random_vector(k);
+    let r = random_vector(&mut rng, k);
     let c0_field = matrix_vector_mul(&m_matrix, &r);
     let c0_g1: Vec<G1> = vector_lift_g1(&c0_field);
 
-    let mut pi = qanizk.prove(&crs, &tag, &c0_g1, &r);
+    let mut pi = qanizk.prove(&mut rng, &crs, &tag, &c0_g1, &r).unwrap();
 
     pi.t1_g1[0] = pi.t1_g1[0] + G1::generator();
 
-    let is_valid = qanizk.verify(&crs, &tag, &c0_g1, &pi);
-    assert!(!is_valid, "Verification should fail with tampered proof");
+    assert!(
+        matches!(
+            qanizk.verify(&crs, &tag, &c0_g1, &pi),
+            Err(IbeError::InvalidProof)
+        ),
+        "Verification should fail with tampered proof"
+    );
 }
 
 #[test]
 fn qanizk_fail_wrong_crs() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
     let k = 2;
     let lambda = 128;
     let qanizk = QANIZK::new(k, lambda);
-    let m_matrix = random_matrix(3 * k, k);
+    let m_matrix = random_matrix(&mut rng, 3 * k, k);
     let m_g1_matrix: Matrix<G1> = matrix_lift_g1(&m_matrix);
 
-    let (crs1, _) = qanizk.gen_crs(&m_g1_matrix);
-    let (crs2, _) = qanizk.gen_crs(&m_g1_matrix);
+    let (crs1, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
+    let (crs2, _) = qanizk.gen_crs(&mut rng, &m_g1_matrix).unwrap();
 
     let tag = generate_random_message_128();
-    let r = random_vector(k);
+    let r = random_vector(&mut rng, k);
     let c0_field = matrix_vector_mul(&m_matrix, &r);
     let c0_g1: Vec<G1> = vector_lift_g1(&c0_field);
 
-    let pi = qanizk.prove(&crs1, &tag, &c0_g1, &r);
+    let pi = qanizk.prove(&mut rng, &crs1, &tag, &c0_g1, &r).unwrap();
 
-    let is_valid = qanizk.verify(&crs2, &tag, &c0_g1, &pi);
+    let is_valid = qanizk.verify(&crs2, &tag, &c0_g1, &pi).is_ok();
     assert!(!is_valid, "Verification should fail with different CRS");
 }
@@ -0,0 +1,171 @@
+mod common;
+pub use common::*;
+
+use ark_bls12_381::Fq12 as Gt;
+use ark_ff::UniformRand;
+use ark_serialize::{CanonicalSerialize, Compress};
+
+use ibe_schemes::pes::IBEScheme;
+use ibe_schemes::pes::kem::PesKem;
+use ibe_schemes::pes::sakai_kasahara::{self, SakaiKasahara, SakaiKasaharaKem};
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+#[test]
+fn sakai_kasahara_minimal_ok() {
+    let scheme = SakaiKasahara::new();
+    test_ibe_decrypt_ok(scheme, "A", "A");
+}
+
+#[test]
+fn sakai_kasahara_longer_ok() {
+    let scheme = SakaiKasahara::new();
+    test_ibe_decrypt_ok(scheme, "ABCDEFG", "ABCDEFG");
+}
+
+#[test]
+fn sakai_kasahara_minimal_fail() {
+    let scheme = SakaiKasahara::new();
+    test_ibe_decrypt_fail(scheme, "A", "B");
+}
+
+#[test]
+fn sakai_kasahara_longer_fail() {
+    let scheme = SakaiKasahara::new();
+    test_ibe_decrypt_fail(scheme, "ABCDEFG", "ABCDeFG");
+}
+
+#[test]
+fn sakai_kasahara_serialize_compressed_ok() {
+    let scheme = SakaiKasahara::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::Yes);
+}
+
+#[test]
+fn sakai_kasahara_serialize_uncompressed_ok() {
+    let scheme = SakaiKasahara::new();
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::No);
+}
+
+#[test]
+fn sakai_kasahara_keys_are_deterministic() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scheme = SakaiKasahara::new();
+    let (msk, _) = scheme.setup(&mut rng);
+
+    let usk_1 = scheme
+        .keygen(&mut rng, &msk, String::from("alice"))
+        .unwrap();
+    let usk_2 = scheme
+        .keygen(&mut rng, &msk, String::from("alice"))
+        .unwrap();
+    assert_eq!(usk_1.d, usk_2.d);
+}
+
+#[test]
+fn sakai_kasahara_keygen_rejects_identity_hashing_to_minus_z() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scheme = SakaiKasahara::new();
+    let msk = sakai_kasahara::MSK {
+        z: -hash_to_fr("alice"),
+    };
+
+    assert!(matches!(
+        scheme.keygen(&mut rng, &msk, String::from("alice")),
+        Err(IbeError::InvalidIdentity)
+    ));
+    assert!(scheme.keygen(&mut rng, &msk, String::from("bob")).is_ok());
+}
+
+#[test]
+fn sakai_kasahara_kem_shares_keys_with_pes_kem() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = SakaiKasaharaKem::new();
+    let pes_kem = PesKem::new(SakaiKasahara::new());
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+
+    let usk = IBKEM::extract(&pes_kem, &mut rng, &sk, b"alice").unwrap();
+    let (ct, k_enc) = IBKEM::encrypt(&kem, &mut rng, &pk, b"alice").unwrap();
+    assert_eq!(
+        IBKEM::decrypt(&kem, &pk, &usk, b"alice", &ct).unwrap(),
+        k_enc
+    );
+}
+
+#[test]
+fn sakai_kasahara_kem_ciphertext_is_one_element() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = SakaiKasaharaKem::new();
+    let (pk, _) = IBKEM::setup(&kem, &mut rng).unwrap();
+
+    let (ct, _) = IBKEM::encrypt(&kem, &mut rng, &pk, b"alice").unwrap();
+    assert_eq!(ct.compressed_size(), 48);
+}
+
+#[test]
+fn sakai_kasahara_kem_rejects_other_identity() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = SakaiKasaharaKem::new();
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, b"alice").unwrap();
+    let (ct, _) = IBKEM::encrypt(&kem, &mut rng, &pk, b"alice").unwrap();
+    assert!(matches!(
+        IBKEM::decrypt(&kem, &pk, &usk, b"bob", &ct),
+        Err(IbeError::IdentityMismatch)
+    ));
+}
+
+#[test]
+fn sakai_kasahara_kem_envelope_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = SakaiKasaharaKem::new();
+    let (pk, _) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let (ct, _) = IBKEM::encrypt(&kem, &mut rng, &pk, b"alice").unwrap();
+
+    let bytes = seal(&kem, &ct).unwrap();
+    let AnyCiphertext::SakaiKasaharaKem(_, opened) = open_any(&bytes).unwrap() else {
+        panic!("expected a sakai_kasahara_kem ciphertext");
+    };
+    assert!(opened == ct);
+}
+
+#[test]
+fn sakai_kasahara_armor_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let scheme = SakaiKasahara::new();
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    let text = to_armor(&mpk).unwrap();
+    assert!(text.starts_with("-----BEGIN IBE SAKAI KASAHARA PUBLIC KEY-----\n"));
+    let mpk: sakai_kasahara::MPK = from_armor(&text).unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("alice"))
+        .unwrap();
+    let ct: sakai_kasahara::CT = from_armor(&to_armor(&ct).unwrap()).unwrap();
+
+    let msk: sakai_kasahara::MSK = from_armor(&to_armor(&msk).unwrap()).unwrap();
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from("alice"))
+        .unwrap();
+    let usk: sakai_kasahara::USK = from_armor(&to_armor(&usk).unwrap()).unwrap();
+    assert_eq!(scheme.decrypt(&usk, &ct).unwrap(), msg);
+}
+
+#[test]
+fn sakai_kasahara_kem_armor_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = SakaiKasaharaKem::new();
+    let (pk, sk) = IBKEM::setup(&kem, &mut rng).unwrap();
+    let usk = IBKEM::extract(&kem, &mut rng, &sk, b"alice").unwrap();
+    let (ct, key) = IBKEM::encrypt(&kem, &mut rng, &pk, b"alice").unwrap();
+
+    let text = to_armor(&ct).unwrap();
+    assert!(text.starts_with("-----BEGIN IBE SAKAI KASAHARA KEM CIPHERTEXT-----\n"));
+    let ct: sakai_kasahara::KemCT = from_armor(&text).unwrap();
+    assert_eq!(IBKEM::decrypt(&kem, &pk, &usk, b"alice", &ct).unwrap(), key);
+}
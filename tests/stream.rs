@@ -0,0 +1,259 @@
+use std::io::{self, Read, Write};
+
+use ibe_schemes::pes::bf::BF;
+use ibe_schemes::pes::kem::PesKem;
+use ibe_schemes::pes::lw::LW;
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+const CHUNK: usize = 16;
+const IDENTITY: &[u8] = b"alice@example.com";
+
+type Kem = PesKem<BF>;
+type Keys = (<Kem as IBKEM>::PK, <Kem as IBKEM>::USK);
+
+fn setup(rng: &mut ChaCha20Rng) -> (Kem, Keys) {
+    let kem = PesKem::new(BF::new());
+    let (pk, sk) = IBKEM::setup(&kem, rng).unwrap();
+    let usk = IBKEM::extract(&kem, rng, &sk, IDENTITY).unwrap();
+    (kem, (pk, usk))
+}
+
+fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn encrypt(kem: &Kem, rng: &mut ChaCha20Rng, pk: &<Kem as IBKEM>::PK, data: &[u8]) -> Vec<u8> {
+    let mut writer = encrypt_stream(kem, rng, pk, IDENTITY, CHUNK, Vec::new()).unwrap();
+    writer.write_all(data).unwrap();
+    writer.finish().unwrap()
+}
+
+fn decrypt(kem: &Kem, keys: &Keys, sealed: &[u8]) -> io::Result<Vec<u8>> {
+    let mut reader = decrypt_stream(kem, &keys.0, &keys.1, sealed).map_err(io::Error::other)?;
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out)?;
+    Ok(out)
+}
+
+fn hashed_identity() -> Vec<u8> {
+    blake3_hash_to_bits(IDENTITY, 128).to_bytes()
+}
+
+// number of sealed bytes following the header for `len` bytes of plaintext
+fn sealed_body_len(len: usize) -> usize {
+    let full_chunks = if len == 0 { 0 } else { (len - 1) / CHUNK };
+    len + (full_chunks + 1) * STREAM_TAG_LEN
+}
+
+#[test]
+fn stream_roundtrip_chunk_boundaries() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (kem, keys) = setup(&mut rng);
+
+    for len in [
+        0,
+        1,
+        CHUNK - 1,
+        CHUNK,
+        CHUNK + 1,
+        2 * CHUNK - 1,
+        2 * CHUNK,
+        2 * CHUNK + 1,
+        1000,
+    ] {
+        let data = plaintext(len);
+        let sealed = encrypt(&kem, &mut rng, &keys.0, &data);
+        assert!(sealed.len() > sealed_body_len(len));
+        assert_eq!(
+            decrypt(&kem, &keys, &sealed).unwrap(),
+            data,
+            "length {}",
+            len
+        );
+    }
+}
+
+#[test]
+fn stream_write_granularity_does_not_matter() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (kem, keys) = setup(&mut rng);
+    let data = plaintext(5 * CHUNK + 3);
+
+    for piece in [1, 3, CHUNK, CHUNK + 1, data.len()] {
+        let mut writer =
+            encrypt_stream(&kem, &mut rng, &keys.0, IDENTITY, CHUNK, Vec::new()).unwrap();
+        for part in data.chunks(piece) {
+            writer.write_all(part).unwrap();
+        }
+        let sealed = writer.finish().unwrap();
+        assert_eq!(decrypt(&kem, &keys, &sealed).unwrap(), data);
+    }
+}
+
+#[test]
+fn stream_small_reads() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (kem, keys) = setup(&mut rng);
+    let data = plaintext(3 * CHUNK);
+    let sealed = encrypt(&kem, &mut rng, &keys.0, &data);
+
+    let mut reader = decrypt_stream(&kem, &keys.0, &keys.1, sealed.as_slice()).unwrap();
+    let mut out = Vec::new();
+    let mut buf = [0u8; 5];
+    loop {
+        let n = reader.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        out.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(out, data);
+}
+
+#[test]
+fn stream_detects_truncation() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (kem, keys) = setup(&mut rng);
+    let data = plaintext(3 * CHUNK);
+    let sealed = encrypt(&kem, &mut rng, &keys.0, &data);
+    let header_len = sealed.len() - sealed_body_len(data.len());
+    let sealed_chunk = CHUNK + STREAM_TAG_LEN;
+
+    // at every chunk boundary, inside chunks and right after the header
+    for cut in [
+        header_len,
+        header_len + 1,
+        header_len + sealed_chunk,
+        header_len + 2 * sealed_chunk,
+        sealed.len() - 1,
+    ] {
+        let err = decrypt(&kem, &keys, &sealed[..cut]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData, "cut at {}", cut);
+    }
+}
+
+#[test]
+fn stream_detects_missing_finish() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (kem, keys) = setup(&mut rng);
+    let mut sealed = Vec::new();
+    {
+        let mut writer =
+            encrypt_stream(&kem, &mut rng, &keys.0, IDENTITY, CHUNK, &mut sealed).unwrap();
+        writer.write_all(&plaintext(2 * CHUNK + 5)).unwrap();
+        // dropped without `finish`
+    }
+    assert!(decrypt(&kem, &keys, &sealed).is_err());
+}
+
+#[test]
+fn stream_detects_reordering() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (kem, keys) = setup(&mut rng);
+    let data = plaintext(3 * CHUNK);
+    let sealed = encrypt(&kem, &mut rng, &keys.0, &data);
+    let header_len = sealed.len() - sealed_body_len(data.len());
+    let sealed_chunk = CHUNK + STREAM_TAG_LEN;
+
+    let mut swapped = sealed[..header_len].to_vec();
+    swapped.extend_from_slice(&sealed[header_len + sealed_chunk..header_len + 2 * sealed_chunk]);
+    swapped.extend_from_slice(&sealed[header_len..header_len + sealed_chunk]);
+    swapped.extend_from_slice(&sealed[header_len + 2 * sealed_chunk..]);
+    assert_eq!(swapped.len(), sealed.len());
+    assert!(decrypt(&kem, &keys, &swapped).is_err());
+}
+
+#[test]
+fn stream_detects_appended_data() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (kem, keys) = setup(&mut rng);
+    let data = plaintext(2 * CHUNK);
+    let sealed = encrypt(&kem, &mut rng, &keys.0, &data);
+    let last_chunk = &sealed[sealed.len() - (CHUNK + STREAM_TAG_LEN)..];
+
+    let mut extended = sealed.clone();
+    extended.push(0);
+    assert!(decrypt(&kem, &keys, &extended).is_err());
+
+    let mut repeated = sealed.clone();
+    repeated.extend_from_slice(last_chunk);
+    assert!(decrypt(&kem, &keys, &repeated).is_err());
+}
+
+#[test]
+fn stream_detects_tampering() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = IBKEM1::new(2, 128);
+    let (pk, sk) = kem.setup(&mut rng).unwrap();
+    let identity = hashed_identity();
+    let usk = kem.extract(&mut rng, &sk, &identity).unwrap();
+    let data = plaintext(2 * CHUNK + 1);
+    let mut writer = encrypt_stream(&kem, &mut rng, &pk, &identity, CHUNK, Vec::new()).unwrap();
+    writer.write_all(&data).unwrap();
+    let sealed = writer.finish().unwrap();
+
+    // covers magic, identity, KEM envelope, chunk size, nonce prefix and every chunk
+    for i in 0..sealed.len() {
+        let mut tampered = sealed.clone();
+        tampered[i] ^= 0x01;
+        let result = decrypt_stream(&kem, &pk, &usk, tampered.as_slice())
+            .map_err(io::Error::other)
+            .and_then(|mut reader| reader.read_to_end(&mut Vec::new()));
+        assert!(result.is_err(), "flipping byte {} went unnoticed", i);
+    }
+}
+
+#[test]
+fn stream_rejects_invalid_chunk_size() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (kem, keys) = setup(&mut rng);
+
+    for size in [0, STREAM_MAX_CHUNK_SIZE + 1] {
+        assert!(matches!(
+            encrypt_stream(&kem, &mut rng, &keys.0, IDENTITY, size, Vec::new()),
+            Err(IbeError::InvalidChunkSize { .. })
+        ));
+    }
+}
+
+#[test]
+fn stream_wrong_key_fails() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let (kem, keys) = setup(&mut rng);
+    let (_, other) = setup(&mut rng);
+    let sealed = encrypt(&kem, &mut rng, &keys.0, &plaintext(CHUNK));
+
+    let err = decrypt(&kem, &(keys.0, other.1), &sealed).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn stream_hierarchical_roundtrip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let kem = PesKem::new(LW::new());
+    let (pk, dk, sk) = HIBKEM::setup(&kem, &mut rng).unwrap();
+    let id = vec![b"example.com".to_vec(), b"alice".to_vec()];
+    let usk = HIBKEM::extract(&kem, &mut rng, &sk, &id[..1]).unwrap();
+    let usk = HIBKEM::delegate(&kem, &mut rng, &dk, &usk, &id[..1], id[1].clone()).unwrap();
+    let data = plaintext(STREAM_DEFAULT_CHUNK_SIZE + 1);
+
+    let mut writer = encrypt_stream_hierarchical(
+        &kem,
+        &mut rng,
+        &pk,
+        &id,
+        STREAM_DEFAULT_CHUNK_SIZE,
+        Vec::new(),
+    )
+    .unwrap();
+    writer.write_all(&data).unwrap();
+    let sealed = writer.finish().unwrap();
+
+    let mut reader = decrypt_stream_hierarchical(&kem, &usk, sealed.as_slice()).unwrap();
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out).unwrap();
+    assert_eq!(out, data);
+}
@@ -0,0 +1,157 @@
+use ibe_schemes::games::uf_cmva::*;
+use ibe_schemes::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+const SEED: u64 = 42;
+
+fn rng() -> ChaCha20Rng {
+    ChaCha20Rng::seed_from_u64(SEED)
+}
+
+fn levels() -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+    (
+        vec![vec![0x12, 0x34], vec![0x56, 0x78], vec![0x9a, 0xbc]],
+        vec![vec![0xfe, 0xdc], vec![0xba, 0x98], vec![0x76, 0x54]],
+    )
+}
+
+#[test]
+fn uf_cmva_counts_a_valid_tag_on_a_fresh_message() {
+    let mut rng = rng();
+    let mac = AffineMAC::new(2, 16);
+    let sk = AffineMac::gen_mac(&mac, &mut rng);
+    let forged = AffineMac::tag(&mac, &mut rng, &sk, &[0xab, 0xcd]).unwrap();
+
+    let mut challenger = UfCmvaChallenger::with_key(&mac, sk, rng);
+    let tag = challenger.tag_oracle(&vec![0x01, 0x02]).unwrap();
+    assert!(challenger.verify_oracle(&vec![0x01, 0x02], &tag));
+    assert!(!challenger.adversary_won());
+
+    assert!(challenger.is_forgery(&vec![0xab, 0xcd], &forged));
+    assert!(challenger.verify_oracle(&vec![0xab, 0xcd], &forged));
+    assert!(challenger.adversary_won());
+}
+
+#[test]
+fn uf_cmva_queried_messages_are_not_fresh() {
+    let mac = AffineMacLevels1::new(2, 3, 16);
+    let (first, _) = levels();
+
+    let mut challenger = UfCmvaChallenger::new(&mac, rng());
+    assert!(challenger.is_fresh(&first));
+    // even a failed query uses up the message
+    assert!(challenger.tag_oracle(&vec![]).is_err());
+    assert!(!challenger.is_fresh(&vec![]));
+
+    let tag = challenger.tag_oracle(&first).unwrap();
+    assert!(!challenger.is_fresh(&first));
+    assert!(!challenger.is_forgery(&first, &tag));
+}
+
+#[test]
+fn uf_cmva_replay_never_wins() {
+    let message = vec![0x5a, 0xa5];
+    let (first, _) = levels();
+
+    assert!(!play_uf_cmva(
+        &AffineMAC::new(2, 16),
+        &mut ReplayAdversary {
+            message: message.clone()
+        },
+        rng()
+    ));
+    assert!(!play_uf_cmva(
+        &AffineMacNr::new(2, 16),
+        &mut ReplayAdversary { message },
+        rng()
+    ));
+    assert!(!play_uf_cmva(
+        &AffineMacLevels1::new(2, 3, 16),
+        &mut ReplayAdversary {
+            message: first.clone()
+        },
+        rng()
+    ));
+    assert!(!play_uf_cmva(
+        &AffineMacLevels2::new(2, 3, 16),
+        &mut ReplayAdversary { message: first },
+        rng()
+    ));
+}
+
+#[test]
+fn uf_cmva_bit_flip_never_wins() {
+    let message = vec![0x5a, 0xa5];
+    let bits = vec![0, 7, 8, 15];
+    // the first, a middle and the last bit of the hierarchical message
+    let (first, _) = levels();
+    let level_bits = vec![0, 17, 47];
+
+    assert!(!play_uf_cmva(
+        &AffineMAC::new(2, 16),
+        &mut BitFlipAdversary {
+            message: message.clone(),
+            bits: bits.clone(),
+        },
+        rng()
+    ));
+    assert!(!play_uf_cmva(
+        &AffineMacNr::new(2, 16),
+        &mut BitFlipAdversary { message, bits },
+        rng()
+    ));
+    assert!(!play_uf_cmva(
+        &AffineMacLevels1::new(2, 3, 16),
+        &mut BitFlipAdversary {
+            message: first.clone(),
+            bits: level_bits.clone(),
+        },
+        rng()
+    ));
+    assert!(!play_uf_cmva(
+        &AffineMacLevels2::new(2, 3, 16),
+        &mut BitFlipAdversary {
+            message: first,
+            bits: level_bits,
+        },
+        rng()
+    ));
+}
+
+#[test]
+fn uf_cmva_tag_mixing_never_wins() {
+    let (first, second) = levels();
+
+    assert!(!play_uf_cmva(
+        &AffineMAC::new(2, 16),
+        &mut TagMixAdversary {
+            first: vec![0x5a, 0xa5],
+            second: vec![0xc3, 0x3c],
+        },
+        rng()
+    ));
+    assert!(!play_uf_cmva(
+        &AffineMacLevels1::new(2, 3, 16),
+        &mut TagMixAdversary {
+            first: first.clone(),
+            second: second.clone(),
+        },
+        rng()
+    ));
+    assert!(!play_uf_cmva(
+        &AffineMacLevels2::new(2, 3, 16),
+        &mut TagMixAdversary { first, second },
+        rng()
+    ));
+}
+
+#[test]
+fn uf_cmva_flip_bit_counts_across_levels() {
+    let (first, _) = levels();
+    let flipped = <AffineMacLevels1 as UfCmvaMac>::flip_bit(&first, 17);
+
+    assert_eq!(flipped[0], first[0]);
+    assert_eq!(flipped[1], vec![0x56 ^ 0x40, 0x78]);
+    assert_eq!(flipped[2], first[2]);
+}
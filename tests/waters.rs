@@ -0,0 +1,118 @@
+mod common;
+pub use common::*;
+
+use ark_bls12_381::Fq12 as Gt;
+use ark_ff::UniformRand;
+use ark_serialize::Compress;
+use rand::thread_rng;
+
+use ibe_schemes::pes::IBEScheme;
+use ibe_schemes::pes::waters::{self, Waters};
+use ibe_schemes::*;
+
+const N: usize = 128;
+
+#[test]
+fn waters_minimal_ok() {
+    let scheme = Waters::new(N);
+    test_ibe_decrypt_ok(scheme, "A", "A");
+}
+
+#[test]
+fn waters_longer_ok() {
+    let scheme = Waters::new(N);
+    test_ibe_decrypt_ok(scheme, "ABCDEFG", "ABCDEFG");
+}
+
+#[test]
+fn waters_full_length_ok() {
+    let scheme = Waters::new(256);
+    test_ibe_decrypt_ok(scheme, "ABCDEFG", "ABCDEFG");
+}
+
+#[test]
+fn waters_minimal_fail() {
+    let scheme = Waters::new(N);
+    test_ibe_decrypt_fail(scheme, "A", "B");
+}
+
+#[test]
+fn waters_longer_fail() {
+    let scheme = Waters::new(N);
+    test_ibe_decrypt_fail(scheme, "ABCDEFG", "ABCDeFG");
+}
+
+#[test]
+fn waters_serialize_compressed_ok() {
+    let scheme = Waters::new(N);
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::Yes);
+}
+
+#[test]
+fn waters_serialize_uncompressed_ok() {
+    let scheme = Waters::new(N);
+    test_ibe_serialize_roundtrip(scheme, "ABCDEFG", Compress::No);
+}
+
+#[test]
+fn waters_envelope_roundtrip() {
+    let mut rng = thread_rng();
+    let scheme = Waters::new(N);
+    assert_eq!(Waters::SCHEME_ID, scheme.name());
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("alice"))
+        .unwrap();
+
+    let bytes = seal(&scheme, &ct).unwrap();
+    let AnyCiphertext::Waters(opened, ct) = open_any(&bytes).unwrap() else {
+        panic!("expected a waters ciphertext");
+    };
+    assert_eq!(opened.n, N);
+
+    let usk = opened
+        .keygen(&mut rng, &msk, String::from("alice"))
+        .unwrap();
+    assert_eq!(opened.decrypt(&usk, &ct).unwrap(), msg);
+}
+
+#[test]
+fn waters_envelope_rejects_long_identities() {
+    let mut rng = thread_rng();
+    let scheme = Waters::new(N);
+    let (_, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("alice"))
+        .unwrap();
+
+    // the parameter block starts after the magic, the version, both ids and its length
+    let mut bytes = seal(&scheme, &ct).unwrap();
+    let params = 4 + 1 + 1 + "waters".len() + 1 + CURVE_BLS12_381.len() + 4;
+    bytes[params..params + 4].copy_from_slice(&257u32.to_le_bytes());
+    assert!(matches!(open_any(&bytes), Err(EnvelopeError::Malformed(_))));
+}
+
+#[test]
+fn waters_armor_roundtrip() {
+    let mut rng = thread_rng();
+    let scheme = Waters::new(N);
+    let (msk, mpk) = scheme.setup(&mut rng);
+    let msg = Gt::rand(&mut rng);
+
+    let text = to_armor(&mpk).unwrap();
+    assert!(text.starts_with("-----BEGIN IBE WATERS PUBLIC KEY-----\n"));
+    let mpk: waters::MPK = from_armor(&text).unwrap();
+    let ct = scheme
+        .encrypt(&mut rng, &msg, &mpk, String::from("alice"))
+        .unwrap();
+    let ct: waters::CT = from_armor(&to_armor(&ct).unwrap()).unwrap();
+
+    let msk: waters::MSK = from_armor(&to_armor(&msk).unwrap()).unwrap();
+    let usk = scheme
+        .keygen(&mut rng, &msk, String::from("alice"))
+        .unwrap();
+    let usk: waters::USK = from_armor(&to_armor(&usk).unwrap()).unwrap();
+    assert_eq!(scheme.decrypt(&usk, &ct).unwrap(), msg);
+}